@@ -0,0 +1,316 @@
+//! 无界面的命令行入口，供没有桌面环境的服务器和 CI 使用
+//!
+//! 第一个参数是子命令时不创建窗口，直接复用 `Config`、`feat`、`enhance` 和 `CoreManager`
+
+use crate::{
+    cmd,
+    config::{Config, IProfiles},
+    core::CoreManager,
+    enhance, feat,
+    utils::{dirs, init},
+};
+use anyhow::{anyhow, bail, Result};
+
+const USAGE: &str = "Usage: koala-clash <command> [options]
+
+Commands:
+  import <url|share-link> [--name <name>] [--template <default|without_ru>]
+                          Import a subscription URL or a proxy share link
+  profiles                List profiles, the current one is marked with *
+  activate <uid|name>     Set the current profile
+  enhance                 Print the runtime config generated from the current profile
+  validate [file]         Validate a config file with the core, default is the runtime config
+  core run                Run the core in the foreground until it exits or Ctrl-C
+  core stop               Stop cores started by the service or another instance
+  update [<uid|name>...]  Update remote profiles, default is all of them
+  help                    Show this message
+
+A running desktop instance keeps its own state, use the control API to drive it instead.";
+
+#[derive(Debug, PartialEq, Eq)]
+enum CliCommand {
+    Import {
+        source: String,
+        name: Option<String>,
+        template: String,
+    },
+    Profiles,
+    Activate(String),
+    Enhance,
+    Validate(Option<String>),
+    CoreRun,
+    CoreStop,
+    Update(Vec<String>),
+    Help,
+}
+
+impl CliCommand {
+    /// 不是子命令时返回 None，交给桌面程序处理（例如 deep link）
+    fn parse(args: &[String]) -> Option<Result<Self>> {
+        let (command, rest) = args.split_first()?;
+        let command = match command.as_str() {
+            "import" => Self::parse_import(rest),
+            "profiles" => Ok(Self::Profiles),
+            "activate" => match rest {
+                [key] => Ok(Self::Activate(key.clone())),
+                _ => Err(anyhow!("activate expects exactly one profile")),
+            },
+            "enhance" => Ok(Self::Enhance),
+            "validate" => match rest {
+                [] => Ok(Self::Validate(None)),
+                [file] => Ok(Self::Validate(Some(file.clone()))),
+                _ => Err(anyhow!("validate expects at most one file")),
+            },
+            "core" => match rest.first().map(String::as_str) {
+                Some("run") => Ok(Self::CoreRun),
+                Some("stop") => Ok(Self::CoreStop),
+                _ => Err(anyhow!("core expects \"run\" or \"stop\"")),
+            },
+            "update" => Ok(Self::Update(rest.to_vec())),
+            "help" | "--help" | "-h" => Ok(Self::Help),
+            _ => return None,
+        };
+        Some(command)
+    }
+
+    fn parse_import(args: &[String]) -> Result<Self> {
+        let mut source = None;
+        let mut name = None;
+        let mut template = "default".to_string();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--name" => name = args.next().cloned(),
+                "--template" => {
+                    template = args
+                        .next()
+                        .cloned()
+                        .ok_or(anyhow!("--template expects a value"))?
+                }
+                _ if source.is_none() => source = Some(arg.clone()),
+                _ => bail!("unexpected argument \"{arg}\""),
+            }
+        }
+        let source = source.ok_or(anyhow!("import expects a subscription URL or share link"))?;
+        Ok(Self::Import {
+            source,
+            name,
+            template,
+        })
+    }
+}
+
+/// 以 uid 或名称查找配置
+fn resolve_profile(key: &str) -> Result<String> {
+    let profiles = Config::profiles();
+    let profiles = profiles.latest();
+    profiles
+        .get_items()
+        .into_iter()
+        .flatten()
+        .find(|item| item.uid.as_deref() == Some(key) || item.name.as_deref() == Some(key))
+        .and_then(|item| item.uid.clone())
+        .ok_or(anyhow!("profile \"{key}\" not found"))
+}
+
+async fn import(source: String, name: Option<String>, template: String) -> Result<()> {
+    if !source.starts_with("http://") && !source.starts_with("https://") {
+        cmd::create_profile_from_share_link(source, template)
+            .await
+            .map_err(|err| anyhow!(err))?;
+        eprintln!("Imported share link");
+        return Ok(());
+    }
+
+    match feat::import_profile(&source, name, None, false).await? {
+        feat::ProfileImport::Updated(uid) => eprintln!("Profile {uid} already exists, updated"),
+        feat::ProfileImport::Created(uid) => eprintln!("Imported profile {uid}"),
+    }
+    Ok(())
+}
+
+fn list_profiles() {
+    let profiles = Config::profiles();
+    let profiles = profiles.latest();
+    let current = profiles.get_current();
+    for item in profiles.get_items().into_iter().flatten() {
+        let Some(uid) = item.uid.as_deref() else {
+            continue;
+        };
+        if !matches!(item.itype.as_deref(), Some("remote" | "local")) {
+            continue;
+        }
+        let marker = if current.as_deref() == Some(uid) {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            "{marker} {uid}\t{}\t{}\t{}",
+            item.itype.as_deref().unwrap_or_default(),
+            item.name.as_deref().unwrap_or_default(),
+            item.url.as_deref().unwrap_or_default(),
+        );
+    }
+}
+
+fn activate(key: &str) -> Result<()> {
+    let uid = resolve_profile(key)?;
+    let profiles = Config::profiles();
+    let mut profiles = profiles.data();
+    profiles.patch_config(IProfiles {
+        current: Some(uid.clone()),
+        items: None,
+        ..IProfiles::default()
+    })?;
+    profiles.save_file()?;
+    eprintln!("Activated profile {uid}");
+    Ok(())
+}
+
+async fn print_enhanced() -> Result<()> {
+    let (config, _, logs) = enhance::enhance().await;
+    for (uid, lines) in logs {
+        for (level, message) in lines {
+            eprintln!("[{uid}] {level}: {message}");
+        }
+    }
+    print!("{}", serde_yaml::to_string(&config)?);
+    Ok(())
+}
+
+async fn validate(file: Option<String>) -> Result<()> {
+    let (valid, message) = match file {
+        Some(file) => {
+            let path = std::fs::canonicalize(&file)?;
+            let path = dirs::path_to_str(&path)?;
+            CoreManager::global()
+                .validate_config_file(path, None)
+                .await?
+        }
+        None => {
+            Config::generate().await?;
+            CoreManager::global().validate_config().await?
+        }
+    };
+    if !valid {
+        bail!("invalid config:\n{message}");
+    }
+    eprintln!("Config is valid");
+    Ok(())
+}
+
+async fn update(targets: Vec<String>) -> Result<()> {
+    let uids: Vec<String> = match targets.is_empty() {
+        true => Config::profiles()
+            .latest()
+            .get_items()
+            .into_iter()
+            .flatten()
+            .filter(|item| item.itype.as_deref() == Some("remote"))
+            .filter_map(|item| item.uid.clone())
+            .collect(),
+        false => targets
+            .iter()
+            .map(|key| resolve_profile(key))
+            .collect::<Result<Vec<_>>>()?,
+    };
+
+    let mut failed = 0;
+    for uid in uids {
+        match feat::update_profile(uid.clone(), None, Some(false)).await {
+            Ok(_) => eprintln!("Updated {uid}"),
+            Err(err) => {
+                failed += 1;
+                eprintln!("Failed to update {uid}: {err}");
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{failed} profile(s) failed to update");
+    }
+    Ok(())
+}
+
+async fn execute(command: CliCommand) -> Result<i32> {
+    match command {
+        CliCommand::Import {
+            source,
+            name,
+            template,
+        } => import(source, name, template).await?,
+        CliCommand::Profiles => list_profiles(),
+        CliCommand::Activate(key) => activate(&key)?,
+        CliCommand::Enhance => print_enhanced().await?,
+        CliCommand::Validate(file) => validate(file).await?,
+        CliCommand::CoreRun => {
+            return Ok(CoreManager::global()
+                .run_core_foreground()
+                .await?
+                .unwrap_or(1))
+        }
+        CliCommand::CoreStop => CoreManager::global().stop_external_cores().await?,
+        CliCommand::Update(targets) => update(targets).await?,
+        CliCommand::Help => println!("{USAGE}"),
+    }
+    Ok(0)
+}
+
+/// 命令行参数是子命令时执行并返回退出码
+pub fn run_from_args() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match CliCommand::parse(&args)? {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return Some(2);
+        }
+    };
+
+    if command != CliCommand::Help {
+        if let Err(err) = init::init_config() {
+            eprintln!("error: {err}");
+            return Some(1);
+        }
+    }
+    let code = tauri::async_runtime::block_on(execute(command)).unwrap_or_else(|err| {
+        eprintln!("error: {err:#}");
+        1
+    });
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Option<Result<CliCommand>> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        CliCommand::parse(&args)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(parse(&[]).is_none());
+        assert!(parse(&["clash://install-config?url=x"]).is_none());
+        assert_eq!(
+            parse(&["import", "https://example.com/sub", "--name", "Work"]).map(Result::unwrap),
+            Some(CliCommand::Import {
+                source: "https://example.com/sub".into(),
+                name: Some("Work".into()),
+                template: "default".into(),
+            })
+        );
+        assert_eq!(
+            parse(&["core", "run"]).map(Result::unwrap),
+            Some(CliCommand::CoreRun)
+        );
+        assert_eq!(
+            parse(&["update"]).map(Result::unwrap),
+            Some(CliCommand::Update(vec![]))
+        );
+        assert!(parse(&["import"]).unwrap().is_err());
+        assert!(parse(&["core", "restart"]).unwrap().is_err());
+        assert!(parse(&["activate", "a", "b"]).unwrap().is_err());
+    }
+}
@@ -1,6 +1,11 @@
 use super::CmdResult;
 use crate::{
-    config::*, core::*, feat, module::mihomo::MihomoManager, process::AsyncHandler, wrap_err,
+    config::*,
+    core::*,
+    feat,
+    module::{connections, mihomo::MihomoManager},
+    process::AsyncHandler,
+    wrap_err,
 };
 use serde_yaml::Mapping;
 
@@ -83,6 +88,12 @@ pub async fn restart_core() -> CmdResult {
     wrap_err!(CoreManager::global().restart_core().await)
 }
 
+/// 为被其他进程占用的端口换用空闲端口并重启内核
+#[tauri::command]
+pub async fn reassign_port_conflicts() -> CmdResult<Vec<port_preflight::PortConflict>> {
+    wrap_err!(CoreManager::global().reassign_ports().await)
+}
+
 /// 获取代理延迟
 #[tauri::command]
 pub async fn clash_api_get_proxy_delay(
@@ -101,114 +112,68 @@ pub async fn test_delay(url: String) -> CmdResult<u32> {
     Ok(feat::test_delay(url).await.unwrap_or(10000u32))
 }
 
-/// 保存DNS配置到单独文件
+/// 按主机、进程、规则或代理链统计连接流量
 #[tauri::command]
-pub async fn save_dns_config(dns_config: Mapping) -> CmdResult {
-    use crate::utils::dirs;
-    use serde_yaml;
-    use std::fs;
-
-    // 获取DNS配置文件路径
-    let dns_path = dirs::app_home_dir()
-        .map_err(|e| e.to_string())?
-        .join("dns_config.yaml");
+pub fn get_connection_stats(
+    group: connections::ConnectionGroup,
+) -> CmdResult<Vec<connections::ConnectionStat>> {
+    Ok(connections::connection_stats(group))
+}
 
-    // 保存DNS配置到文件
-    let yaml_str = serde_yaml::to_string(&dns_config).map_err(|e| e.to_string())?;
-    fs::write(&dns_path, yaml_str).map_err(|e| e.to_string())?;
-    log::info!(target: "app", "DNS config saved to {dns_path:?}");
+/// 获取最近关闭的连接
+#[tauri::command]
+pub fn get_closed_connections() -> CmdResult<Vec<connections::ClosedConnection>> {
+    Ok(connections::closed_connections())
+}
 
+/// 清空已关闭连接的历史
+#[tauri::command]
+pub fn clear_closed_connections() -> CmdResult {
+    connections::clear_closed_connections();
     Ok(())
 }
 
-/// 应用或撤销DNS配置
+/// 关闭所有符合条件的连接，返回关闭的数量
 #[tauri::command]
-pub fn apply_dns_config(apply: bool) -> CmdResult {
-    use crate::{
-        config::Config,
-        core::{handle, CoreManager},
-        utils::dirs,
-    };
+pub async fn close_connections(filter: connections::ConnectionFilter) -> CmdResult<usize> {
+    wrap_err!(connections::close_connections(&filter).await)
+}
 
-    // 使用spawn来处理异步操作
-    AsyncHandler::spawn(move || async move {
-        if apply {
-            // 读取DNS配置文件
-            let dns_path = match dirs::app_home_dir() {
-                Ok(path) => path.join("dns_config.yaml"),
-                Err(e) => {
-                    log::error!(target: "app", "Failed to get home dir: {e}");
-                    return;
-                }
-            };
+/// 校验并保存DNS配置到单独文件
+#[tauri::command]
+pub async fn save_dns_config(dns_config: IDnsConfig) -> CmdResult {
+    wrap_err!(dns_config.validate())?;
+    wrap_err!(dns_config.save())?;
+    log::info!(target: "app", "DNS config saved");
 
-            if !dns_path.exists() {
-                log::warn!(target: "app", "DNS config file not found");
-                return;
-            }
+    Ok(())
+}
 
-            let dns_yaml = match std::fs::read_to_string(&dns_path) {
-                Ok(content) => content,
-                Err(e) => {
-                    log::error!(target: "app", "Failed to read DNS config: {e}");
-                    return;
-                }
-            };
-
-            // 解析DNS配置并创建patch
-            let patch_config = match serde_yaml::from_str::<serde_yaml::Mapping>(&dns_yaml) {
-                Ok(config) => {
-                    let mut patch = serde_yaml::Mapping::new();
-                    patch.insert("dns".into(), config.into());
-                    patch
-                }
-                Err(e) => {
-                    log::error!(target: "app", "Failed to parse DNS config: {e}");
-                    return;
-                }
-            };
+/// 应用或撤销DNS配置
+#[tauri::command]
+pub fn apply_dns_config(apply: bool) -> CmdResult {
+    use crate::core::{handle, CoreManager};
 
-            log::info!(target: "app", "Applying DNS config from file");
+    if apply {
+        wrap_err!(IDnsConfig::load())?;
+    }
 
-            // 重新生成配置，确保DNS配置被正确应用
-            // 这里不调用patch_clash以避免将DNS配置写入config.yaml
-            Config::runtime()
-                .latest()
-                .patch_config(patch_config.clone());
+    // enhance 会根据 enable_dns_settings 决定是否应用DNS配置，这里只需重新生成
+    AsyncHandler::spawn(move || async move {
+        log::info!(target: "app", "Regenerating config with DNS settings {apply}");
 
-            // 首先重新生成配置
-            if let Err(err) = Config::generate().await {
-                log::error!(target: "app", "Failed to regenerate config with DNS: {err}");
-                return;
-            }
+        if let Err(err) = Config::generate().await {
+            log::error!(target: "app", "Failed to regenerate config: {err}");
+            return;
+        }
 
-            // 然后应用新配置
-            if let Err(err) = CoreManager::global().update_config().await {
-                log::error!(target: "app", "Failed to apply config with DNS: {err}");
-            } else {
-                log::info!(target: "app", "DNS config successfully applied");
+        match CoreManager::global().update_config().await {
+            Ok(_) => {
+                log::info!(target: "app", "Config regenerated successfully");
                 handle::Handle::refresh_clash();
             }
-        } else {
-            // 当关闭DNS设置时，不需要对配置进行任何修改
-            // 直接重新生成配置，让enhance函数自动跳过DNS配置的加载
-            log::info!(target: "app", "DNS settings disabled, regenerating config");
-
-            // 重新生成配置
-            if let Err(err) = Config::generate().await {
-                log::error!(target: "app", "Failed to regenerate config: {err}");
-                return;
-            }
-
-            // 应用新配置
-            match CoreManager::global().update_config().await {
-                Ok(_) => {
-                    log::info!(target: "app", "Config regenerated successfully");
-                    handle::Handle::refresh_clash();
-                }
-                Err(err) => {
-                    log::error!(target: "app", "Failed to apply regenerated config: {err}");
-                }
+            Err(err) => {
+                log::error!(target: "app", "Failed to apply regenerated config: {err}");
             }
         }
     });
@@ -216,14 +181,46 @@ pub fn apply_dns_config(apply: bool) -> CmdResult {
     Ok(())
 }
 
+/// 测试DNS服务器的延迟、失败率和污染情况
+#[tauri::command]
+pub async fn benchmark_dns(
+    options: Option<feat::DnsBenchmarkOptions>,
+) -> CmdResult<feat::DnsBenchmarkReport> {
+    wrap_err!(feat::benchmark_dns(options.unwrap_or_default()).await)
+}
+
+/// 将测试结果中最快的服务器写入DNS配置
+#[tauri::command]
+pub async fn apply_fastest_dns(report: feat::DnsBenchmarkReport) -> CmdResult<Vec<String>> {
+    wrap_err!(feat::apply_fastest_nameservers(report).await)
+}
+
+/// 清空fake-ip缓存
+#[tauri::command]
+pub async fn flush_fakeip_cache() -> CmdResult {
+    wrap_err!(feat::flush_fakeip_cache().await)
+}
+
+/// 通过内核DNS查询域名，用于调试
+#[tauri::command]
+pub async fn query_dns(name: String, query_type: Option<String>) -> CmdResult<serde_json::Value> {
+    wrap_err!(feat::query_core_dns(name, query_type).await)
+}
+
+/// 导入系统hosts到DNS配置
+#[tauri::command]
+pub async fn import_system_hosts(
+    overwrite: Option<bool>,
+) -> CmdResult<crate::config::HostsImportReport> {
+    wrap_err!(feat::import_system_hosts(overwrite.unwrap_or(false)).await)
+}
+
 /// 检查DNS配置文件是否存在
 #[tauri::command]
 pub fn check_dns_config_exists() -> CmdResult<bool> {
     use crate::utils::dirs;
 
-    let dns_path = dirs::app_home_dir()
-        .map_err(|e| e.to_string())?
-        .join("dns_config.yaml");
+    let dns_path = wrap_err!(dirs::dns_config_path())?;
 
     Ok(dns_path.exists())
 }
@@ -234,9 +231,7 @@ pub async fn get_dns_config_content() -> CmdResult<String> {
     use crate::utils::dirs;
     use std::fs;
 
-    let dns_path = dirs::app_home_dir()
-        .map_err(|e| e.to_string())?
-        .join("dns_config.yaml");
+    let dns_path = wrap_err!(dirs::dns_config_path())?;
 
     if !dns_path.exists() {
         return Err("DNS config file not found".into());
@@ -246,19 +241,22 @@ pub async fn get_dns_config_content() -> CmdResult<String> {
     Ok(content)
 }
 
-/// 验证DNS配置文件
+/// 验证DNS配置文件，先逐个字段校验，再交给内核检查
 #[tauri::command]
 pub async fn validate_dns_config() -> CmdResult<(bool, String)> {
     use crate::{core::CoreManager, utils::dirs};
 
-    let app_dir = dirs::app_home_dir().map_err(|e| e.to_string())?;
-    let dns_path = app_dir.join("dns_config.yaml");
+    let dns_path = wrap_err!(dirs::dns_config_path())?;
     let dns_path_str = dns_path.to_str().unwrap_or_default();
 
     if !dns_path.exists() {
         return Ok((false, "DNS config file not found".to_string()));
     }
 
+    if let Err(err) = IDnsConfig::load() {
+        return Ok((false, err.to_string()));
+    }
+
     match CoreManager::global()
         .validate_config_file(dns_path_str, None)
         .await
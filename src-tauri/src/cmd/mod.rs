@@ -15,6 +15,7 @@ pub mod runtime;
 pub mod save_profile;
 pub mod service;
 pub mod system;
+pub mod tproxy;
 pub mod uwp;
 pub mod validate;
 pub mod verge;
@@ -32,6 +33,7 @@ pub use runtime::*;
 pub use save_profile::*;
 pub use service::*;
 pub use system::*;
+pub use tproxy::*;
 pub use uwp::*;
 pub use validate::*;
 pub use verge::*;
@@ -1,5 +1,10 @@
 use super::CmdResult;
-use crate::core::{async_proxy_query::AsyncProxyQuery, EventDrivenProxyManager};
+use crate::core::{
+    async_proxy_query::AsyncProxyQuery, event_driven_proxy::ProxyGuardStatus,
+    EventDrivenProxyManager,
+};
+use crate::feat::{self, LanGatewayInfo};
+use crate::module::network_switch::NetworkSnapshot;
 use crate::wrap_err;
 use network_interface::NetworkInterface;
 use serde_yaml::Mapping;
@@ -44,6 +49,19 @@ pub async fn get_auto_proxy() -> CmdResult<Mapping> {
     Ok(map)
 }
 
+/// 获取系统代理守护的篡改记录
+#[tauri::command]
+pub fn get_proxy_guard_status() -> CmdResult<ProxyGuardStatus> {
+    Ok(EventDrivenProxyManager::global().guard_status())
+}
+
+/// 清空篡改记录并恢复守护
+#[tauri::command]
+pub fn reset_proxy_guard() -> CmdResult {
+    EventDrivenProxyManager::global().reset_guard();
+    Ok(())
+}
+
 /// 获取系统主机名
 #[tauri::command]
 pub fn get_system_hostname() -> CmdResult<String> {
@@ -93,3 +111,21 @@ pub fn get_network_interfaces_info() -> CmdResult<Vec<NetworkInterface>> {
 
     Ok(result)
 }
+
+/// 获取局域网设备接入本机代理需要的地址和端口
+#[tauri::command]
+pub fn get_lan_gateway_info() -> CmdResult<LanGatewayInfo> {
+    Ok(feat::lan_gateway_info())
+}
+
+/// 生成二维码 SVG
+#[tauri::command]
+pub fn get_qr_code_svg(text: String) -> CmdResult<String> {
+    wrap_err!(feat::qr_code_svg(&text))
+}
+
+/// 获取当前网络环境，用于配置自动切换规则
+#[tauri::command]
+pub async fn get_network_snapshot() -> CmdResult<NetworkSnapshot> {
+    Ok(NetworkSnapshot::collect().await)
+}
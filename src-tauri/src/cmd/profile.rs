@@ -1,7 +1,9 @@
 use super::CmdResult;
 use crate::{
-    config::{Config, IProfiles, PrfItem, PrfOption},
-    core::{handle, timer::Timer, tray::Tray, CoreManager},
+    config::{
+        Config, DeviceLimitError, IProfiles, PrfAuth, PrfGroup, PrfItem, PrfNetworkRule, PrfOption,
+    },
+    core::{handle, timer::Timer, CoreManager},
     feat, logging, ret_err,
     utils::{dirs, help, logging::Type},
     wrap_err,
@@ -10,30 +12,15 @@ use base64::{engine::general_purpose::STANDARD, Engine as _};
 use percent_encoding::percent_decode_str;
 use serde_yaml::Value;
 use std::collections::BTreeMap;
-use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
-use tokio::sync::{Mutex, RwLock};
 use url::Url;
 
-// 全局互斥锁防止并发配置更新
-static PROFILE_UPDATE_MUTEX: Mutex<()> = Mutex::const_new(());
-
-// 全局请求序列号跟踪，用于避免队列化执行
-static CURRENT_REQUEST_SEQUENCE: AtomicU64 = AtomicU64::new(0);
-
-static CURRENT_PROCESSING_PROFILE: RwLock<Option<String>> = RwLock::const_new(None);
-
-/// 清理配置处理状态
-async fn cleanup_processing_state(sequence: u64, reason: &str) {
-    *CURRENT_PROCESSING_PROFILE.write().await = None;
-    logging!(
-        info,
-        Type::Cmd,
-        true,
-        "{}，Cleanup status, serial number: {}",
-        reason,
-        sequence
-    );
+/// 设备数量超限时返回固定的错误，提示已由 `profile::device_limit` 通知显示
+fn profile_error(err: anyhow::Error) -> String {
+    match err.downcast_ref::<DeviceLimitError>() {
+        Some(_) => "profile::device_limit".into(),
+        None => err.to_string(),
+    }
 }
 
 /// 获取配置文件避免锁竞争
@@ -48,6 +35,8 @@ pub async fn get_profiles() -> CmdResult<IProfiles> {
             IProfiles {
                 current: latest.current.clone(),
                 items: latest.items.clone(),
+                groups: latest.groups.clone(),
+                network_rules: latest.network_rules.clone(),
             }
         }),
     )
@@ -75,6 +64,8 @@ pub async fn get_profiles() -> CmdResult<IProfiles> {
             IProfiles {
                 current: data.current.clone(),
                 items: data.items.clone(),
+                groups: data.groups.clone(),
+                network_rules: data.network_rules.clone(),
             }
         }),
     )
@@ -118,6 +109,7 @@ pub async fn get_profiles() -> CmdResult<IProfiles> {
             Ok(IProfiles {
                 current: None,
                 items: Some(vec![]),
+                ..IProfiles::default()
             })
         }
     }
@@ -134,47 +126,22 @@ pub async fn enhance_profiles() -> CmdResult {
 /// 导入配置文件
 #[tauri::command]
 pub async fn import_profile(url: String, option: Option<PrfOption>) -> CmdResult {
-    let existing_uid = {
-        let profiles = Config::profiles();
-        let profiles = profiles.latest();
-
-        profiles
-            .items
-            .as_ref()
-            .and_then(|items| items.iter().find(|item| item.url.as_deref() == Some(&url)))
-            .and_then(|item| item.uid.clone())
-    };
-
-    if let Some(uid) = existing_uid {
-        logging!(
-            info,
-            Type::Cmd,
-            true,
-            "The profile with URL {} already exists (UID: {}). Running the update...",
-            url,
-            uid
-        );
-        update_profile(uid, option).await
-    } else {
-        logging!(
-            info,
-            Type::Cmd,
-            true,
-            "Profile with URL {} not found. Create a new one...",
-            url
-        );
-        let item = wrap_err!(PrfItem::from_url(&url, None, None, option).await)?;
-        let new_uid = item.uid.clone().unwrap_or_default();
-        wrap_err!(Config::profiles().data().append_item(item))?;
-        if !new_uid.is_empty() {
-            let _ = patch_profiles_config(IProfiles {
-                current: Some(new_uid),
-                items: None,
-            })
-            .await?;
+    let imported = feat::import_profile(&url, None, option, true)
+        .await
+        .map_err(profile_error)?;
+    if let feat::ProfileImport::Created(uid) = imported {
+        if !uid.is_empty() {
+            let _ = wrap_err!(
+                feat::patch_profiles_config(IProfiles {
+                    current: Some(uid),
+                    items: None,
+                    ..IProfiles::default()
+                })
+                .await
+            )?;
         }
-        Ok(())
     }
+    Ok(())
 }
 
 /// 重新排序配置文件
@@ -194,6 +161,7 @@ pub async fn create_profile(item: PrfItem, file_data: Option<String>) -> CmdResu
         let _ = patch_profiles_config(IProfiles {
             current: Some(new_uid),
             items: None,
+            ..IProfiles::default()
         })
         .await?;
     }
@@ -203,7 +171,9 @@ pub async fn create_profile(item: PrfItem, file_data: Option<String>) -> CmdResu
 /// 更新配置文件
 #[tauri::command]
 pub async fn update_profile(index: String, option: Option<PrfOption>) -> CmdResult {
-    wrap_err!(feat::update_profile(index, option, Some(true)).await)
+    feat::update_profile(index, option, Some(true))
+        .await
+        .map_err(profile_error)
 }
 
 /// 删除配置文件
@@ -249,6 +219,8 @@ pub async fn delete_profile(index: String) -> CmdResult {
 
     // 删除后自动清理冗余文件
     let _ = Config::profiles().latest().auto_cleanup();
+    feat::remove_subscription_usage_history(&index);
+    feat::remove_profile_announcements(&index);
 
     if should_update {
         wrap_err!(CoreManager::global().update_config().await)?;
@@ -260,398 +232,7 @@ pub async fn delete_profile(index: String) -> CmdResult {
 /// 修改profiles的配置
 #[tauri::command]
 pub async fn patch_profiles_config(profiles: IProfiles) -> CmdResult<bool> {
-    // 为当前请求分配序列号
-    let current_sequence = CURRENT_REQUEST_SEQUENCE.fetch_add(1, Ordering::SeqCst) + 1;
-    let target_profile = profiles.current.clone();
-
-    logging!(
-        info,
-        Type::Cmd,
-        true,
-        "Starting to modify profiles, sequence: {}, target profile: {:?}",
-        current_sequence,
-        target_profile
-    );
-
-    let mutex_result =
-        tokio::time::timeout(Duration::from_millis(100), PROFILE_UPDATE_MUTEX.lock()).await;
-
-    let _guard = match mutex_result {
-        Ok(guard) => guard,
-        Err(_) => {
-            let latest_sequence = CURRENT_REQUEST_SEQUENCE.load(Ordering::SeqCst);
-            if current_sequence < latest_sequence {
-                logging!(
-                    info,
-                    Type::Cmd,
-                    true,
-                    "Newer request detected (seq: {} < {}), abandoning current",
-                    current_sequence,
-                    latest_sequence
-                );
-                return Ok(false);
-            }
-            logging!(
-                info,
-                Type::Cmd,
-                true,
-                "Force acquiring lock to process latest request: {}",
-                current_sequence
-            );
-            PROFILE_UPDATE_MUTEX.lock().await
-        }
-    };
-
-    let latest_sequence = CURRENT_REQUEST_SEQUENCE.load(Ordering::SeqCst);
-    if current_sequence < latest_sequence {
-        logging!(
-            info,
-            Type::Cmd,
-            true,
-            "After acquiring lock, found newer request (seq: {} < {}), abandoning current",
-            current_sequence,
-            latest_sequence
-        );
-        return Ok(false);
-    }
-
-    // 保存当前配置，以便在验证失败时恢复
-    let current_profile = Config::profiles().latest().current.clone();
-    logging!(info, Type::Cmd, true, "Current profile: {:?}", current_profile);
-
-    // 如果要切换配置，先检查目标配置文件是否有语法错误
-    if let Some(new_profile) = profiles.current.as_ref() {
-        if current_profile.as_ref() != Some(new_profile) {
-            logging!(info, Type::Cmd, true, "Switching to new profile: {}", new_profile);
-
-            // 获取目标配置文件路径
-            let config_file_result = {
-                let profiles_config = Config::profiles();
-                let profiles_data = profiles_config.latest();
-                match profiles_data.get_item(new_profile) {
-                    Ok(item) => {
-                        if let Some(file) = &item.file {
-                            let path = dirs::app_profiles_dir().map(|dir| dir.join(file));
-                            path.ok()
-                        } else {
-                            None
-                        }
-                    }
-                    Err(e) => {
-                        logging!(error, Type::Cmd, true, "Failed to get target profile info: {}", e);
-                        None
-                    }
-                }
-            };
-
-            // 如果获取到文件路径，检查YAML语法
-            if let Some(file_path) = config_file_result {
-                if !file_path.exists() {
-                    logging!(
-                        error,
-                        Type::Cmd,
-                        true,
-                        "Target profile does not exist: {}",
-                        file_path.display()
-                    );
-                    handle::Handle::notice_message(
-                        "config_validate::file_not_found",
-                        format!("{}", file_path.display()),
-                    );
-                    return Ok(false);
-                }
-
-                // 超时保护
-                let file_read_result = tokio::time::timeout(
-                    Duration::from_secs(5),
-                    tokio::fs::read_to_string(&file_path),
-                )
-                .await;
-
-                match file_read_result {
-                    Ok(Ok(content)) => {
-                        let yaml_parse_result = tokio::task::spawn_blocking(move || {
-                            serde_yaml::from_str::<serde_yaml::Value>(&content)
-                        })
-                        .await;
-
-                        match yaml_parse_result {
-                            Ok(Ok(_)) => {
-                                logging!(info, Type::Cmd, true, "Target profile file syntax is correct");
-                            }
-                            Ok(Err(err)) => {
-                                let error_msg = format!(" {err}");
-                                logging!(
-                                    error,
-                                    Type::Cmd,
-                                    true,
-                                    "YAML syntax error in target profile file: {}",
-                                    error_msg
-                                );
-                                handle::Handle::notice_message(
-                                    "config_validate::yaml_syntax_error",
-                                    &error_msg,
-                                );
-                                return Ok(false);
-                            }
-                            Err(join_err) => {
-                                let error_msg = format!("YAML parse task failed: {join_err}");
-                                logging!(error, Type::Cmd, true, "{}", error_msg);
-                                handle::Handle::notice_message(
-                                    "config_validate::yaml_parse_error",
-                                    &error_msg,
-                                );
-                                return Ok(false);
-                            }
-                        }
-                    }
-                    Ok(Err(err)) => {
-                        let error_msg = format!("Failed to read target profile file: {err}");
-                        logging!(error, Type::Cmd, true, "{}", error_msg);
-                        handle::Handle::notice_message(
-                            "config_validate::file_read_error",
-                            &error_msg,
-                        );
-                        return Ok(false);
-                    }
-                    Err(_) => {
-                        let error_msg = "Reading config file timed out (5s)".to_string();
-                        logging!(error, Type::Cmd, true, "{}", error_msg);
-                        handle::Handle::notice_message(
-                            "config_validate::file_read_timeout",
-                            &error_msg,
-                        );
-                        return Ok(false);
-                    }
-                }
-            }
-        }
-    }
-
-    // 检查请求有效性
-    let latest_sequence = CURRENT_REQUEST_SEQUENCE.load(Ordering::SeqCst);
-    if current_sequence < latest_sequence {
-        logging!(
-            info,
-            Type::Cmd,
-            true,
-            "Found newer request before core operation (seq: {} < {}), abandoning current",
-            current_sequence,
-            latest_sequence
-        );
-        return Ok(false);
-    }
-
-    if let Some(ref profile) = target_profile {
-        *CURRENT_PROCESSING_PROFILE.write().await = Some(profile.clone());
-        logging!(
-            info,
-            Type::Cmd,
-            true,
-            "Set current processing profile: {}, serial number: {}",
-            profile,
-            current_sequence
-        );
-    }
-
-    // 更新profiles配置
-    logging!(
-        info,
-        Type::Cmd,
-        true,
-        "Updating draft profiles, sequence: {}",
-        current_sequence
-    );
-
-    let current_value = profiles.current.clone();
-
-    let _ = Config::profiles().draft().patch_config(profiles);
-
-    // 在调用内核前再次验证请求有效性
-    let latest_sequence = CURRENT_REQUEST_SEQUENCE.load(Ordering::SeqCst);
-    if current_sequence < latest_sequence {
-        logging!(
-            info,
-            Type::Cmd,
-            true,
-            "Detect updated requests before kernel interaction (sequence number: {} < {}) and abandon the current request.",
-            current_sequence,
-            latest_sequence
-        );
-        Config::profiles().discard();
-        return Ok(false);
-    }
-
-    // 为配置更新添加超时保护
-    logging!(
-        info,
-        Type::Cmd,
-        true,
-        "Starting kernel config update, sequence: {}",
-        current_sequence
-    );
-    let update_result = tokio::time::timeout(
-        Duration::from_secs(30), // 30秒超时
-        CoreManager::global().update_config(),
-    )
-    .await;
-
-    // 更新配置并进行验证
-    match update_result {
-        Ok(Ok((true, _))) => {
-            // 内核操作完成后再次检查请求有效性
-            let latest_sequence = CURRENT_REQUEST_SEQUENCE.load(Ordering::SeqCst);
-            if current_sequence < latest_sequence {
-                logging!(
-                    info,
-                    Type::Cmd,
-                    true,
-                    "After kernel operation, an updated request was found (sequence number: {} < {}), ignore the current result.",
-                    current_sequence,
-                    latest_sequence
-                );
-                Config::profiles().discard();
-                return Ok(false);
-            }
-
-            logging!(
-                info,
-                Type::Cmd,
-                true,
-                "Configuration update successful, serial number: {}",
-                current_sequence
-            );
-            Config::profiles().apply();
-            handle::Handle::refresh_clash();
-
-            // 强制刷新代理缓存，确保profile切换后立即获取最新节点数据
-            crate::process::AsyncHandler::spawn(|| async move {
-                if let Err(e) = super::proxy::force_refresh_proxies().await {
-                    log::warn!(target: "app", "Force refresh proxy cache failed: {e}");
-                }
-            });
-
-            crate::process::AsyncHandler::spawn(|| async move {
-                if let Err(e) = Tray::global().update_tooltip() {
-                    log::warn!(target: "app", "Async tray tooltip update failed: {e}");
-                }
-
-                if let Err(e) = Tray::global().update_menu() {
-                    log::warn!(target: "app", "Async tray menu update failed: {e}");
-                }
-
-                // 保存配置文件
-                if let Err(e) = Config::profiles().data().save_file() {
-                    log::warn!(target: "app", "Async save profiles file failed: {e}");
-                }
-            });
-
-            // 立即通知前端配置变更
-            if let Some(current) = &current_value {
-                logging!(
-                    info,
-                    Type::Cmd,
-                    true,
-                    "Sending profile change event to frontend: {}, sequence: {}",
-                    current,
-                    current_sequence
-                );
-                handle::Handle::notify_profile_changed(current.clone());
-            }
-
-            cleanup_processing_state(current_sequence, "Profile switch completed").await;
-
-            Ok(true)
-        }
-        Ok(Ok((false, error_msg))) => {
-            logging!(warn, Type::Cmd, true, "Profile validation failed: {}", error_msg);
-            Config::profiles().discard();
-            // 如果验证失败，恢复到之前的配置
-            if let Some(prev_profile) = current_profile {
-                logging!(
-                    info,
-                    Type::Cmd,
-                    true,
-                    "Attempting to restore previous profile: {}",
-                    prev_profile
-                );
-                let restore_profiles = IProfiles {
-                    current: Some(prev_profile),
-                    items: None,
-                };
-                // 静默恢复，不触发验证
-                wrap_err!({ Config::profiles().draft().patch_config(restore_profiles) })?;
-                Config::profiles().apply();
-
-                crate::process::AsyncHandler::spawn(|| async move {
-                    if let Err(e) = Config::profiles().data().save_file() {
-                        log::warn!(target: "app", "Failed to save and restore configuration file asynchronously: {e}");
-                    }
-                });
-
-                logging!(info, Type::Cmd, true, "Successfully restored previous profile");
-            }
-
-            // 发送验证错误通知
-            handle::Handle::notice_message("config_validate::error", &error_msg);
-
-            cleanup_processing_state(current_sequence, "Profile validation failed").await;
-
-            Ok(false)
-        }
-        Ok(Err(e)) => {
-            logging!(
-                warn,
-                Type::Cmd,
-                true,
-                "Error occurred during update: {}, sequence: {}",
-                e,
-                current_sequence
-            );
-            Config::profiles().discard();
-            handle::Handle::notice_message("config_validate::boot_error", e.to_string());
-
-            cleanup_processing_state(current_sequence, "Update process error").await;
-
-            Ok(false)
-        }
-        Err(_) => {
-            // 超时处理
-            let timeout_msg = "Profile update timed out (30s), possibly due to validation or kernel communication";
-            logging!(
-                error,
-                Type::Cmd,
-                true,
-                "{}, sequence: {}",
-                timeout_msg,
-                current_sequence
-            );
-            Config::profiles().discard();
-
-            if let Some(prev_profile) = current_profile {
-                logging!(
-                    info,
-                    Type::Cmd,
-                    true,
-                    "After timeout, attempting to restore previous profile: {}, sequence: {}",
-                    prev_profile,
-                    current_sequence
-                );
-                let restore_profiles = IProfiles {
-                    current: Some(prev_profile),
-                    items: None,
-                };
-                wrap_err!({ Config::profiles().draft().patch_config(restore_profiles) })?;
-                Config::profiles().apply();
-            }
-
-            handle::Handle::notice_message("config_validate::timeout", timeout_msg);
-
-            cleanup_processing_state(current_sequence, "Profile update timeout").await;
-
-            Ok(false)
-        }
-    }
+    wrap_err!(feat::patch_profiles_config(profiles).await)
 }
 
 /// 根据profile name修改profiles
@@ -665,8 +246,9 @@ pub async fn patch_profiles_config_by_profile_index(
     let profiles = IProfiles {
         current: Some(profile_index),
         items: None,
+        ..IProfiles::default()
     };
-    patch_profiles_config(profiles).await
+    wrap_err!(feat::patch_profiles_config(profiles).await)
 }
 
 /// 修改某个profile item的
@@ -702,6 +284,33 @@ pub fn patch_profile(index: String, profile: PrfItem) -> CmdResult {
     Ok(())
 }
 
+/// 获取订阅的自定义请求头和认证信息
+#[tauri::command]
+pub fn get_profile_auth(index: String) -> CmdResult<Option<PrfAuth>> {
+    let profiles = Config::profiles();
+    let profiles = profiles.latest();
+    let item = wrap_err!(profiles.get_item(&index))?;
+    Ok(item.option.as_ref().and_then(|o| o.auth.clone()))
+}
+
+/// 修改订阅的自定义请求头和认证信息
+#[tauri::command]
+pub fn patch_profile_auth(index: String, auth: Option<PrfAuth>) -> CmdResult {
+    wrap_err!(Config::profiles().data().patch_item_auth(&index, auth))
+}
+
+/// 修改订阅分组
+#[tauri::command]
+pub fn patch_profile_groups(groups: Vec<PrfGroup>) -> CmdResult {
+    wrap_err!(Config::profiles().data().patch_groups(groups))
+}
+
+/// 修改按网络自动切换的规则
+#[tauri::command]
+pub fn patch_network_rules(rules: Vec<PrfNetworkRule>) -> CmdResult {
+    wrap_err!(Config::profiles().data().patch_network_rules(rules))
+}
+
 /// 查看配置文件
 #[tauri::command]
 pub fn view_profile(app_handle: tauri::AppHandle, index: String) -> CmdResult {
@@ -738,6 +347,24 @@ pub fn get_next_update_time(uid: String) -> CmdResult<Option<i64>> {
     Ok(next_time)
 }
 
+/// 获取订阅提供方的公告
+#[tauri::command]
+pub fn get_announcements() -> CmdResult<Vec<feat::Announcement>> {
+    Ok(feat::get_announcements())
+}
+
+/// 标记公告为已读，id 为空时标记全部
+#[tauri::command]
+pub fn mark_announcement_read(id: Option<String>) -> CmdResult {
+    wrap_err!(feat::mark_announcement_read(id))
+}
+
+/// 获取订阅的流量历史
+#[tauri::command]
+pub fn get_profile_usage_history(uid: String) -> CmdResult<Vec<feat::UsageSample>> {
+    Ok(feat::get_subscription_usage_history(&uid))
+}
+
 #[tauri::command]
 pub async fn update_profiles_on_startup() -> CmdResult {
     logging!(
@@ -1,5 +1,7 @@
 use super::CmdResult;
-use crate::{core::handle, module::mihomo::MihomoManager, state::proxy::CmdProxyState};
+use crate::{
+    core::handle, feat, module::mihomo::MihomoManager, state::proxy::CmdProxyState, wrap_err,
+};
 use std::{
     sync::Mutex,
     time::{Duration, Instant},
@@ -46,23 +48,7 @@ pub async fn get_proxies() -> CmdResult<serde_json::Value> {
 /// 强制刷新代理缓存用于profile切换
 #[tauri::command]
 pub async fn force_refresh_proxies() -> CmdResult<serde_json::Value> {
-    let manager = MihomoManager::global();
-    let app_handle = handle::Handle::global().app_handle().unwrap();
-    let cmd_proxy_state = app_handle.state::<Mutex<CmdProxyState>>();
-
-    log::debug!(target: "app", "Force refresh proxy cache");
-
-    let proxies = manager.get_refresh_proxies().await?;
-
-    {
-        let mut state = cmd_proxy_state.lock().unwrap();
-        state.proxies = Box::new(proxies.clone());
-        state.need_refresh = false;
-        state.last_refresh_time = Instant::now();
-    }
-
-    log::debug!(target: "app", "Force refresh proxy cache completed");
-    Ok(proxies)
+    wrap_err!(feat::force_refresh_proxies().await)
 }
 
 #[tauri::command]
@@ -2,6 +2,7 @@ use super::CmdResult;
 use crate::{
     core::{service, CoreManager},
     utils::i18n::t,
+    wrap_err,
 };
 
 async fn execute_service_operation(
@@ -34,9 +35,16 @@ pub async fn reinstall_service() -> CmdResult {
     execute_service_operation(service::reinstall_service(), "Reinstall").await
 }
 
+/// 逐步修复服务，进度通过 `verge://service-repair-progress` 事件发送
 #[tauri::command]
-pub async fn repair_service() -> CmdResult {
-    execute_service_operation(service::force_reinstall_service(), "Repair").await
+pub async fn repair_service() -> CmdResult<service::ServiceHealth> {
+    wrap_err!(service::repair_service().await)
+}
+
+/// 获取服务的安装、连接、签名和版本状况
+#[tauri::command]
+pub async fn get_service_health() -> CmdResult<service::ServiceHealth> {
+    Ok(service::check_service_health().await)
 }
 
 #[tauri::command]
@@ -46,3 +54,21 @@ pub async fn is_service_available() -> CmdResult<bool> {
         .map(|_| true)
         .map_err(|e| e.to_string())
 }
+
+/// 获取服务模式下 core 最近的日志
+#[tauri::command]
+pub async fn get_service_core_logs(lines: Option<usize>) -> CmdResult<Vec<String>> {
+    wrap_err!(service::get_core_logs_by_service(lines.unwrap_or(200)).await)
+}
+
+/// 通过服务设置系统 DNS，servers 为空时恢复系统默认
+#[tauri::command]
+pub async fn set_service_dns(servers: Vec<String>) -> CmdResult {
+    wrap_err!(service::set_dns_by_service(servers).await)
+}
+
+/// 获取服务上报的运行状况
+#[tauri::command]
+pub async fn get_service_health_report() -> CmdResult<service::ServiceHealthReport> {
+    wrap_err!(service::get_service_health_report().await)
+}
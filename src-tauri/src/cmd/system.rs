@@ -1,7 +1,11 @@
 use super::CmdResult;
 use crate::{
-    core::{handle, CoreManager},
+    config::{Config, IVerge},
+    core::{core_caps, handle, CoreManager},
+    feat,
     module::sysinfo::PlatformSpecification,
+    utils::sys_info,
+    wrap_err,
 };
 use once_cell::sync::Lazy;
 use std::{
@@ -41,6 +45,35 @@ pub async fn get_system_info() -> CmdResult<String> {
     Ok(info)
 }
 
+/// 获取发送给订阅提供方的 HWID
+#[tauri::command]
+pub fn get_hwid() -> CmdResult<String> {
+    Ok(sys_info::get_hwid())
+}
+
+/// 重置 HWID，random 为真时生成新的随机值，否则恢复为机器 ID
+#[tauri::command]
+pub async fn reset_hwid(random: bool) -> CmdResult<String> {
+    let custom_hwid = if random {
+        wrap_err!(sys_info::generate_hwid())?
+    } else {
+        String::new()
+    };
+
+    wrap_err!(
+        feat::patch_verge(
+            IVerge {
+                custom_hwid: Some(custom_hwid),
+                ..IVerge::default()
+            },
+            false
+        )
+        .await
+    )?;
+
+    Ok(sys_info::get_hwid())
+}
+
 /// 获取当前内核运行模式
 #[tauri::command]
 pub async fn get_running_mode() -> Result<String, String> {
@@ -92,3 +125,44 @@ pub fn is_admin() -> CmdResult<bool> {
         Ok(false)
     }
 }
+
+/// 当前 core 副本的 capabilities 状态，仅 Linux 支持
+#[tauri::command]
+pub fn get_core_capability_state() -> CmdResult<core_caps::CoreCapState> {
+    let clash_core = Config::verge().latest().get_valid_clash_core();
+    Ok(core_caps::capability_state(&clash_core))
+}
+
+// 切换 capabilities 模式后重新选择运行模式
+async fn switch_core_capabilities(enable: bool) -> CmdResult {
+    wrap_err!(
+        feat::patch_verge(
+            IVerge {
+                enable_core_capabilities: Some(enable),
+                ..IVerge::default()
+            },
+            false
+        )
+        .await
+    )?;
+    wrap_err!(CoreManager::global().stop_core().await)?;
+    wrap_err!(CoreManager::global().start_core().await)?;
+    handle::Handle::refresh_verge();
+    Ok(())
+}
+
+/// 为 core 副本授予 TUN 所需的 capabilities，之后 Sidecar 模式即可使用 TUN
+#[tauri::command]
+pub async fn grant_core_capabilities() -> CmdResult {
+    let clash_core = Config::verge().latest().get_valid_clash_core();
+    wrap_err!(core_caps::grant_capabilities(&clash_core))?;
+    switch_core_capabilities(true).await
+}
+
+/// 删除 core 副本，恢复使用服务运行 TUN
+#[tauri::command]
+pub async fn revoke_core_capabilities() -> CmdResult {
+    let clash_core = Config::verge().latest().get_valid_clash_core();
+    switch_core_capabilities(false).await?;
+    wrap_err!(core_caps::revoke_capabilities(&clash_core))
+}
@@ -0,0 +1,28 @@
+use super::CmdResult;
+
+/// Platform-specific implementation for transparent proxy rules
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::CmdResult;
+    use crate::{core::tproxy, wrap_err};
+
+    pub fn get_transparent_proxy_script() -> CmdResult<String> {
+        wrap_err!(tproxy::dry_run())
+    }
+}
+
+/// Stub implementation for non-Linux platforms
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::CmdResult;
+
+    pub fn get_transparent_proxy_script() -> CmdResult<String> {
+        Err("Transparent proxy rules are only supported on Linux".into())
+    }
+}
+
+/// 返回将要应用的 nftables 规则和策略路由脚本，不做任何修改
+#[tauri::command]
+pub fn get_transparent_proxy_script() -> CmdResult<String> {
+    platform::get_transparent_proxy_script()
+}
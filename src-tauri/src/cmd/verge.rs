@@ -14,3 +14,9 @@ pub fn get_verge_config() -> CmdResult<IVergeResponse> {
 pub async fn patch_verge_config(payload: IVerge) -> CmdResult {
     wrap_err!(feat::patch_verge(payload, false).await)
 }
+
+/// 重新生成本地控制接口的访问令牌
+#[tauri::command]
+pub async fn regenerate_control_api_token() -> CmdResult<String> {
+    wrap_err!(feat::regenerate_control_api_token().await)
+}
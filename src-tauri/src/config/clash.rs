@@ -1,8 +1,9 @@
-use crate::utils::{dirs, help};
-use anyhow::Result;
+use crate::utils::{cidr::IpCidr, dirs, help};
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 use serde_yaml::{Mapping, Value};
 use std::{
+    collections::BTreeMap,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     str::FromStr,
 };
@@ -87,7 +88,11 @@ impl IClashTemp {
         let mixed_port = Self::guard_mixed_port(&config);
         let socks_port = Self::guard_socks_port(&config);
         let port = Self::guard_port(&config);
-        let ctrl = Self::guard_server_ctrl(&config);
+        let mut ctrl = Self::guard_server_ctrl(&config);
+        if Self::is_exposed_ctrl(&ctrl) && !Self::has_secret(&config) {
+            log::warn!(target: "app", "external-controller {ctrl} has no secret, fallback to localhost");
+            ctrl = Self::guard_client_ctrl(&config);
+        }
         #[cfg(not(target_os = "windows"))]
         config.insert("redir-port".into(), redir_port.into());
         #[cfg(target_os = "linux")]
@@ -133,6 +138,62 @@ impl IClashTemp {
         )
     }
 
+    /// 校验局域网相关的字段，控制器监听所有地址时必须设置 secret
+    pub fn validate(&self) -> Result<()> {
+        let config = &self.0;
+        let ctrl = Self::guard_server_ctrl(config);
+        if Self::is_exposed_ctrl(&ctrl) && !Self::has_secret(config) {
+            bail!("external-controller {ctrl} listens on all addresses, a secret is required");
+        }
+
+        if let Some(bind) = config.get("bind-address") {
+            let bind = bind.as_str().unwrap_or_default();
+            if bind != "*" && bind.parse::<IpAddr>().is_err() {
+                bail!("invalid bind-address \"{bind}\"");
+            }
+        }
+        for key in ["lan-allowed-ips", "lan-disallowed-ips"] {
+            for item in Self::string_list(config, key)? {
+                item.parse::<IpCidr>()?;
+            }
+        }
+        for user in Self::string_list(config, "authentication")? {
+            match user.split_once(':') {
+                Some((name, pass)) if !name.is_empty() && !pass.is_empty() => {}
+                _ => bail!("authentication entry \"{user}\" must be user:pass"),
+            }
+        }
+        Ok(())
+    }
+
+    /// 读取字符串列表字段，不存在时为空
+    pub fn string_list(config: &Mapping, key: &str) -> Result<Vec<String>> {
+        let Some(value) = config.get(key) else {
+            return Ok(vec![]);
+        };
+        let Some(items) = value.as_sequence() else {
+            bail!("{key} must be a list");
+        };
+        items
+            .iter()
+            .map(|item| match item.as_str() {
+                Some(item) => Ok(item.to_string()),
+                None => bail!("{key} must be a list of strings"),
+            })
+            .collect()
+    }
+
+    fn has_secret(config: &Mapping) -> bool {
+        config
+            .get("secret")
+            .and_then(|secret| secret.as_str())
+            .is_some_and(|secret| !secret.trim().is_empty() && secret != "set-your-secret")
+    }
+
+    fn is_exposed_ctrl(ctrl: &str) -> bool {
+        SocketAddr::from_str(ctrl).is_ok_and(|addr| addr.ip().is_unspecified())
+    }
+
     pub fn get_mixed_port(&self) -> u16 {
         Self::guard_mixed_port(&self.0)
     }
@@ -355,6 +416,34 @@ fn test_clash_info() {
     );
 }
 
+#[test]
+fn test_validate_lan() {
+    fn validate(yaml: &str) -> Result<()> {
+        IClashTemp(serde_yaml::from_str(yaml).unwrap()).validate()
+    }
+
+    assert!(validate("external-controller: 127.0.0.1:9097").is_ok());
+    assert!(validate("external-controller: 0.0.0.0:9097").is_err());
+    assert!(validate("external-controller: 0.0.0.0:9097\nsecret: set-your-secret").is_err());
+    assert!(validate("external-controller: '[::]:9097'\nsecret: s3cret").is_ok());
+
+    assert!(validate("bind-address: '*'").is_ok());
+    assert!(validate("bind-address: 192.168.1.2").is_ok());
+    assert!(validate("bind-address: lan").is_err());
+    assert!(validate("lan-allowed-ips: [192.168.0.0/16, 'fd00::/8']").is_ok());
+    assert!(validate("lan-disallowed-ips: [192.168.0.0/33]").is_err());
+    assert!(validate("authentication: ['user:pass']").is_ok());
+    assert!(validate("authentication: ['user']").is_err());
+
+    let mut map = Mapping::new();
+    map.insert("external-controller".into(), "0.0.0.0:9097".into());
+    let guarded = IClashTemp::guard(map);
+    assert_eq!(
+        guarded.get("external-controller").and_then(|v| v.as_str()),
+        Some("127.0.0.1:9097")
+    );
+}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct IClashExternalControllerCors {
@@ -370,6 +459,10 @@ pub struct IClash {
     pub log_level: Option<String>,
     pub ipv6: Option<bool>,
     pub mode: Option<String>,
+    pub bind_address: Option<String>,
+    pub lan_allowed_ips: Option<Vec<String>>,
+    pub lan_disallowed_ips: Option<Vec<String>>,
+    pub authentication: Option<Vec<String>>,
     pub external_controller: Option<String>,
     pub secret: Option<String>,
     pub dns: Option<IClashDNS>,
@@ -391,24 +484,80 @@ pub struct IClashTUN {
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct IClashDNS {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub enable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub listen: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefer_h3: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub default_nameserver: Option<Vec<String>>,
+    /// fake-ip / redir-host / normal
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub enhanced_mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub fake_ip_range: Option<String>,
-    pub use_hosts: Option<bool>,
+    /// blacklist / whitelist / rule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fake_ip_filter_mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub fake_ip_filter: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub respect_rules: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_hosts: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_system_hosts: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub nameserver: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub fallback: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub fallback_filter: Option<IClashFallbackFilter>,
-    pub nameserver_policy: Option<Vec<String>>,
+    /// 域名（可用逗号分隔多个）到 DNS 服务器的映射
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nameserver_policy: Option<BTreeMap<String, IClashOneOrMany>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_server_nameserver: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direct_nameserver: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direct_nameserver_follow_policy: Option<bool>,
+    /// 没有单独建模的内核字段，原样保留
+    #[serde(flatten)]
+    pub extra: Mapping,
+}
+
+/// 配置中既可以写成单个字符串也可以写成列表的值
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum IClashOneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl IClashOneOrMany {
+    pub fn items(&self) -> &[String] {
+        match self {
+            Self::One(item) => std::slice::from_ref(item),
+            Self::Many(items) => items,
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct IClashFallbackFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub geoip: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub geoip_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ipcidr: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub domain: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Mapping,
 }
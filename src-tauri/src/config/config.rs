@@ -1,4 +1,4 @@
-use super::{Draft, IClashTemp, IProfiles, IRuntime, IVerge};
+use super::{generate_pac, Draft, IClashTemp, IProfiles, IRuntime, IVerge};
 use crate::{
     config::PrfItem,
     core::{handle, CoreManager},
@@ -148,11 +148,13 @@ impl Config {
     /// 生成订阅存好
     pub async fn generate() -> Result<()> {
         let (config, exists_keys, logs) = enhance::enhance().await;
+        let pac = generate_pac(&config);
 
         *Config::runtime().draft() = Box::new(IRuntime {
             config: Some(config),
             exists_keys,
             chain_logs: logs,
+            pac: Some(pac),
         });
 
         Ok(())
@@ -0,0 +1,558 @@
+use super::{IClashDNS, IClashFallbackFilter, IClashOneOrMany};
+use crate::utils::{cidr::IpCidr, dirs, help};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Mapping;
+use std::{collections::BTreeMap, net::IpAddr};
+
+/// DNS 服务器支持的协议
+const NAMESERVER_SCHEMES: &[&str] = &[
+    "udp", "tcp", "tls", "https", "quic", "h3", "dhcp", "system", "rcode",
+];
+
+/// 系统 hosts 中的默认条目，不导入
+const SYSTEM_HOSTNAMES: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "ip6-localnet",
+    "ip6-mcastprefix",
+    "ip6-allnodes",
+    "ip6-allrouters",
+    "ip6-allhosts",
+];
+
+/// `dns_config.yaml`，启用 DNS 覆写时替换订阅中的 `dns` 和 `hosts`
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct IDnsConfig {
+    #[serde(default)]
+    pub dns: IClashDNS,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hosts: Option<BTreeMap<String, IClashOneOrMany>>,
+    /// 其他顶层字段，保存时原样写回
+    #[serde(flatten)]
+    pub extra: Mapping,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct HostsConflict {
+    pub domain: String,
+    pub current: Vec<String>,
+    pub system: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct HostsImportReport {
+    pub imported: Vec<String>,
+    pub unchanged: Vec<String>,
+    /// 已存在且值不同的域名，覆盖时同样会列出
+    pub conflicts: Vec<HostsConflict>,
+    /// 无法解析的行和不支持的域名
+    pub skipped: Vec<String>,
+}
+
+impl IDnsConfig {
+    pub fn template() -> Self {
+        let list = |items: &[&str]| -> Option<Vec<String>> {
+            Some(items.iter().map(|s| s.to_string()).collect())
+        };
+
+        Self {
+            dns: IClashDNS {
+                enable: Some(true),
+                listen: Some(":53".into()),
+                enhanced_mode: Some("fake-ip".into()),
+                fake_ip_range: Some("198.18.0.1/16".into()),
+                fake_ip_filter_mode: Some("blacklist".into()),
+                prefer_h3: Some(false),
+                respect_rules: Some(false),
+                use_hosts: Some(false),
+                use_system_hosts: Some(false),
+                fake_ip_filter: list(&[
+                    "*.lan",
+                    "*.local",
+                    "*.arpa",
+                    "time.*.com",
+                    "ntp.*.com",
+                    "+.market.xiaomi.com",
+                    "localhost.ptlogin2.qq.com",
+                    "*.msftncsi.com",
+                    "www.msftconnecttest.com",
+                ]),
+                default_nameserver: list(&["system", "8.8.8.8", "1.1.1.1", "2001:4860:4860::8888"]),
+                nameserver: list(&[
+                    "8.8.8.8",
+                    "https://doh.pub/dns-query",
+                    "https://dns.google/dns-query",
+                    "https://cloudflare-dns.com/dns-query",
+                ]),
+                fallback: Some(vec![]),
+                nameserver_policy: Some(BTreeMap::new()),
+                proxy_server_nameserver: list(&[
+                    "https://doh.pub/dns-query",
+                    "https://dns.google/dns-query",
+                    "https://cloudflare-dns.com/dns-query",
+                    "tls://1.1.1.1",
+                ]),
+                direct_nameserver: Some(vec![]),
+                direct_nameserver_follow_policy: Some(false),
+                fallback_filter: Some(IClashFallbackFilter {
+                    geoip: Some(true),
+                    geoip_code: Some("CN".into()),
+                    ipcidr: list(&["240.0.0.0/4", "0.0.0.0/32"]),
+                    domain: list(&["+.google.com", "+.facebook.com", "+.youtube.com"]),
+                    ..IClashFallbackFilter::default()
+                }),
+                ..IClashDNS::default()
+            },
+            hosts: Some(BTreeMap::new()),
+            extra: Mapping::new(),
+        }
+    }
+
+    /// 读取并校验 DNS 配置
+    pub fn load() -> Result<Self> {
+        let config: Self = help::read_yaml(&dirs::dns_config_path()?)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        help::save_yaml(
+            &dirs::dns_config_path()?,
+            self,
+            Some("# Koala Clash DNS Config"),
+        )
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.dns.validate()?;
+
+        for (domain, values) in self.hosts.iter().flatten() {
+            check_entry("hosts", domain, validate_domain)?;
+            for value in values.items() {
+                // 值可以是 IP 或者另一个域名
+                check_entry(&format!("hosts.{domain}"), value, |value| {
+                    match value.parse::<IpAddr>() {
+                        Ok(_) => Ok(()),
+                        Err(_) => validate_domain(value),
+                    }
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 用 DNS 配置替换订阅中的 `dns`，并在设置了 hosts 时替换 `hosts`
+    pub fn apply(&self, config: &mut Mapping) -> Result<()> {
+        config.insert("dns".into(), serde_yaml::to_value(&self.dns)?);
+        if let Some(hosts) = &self.hosts {
+            config.insert("hosts".into(), serde_yaml::to_value(hosts)?);
+        }
+        Ok(())
+    }
+
+    /// 合并系统 hosts，`overwrite` 时冲突的条目使用系统的值
+    pub fn import_hosts(&mut self, content: &str, overwrite: bool) -> HostsImportReport {
+        let (entries, skipped) = parse_hosts(content);
+        let mut report = HostsImportReport {
+            skipped,
+            ..HostsImportReport::default()
+        };
+        let hosts = self.hosts.get_or_insert_with(BTreeMap::new);
+
+        for (domain, ips) in entries {
+            let system = match ips.as_slice() {
+                [ip] => IClashOneOrMany::One(ip.clone()),
+                _ => IClashOneOrMany::Many(ips.clone()),
+            };
+            match hosts.get(&domain) {
+                None => {
+                    hosts.insert(domain.clone(), system);
+                    report.imported.push(domain);
+                }
+                Some(current) if current.items() == ips.as_slice() => {
+                    report.unchanged.push(domain);
+                }
+                Some(current) => {
+                    report.conflicts.push(HostsConflict {
+                        domain: domain.clone(),
+                        current: current.items().to_vec(),
+                        system: ips,
+                    });
+                    if overwrite {
+                        hosts.insert(domain, system);
+                    }
+                }
+            }
+        }
+        report
+    }
+}
+
+/// 解析 hosts 文件，同一域名的多个地址合并，返回 (条目, 跳过的内容)
+fn parse_hosts(content: &str) -> (BTreeMap<String, Vec<String>>, Vec<String>) {
+    let mut entries: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut skipped = vec![];
+
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut fields = line.split_whitespace();
+        let Some(ip) = fields.next() else {
+            continue;
+        };
+        // 带接口的地址（fe80::1%lo0）mihomo 不支持
+        if ip.parse::<IpAddr>().is_err() {
+            skipped.push(line.to_string());
+            continue;
+        }
+
+        for name in fields {
+            let name = name.trim_end_matches('.').to_ascii_lowercase();
+            if SYSTEM_HOSTNAMES.contains(&name.as_str()) {
+                continue;
+            }
+            if name.contains(['*', ':']) || validate_domain(&name).is_err() {
+                skipped.push(name);
+                continue;
+            }
+            let ips = entries.entry(name).or_default();
+            if !ips.iter().any(|item| item == ip) {
+                ips.push(ip.to_string());
+            }
+        }
+    }
+    (entries, skipped)
+}
+
+impl IClashDNS {
+    /// 逐个字段校验，错误信息中包含字段名和出错的条目
+    pub fn validate(&self) -> Result<()> {
+        if let Some(listen) = &self.listen {
+            check_entry("listen", listen, validate_listen)?;
+        }
+        if let Some(mode) = &self.enhanced_mode {
+            check_entry("enhanced-mode", mode, |mode| {
+                match ["fake-ip", "redir-host", "normal"].contains(&mode) {
+                    true => Ok(()),
+                    false => bail!("expected fake-ip, redir-host or normal"),
+                }
+            })?;
+        }
+        if let Some(range) = &self.fake_ip_range {
+            check_entry("fake-ip-range", range, |range| {
+                range.parse::<IpCidr>().map(|_| ())
+            })?;
+        }
+
+        let filter_mode = self.fake_ip_filter_mode.as_deref().unwrap_or("blacklist");
+        check_entry("fake-ip-filter-mode", filter_mode, |mode| {
+            match ["blacklist", "whitelist", "rule"].contains(&mode) {
+                true => Ok(()),
+                false => bail!("expected blacklist, whitelist or rule"),
+            }
+        })?;
+        // rule 模式下的条目是规则而不是域名
+        if filter_mode != "rule" {
+            check_each("fake-ip-filter", &self.fake_ip_filter, validate_domain)?;
+        }
+
+        // default-nameserver 用于解析其他 DNS 服务器的域名，只能使用 IP
+        check_each("default-nameserver", &self.default_nameserver, |server| {
+            validate_nameserver(server, true)
+        })?;
+        for (field, servers) in [
+            ("nameserver", &self.nameserver),
+            ("fallback", &self.fallback),
+            ("proxy-server-nameserver", &self.proxy_server_nameserver),
+            ("direct-nameserver", &self.direct_nameserver),
+        ] {
+            check_each(field, servers, |server| validate_nameserver(server, false))?;
+        }
+
+        for (domains, servers) in self.nameserver_policy.iter().flatten() {
+            for domain in domains.split(',') {
+                check_entry("nameserver-policy", domain.trim(), validate_domain)?;
+            }
+            let field = format!("nameserver-policy.{domains}");
+            for server in servers.items() {
+                check_entry(&field, server, |server| validate_nameserver(server, false))?;
+            }
+        }
+
+        if let Some(filter) = &self.fallback_filter {
+            check_each("fallback-filter.ipcidr", &filter.ipcidr, |cidr| {
+                cidr.parse::<IpCidr>().map(|_| ())
+            })?;
+            check_each("fallback-filter.domain", &filter.domain, validate_domain)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn check_entry(field: &str, entry: &str, check: impl Fn(&str) -> Result<()>) -> Result<()> {
+    check(entry).map_err(|err| anyhow!("dns {field}: invalid entry \"{entry}\", {err}"))
+}
+
+fn check_each(
+    field: &str,
+    entries: &Option<Vec<String>>,
+    check: impl Fn(&str) -> Result<()>,
+) -> Result<()> {
+    entries
+        .iter()
+        .flatten()
+        .try_for_each(|entry| check_entry(field, entry, &check))
+}
+
+/// `:53`、`127.0.0.1:53` 或 `[::]:53`
+fn validate_listen(listen: &str) -> Result<()> {
+    let Some((host, port)) = listen.rsplit_once(':') else {
+        bail!("expected host:port");
+    };
+    if port.parse::<u16>().is_err() {
+        bail!("invalid port \"{port}\"");
+    }
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if !host.is_empty() && host.parse::<IpAddr>().is_err() {
+        bail!("invalid listen address \"{host}\"");
+    }
+    Ok(())
+}
+
+/// 支持 `8.8.8.8`、`tls://1.1.1.1:853`、`https://dns.google/dns-query#PROXY` 等写法
+fn validate_nameserver(server: &str, require_ip: bool) -> Result<()> {
+    // `#` 后面是出站代理或附加参数
+    let server = server.split('#').next().unwrap_or_default().trim();
+    if server == "system" || server.parse::<IpAddr>().is_ok() {
+        return Ok(());
+    }
+
+    let url = match server.contains("://") {
+        true => url::Url::parse(server),
+        false => url::Url::parse(&format!("udp://{server}")),
+    }
+    .map_err(|err| anyhow!("{err}"))?;
+
+    let scheme = url.scheme();
+    if !NAMESERVER_SCHEMES.contains(&scheme) {
+        bail!("unsupported scheme \"{scheme}\"");
+    }
+    if matches!(scheme, "system" | "rcode") {
+        return Ok(());
+    }
+
+    let Some(host) = url.host_str().filter(|host| !host.is_empty()) else {
+        bail!("missing host");
+    };
+    // dhcp 的 host 是网卡名
+    if require_ip && scheme != "dhcp" {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.parse::<IpAddr>().is_err() {
+            bail!("must use an ip address");
+        }
+    }
+    Ok(())
+}
+
+/// 支持 `+.example.com`、`*.lan`、`time.*.com`、`geosite:cn`、`rule-set:name` 等写法
+fn validate_domain(pattern: &str) -> Result<()> {
+    if let Some((prefix, name)) = pattern.split_once(':') {
+        if matches!(prefix, "geosite" | "rule-set") && !name.is_empty() {
+            return Ok(());
+        }
+    }
+
+    let domain = pattern
+        .strip_prefix("+.")
+        .or_else(|| pattern.strip_prefix('.'))
+        .unwrap_or(pattern);
+    if domain.is_empty() || domain.len() > 253 {
+        bail!("invalid domain length");
+    }
+    for label in domain.split('.') {
+        if label == "*" {
+            continue;
+        }
+        let valid_char = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+        if label.is_empty() || label.len() > 63 || !label.chars().all(valid_char) {
+            bail!("invalid domain label \"{label}\"");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_is_valid() {
+        IDnsConfig::template().validate().unwrap();
+    }
+
+    #[test]
+    fn test_nameserver() {
+        for server in [
+            "8.8.8.8",
+            "2001:4860:4860::8888",
+            "1.1.1.1:53",
+            "tls://1.1.1.1:853",
+            "https://dns.google/dns-query#PROXY",
+            "quic://dns.adguard.com:784",
+            "dhcp://en0",
+            "system://",
+            "rcode://success",
+        ] {
+            assert!(validate_nameserver(server, false).is_ok(), "{server}");
+        }
+
+        assert!(validate_nameserver("ftp://1.1.1.1", false).is_err());
+        assert!(validate_nameserver("https://", false).is_err());
+        assert!(validate_nameserver("https://dns.google/dns-query", true).is_err());
+        assert!(validate_nameserver("tls://[2606:4700::1111]:853", true).is_ok());
+    }
+
+    #[test]
+    fn test_domain() {
+        for domain in [
+            "example.com",
+            "+.example.com",
+            "*.lan",
+            "time.*.com",
+            "geosite:cn",
+            "rule-set:private",
+        ] {
+            assert!(validate_domain(domain).is_ok(), "{domain}");
+        }
+        assert!(validate_domain("bad..com").is_err());
+        assert!(validate_domain("exa mple.com").is_err());
+        assert!(validate_domain("").is_err());
+    }
+
+    #[test]
+    fn test_field_errors() {
+        let config: IDnsConfig = serde_yaml::from_str(
+            r#"
+dns:
+  nameserver-policy:
+    "geosite:cn,+.lan": [https://doh.pub/dns-query, 223.5.5.5]
+    "+.corp.example": 10.0.0.1
+  fake-ip-range: 198.18.0.1/33
+hosts:
+  router.lan: 192.168.1.1
+"#,
+        )
+        .unwrap();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("fake-ip-range"), "{err}");
+
+        let mut config = IDnsConfig::template();
+        config.dns.listen = Some("localhost:53".into());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_keep_unknown_fields() {
+        let config: IDnsConfig = serde_yaml::from_str(
+            r#"
+dns:
+  enable: true
+  cache-algorithm: arc
+  fake-ip-range6: fdfe:dcba:9876::1/64
+  fallback-filter:
+    geoip: true
+    geosite: [gfw]
+"#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.dns.extra["cache-algorithm"], "arc");
+        let filter = config.dns.fallback_filter.as_ref().unwrap();
+        assert!(filter.extra.contains_key("geosite"));
+
+        let mut runtime = Mapping::new();
+        config.apply(&mut runtime).unwrap();
+        let dns = runtime["dns"].as_mapping().unwrap();
+        assert_eq!(dns["fake-ip-range6"], "fdfe:dcba:9876::1/64");
+        assert!(dns["fallback-filter"]["geosite"].is_sequence());
+    }
+
+    #[test]
+    fn test_round_trip_without_nulls() {
+        let config: IDnsConfig = serde_yaml::from_str(
+            r#"
+dns:
+  enable: true
+  fallback-filter:
+    geoip: true
+"#,
+        )
+        .unwrap();
+
+        let saved = serde_yaml::to_string(&config).unwrap();
+        assert!(!saved.contains("null"), "{saved}");
+        assert_eq!(serde_yaml::from_str::<IDnsConfig>(&saved).unwrap(), config);
+
+        let mut runtime = Mapping::new();
+        config.apply(&mut runtime).unwrap();
+        let applied = serde_yaml::to_string(&runtime).unwrap();
+        assert!(!applied.contains("null"), "{applied}");
+        let dns = runtime["dns"].as_mapping().unwrap();
+        assert!(!dns.contains_key("nameserver"));
+        assert!(!dns.contains_key("fake-ip-filter"));
+        assert!(!runtime.contains_key("hosts"));
+    }
+
+    #[test]
+    fn test_import_hosts() {
+        let content = r#"
+# comment
+127.0.0.1 localhost
+::1 localhost ip6-localhost ip6-loopback
+192.168.1.1 router.lan  # inline comment
+10.0.0.2 nas.lan NAS.lan
+10.0.0.3 nas.lan
+fe80::1%lo0 localhost
+not-an-ip example.com
+10.0.0.4 bad..name
+"#;
+        let mut config = IDnsConfig::template();
+        config.hosts = Some(BTreeMap::from([
+            (
+                "router.lan".into(),
+                IClashOneOrMany::One("192.168.0.1".into()),
+            ),
+            (
+                "nas.lan".into(),
+                IClashOneOrMany::Many(vec!["10.0.0.2".into(), "10.0.0.3".into()]),
+            ),
+        ]));
+
+        let report = config.clone().import_hosts(content, false);
+        assert!(report.imported.is_empty());
+        assert_eq!(report.unchanged, vec!["nas.lan".to_string()]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].domain, "router.lan");
+        assert_eq!(report.conflicts[0].system, vec!["192.168.1.1".to_string()]);
+        assert_eq!(report.skipped.len(), 3);
+
+        let mut overwritten = config.clone();
+        overwritten.import_hosts(content, true);
+        assert_eq!(
+            overwritten.hosts.unwrap()["router.lan"],
+            IClashOneOrMany::One("192.168.1.1".into())
+        );
+
+        config.hosts = None;
+        let report = config.import_hosts(content, false);
+        assert_eq!(
+            report.imported,
+            vec!["nas.lan".to_string(), "router.lan".to_string()]
+        );
+        config.validate().unwrap();
+    }
+}
@@ -93,3 +93,33 @@ where
         Err(_) => Ok(T::default()),
     }
 }
+
+/// Deserialize a value that is either encrypted or still in plain form,
+/// e.g. when it comes straight from the frontend
+pub fn deserialize_encrypted_or_plain<'a, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: for<'de> Deserialize<'de> + Default,
+    D: Deserializer<'a>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum EncryptedOrPlain<T> {
+        Encrypted(String),
+        Plain(T),
+    }
+
+    let encrypted = match EncryptedOrPlain::<T>::deserialize(deserializer) {
+        Ok(EncryptedOrPlain::Encrypted(s)) => s,
+        Ok(EncryptedOrPlain::Plain(value)) => return Ok(value),
+        Err(_) => return Ok(T::default()),
+    };
+
+    let decrypted_string = match decrypt_data(&encrypted) {
+        Ok(data) => data,
+        Err(_) => return Ok(T::default()),
+    };
+    match serde_json::from_str(&decrypted_string) {
+        Ok(value) => Ok(value),
+        Err(_) => Ok(T::default()),
+    }
+}
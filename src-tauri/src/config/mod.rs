@@ -1,15 +1,18 @@
 mod clash;
 #[allow(clippy::module_inception)]
 mod config;
+mod dns;
 mod draft;
 mod encrypt;
+mod pac;
 mod prfitem;
 mod profiles;
 mod runtime;
 mod verge;
 
 pub use self::{
-    clash::*, config::*, draft::*, encrypt::*, prfitem::*, profiles::*, runtime::*, verge::*,
+    clash::*, config::*, dns::*, draft::*, encrypt::*, pac::*, prfitem::*, profiles::*,
+    runtime::*, verge::*,
 };
 
 pub const DEFAULT_PAC: &str = r#"function FindProxyForURL(url, host) {
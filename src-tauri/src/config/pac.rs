@@ -0,0 +1,277 @@
+use crate::utils::cidr::IpCidr;
+use serde::Serialize;
+use serde_yaml::Mapping;
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr},
+};
+
+/// REJECT 规则指向一个不会有服务监听的地址，连接会立即失败
+const PAC_REJECT: &str = "PROXY 127.0.0.1:9";
+const PAC_PROXY: &str = "PROXY 127.0.0.1:%mixed-port%; SOCKS5 127.0.0.1:%mixed-port%; DIRECT";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PacAction {
+    Direct,
+    Proxy,
+    Reject,
+}
+
+impl PacAction {
+    fn from_target(target: &str) -> Self {
+        match target {
+            "DIRECT" => Self::Direct,
+            "REJECT" | "REJECT-DROP" => Self::Reject,
+            _ => Self::Proxy,
+        }
+    }
+
+    fn code(self) -> char {
+        match self {
+            Self::Direct => 'D',
+            Self::Proxy => 'P',
+            Self::Reject => 'R',
+        }
+    }
+}
+
+/// 规则按出现顺序编号，匹配多条时取编号最小的，与内核的匹配顺序一致
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PacRules {
+    actions: Vec<PacAction>,
+    exact: BTreeMap<String, usize>,
+    suffix: BTreeMap<String, usize>,
+    keyword: Vec<(String, usize)>,
+    /// (网络地址, 掩码, 编号)，PAC 的 `isInNet` 只支持 IPv4
+    cidr: Vec<(String, String, usize)>,
+    fallback: Option<PacAction>,
+}
+
+impl PacRules {
+    fn push(&mut self, target: &str) -> usize {
+        self.actions.push(PacAction::from_target(target));
+        self.actions.len() - 1
+    }
+
+    /// 只编译域名和 IPv4 CIDR 规则，GEOSITE、RULE-SET、逻辑规则等交给内核处理
+    ///
+    /// 遇到第一条无法编译的规则后停止，之后的流量全部交给内核，
+    /// 否则后面的 DIRECT 规则和 MATCH 会让这部分流量绕过内核
+    fn compile(config: &Mapping) -> Self {
+        let mut rules = Self::default();
+        let items = config
+            .get("rules")
+            .and_then(|rules| rules.as_sequence())
+            .into_iter()
+            .flatten()
+            .filter_map(|rule| rule.as_str());
+
+        for rule in items {
+            let parts: Vec<&str> = rule.split(',').map(str::trim).collect();
+            match parts.as_slice() {
+                ["MATCH" | "FINAL", target, ..] => {
+                    rules.fallback = Some(PacAction::from_target(target));
+                    break;
+                }
+                [kind @ ("DOMAIN" | "DOMAIN-SUFFIX" | "DOMAIN-KEYWORD"), payload, target, ..] => {
+                    let payload = payload.trim_start_matches('.').to_ascii_lowercase();
+                    if payload.is_empty() {
+                        continue;
+                    }
+                    let index = rules.push(target);
+                    match *kind {
+                        "DOMAIN" => {
+                            rules.exact.entry(payload).or_insert(index);
+                        }
+                        "DOMAIN-SUFFIX" => {
+                            rules.suffix.entry(payload).or_insert(index);
+                        }
+                        _ => rules.keyword.push((payload, index)),
+                    }
+                }
+                // 没有 no-resolve 时内核会先解析域名再匹配，PAC 中无法等价实现
+                ["IP-CIDR", payload, target, options @ ..] if options.contains(&"no-resolve") => {
+                    let Ok(cidr) = payload.parse::<IpCidr>() else {
+                        break;
+                    };
+                    let IpAddr::V4(network) = cidr.network().addr() else {
+                        break;
+                    };
+                    let mask = u32::MAX.checked_shl(32 - cidr.prefix() as u32).unwrap_or(0);
+                    let index = rules.push(target);
+                    rules
+                        .cidr
+                        .push((network.to_string(), Ipv4Addr::from(mask).to_string(), index));
+                }
+                _ => break,
+            }
+        }
+        rules
+    }
+
+    /// CIDR 只匹配 IP 形式的 host，避免在 PAC 中同步解析域名
+    fn render(&self) -> String {
+        let actions: String = self.actions.iter().map(|action| action.code()).collect();
+        let fallback = self.fallback.unwrap_or(PacAction::Proxy).code();
+
+        format!(
+            r#"// Generated by Koala Clash from the rules of the current profile
+var ACTIONS = {{ D: "DIRECT", P: "{PAC_PROXY}", R: "{PAC_REJECT}" }};
+var RULES = "{actions}";
+var FALLBACK = "{fallback}";
+var EXACT = {exact};
+var SUFFIX = {suffix};
+var KEYWORD = {keyword};
+var CIDR = {cidr};
+
+function FindProxyForURL(url, host) {{
+  host = host.toLowerCase();
+  var best = EXACT.hasOwnProperty(host) ? EXACT[host] : -1;
+  var suffix = host;
+  while (true) {{
+    if (SUFFIX.hasOwnProperty(suffix) && (best < 0 || SUFFIX[suffix] < best)) {{
+      best = SUFFIX[suffix];
+    }}
+    var dot = suffix.indexOf(".");
+    if (dot < 0) break;
+    suffix = suffix.substring(dot + 1);
+  }}
+  for (var i = 0; i < KEYWORD.length && (best < 0 || KEYWORD[i][1] < best); i++) {{
+    if (host.indexOf(KEYWORD[i][0]) >= 0) {{
+      best = KEYWORD[i][1];
+      break;
+    }}
+  }}
+  if (/^\d+\.\d+\.\d+\.\d+$/.test(host)) {{
+    for (var j = 0; j < CIDR.length && (best < 0 || CIDR[j][2] < best); j++) {{
+      if (isInNet(host, CIDR[j][0], CIDR[j][1])) {{
+        best = CIDR[j][2];
+        break;
+      }}
+    }}
+  }}
+  return ACTIONS[best < 0 ? FALLBACK : RULES.charAt(best)];
+}}
+"#,
+            exact = to_js(&self.exact),
+            suffix = to_js(&self.suffix),
+            keyword = to_js(&self.keyword),
+            cidr = to_js(&self.cidr),
+        )
+    }
+}
+
+fn to_js<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "null".into())
+}
+
+/// 由运行时配置的规则生成 PAC，`%mixed-port%` 在提供 PAC 时替换
+pub fn generate_pac(config: &Mapping) -> String {
+    PacRules::compile(config).render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_rules() {
+        let config: Mapping = serde_yaml::from_str(
+            r#"
+rules:
+  - DOMAIN,direct.example.com,Proxies
+  - DOMAIN-SUFFIX,example.com,DIRECT
+  - DOMAIN-SUFFIX,Example.com,REJECT
+  - DOMAIN-KEYWORD,ads,REJECT
+  - IP-CIDR,192.168.1.1/16,DIRECT,no-resolve
+  - MATCH,DIRECT
+  - DOMAIN,after.match.com,REJECT
+"#,
+        )
+        .unwrap();
+        let rules = PacRules::compile(&config);
+
+        assert_eq!(
+            rules.actions,
+            vec![
+                PacAction::Proxy,
+                PacAction::Direct,
+                PacAction::Reject,
+                PacAction::Reject,
+                PacAction::Direct,
+            ]
+        );
+        assert_eq!(rules.exact["direct.example.com"], 0);
+        assert_eq!(rules.suffix["example.com"], 1);
+        assert_eq!(rules.keyword, vec![("ads".to_string(), 3)]);
+        assert_eq!(
+            rules.cidr,
+            vec![("192.168.0.0".to_string(), "255.255.0.0".to_string(), 4)]
+        );
+        assert_eq!(rules.fallback, Some(PacAction::Direct));
+
+        let pac = rules.render();
+        assert!(pac.contains(r#"var RULES = "PDRRD";"#));
+        assert!(pac.contains(r#"var SUFFIX = {"example.com":1};"#));
+    }
+
+    #[test]
+    fn test_stop_at_opaque_rule() {
+        let config: Mapping = serde_yaml::from_str(
+            r#"
+rules:
+  - DOMAIN-SUFFIX,lan,DIRECT
+  - RULE-SET,x,Proxy
+  - DOMAIN-SUFFIX,example.com,DIRECT
+  - IP-CIDR,10.0.0.0/8,DIRECT
+  - MATCH,DIRECT
+"#,
+        )
+        .unwrap();
+        let rules = PacRules::compile(&config);
+
+        assert_eq!(rules.actions, vec![PacAction::Direct]);
+        assert_eq!(rules.suffix["lan"], 0);
+        assert!(rules.cidr.is_empty());
+        assert_eq!(rules.fallback, None);
+        assert!(rules.render().contains(r#"var FALLBACK = "P";"#));
+
+        for opaque in [
+            "RULE-SET,x,Proxy",
+            "GEOSITE,cn,DIRECT",
+            "GEOIP,CN,DIRECT",
+            "IP-CIDR6,fc00::/7,DIRECT",
+            "IP-CIDR,10.0.0.0/8,DIRECT",
+            "AND,((DOMAIN,a.com),(NETWORK,UDP)),DIRECT",
+        ] {
+            let config: Mapping =
+                serde_yaml::from_str(&format!("rules: ['{opaque}', 'MATCH,DIRECT']")).unwrap();
+            let rules = PacRules::compile(&config);
+            assert!(rules.actions.is_empty(), "{opaque}");
+            assert_eq!(rules.fallback, None, "{opaque}");
+        }
+    }
+
+    #[test]
+    fn test_cidr_requires_no_resolve() {
+        let config: Mapping = serde_yaml::from_str(
+            r#"
+rules:
+  - IP-CIDR,192.168.0.0/16,DIRECT,no-resolve
+  - IP-CIDR,10.0.0.0/8,DIRECT
+  - DOMAIN-SUFFIX,example.com,DIRECT
+  - MATCH,DIRECT
+"#,
+        )
+        .unwrap();
+        let rules = PacRules::compile(&config);
+
+        assert_eq!(rules.actions, vec![PacAction::Direct]);
+        assert_eq!(
+            rules.cidr,
+            vec![("192.168.0.0".to_string(), "255.255.0.0".to_string(), 0)]
+        );
+        assert!(rules.suffix.is_empty());
+        assert_eq!(rules.fallback, None);
+    }
+}
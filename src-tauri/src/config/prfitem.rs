@@ -1,17 +1,20 @@
-use crate::utils::{
-    dirs, help,
-    network::{NetworkManager, ProxyType},
-    tmpl,
+use crate::{
+    logging,
+    utils::{
+        dirs, help,
+        logging::Type,
+        network::{NetworkManager, ProxyType},
+        tmpl,
+    },
 };
 use anyhow::{bail, Context, Result};
 use base64::{engine::general_purpose::STANDARD, Engine as _};
-use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Mapping;
-use std::{fs, time::Duration};
+use std::{collections::BTreeMap, fs, time::Duration};
 use url::Url;
 
-use super::Config;
+use super::{deserialize_encrypted_or_plain, serialize_encrypted, Config};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct PrfItem {
@@ -72,6 +75,32 @@ pub struct PrfItem {
     pub file_data: Option<String>,
 }
 
+/// the provider refused the subscription because the device limit was reached
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceLimitError {
+    /// announce text sent by the provider
+    pub announce: String,
+
+    /// announce url sent by the provider
+    pub announce_url: Option<String>,
+}
+
+impl DeviceLimitError {
+    /// whether the announce text describes a device limit rejection
+    pub fn matches(announce: &str) -> bool {
+        let lower_msg = announce.to_lowercase();
+        lower_msg.contains("device") || lower_msg.contains("устройств")
+    }
+}
+
+impl std::fmt::Display for DeviceLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.announce)
+    }
+}
+
+impl std::error::Error for DeviceLimitError {}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct PrfSelected {
     pub name: Option<String>,
@@ -133,6 +162,46 @@ pub struct PrfOption {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub update_always: Option<bool>,
+
+    /// for `remote` profile
+    /// mirror urls tried when the main url fails
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirrors: Option<Vec<String>>,
+
+    /// for `remote` profile
+    /// race the main url and mirrors in parallel instead of trying them in order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirror_race: Option<bool>,
+
+    /// for `remote` profile
+    /// custom headers and credentials (encrypted)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted_or_plain",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub auth: Option<PrfAuth>,
+}
+
+/// custom headers and credentials sent with subscription requests
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PrfAuth {
+    /// extra http headers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<BTreeMap<String, String>>,
+
+    /// basic auth username
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// basic auth password
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    /// bearer token, takes precedence over basic auth
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bearer_token: Option<String>,
 }
 
 impl PrfOption {
@@ -154,6 +223,9 @@ impl PrfOption {
                 a.timeout_seconds = b.timeout_seconds.or(a.timeout_seconds);
                 a.use_hwid = b.use_hwid.or(a.use_hwid);
                 a.update_always = b.update_always.or(a.update_always);
+                a.mirrors = b.mirrors.or(a.mirrors);
+                a.mirror_race = b.mirror_race.or(a.mirror_race);
+                a.auth = b.auth.or(a.auth);
                 Some(a)
             }
             t => t.0.or(t.1),
@@ -161,6 +233,36 @@ impl PrfOption {
     }
 }
 
+/// 主地址在前，镜像地址按顺序排在后面，去掉空白和重复的地址
+fn mirror_candidates(url: &str, mirrors: Vec<String>) -> Vec<String> {
+    let mut candidates = vec![url.to_string()];
+    for mirror in mirrors {
+        let mirror = mirror.trim().to_string();
+        if !mirror.is_empty() && !candidates.contains(&mirror) {
+            candidates.push(mirror);
+        }
+    }
+    candidates
+}
+
+/// 成功的镜像提升为主地址，其余地址保持原顺序作为镜像；
+/// 主地址被 `new-sub-domain` 改写时，实际使用的地址排在镜像首位
+fn rotate_mirrors(candidates: Vec<String>, used_url: &str, final_url: &str) -> Option<Vec<String>> {
+    if candidates.len() <= 1 {
+        return None;
+    }
+    let mut mirrors = Vec::new();
+    if used_url != final_url {
+        mirrors.push(used_url.to_string());
+    }
+    mirrors.extend(
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate != used_url && candidate != final_url),
+    );
+    Some(mirrors)
+}
+
 impl PrfItem {
     /// From partial item
     /// must contain `itype`
@@ -205,6 +307,13 @@ impl PrfItem {
         let mut rules = opt_ref.and_then(|o| o.rules.clone());
         let mut proxies = opt_ref.and_then(|o| o.proxies.clone());
         let mut groups = opt_ref.and_then(|o| o.groups.clone());
+        let mirror_race = opt_ref.is_some_and(|o| o.mirror_race.unwrap_or(false));
+        let auth = opt_ref.and_then(|o| o.auth.clone());
+
+        let candidates = mirror_candidates(
+            url,
+            opt_ref.and_then(|o| o.mirrors.clone()).unwrap_or_default(),
+        );
 
         if merge.is_none() {
             let merge_item = PrfItem::from_merge(None)?;
@@ -293,14 +402,16 @@ impl PrfItem {
         };
 
         // 使用网络管理器发送请求
-        let resp = match NetworkManager::global()
-            .get_with_interrupt(
-                url,
+        let (resp, used_url) = match NetworkManager::global()
+            .get_from_mirrors(
+                &candidates,
+                mirror_race,
                 proxy_type,
                 Some(timeout),
                 user_agent.clone(),
                 accept_invalid_certs,
                 use_hwid,
+                auth.as_ref(),
             )
             .await
         {
@@ -311,19 +422,25 @@ impl PrfItem {
             }
         };
 
-        let status_code = resp.status();
-        if !StatusCode::is_success(&status_code) {
-            bail!("failed to fetch remote profile with status {status_code}")
+        if candidates.len() > 1 && used_url != url {
+            logging!(
+                info,
+                Type::Config,
+                true,
+                "[Subscription Update] Mirror rotation: {} -> {}",
+                url,
+                used_url
+            );
         }
 
         let header = resp.headers();
 
-        let mut final_url = url.to_string();
+        let mut final_url = used_url.clone();
 
         if let Some(new_domain_value) = header.get("new-sub-domain") {
             if let Ok(new_domain) = new_domain_value.to_str() {
                 if !new_domain.is_empty() {
-                    if let Ok(mut parsed_url) = Url::parse(url) {
+                    if let Ok(mut parsed_url) = Url::parse(&used_url) {
                         if parsed_url.set_host(Some(new_domain)).is_ok() {
                             final_url = parsed_url.to_string();
                             log::info!(target: "app", "URL host updated to -> {final_url}");
@@ -332,6 +449,7 @@ impl PrfItem {
                 }
             }
         }
+        let mirrors = rotate_mirrors(candidates, &used_url, &final_url);
 
         // parse the Subscription UserInfo
         let extra = match header.get("Subscription-Userinfo") {
@@ -368,7 +486,8 @@ impl PrfItem {
                 }
             }
             None => Some(
-                crate::utils::help::get_last_part_and_decode(url).unwrap_or("Remote File".into()),
+                crate::utils::help::get_last_part_and_decode(&used_url)
+                    .unwrap_or("Remote File".into()),
             ),
         };
         let update_interval = match update_interval {
@@ -418,13 +537,6 @@ impl PrfItem {
             None => None,
         };
 
-        if let Some(announce_msg) = &announce {
-            let lower_msg = announce_msg.to_lowercase();
-            if lower_msg.contains("device") || lower_msg.contains("устройств") {
-                bail!(announce_msg.clone());
-            }
-        }
-
         let announce_url = match header.get("announce-url") {
             Some(value) => {
                 let str_value = value.to_str().unwrap_or("");
@@ -433,6 +545,16 @@ impl PrfItem {
             None => None,
         };
 
+        if let Some(announce_msg) = &announce {
+            if DeviceLimitError::matches(announce_msg) {
+                return Err(DeviceLimitError {
+                    announce: announce_msg.clone(),
+                    announce_url,
+                }
+                .into());
+            }
+        }
+
         let profile_title = match header.get("profile-title") {
             Some(value) => {
                 let str_value = value.to_str().unwrap_or("");
@@ -521,6 +643,9 @@ impl PrfItem {
                 proxies,
                 groups,
                 use_hwid: Some(use_hwid),
+                mirrors,
+                mirror_race: if mirror_race { Some(true) } else { None },
+                auth,
                 ..PrfOption::default()
             }),
             home,
@@ -684,3 +809,67 @@ impl PrfItem {
         fs::write(path, data.as_bytes()).context("failed to save the file")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(list: &[&str]) -> Vec<String> {
+        list.iter().map(|url| url.to_string()).collect()
+    }
+
+    #[test]
+    fn test_mirror_candidates() {
+        let candidates = mirror_candidates(
+            "https://a.com/sub",
+            urls(&[
+                " https://b.com/sub ",
+                "",
+                "https://a.com/sub",
+                "https://c.com/sub",
+            ]),
+        );
+        assert_eq!(
+            candidates,
+            urls(&[
+                "https://a.com/sub",
+                "https://b.com/sub",
+                "https://c.com/sub"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_rotate_mirrors() {
+        let candidates = urls(&[
+            "https://a.com/sub",
+            "https://b.com/sub",
+            "https://c.com/sub",
+        ]);
+
+        assert_eq!(
+            rotate_mirrors(
+                urls(&["https://a.com/sub"]),
+                "https://a.com/sub",
+                "https://a.com/sub"
+            ),
+            None
+        );
+        assert_eq!(
+            rotate_mirrors(candidates.clone(), "https://a.com/sub", "https://a.com/sub"),
+            Some(urls(&["https://b.com/sub", "https://c.com/sub"]))
+        );
+        assert_eq!(
+            rotate_mirrors(candidates.clone(), "https://b.com/sub", "https://b.com/sub"),
+            Some(urls(&["https://a.com/sub", "https://c.com/sub"]))
+        );
+        assert_eq!(
+            rotate_mirrors(candidates, "https://b.com/sub", "https://d.com/sub"),
+            Some(urls(&[
+                "https://b.com/sub",
+                "https://a.com/sub",
+                "https://c.com/sub"
+            ]))
+        );
+    }
+}
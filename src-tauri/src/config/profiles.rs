@@ -1,5 +1,5 @@
-use super::{prfitem::PrfItem, PrfOption};
-use crate::utils::{dirs, help};
+use super::{prfitem::PrfItem, PrfAuth, PrfOption};
+use crate::utils::{cidr::IpCidr, dirs, help};
 use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_yaml::Mapping;
@@ -13,6 +13,106 @@ pub struct IProfiles {
 
     /// profile list
     pub items: Option<Vec<PrfItem>>,
+
+    /// user defined profile groups
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<PrfGroup>>,
+
+    /// rules to switch profile or clash mode by the current network
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_rules: Option<Vec<PrfNetworkRule>>,
+}
+
+/// 订阅分组
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct PrfGroup {
+    pub id: String,
+
+    pub name: String,
+
+    /// profile uids in this group
+    #[serde(default)]
+    pub items: Vec<String>,
+}
+
+/// 按网络自动切换的规则
+/// 所有已设置的条件都满足时才算匹配，按列表顺序取第一个匹配的规则
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PrfNetworkRule {
+    pub id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// wifi ssid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssid: Option<String>,
+
+    /// network interface name, e.g. `en0` `eth0` `Wi-Fi`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface: Option<String>,
+
+    /// default gateway ip
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+
+    /// local subnet in CIDR, e.g. `192.168.1.0/24`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subnet: Option<String>,
+
+    /// profile uid to activate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// profile group to activate, keeps the current profile if it is already in the group
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+
+    /// clash mode to switch, `rule` `global` `direct`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+impl PrfNetworkRule {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// 规则没有任何条件或动作时视为无效
+    pub fn validate(&self) -> Result<()> {
+        let not_empty = |v: &Option<String>| v.as_ref().is_some_and(|v| !v.trim().is_empty());
+
+        if ![&self.ssid, &self.interface, &self.gateway, &self.subnet]
+            .into_iter()
+            .any(not_empty)
+        {
+            bail!("network rule \"{}\" has no condition", self.id);
+        }
+        if ![&self.profile, &self.group, &self.mode]
+            .into_iter()
+            .any(not_empty)
+        {
+            bail!("network rule \"{}\" has no action", self.id);
+        }
+        if let Some(gateway) = self.gateway.as_ref().filter(|v| !v.trim().is_empty()) {
+            gateway
+                .trim()
+                .parse::<std::net::IpAddr>()
+                .with_context(|| format!("invalid gateway \"{gateway}\""))?;
+        }
+        if let Some(subnet) = self.subnet.as_ref().filter(|v| !v.trim().is_empty()) {
+            subnet.parse::<IpCidr>()?;
+        }
+        if let Some(mode) = self.mode.as_ref().filter(|v| !v.trim().is_empty()) {
+            if !matches!(mode.as_str(), "rule" | "global" | "direct") {
+                bail!("invalid clash mode \"{mode}\"");
+            }
+        }
+        Ok(())
+    }
 }
 
 /// 清理结果
@@ -87,6 +187,85 @@ impl IProfiles {
         Ok(())
     }
 
+    /// replace the profile groups, unknown profile uids are dropped
+    pub fn patch_groups(&mut self, groups: Vec<PrfGroup>) -> Result<()> {
+        self.replace_groups(groups)?;
+        self.save_file()
+    }
+
+    /// network rules pointing at removed groups lose the group action,
+    /// rules left without any action are dropped
+    fn replace_groups(&mut self, mut groups: Vec<PrfGroup>) -> Result<()> {
+        let uids: HashSet<String> = self
+            .items
+            .iter()
+            .flatten()
+            .filter_map(|item| item.uid.clone())
+            .collect();
+
+        let mut ids = HashSet::new();
+        for group in groups.iter_mut() {
+            if group.id.is_empty() {
+                group.id = help::get_uid("g");
+            }
+            if !ids.insert(group.id.clone()) {
+                bail!("duplicate profile group id \"{}\"", group.id);
+            }
+            let mut seen = HashSet::new();
+            group
+                .items
+                .retain(|uid| uids.contains(uid) && seen.insert(uid.clone()));
+        }
+
+        if let Some(rules) = self.network_rules.as_mut() {
+            for rule in rules.iter_mut() {
+                if rule
+                    .group
+                    .as_ref()
+                    .is_some_and(|group| !ids.contains(group))
+                {
+                    rule.group = None;
+                }
+            }
+            rules.retain(|rule| rule.validate().is_ok());
+        }
+
+        self.groups = Some(groups);
+        Ok(())
+    }
+
+    /// replace the network rules after validating them
+    pub fn patch_network_rules(&mut self, mut rules: Vec<PrfNetworkRule>) -> Result<()> {
+        let mut ids = HashSet::new();
+        for rule in rules.iter_mut() {
+            if rule.id.is_empty() {
+                rule.id = help::get_uid("n");
+            }
+            if !ids.insert(rule.id.clone()) {
+                bail!("duplicate network rule id \"{}\"", rule.id);
+            }
+            rule.validate()?;
+            if let Some(uid) = rule.profile.as_ref() {
+                self.get_item(uid)?;
+            }
+            if let Some(group) = rule.group.as_ref() {
+                self.get_group(group)?;
+            }
+        }
+
+        self.network_rules = Some(rules);
+        self.save_file()
+    }
+
+    /// find the group by the id
+    pub fn get_group(&self, id: &str) -> Result<&PrfGroup> {
+        self.groups
+            .iter()
+            .flatten()
+            .find(|group| group.id == id)
+            .with_context(|| format!("failed to get the profile group \"id:{id}\""))
+    }
+
     pub fn get_current(&self) -> Option<String> {
         self.current.clone()
     }
@@ -177,11 +356,18 @@ impl IProfiles {
     }
 
     /// update the item value
-    pub fn patch_item(&mut self, uid: String, item: PrfItem) -> Result<()> {
+    pub fn patch_item(&mut self, uid: String, mut item: PrfItem) -> Result<()> {
         let mut items = self.items.take().unwrap_or_default();
 
         for each in items.iter_mut() {
             if each.uid == Some(uid.clone()) {
+                // 认证信息只通过 patch_item_auth 修改
+                if let (Some(old), Some(new)) = (each.option.as_ref(), item.option.as_mut()) {
+                    if new.auth.is_none() {
+                        new.auth = old.auth.clone();
+                    }
+                }
+
                 patch!(each, item, itype);
                 patch!(each, item, name);
                 patch!(each, item, desc);
@@ -201,6 +387,22 @@ impl IProfiles {
         bail!("failed to find the profile item \"uid:{uid}\"")
     }
 
+    /// set or clear the custom headers and credentials of the item
+    pub fn patch_item_auth(&mut self, uid: &String, auth: Option<PrfAuth>) -> Result<()> {
+        let item = self
+            .items
+            .as_mut()
+            .and_then(|items| items.iter_mut().find(|each| each.uid.as_ref() == Some(uid)));
+
+        match item {
+            Some(item) => {
+                item.option.get_or_insert_with(PrfOption::default).auth = auth;
+                self.save_file()
+            }
+            None => bail!("failed to find the profile item \"uid:{uid}\""),
+        }
+    }
+
     /// be used to update the remote item
     /// only patch `updated` `extra` `file_data`
     pub fn update_item(&mut self, uid: String, mut item: PrfItem) -> Result<()> {
@@ -372,6 +574,18 @@ impl IProfiles {
                 });
             }
         }
+        // remove the uid from groups and network rules
+        for group in self.groups.iter_mut().flatten() {
+            group.items.retain(|each| *each != uid);
+        }
+        if let Some(rules) = self.network_rules.as_mut() {
+            for rule in rules.iter_mut() {
+                if rule.profile.as_ref() == Some(&uid) {
+                    rule.profile = None;
+                }
+            }
+            rules.retain(|rule| rule.validate().is_ok());
+        }
         // delete the original uid
         if current == uid {
             self.current = None;
@@ -407,6 +621,16 @@ impl IProfiles {
         }
     }
 
+    /// 获取current指向的订阅中 `tun.stack` 的值
+    pub fn current_tun_stack(&self) -> Option<String> {
+        self.current_mapping()
+            .ok()?
+            .get("tun")?
+            .get("stack")?
+            .as_str()
+            .map(str::to_string)
+    }
+
     /// 获取current指向的订阅的merge
     pub fn current_merge(&self) -> Option<String> {
         match (self.current.as_ref(), self.items.as_ref()) {
@@ -691,3 +915,101 @@ impl IProfiles {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str) -> PrfNetworkRule {
+        PrfNetworkRule {
+            id: id.into(),
+            ssid: Some("Office".into()),
+            profile: Some("p1".into()),
+            ..PrfNetworkRule::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_network_rule() {
+        assert!(rule("a").validate().is_ok());
+
+        let no_condition = PrfNetworkRule {
+            ssid: Some("  ".into()),
+            ..rule("a")
+        };
+        assert!(no_condition.validate().is_err());
+
+        let no_action = PrfNetworkRule {
+            profile: None,
+            ..rule("a")
+        };
+        assert!(no_action.validate().is_err());
+
+        let bad_gateway = PrfNetworkRule {
+            gateway: Some("192.168.1".into()),
+            ..rule("a")
+        };
+        assert!(bad_gateway.validate().is_err());
+
+        let bad_subnet = PrfNetworkRule {
+            subnet: Some("10.0.0.0/33".into()),
+            ..rule("a")
+        };
+        assert!(bad_subnet.validate().is_err());
+
+        let bad_mode = PrfNetworkRule {
+            mode: Some("tun".into()),
+            ..rule("a")
+        };
+        assert!(bad_mode.validate().is_err());
+
+        let full = PrfNetworkRule {
+            gateway: Some("192.168.1.1".into()),
+            subnet: Some("192.168.1.0/24".into()),
+            mode: Some("direct".into()),
+            ..rule("a")
+        };
+        assert!(full.validate().is_ok());
+    }
+
+    #[test]
+    fn test_prune_removed_groups() {
+        let mut profiles = IProfiles {
+            items: Some(vec![PrfItem {
+                uid: Some("p1".into()),
+                ..PrfItem::default()
+            }]),
+            groups: Some(vec![PrfGroup {
+                id: "g1".into(),
+                name: "Work".into(),
+                items: vec!["p1".into(), "missing".into()],
+            }]),
+            network_rules: Some(vec![
+                PrfNetworkRule {
+                    profile: None,
+                    group: Some("g1".into()),
+                    ..rule("only-group")
+                },
+                PrfNetworkRule {
+                    group: Some("g1".into()),
+                    ..rule("with-profile")
+                },
+            ]),
+            ..IProfiles::default()
+        };
+
+        profiles
+            .replace_groups(vec![PrfGroup {
+                id: "g2".into(),
+                name: "Home".into(),
+                items: vec!["p1".into(), "p1".into()],
+            }])
+            .unwrap();
+
+        let rules = profiles.network_rules.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].id, "with-profile");
+        assert_eq!(rules[0].group, None);
+        assert_eq!(profiles.groups.unwrap()[0].items, vec!["p1".to_string()]);
+    }
+}
@@ -9,6 +9,8 @@ pub struct IRuntime {
     // 这些keys不一定都生效
     pub exists_keys: Vec<String>,
     pub chain_logs: HashMap<String, Vec<(String, String)>>,
+    // 由规则生成的 PAC，随配置一起重新生成
+    pub pac: Option<String>,
 }
 
 impl IRuntime {
@@ -1,9 +1,9 @@
 use crate::{
     config::{deserialize_encrypted, serialize_encrypted, DEFAULT_PAC},
     logging,
-    utils::{dirs, help, i18n, logging::Type},
+    utils::{cidr::IpCidr, dirs, help, i18n, logging::Type},
 };
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use log::LevelFilter;
 use serde::{Deserialize, Serialize};
 
@@ -208,6 +208,51 @@ pub struct IVerge {
     /// 启用代理页面自动滚动
     pub enable_hover_jump_navigator: Option<bool>,
 
+    /// 订阅流量与到期提醒
+    pub enable_subscription_alerts: Option<bool>,
+
+    /// 订阅流量提醒阈值（百分比）
+    pub subscription_quota_thresholds: Option<Vec<u8>>,
+
+    /// 订阅到期前多少天提醒
+    pub subscription_expire_alert_days: Option<u64>,
+
+    /// 用户重置后的 HWID，为空时使用机器 ID
+    pub custom_hwid: Option<String>,
+
+    /// 是否根据当前网络自动切换订阅和代理模式
+    pub enable_network_auto_switch: Option<bool>,
+
+    /// Linux 下使用带 capabilities 的 core 副本运行 TUN，无需服务
+    pub enable_core_capabilities: Option<bool>,
+
+    /// Linux 下为 redir/tproxy 端口自动配置 nftables 和策略路由
+    pub enable_transparent_proxy: Option<bool>,
+
+    /// TUN 设置，启用 TUN 时覆盖订阅中的同名字段
+    pub tun_settings: Option<IVergeTun>,
+
+    /// 本地 HTTP 控制接口
+    pub enable_control_api: Option<bool>,
+
+    /// 控制接口的访问令牌 (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub control_api_token: Option<String>,
+
+    /// 是否把代理环境变量写入 shell 配置
+    pub enable_shell_env: Option<bool>,
+
+    /// shell 环境变量只使用 SOCKS 代理
+    pub shell_env_socks_only: Option<bool>,
+
+    /// 端口被其他进程占用时自动换用空闲端口
+    pub auto_reassign_ports: Option<bool>,
+
     /// 服务状态跟踪
     pub service_state: Option<crate::core::service::ServiceState>,
 }
@@ -236,6 +281,112 @@ pub struct IVergeTheme {
     pub css_injection: Option<String>,
 }
 
+/// TUN 设置，字段与 mihomo 的 `tun` 配置一一对应（下划线换成连字符）
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct IVergeTun {
+    /// system / gvisor / mixed
+    pub stack: Option<String>,
+    pub device: Option<String>,
+    pub mtu: Option<u32>,
+    pub auto_route: Option<bool>,
+    pub auto_detect_interface: Option<bool>,
+    pub strict_route: Option<bool>,
+    pub dns_hijack: Option<Vec<String>>,
+    pub route_exclude_address: Option<Vec<String>>,
+    pub inet4_route_address: Option<Vec<String>>,
+    pub inet6_route_address: Option<Vec<String>>,
+    /// 仅 Linux
+    pub include_uid: Option<Vec<u32>>,
+    /// 仅 Linux
+    pub exclude_uid: Option<Vec<u32>>,
+    /// 仅 Android
+    pub include_package: Option<Vec<String>>,
+    /// 仅 Android
+    pub exclude_package: Option<Vec<String>>,
+    pub endpoint_independent_nat: Option<bool>,
+    /// 仅 Linux
+    pub gso: Option<bool>,
+}
+
+impl IVergeTun {
+    pub const STACKS: &'static [&'static str] = &["system", "gvisor", "mixed"];
+
+    /// 未设置时沿用订阅中的 `tun.stack`，都没有时与默认配置一致，使用 gvisor
+    pub fn stack(&self, base: Option<&str>) -> String {
+        self.stack
+            .as_deref()
+            .or(base)
+            .unwrap_or("gvisor")
+            .to_ascii_lowercase()
+    }
+
+    /// 检查取值以及与实际生效的协议栈的兼容性，`base_stack` 为订阅中的 `tun.stack`
+    pub fn validate(&self, base_stack: Option<&str>) -> Result<()> {
+        if let Some(stack) = &self.stack {
+            if !Self::STACKS.contains(&stack.to_ascii_lowercase().as_str()) {
+                bail!("invalid TUN stack \"{stack}\", expected system, gvisor or mixed");
+            }
+        }
+        let stack = self.stack(base_stack);
+
+        if let Some(mtu) = self.mtu {
+            if !(576..=65535).contains(&mtu) {
+                bail!("invalid TUN mtu {mtu}, expected 576-65535");
+            }
+        }
+
+        let routes = [
+            ("route-exclude-address", &self.route_exclude_address, None),
+            ("inet4-route-address", &self.inet4_route_address, Some(true)),
+            (
+                "inet6-route-address",
+                &self.inet6_route_address,
+                Some(false),
+            ),
+        ];
+        for (name, list, ipv4) in routes {
+            for item in list.iter().flatten() {
+                let cidr: IpCidr = item
+                    .parse()
+                    .with_context(|| format!("invalid {name} entry \"{item}\""))?;
+                if ipv4.is_some_and(|ipv4| cidr.addr().is_ipv4() != ipv4) {
+                    bail!("{name} entry \"{item}\" has the wrong address family");
+                }
+            }
+        }
+
+        if !self.auto_route.unwrap_or(true) {
+            let requires_auto_route = [
+                ("strict-route", self.strict_route == Some(true)),
+                (
+                    "route-exclude-address",
+                    has_items(&self.route_exclude_address),
+                ),
+                ("inet4-route-address", has_items(&self.inet4_route_address)),
+                ("inet6-route-address", has_items(&self.inet6_route_address)),
+                ("include-uid", has_items(&self.include_uid)),
+                ("exclude-uid", has_items(&self.exclude_uid)),
+            ];
+            if let Some((name, _)) = requires_auto_route.iter().find(|(_, set)| *set) {
+                bail!("{name} requires auto-route to be enabled");
+            }
+        }
+
+        if self.endpoint_independent_nat == Some(true) && stack == "system" {
+            bail!("endpoint-independent-nat is not supported by the system stack");
+        }
+        if self.gso == Some(true) && stack == "gvisor" {
+            bail!("gso is not supported by the gvisor stack");
+        }
+
+        Ok(())
+    }
+}
+
+fn has_items<T>(list: &Option<Vec<T>>) -> bool {
+    list.as_ref().is_some_and(|list| !list.is_empty())
+}
+
 impl IVerge {
     /// 有效的clash核心名称
     pub const VALID_CLASH_CORES: &'static [&'static str] = &["koala-mihomo", "koala-mihomo-alpha", "koala-mihomo-alpha-smart"];
@@ -407,6 +558,9 @@ impl IVerge {
             enable_send_hwid: Some(true),
             primary_action: Some("tun-mode".into()),
             home_cards: None,
+            enable_subscription_alerts: Some(true),
+            subscription_quota_thresholds: Some(vec![80, 95]),
+            subscription_expire_alert_days: Some(3),
             service_state: None,
             ..Self::default()
         }
@@ -497,6 +651,19 @@ impl IVerge {
         patch!(enable_send_hwid);
         patch!(primary_action);
         patch!(home_cards);
+        patch!(enable_subscription_alerts);
+        patch!(subscription_quota_thresholds);
+        patch!(subscription_expire_alert_days);
+        patch!(custom_hwid);
+        patch!(enable_network_auto_switch);
+        patch!(enable_core_capabilities);
+        patch!(enable_transparent_proxy);
+        patch!(tun_settings);
+        patch!(enable_control_api);
+        patch!(control_api_token);
+        patch!(enable_shell_env);
+        patch!(shell_env_socks_only);
+        patch!(auto_reassign_ports);
         patch!(service_state);
     }
 
@@ -509,6 +676,11 @@ impl IVerge {
         SERVER_PORT
     }
 
+    /// 局域网 PAC 服务的端口，紧跟单例端口
+    pub fn get_lan_pac_port() -> u16 {
+        Self::get_singleton_port() + 1
+    }
+
     /// 获取日志等级
     pub fn get_log_level(&self) -> LevelFilter {
         if let Some(level) = self.app_log_level.as_ref() {
@@ -595,6 +767,19 @@ pub struct IVergeResponse {
     pub primary_action: Option<String>,
     pub home_cards: Option<serde_json::Value>,
     pub enable_hover_jump_navigator: Option<bool>,
+    pub enable_subscription_alerts: Option<bool>,
+    pub subscription_quota_thresholds: Option<Vec<u8>>,
+    pub subscription_expire_alert_days: Option<u64>,
+    pub custom_hwid: Option<String>,
+    pub enable_network_auto_switch: Option<bool>,
+    pub enable_core_capabilities: Option<bool>,
+    pub enable_transparent_proxy: Option<bool>,
+    pub tun_settings: Option<IVergeTun>,
+    pub enable_control_api: Option<bool>,
+    pub control_api_token: Option<String>,
+    pub enable_shell_env: Option<bool>,
+    pub shell_env_socks_only: Option<bool>,
+    pub auto_reassign_ports: Option<bool>,
     pub service_state: Option<crate::core::service::ServiceState>,
 }
 
@@ -669,6 +854,19 @@ impl From<IVerge> for IVergeResponse {
             primary_action: verge.primary_action,
             home_cards: verge.home_cards,
             enable_hover_jump_navigator: verge.enable_hover_jump_navigator,
+            enable_subscription_alerts: verge.enable_subscription_alerts,
+            subscription_quota_thresholds: verge.subscription_quota_thresholds,
+            subscription_expire_alert_days: verge.subscription_expire_alert_days,
+            custom_hwid: verge.custom_hwid,
+            enable_network_auto_switch: verge.enable_network_auto_switch,
+            enable_core_capabilities: verge.enable_core_capabilities,
+            enable_transparent_proxy: verge.enable_transparent_proxy,
+            tun_settings: verge.tun_settings,
+            enable_control_api: verge.enable_control_api,
+            control_api_token: verge.control_api_token,
+            enable_shell_env: verge.enable_shell_env,
+            shell_env_socks_only: verge.shell_env_socks_only,
+            auto_reassign_ports: verge.auto_reassign_ports,
             service_state: verge.service_state,
         }
     }
@@ -2,6 +2,8 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use tokio::time::{timeout, Duration};
 
+#[cfg(target_os = "linux")]
+use crate::core::linux_proxy::{self, LinuxProxy};
 #[cfg(target_os = "linux")]
 use anyhow::anyhow;
 #[cfg(not(target_os = "windows"))]
@@ -216,6 +218,17 @@ impl AsyncProxyQuery {
             }
         }
 
+        // KDE 的代理设置保存在 kioslaverc
+        if let Some(proxy) = linux_proxy::read_kde().await {
+            return Ok(match proxy {
+                LinuxProxy::Pac(url) => AsyncAutoproxy {
+                    enable: !url.is_empty(),
+                    url,
+                },
+                _ => AsyncAutoproxy::default(),
+            });
+        }
+
         // 尝试使用 gsettings 获取 GNOME 代理设置
         let output = Command::new("gsettings")
             .args(["get", "org.gnome.system.proxy", "mode"])
@@ -441,6 +454,23 @@ impl AsyncProxyQuery {
             }
         }
 
+        // KDE 的代理设置保存在 kioslaverc，没有桌面代理设置时写在 environment.d
+        let desktop_proxy = match linux_proxy::read_kde().await {
+            Some(proxy) => Some(proxy),
+            None => linux_proxy::read_env().await,
+        };
+        if let Some(proxy) = desktop_proxy {
+            return Ok(match proxy {
+                LinuxProxy::Manual { host, port, bypass } => AsyncSysproxy {
+                    enable: true,
+                    host,
+                    port,
+                    bypass,
+                },
+                _ => AsyncSysproxy::default(),
+            });
+        }
+
         // 尝试使用 gsettings 获取 GNOME 代理设置
         let mode_output = Command::new("gsettings")
             .args(["get", "org.gnome.system.proxy", "mode"])
@@ -1,8 +1,13 @@
 use crate::{
     config::*,
     core::{
+        core_caps,
+        core_log::{CoreLogSink, CoreLogStream},
         handle,
+        port_preflight::{self, PortConflict, PortKind},
         service::{self},
+        service_ipc::IpcCommand,
+        sysopt,
     },
     logging, logging_error,
     module::mihomo::MihomoManager,
@@ -13,16 +18,12 @@ use crate::{
     },
 };
 use anyhow::Result;
-use chrono::Local;
 use once_cell::sync::OnceCell;
-use std::{
-    fmt,
-    fs::{create_dir_all, File},
-    io::Write,
-    path::PathBuf,
-    sync::Arc,
+use std::{fmt, path::PathBuf, sync::Arc};
+use tauri_plugin_shell::{
+    process::{CommandChild, CommandEvent},
+    ShellExt,
 };
-use tauri_plugin_shell::{process::CommandChild, ShellExt};
 use tokio::sync::Mutex;
 
 #[derive(Debug)]
@@ -142,6 +143,7 @@ impl CoreManager {
             config: Some(Config::clash().latest().0.clone()),
             exists_keys: vec![],
             chain_logs: Default::default(),
+            pac: None,
         });
         help::save_yaml(
             &runtime_path,
@@ -265,7 +267,6 @@ impl CoreManager {
         let clash_core = Config::verge().latest().get_valid_clash_core();
         logging!(info, Type::Config, true, "Using core: {}", clash_core);
 
-        let app_handle = handle::Handle::global().app_handle().unwrap();
         let app_dir = dirs::app_home_dir()?;
         let app_dir_str = dirs::path_to_str(&app_dir)?;
         logging!(
@@ -276,21 +277,43 @@ impl CoreManager {
             app_dir_str
         );
 
-        // 使用子进程运行clash验证配置
-        let output = app_handle
-            .shell()
-            .sidecar(clash_core)?
-            .args(["-t", "-d", app_dir_str, "-f", config_path])
-            .output()
-            .await?;
+        // 使用子进程运行clash验证配置，命令行模式下没有 app handle，直接运行内核
+        let args = ["-t", "-d", app_dir_str, "-f", config_path];
+        let (success, code, stdout, stderr) = match handle::Handle::global().app_handle() {
+            Some(app_handle) => {
+                let output = app_handle
+                    .shell()
+                    .sidecar(clash_core)?
+                    .args(args)
+                    .output()
+                    .await?;
+                (
+                    output.status.success(),
+                    output.status.code(),
+                    output.stdout,
+                    output.stderr,
+                )
+            }
+            None => {
+                let output = tokio::process::Command::new(dirs::sidecar_path(&clash_core)?)
+                    .args(args)
+                    .output()
+                    .await?;
+                (
+                    output.status.success(),
+                    output.status.code(),
+                    output.stdout,
+                    output.stderr,
+                )
+            }
+        };
 
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&stderr);
+        let stdout = String::from_utf8_lossy(&stdout);
 
         // 检查进程退出状态和错误输出
         let error_keywords = ["FATA", "fatal", "Parse config error", "level=fatal"];
-        let has_error =
-            !output.status.success() || error_keywords.iter().any(|&kw| stderr.contains(kw));
+        let has_error = !success || error_keywords.iter().any(|&kw| stderr.contains(kw));
 
         logging!(
             info,
@@ -314,7 +337,7 @@ impl CoreManager {
                 stdout.to_string()
             } else if !stderr.is_empty() {
                 stderr.to_string()
-            } else if let Some(code) = output.status.code() {
+            } else if let Some(code) = code {
                 format!("Validation process exited abnormally, exit code: {code}")
             } else {
                 "Validation process was terminated".to_string()
@@ -847,19 +870,24 @@ impl CoreManager {
         let clash_core = Config::verge().latest().get_valid_clash_core();
         let config_dir = dirs::app_home_dir()?;
 
-        let service_log_dir = dirs::app_home_dir()?.join("logs").join("service");
-        create_dir_all(&service_log_dir)?;
-
-        let now = Local::now();
-        let timestamp = now.format("%Y%m%d_%H%M%S").to_string();
+        let mut log_sink = CoreLogSink::create("sidecar")?;
 
-        let log_path = service_log_dir.join(format!("sidecar_{timestamp}.log"));
-
-        let mut log_file = File::create(log_path)?;
+        // 已授予 capabilities 的 core 副本无需服务即可运行 TUN
+        let command = match core_caps::capable_core(&clash_core) {
+            Some(core_path) => {
+                logging!(
+                    info,
+                    Type::Core,
+                    true,
+                    "Using core with capabilities: {:?}",
+                    core_path
+                );
+                app_handle.shell().command(core_path)
+            }
+            None => app_handle.shell().sidecar(&clash_core)?,
+        };
 
-        let (mut rx, child) = app_handle
-            .shell()
-            .sidecar(&clash_core)?
+        let (mut rx, child) = command
             .args([
                 "-d",
                 dirs::path_to_str(&config_dir)?,
@@ -870,16 +898,14 @@ impl CoreManager {
 
         tokio::spawn(async move {
             while let Some(event) = rx.recv().await {
-                if let tauri_plugin_shell::process::CommandEvent::Stdout(line) = event {
-                    if let Err(e) = writeln!(log_file, "{}", String::from_utf8_lossy(&line)) {
-                        logging!(
-                            error,
-                            Type::Core,
-                            true,
-                            "[Sidecar] Failed to write stdout to file: {}",
-                            e
-                        );
+                match event {
+                    CommandEvent::Stdout(line) => {
+                        log_sink.write(CoreLogStream::Stdout, &String::from_utf8_lossy(&line))
                     }
+                    CommandEvent::Stderr(line) => {
+                        log_sink.write(CoreLogStream::Stderr, &String::from_utf8_lossy(&line))
+                    }
+                    _ => {}
                 }
             }
         });
@@ -921,10 +947,12 @@ impl CoreManager {
         let config_file = &Config::generate_file(ConfigType::Run)?;
         service::run_core_by_service(config_file).await?;
         self.set_running_mode(RunningMode::Service).await;
+        service::start_core_log_stream();
         Ok(())
     }
     async fn stop_core_by_service(&self) -> Result<()> {
         logging!(trace, Type::Core, true, "Stopping core by service");
+        service::stop_core_log_stream();
         service::stop_core_by_service().await?;
         self.set_running_mode(RunningMode::NotRunning).await;
         Ok(())
@@ -1197,8 +1225,97 @@ impl CoreManager {
         (*guard).clone()
     }
 
+    /// 启动前检查端口占用，开启自动分配时换用空闲端口，否则提示用户
+    /// 内核仍在运行时端口本就由它占用，不做检查
+    async fn preflight_ports(&self) {
+        if self.get_running_mode().await != RunningMode::NotRunning {
+            return;
+        }
+        let reassign = Config::verge()
+            .latest()
+            .auto_reassign_ports
+            .unwrap_or(false);
+        let conflicts = match tokio::task::spawn_blocking(move || {
+            port_preflight::check_ports(reassign)
+        })
+        .await
+        {
+            Ok(Ok(conflicts)) => conflicts,
+            Ok(Err(err)) => {
+                logging!(error, Type::Core, true, "Failed to reassign ports: {}", err);
+                return;
+            }
+            Err(err) => {
+                logging!(error, Type::Core, true, "Port preflight panicked: {}", err);
+                return;
+            }
+        };
+        if conflicts.is_empty() {
+            return;
+        }
+
+        let message = serde_json::to_string(&conflicts).unwrap_or_default();
+        if reassign {
+            handle::Handle::notice_message("port_reassigned", message);
+            self.on_ports_reassigned(&conflicts).await;
+        } else {
+            handle::Handle::notice_message("port_conflict", message);
+        }
+    }
+
+    /// 端口换用后刷新界面，并更新依赖混合端口的系统代理和 shell 环境变量
+    async fn on_ports_reassigned(&self, conflicts: &[PortConflict]) {
+        handle::Handle::refresh_verge();
+        handle::Handle::refresh_clash();
+        let mixed_changed = conflicts
+            .iter()
+            .any(|conflict| conflict.kind == PortKind::Mixed && conflict.reassigned.is_some());
+        if !mixed_changed {
+            return;
+        }
+        if Config::verge()
+            .latest()
+            .enable_system_proxy
+            .unwrap_or(false)
+        {
+            logging_error!(
+                Type::Core,
+                true,
+                sysopt::Sysopt::global().update_sysproxy().await
+            );
+        }
+        logging_error!(Type::Core, true, crate::feat::sync_shell_env());
+    }
+
+    /// 停止内核，为被占用的端口换用空闲端口后重新启动
+    pub async fn reassign_ports(&self) -> Result<Vec<PortConflict>> {
+        self.stop_core().await?;
+        let conflicts =
+            match tokio::task::spawn_blocking(|| port_preflight::check_ports(true)).await {
+                Ok(result) => result,
+                Err(err) => Err(err.into()),
+            };
+        if let Ok(conflicts) = &conflicts {
+            self.on_ports_reassigned(conflicts).await;
+        }
+        self.start_core().await?;
+        conflicts
+    }
+
     /// 启动核心
     pub async fn start_core(&self) -> Result<()> {
+        self.preflight_ports().await;
+        let clash_core = Config::verge().latest().get_valid_clash_core();
+        if core_caps::check_capable_core(&clash_core) {
+            logging!(
+                info,
+                Type::Core,
+                true,
+                "Core capabilities granted; starting in Sidecar mode"
+            );
+            return self.start_core_by_sidecar().await;
+        }
+
         if service::is_service_available().await.is_ok() {
             if service::check_service_needs_reinstall().await {
                 service::reinstall_service().await?;
@@ -1234,6 +1351,56 @@ impl CoreManager {
         Ok(())
     }
 
+    /// 在前台运行内核直到其退出或收到 Ctrl-C，供命令行使用
+    pub async fn run_core_foreground(&self) -> Result<Option<i32>> {
+        Config::generate().await?;
+        Config::runtime().apply();
+        let config_file = Config::generate_file(ConfigType::Run)?;
+        let config_dir = dirs::app_home_dir()?;
+        let clash_core = Config::verge().latest().get_valid_clash_core();
+        let core_path = match core_caps::capable_core(&clash_core) {
+            Some(core_path) => core_path,
+            None => dirs::sidecar_path(&clash_core)?,
+        };
+        logging!(
+            info,
+            Type::Core,
+            true,
+            "Running core in foreground: {:?}",
+            core_path
+        );
+
+        let mut child = tokio::process::Command::new(core_path)
+            .args([
+                "-d",
+                dirs::path_to_str(&config_dir)?,
+                "-f",
+                dirs::path_to_str(&config_file)?,
+            ])
+            .kill_on_drop(true)
+            .spawn()?;
+        self.set_running_mode(RunningMode::Sidecar).await;
+
+        let status = tokio::select! {
+            status = child.wait() => status?,
+            _ = tokio::signal::ctrl_c() => {
+                logging!(info, Type::Core, true, "Interrupted, stopping core");
+                child.kill().await?;
+                child.wait().await?
+            }
+        };
+        self.set_running_mode(RunningMode::NotRunning).await;
+        Ok(status.code())
+    }
+
+    /// 停止由服务或其他实例启动的内核，供命令行使用
+    pub async fn stop_external_cores(&self) -> Result<()> {
+        if service::is_service_available().await.is_ok() {
+            logging_error!(Type::Core, true, service::stop_core_by_service().await);
+        }
+        self.cleanup_orphaned_mihomo_processes().await
+    }
+
     /// 停止核心运行
     pub async fn stop_core(&self) -> Result<()> {
         match self.get_running_mode().await {
@@ -1245,6 +1412,28 @@ impl CoreManager {
 
     /// 重启内核
     pub async fn restart_core(&self) -> Result<()> {
+        // 服务模式下优先由服务直接重启，旧版本服务不支持时退回到先停止再启动
+        // 内核已获得授权时交由 start_core 切换到 Sidecar 模式
+        let clash_core = Config::verge().latest().get_valid_clash_core();
+        if self.get_running_mode().await == RunningMode::Service
+            && !core_caps::check_capable_core(&clash_core)
+            && service::service_supports(IpcCommand::RestartClash).await
+        {
+            let config_file = Config::generate_file(ConfigType::Run)?;
+            match service::restart_core_by_service(&config_file).await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    logging!(
+                        warn,
+                        Type::Core,
+                        true,
+                        "Failed to restart core via service, falling back to stop/start: {}",
+                        e
+                    );
+                }
+            }
+        }
+
         self.stop_core().await?;
 
         self.start_core().await?;
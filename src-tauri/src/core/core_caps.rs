@@ -0,0 +1,272 @@
+use crate::{
+    config::Config,
+    core::handle,
+    logging,
+    utils::{dirs, logging::Type},
+};
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{path::PathBuf, time::SystemTime};
+
+/// TUN 需要的 capabilities
+#[cfg(target_os = "linux")]
+const CORE_CAPABILITIES: [&str; 2] = ["cap_net_admin", "cap_net_bind_service"];
+
+/// core 副本的 capabilities 状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoreCapState {
+    /// 非 Linux 平台
+    Unsupported,
+    NotGranted,
+    Granted,
+    /// 副本被覆盖（例如 core 自升级）后 capabilities 丢失
+    Dropped,
+    /// 随应用更新的 core 与副本不一致
+    Outdated,
+}
+
+/// 带 capabilities 的 core 副本位置
+fn capable_core_path(clash_core: &str) -> Result<PathBuf> {
+    Ok(dirs::app_home_dir()?.join("core").join(clash_core))
+}
+
+#[cfg(target_os = "linux")]
+fn source_core_path(clash_core: &str) -> Result<PathBuf> {
+    Ok(tauri::utils::platform::current_exe()?.with_file_name(clash_core))
+}
+
+// 授权时记录的原始 core 摘要，用于发现 core 随应用更新
+#[cfg(target_os = "linux")]
+fn source_digest_path(clash_core: &str) -> Result<PathBuf> {
+    Ok(capable_core_path(clash_core)?.with_extension("sha256"))
+}
+
+// 按副本路径及副本、原始 core 的修改时间缓存状态，避免每次启动内核都计算摘要并运行 getcap
+type StateKey = (PathBuf, SystemTime, Option<SystemTime>);
+static STATE_CACHE: Mutex<Option<(StateKey, CoreCapState)>> = Mutex::new(None);
+
+#[cfg(target_os = "linux")]
+fn modified(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+#[cfg(target_os = "linux")]
+fn file_digest(path: &std::path::Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(target_os = "linux")]
+fn has_capabilities(path: &std::path::Path) -> bool {
+    match std::process::Command::new("getcap").arg(path).output() {
+        Ok(output) => {
+            let caps = String::from_utf8_lossy(&output.stdout);
+            CORE_CAPABILITIES.iter().all(|cap| caps.contains(cap))
+        }
+        Err(e) => {
+            log::warn!(target: "app", "Failed to run getcap: {e}");
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn shell_quote(path: &std::path::Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"))
+}
+
+/// 检查 core 副本的 capabilities 状态
+#[cfg(target_os = "linux")]
+pub fn capability_state(clash_core: &str) -> CoreCapState {
+    let Ok(core_path) = capable_core_path(clash_core) else {
+        return CoreCapState::NotGranted;
+    };
+    let Some(core_modified) = modified(&core_path) else {
+        return CoreCapState::NotGranted;
+    };
+    let source = source_core_path(clash_core).ok();
+    let key = (
+        core_path.clone(),
+        core_modified,
+        source.as_deref().and_then(modified),
+    );
+    if let Some((cached, state)) = &*STATE_CACHE.lock() {
+        if *cached == key {
+            return *state;
+        }
+    }
+
+    let recorded = source_digest_path(clash_core)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok());
+    let current = source.and_then(|path| file_digest(&path).ok());
+    let state = resolve_state(
+        has_capabilities(&core_path),
+        recorded.as_deref(),
+        current.as_deref(),
+    );
+    *STATE_CACHE.lock() = Some((key, state));
+    state
+}
+
+/// 根据副本的 capabilities 和授权时记录的摘要判断状态
+#[cfg(target_os = "linux")]
+fn resolve_state(has_caps: bool, recorded: Option<&str>, current: Option<&str>) -> CoreCapState {
+    if !has_caps {
+        return CoreCapState::Dropped;
+    }
+    match (recorded, current) {
+        (Some(recorded), Some(current)) if recorded.trim() == current => CoreCapState::Granted,
+        _ => CoreCapState::Outdated,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn capability_state(_clash_core: &str) -> CoreCapState {
+    CoreCapState::Unsupported
+}
+
+/// 复制当前 core 并授予 capabilities，需要通过 pkexec 或 sudo 提权一次
+#[cfg(target_os = "linux")]
+pub fn grant_capabilities(clash_core: &str) -> Result<()> {
+    use std::process::Command;
+    use users::{get_current_gid, get_effective_uid};
+
+    let source = source_core_path(clash_core)?;
+    if !source.exists() {
+        bail!("core binary not found: {source:?}");
+    }
+    let target = capable_core_path(clash_core)?;
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // 副本归 root 所有，普通用户无法替换其内容，且仅安装用户所在的组可以执行
+    let script = format!(
+        "install -o root -g {gid} -m 0750 {source} {target} && setcap {caps}=+ep {target}",
+        gid = get_current_gid(),
+        source = shell_quote(&source),
+        target = shell_quote(&target),
+        caps = CORE_CAPABILITIES.join(","),
+    );
+    logging!(
+        info,
+        Type::Core,
+        true,
+        "Granting core capabilities: {}",
+        script
+    );
+
+    let status = match get_effective_uid() {
+        0 => Command::new("sh").arg("-c").arg(&script).status()?,
+        _ => Command::new(crate::utils::help::linux_elevator())
+            .arg("sh")
+            .arg("-c")
+            .arg(&script)
+            .status()?,
+    };
+    if !status.success() {
+        bail!("failed to grant core capabilities with status {status}");
+    }
+
+    std::fs::write(source_digest_path(clash_core)?, file_digest(&source)?)?;
+    *STATE_CACHE.lock() = None;
+    if !has_capabilities(&target) {
+        bail!("capabilities were not applied to {target:?}");
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn grant_capabilities(_clash_core: &str) -> Result<()> {
+    bail!("core capabilities are only supported on Linux")
+}
+
+/// 删除 core 副本
+pub fn revoke_capabilities(clash_core: &str) -> Result<()> {
+    let core_path = capable_core_path(clash_core)?;
+    for path in [core_path.clone(), core_path.with_extension("sha256")] {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    *STATE_CACHE.lock() = None;
+    Ok(())
+}
+
+fn is_enabled() -> bool {
+    Config::verge()
+        .latest()
+        .enable_core_capabilities
+        .unwrap_or(false)
+}
+
+/// 启用了 capabilities 模式且副本可用时，返回用于 sidecar 的 core 路径
+pub fn capable_core(clash_core: &str) -> Option<PathBuf> {
+    if is_enabled() && capability_state(clash_core) == CoreCapState::Granted {
+        capable_core_path(clash_core).ok()
+    } else {
+        None
+    }
+}
+
+/// 启动 core 前检查 capabilities 模式是否可用，副本失效时提醒用户重新授权
+pub fn check_capable_core(clash_core: &str) -> bool {
+    if !is_enabled() {
+        return false;
+    }
+
+    match capability_state(clash_core) {
+        CoreCapState::Granted => true,
+        CoreCapState::Unsupported => false,
+        state => {
+            logging!(
+                warn,
+                Type::Core,
+                true,
+                "Core capabilities unavailable ({:?}), TUN requires granting them again",
+                state
+            );
+            handle::Handle::notice_message("core_capabilities_lost", format!("{state:?}"));
+            false
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_state() {
+        let digest = "abc123";
+        assert_eq!(
+            resolve_state(true, Some("abc123\n"), Some(digest)),
+            CoreCapState::Granted
+        );
+        assert_eq!(
+            resolve_state(false, Some(digest), Some(digest)),
+            CoreCapState::Dropped
+        );
+        assert_eq!(
+            resolve_state(true, Some("def456"), Some(digest)),
+            CoreCapState::Outdated
+        );
+        assert_eq!(
+            resolve_state(true, None, Some(digest)),
+            CoreCapState::Outdated
+        );
+        assert_eq!(
+            resolve_state(true, Some(digest), None),
+            CoreCapState::Outdated
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{
+    logging,
+    utils::{dirs, logging::Type},
+};
+use anyhow::Result;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{create_dir_all, File},
+    io::Write,
+};
+
+/// core 输出的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoreLogStream {
+    Stdout,
+    Stderr,
+    /// 服务自身的事件，例如 core 崩溃后被重新拉起
+    Service,
+}
+
+/// core 日志文件，sidecar 和服务模式使用相同的目录和格式
+pub struct CoreLogSink {
+    file: File,
+}
+
+impl CoreLogSink {
+    /// 在 `logs/service` 下创建 `{mode}_{timestamp}.log`
+    pub fn create(mode: &str) -> Result<Self> {
+        let log_dir = dirs::app_logs_dir()?.join("service");
+        create_dir_all(&log_dir)?;
+
+        let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+        let file = File::create(log_dir.join(format!("{mode}_{timestamp}.log")))?;
+        Ok(Self { file })
+    }
+
+    pub fn write(&mut self, stream: CoreLogStream, line: &str) {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let result = match stream {
+            CoreLogStream::Stdout => writeln!(self.file, "{line}"),
+            CoreLogStream::Stderr => writeln!(self.file, "[stderr] {line}"),
+            CoreLogStream::Service => {
+                logging!(info, Type::Service, false, "{}", line);
+                writeln!(self.file, "[service] {line}")
+            }
+        };
+
+        if let Err(e) = result {
+            logging!(error, Type::Core, true, "Failed to write core log: {}", e);
+        }
+    }
+}
@@ -1,12 +1,23 @@
 use parking_lot::RwLock;
-use std::sync::Arc;
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, VecDeque},
+    sync::Arc,
+    time::Instant,
+};
 use tokio::sync::{mpsc, oneshot};
-use tokio::time::{sleep, timeout, Duration};
+use tokio::time::{interval_at, sleep, timeout, Duration, Interval};
 
 use crate::config::{Config, IVerge};
-use crate::core::async_proxy_query::AsyncProxyQuery;
-use crate::logging_error;
-use crate::utils::logging::Type;
+#[cfg(target_os = "linux")]
+use crate::core::linux_proxy::{self, LinuxProxy};
+use crate::core::{async_proxy_query::AsyncProxyQuery, handle};
+use crate::utils::{
+    bypass,
+    notification::{notify_event, NotificationEvent},
+};
+#[cfg(not(target_os = "linux"))]
+use crate::{logging_error, utils::logging::Type};
 use once_cell::sync::Lazy;
 use sysproxy::{Autoproxy, Sysproxy};
 
@@ -36,6 +47,40 @@ pub enum ProxyEvent {
     AppStopping,
 }
 
+/// 两次篡改间隔不超过该时间时视为连续篡改
+const TAMPER_WINDOW: Duration = Duration::from_secs(300);
+/// 连续篡改达到该次数时通知用户
+const TAMPER_NOTIFY_THRESHOLD: u32 = 3;
+/// 连续篡改达到该次数时暂停恢复，避免和其他代理软件来回覆盖
+const TAMPER_BACKOFF_THRESHOLD: u32 = 5;
+const BACKOFF_BASE: Duration = Duration::from_secs(60);
+const BACKOFF_MAX: Duration = Duration::from_secs(1800);
+const TAMPER_HISTORY_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProxyFieldChange {
+    pub field: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// 一次被外部修改的记录
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyTamper {
+    /// 毫秒时间戳
+    pub time: i64,
+    pub changes: Vec<ProxyFieldChange>,
+    /// 是否已恢复，暂停期间为 false
+    pub restored: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyGuardStatus {
+    pub history: Vec<ProxyTamper>,
+    /// 暂停恢复的剩余秒数
+    pub backoff_secs: Option<u64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ProxyState {
     pub sys_enabled: bool,
@@ -44,6 +89,11 @@ pub struct ProxyState {
     pub sys_proxy: Sysproxy,
     pub last_updated: std::time::Instant,
     pub is_healthy: bool,
+    pub tamper_history: VecDeque<ProxyTamper>,
+    pub tamper_streak: u32,
+    pub last_tamper: Option<Instant>,
+    pub backoff_level: u32,
+    pub backoff_until: Option<Instant>,
 }
 
 impl Default for ProxyState {
@@ -63,6 +113,11 @@ impl Default for ProxyState {
             },
             last_updated: std::time::Instant::now(),
             is_healthy: true,
+            tamper_history: VecDeque::new(),
+            tamper_streak: 0,
+            last_tamper: None,
+            backoff_level: 0,
+            backoff_until: None,
         }
     }
 }
@@ -164,6 +219,30 @@ impl EventDrivenProxyManager {
         self.send_event(ProxyEvent::ForceCheck);
     }
 
+    /// 守护记录和暂停状态
+    pub fn guard_status(&self) -> ProxyGuardStatus {
+        let state = self.state.read();
+        ProxyGuardStatus {
+            history: state.tamper_history.iter().cloned().collect(),
+            backoff_secs: state
+                .backoff_until
+                .and_then(|until| until.checked_duration_since(Instant::now()))
+                .map(|left| left.as_secs().max(1)),
+        }
+    }
+
+    /// 清空记录并立即恢复守护
+    pub fn reset_guard(&self) {
+        Self::update_state_timestamp(&self.state, |s| {
+            s.tamper_history.clear();
+            s.tamper_streak = 0;
+            s.last_tamper = None;
+            s.backoff_level = 0;
+            s.backoff_until = None;
+        });
+        self.send_event(ProxyEvent::ForceCheck);
+    }
+
     fn send_event(&self, event: ProxyEvent) {
         if let Err(e) = self.event_sender.send(event) {
             log::error!(target: "app", "Failed to send proxy event: {e}");
@@ -178,6 +257,8 @@ impl EventDrivenProxyManager {
         tokio::spawn(async move {
             log::info!(target: "app", "Event-driven proxy manager started");
 
+            let mut guard_period = Self::get_guard_interval();
+            let mut guard_timer = Self::guard_timer(guard_period);
             loop {
                 tokio::select! {
                     event = event_rx.recv() => {
@@ -204,6 +285,18 @@ impl EventDrivenProxyManager {
                             }
                         }
                     }
+                    _ = guard_timer.tick() => {
+                        let config = Self::get_proxy_config();
+                        if config.guard_enabled && config.sys_enabled {
+                            Self::check_and_restore_proxy(&state).await;
+                        }
+                        // 守卫间隔修改后重建计时器
+                        let period = Self::get_guard_interval();
+                        if period != guard_period {
+                            guard_period = period;
+                            guard_timer = Self::guard_timer(period);
+                        }
+                    }
                 }
             }
         });
@@ -268,9 +361,12 @@ impl EventDrivenProxyManager {
 
         let config = Self::get_proxy_config();
 
+        // 用户修改配置后重新开始守护
         Self::update_state_timestamp(state, |s| {
             s.sys_enabled = config.sys_enabled;
             s.pac_enabled = config.pac_enabled;
+            s.tamper_streak = 0;
+            s.backoff_until = None;
         });
 
         if config.guard_enabled && config.sys_enabled {
@@ -288,6 +384,12 @@ impl EventDrivenProxyManager {
             return;
         }
 
+        let backoff_until = state.read().backoff_until;
+        if backoff_until.is_some_and(|until| Instant::now() < until) {
+            log::debug!(target: "app", "Proxy guard is backing off, skip checking");
+            return;
+        }
+
         log::debug!(target: "app", "Checking proxy status");
 
         if pac_enabled {
@@ -298,6 +400,13 @@ impl EventDrivenProxyManager {
     }
 
     async fn check_and_restore_pac_proxy(state: &Arc<RwLock<ProxyState>>) {
+        // 只能写入 environment.d 的桌面无法设置 PAC，读到的值与预期无关
+        #[cfg(target_os = "linux")]
+        if !linux_proxy::pac_supported() {
+            log::debug!(target: "app", "PAC is not supported on this desktop, skip checking");
+            return;
+        }
+
         let current = Self::get_auto_proxy_with_timeout().await;
         let expected = Self::get_expected_pac_config();
 
@@ -305,7 +414,11 @@ impl EventDrivenProxyManager {
             s.auto_proxy = current.clone();
         });
 
-        if !current.enable || current.url != expected.url {
+        let changes = diff_auto_proxy(&expected, &current);
+        if !changes.is_empty() {
+            if !Self::record_tamper(state, changes) {
+                return;
+            }
             log::info!(target: "app", "PAC proxy setting abnormal, recovering...");
             Self::restore_pac_proxy(&expected.url).await;
 
@@ -327,7 +440,11 @@ impl EventDrivenProxyManager {
             s.sys_proxy = current.clone();
         });
 
-        if !current.enable || current.host != expected.host || current.port != expected.port {
+        let changes = diff_sys_proxy(&expected, &current);
+        if changes.iter().any(|change| change.field != "bypass") {
+            if !Self::record_tamper(state, changes) {
+                return;
+            }
             log::info!(target: "app", "System proxy setting abnormal, recovering...");
             Self::restore_sys_proxy(&expected).await;
 
@@ -343,6 +460,70 @@ impl EventDrivenProxyManager {
         }
     }
 
+    /// 记录一次篡改，返回是否需要恢复
+    fn record_tamper(state: &Arc<RwLock<ProxyState>>, changes: Vec<ProxyFieldChange>) -> bool {
+        let now = Instant::now();
+        let mut backoff = None;
+        let mut streak = 0;
+        Self::update_state_timestamp(state, |s| {
+            let continued = s
+                .last_tamper
+                .is_some_and(|last| now.duration_since(last) <= TAMPER_WINDOW);
+            if !continued {
+                s.tamper_streak = 0;
+                s.backoff_level = 0;
+            }
+            s.tamper_streak += 1;
+            s.last_tamper = Some(now);
+            streak = s.tamper_streak;
+
+            if s.tamper_streak >= TAMPER_BACKOFF_THRESHOLD {
+                let duration = backoff_duration(s.backoff_level);
+                s.backoff_level += 1;
+                s.tamper_streak = 0;
+                s.backoff_until = Some(now + duration);
+                backoff = Some(duration);
+            }
+
+            if s.tamper_history.len() >= TAMPER_HISTORY_LIMIT {
+                s.tamper_history.pop_front();
+            }
+            s.tamper_history.push_back(ProxyTamper {
+                time: chrono::Local::now().timestamp_millis(),
+                changes: changes.clone(),
+                restored: backoff.is_none(),
+            });
+        });
+
+        let summary = changes
+            .iter()
+            .map(|change| format!("{}: {} -> {}", change.field, change.expected, change.actual))
+            .collect::<Vec<_>>()
+            .join(", ");
+        log::warn!(target: "app", "System proxy changed externally ({summary}), streak {streak}");
+
+        let app_handle = handle::Handle::global().app_handle();
+        if let Some(duration) = backoff {
+            let minutes = duration.as_secs() / 60;
+            log::warn!(target: "app", "Proxy guard backing off for {minutes} min");
+            handle::Handle::notice_message("proxy_guard_paused", minutes.to_string());
+            if let Some(app_handle) = &app_handle {
+                notify_event(app_handle, NotificationEvent::ProxyGuardPaused { minutes });
+            }
+            return false;
+        }
+        if streak == TAMPER_NOTIFY_THRESHOLD {
+            handle::Handle::notice_message("proxy_guard_tampered", summary);
+            if let Some(app_handle) = &app_handle {
+                notify_event(
+                    app_handle,
+                    NotificationEvent::ProxyTampered { count: streak },
+                );
+            }
+        }
+        true
+    }
+
     async fn enable_system_proxy(state: &Arc<RwLock<ProxyState>>) {
         log::info!(target: "app", "Enabling system proxy");
 
@@ -362,7 +543,12 @@ impl EventDrivenProxyManager {
     async fn disable_system_proxy(_state: &Arc<RwLock<ProxyState>>) {
         log::info!(target: "app", "Disabling system proxy");
 
-        #[cfg(not(target_os = "windows"))]
+        #[cfg(target_os = "linux")]
+        {
+            Self::apply_linux_proxy(LinuxProxy::Off).await;
+        }
+
+        #[cfg(target_os = "macos")]
         {
             let disabled_sys = Sysproxy::default();
             let disabled_auto = Autoproxy::default();
@@ -375,15 +561,22 @@ impl EventDrivenProxyManager {
     async fn switch_proxy_mode(state: &Arc<RwLock<ProxyState>>, to_pac: bool) {
         log::info!(target: "app", "Switching to {} mode", if to_pac { "PAC" } else { "HTTP Proxy" });
 
+        // Linux 后端切换模式时会直接覆盖原来的设置
         if to_pac {
-            let disabled_sys = Sysproxy::default();
-            logging_error!(Type::System, true, disabled_sys.set_system_proxy());
+            #[cfg(not(target_os = "linux"))]
+            {
+                let disabled_sys = Sysproxy::default();
+                logging_error!(Type::System, true, disabled_sys.set_system_proxy());
+            }
 
             let expected = Self::get_expected_pac_config();
             Self::restore_pac_proxy(&expected.url).await;
         } else {
-            let disabled_auto = Autoproxy::default();
-            logging_error!(Type::System, true, disabled_auto.set_auto_proxy());
+            #[cfg(not(target_os = "linux"))]
+            {
+                let disabled_auto = Autoproxy::default();
+                logging_error!(Type::System, true, disabled_auto.set_auto_proxy());
+            }
 
             let expected = Self::get_expected_sys_proxy();
             Self::restore_sys_proxy(&expected).await;
@@ -435,6 +628,17 @@ impl EventDrivenProxyManager {
         }
     }
 
+    fn get_guard_interval() -> Duration {
+        let duration = Config::verge().latest().proxy_guard_duration.unwrap_or(30);
+        Duration::from_secs(duration.max(1))
+    }
+
+    fn guard_timer(period: Duration) -> Interval {
+        let mut timer = interval_at(tokio::time::Instant::now() + period, period);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        timer
+    }
+
     fn get_expected_pac_config() -> Autoproxy {
         let verge_config = Config::verge();
         let verge = verge_config.latest();
@@ -466,37 +670,17 @@ impl EventDrivenProxyManager {
             enable: true,
             host: proxy_host,
             port,
-            bypass: Self::get_bypass_config(),
+            bypass: bypass::system_bypass(),
         }
     }
 
-    fn get_bypass_config() -> String {
-        let verge_config = Config::verge();
-        let verge = verge_config.latest();
-        let use_default = verge.use_default_bypass.unwrap_or(true);
-        let custom_bypass = verge.system_proxy_bypass.clone().unwrap_or_default();
-
-        #[cfg(target_os = "windows")]
-        let default_bypass = "localhost;127.*;192.168.*;10.*;172.16.*;172.17.*;172.18.*;172.19.*;172.20.*;172.21.*;172.22.*;172.23.*;172.24.*;172.25.*;172.26.*;172.27.*;172.28.*;172.29.*;172.30.*;172.31.*;<local>";
-
+    async fn restore_pac_proxy(expected_url: &str) {
         #[cfg(target_os = "linux")]
-        let default_bypass =
-            "localhost,127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,172.29.0.0/16,::1";
-
-        #[cfg(target_os = "macos")]
-        let default_bypass = "127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,172.29.0.0/16,localhost,*.local,*.crashlytics.com,<local>";
-
-        if custom_bypass.is_empty() {
-            default_bypass.to_string()
-        } else if use_default {
-            format!("{default_bypass},{custom_bypass}")
-        } else {
-            custom_bypass
+        {
+            Self::apply_linux_proxy(LinuxProxy::Pac(expected_url.to_string())).await;
         }
-    }
 
-    async fn restore_pac_proxy(expected_url: &str) {
-        #[cfg(not(target_os = "windows"))]
+        #[cfg(target_os = "macos")]
         {
             let new_autoproxy = Autoproxy {
                 enable: true,
@@ -512,7 +696,17 @@ impl EventDrivenProxyManager {
     }
 
     async fn restore_sys_proxy(expected: &Sysproxy) {
-        #[cfg(not(target_os = "windows"))]
+        #[cfg(target_os = "linux")]
+        {
+            Self::apply_linux_proxy(LinuxProxy::Manual {
+                host: expected.host.clone(),
+                port: expected.port,
+                bypass: expected.bypass.clone(),
+            })
+            .await;
+        }
+
+        #[cfg(target_os = "macos")]
         {
             logging_error!(Type::System, true, expected.set_system_proxy());
         }
@@ -524,6 +718,16 @@ impl EventDrivenProxyManager {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    async fn apply_linux_proxy(proxy: LinuxProxy) {
+        match linux_proxy::apply(&proxy).await {
+            Ok(changed) => {
+                log::debug!(target: "app", "Linux proxy backends updated: {changed:?}")
+            }
+            Err(e) => log::error!(target: "app", "Failed to apply Linux proxy: {e}"),
+        }
+    }
+
     #[cfg(target_os = "windows")]
     async fn execute_sysproxy_command(args: &[&str]) {
         use crate::utils::dirs;
@@ -569,3 +773,118 @@ impl EventDrivenProxyManager {
         }
     }
 }
+
+fn backoff_duration(level: u32) -> Duration {
+    BACKOFF_BASE
+        .checked_mul(1 << level.min(16))
+        .map_or(BACKOFF_MAX, |duration| duration.min(BACKOFF_MAX))
+}
+
+fn changed_fields(fields: Vec<(&'static str, String, String)>) -> Vec<ProxyFieldChange> {
+    fields
+        .into_iter()
+        .filter(|(_, expected, actual)| expected != actual)
+        .map(|(field, expected, actual)| ProxyFieldChange {
+            field,
+            expected,
+            actual,
+        })
+        .collect()
+}
+
+fn normalize_bypass(bypass: &str) -> BTreeSet<String> {
+    bypass
+        .split([',', ';'])
+        .map(|item| item.trim().to_ascii_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// 部分平台读不到绕过列表，读到空值时不比较
+fn diff_sys_proxy(expected: &Sysproxy, actual: &Sysproxy) -> Vec<ProxyFieldChange> {
+    let mut fields = vec![
+        (
+            "enable",
+            expected.enable.to_string(),
+            actual.enable.to_string(),
+        ),
+        ("host", expected.host.clone(), actual.host.clone()),
+        ("port", expected.port.to_string(), actual.port.to_string()),
+    ];
+    if !actual.bypass.is_empty()
+        && normalize_bypass(&expected.bypass) != normalize_bypass(&actual.bypass)
+    {
+        fields.push(("bypass", expected.bypass.clone(), actual.bypass.clone()));
+    }
+    changed_fields(fields)
+}
+
+fn diff_auto_proxy(expected: &Autoproxy, actual: &Autoproxy) -> Vec<ProxyFieldChange> {
+    changed_fields(vec![
+        (
+            "enable",
+            expected.enable.to_string(),
+            actual.enable.to_string(),
+        ),
+        ("pac_url", expected.url.clone(), actual.url.clone()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_proxy() {
+        let expected = Sysproxy {
+            enable: true,
+            host: "127.0.0.1".into(),
+            port: 7897,
+            bypass: "localhost;127.*".into(),
+        };
+        let same_bypass = Sysproxy {
+            bypass: "127.*, LOCALHOST".into(),
+            ..expected.clone()
+        };
+        assert!(diff_sys_proxy(&expected, &same_bypass).is_empty());
+
+        let unreadable_bypass = Sysproxy {
+            bypass: String::new(),
+            ..expected.clone()
+        };
+        assert!(diff_sys_proxy(&expected, &unreadable_bypass).is_empty());
+
+        let tampered = Sysproxy {
+            enable: true,
+            host: "127.0.0.1".into(),
+            port: 1080,
+            bypass: "localhost".into(),
+        };
+        let fields: Vec<_> = diff_sys_proxy(&expected, &tampered)
+            .into_iter()
+            .map(|change| change.field)
+            .collect();
+        assert_eq!(fields, vec!["port", "bypass"]);
+
+        let changes = diff_auto_proxy(
+            &Autoproxy {
+                enable: true,
+                url: "http://127.0.0.1:33331/commands/pac".into(),
+            },
+            &Autoproxy {
+                enable: false,
+                url: String::new(),
+            },
+        );
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].actual, "false");
+    }
+
+    #[test]
+    fn test_backoff_duration() {
+        assert_eq!(backoff_duration(0), Duration::from_secs(60));
+        assert_eq!(backoff_duration(2), Duration::from_secs(240));
+        assert_eq!(backoff_duration(10), BACKOFF_MAX);
+        assert_eq!(backoff_duration(u32::MAX), BACKOFF_MAX);
+    }
+}
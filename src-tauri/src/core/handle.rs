@@ -23,6 +23,8 @@ enum FrontendEvent {
     StartupCompleted,
     ProfileUpdateStarted { uid: String },
     ProfileUpdateCompleted { uid: String },
+    AnnouncementsUpdated,
+    ServiceRepairProgress { progress: serde_json::Value },
 }
 
 /// 事件发送统计和监控
@@ -145,6 +147,12 @@ impl NotificationSystem {
                                         FrontendEvent::ProfileUpdateCompleted { uid } => {
                                             ("profile-update-completed", Ok(serde_json::json!({ "uid": uid })))
                                         }
+                                        FrontendEvent::AnnouncementsUpdated => {
+                                            ("verge://announcements-updated", Ok(serde_json::json!(null)))
+                                        }
+                                        FrontendEvent::ServiceRepairProgress { progress } => {
+                                            ("verge://service-repair-progress", Ok(progress))
+                                        }
                                     };
 
                                     if let Ok(payload) = payload_result {
@@ -412,6 +420,34 @@ impl Handle {
         }
     }
 
+    pub fn notify_announcements_updated() {
+        let handle = Self::global();
+        if handle.is_exiting() {
+            return;
+        }
+
+        let system_opt = handle.notification_system.read();
+        if let Some(system) = system_opt.as_ref() {
+            system.send_event(FrontendEvent::AnnouncementsUpdated);
+        } else {
+            log::warn!("Notification system not initialized when trying to send AnnouncementsUpdated event.");
+        }
+    }
+
+    pub fn notify_service_repair_progress(progress: serde_json::Value) {
+        let handle = Self::global();
+        if handle.is_exiting() {
+            return;
+        }
+
+        let system_opt = handle.notification_system.read();
+        if let Some(system) = system_opt.as_ref() {
+            system.send_event(FrontendEvent::ServiceRepairProgress { progress });
+        } else {
+            log::warn!("Notification system not initialized when trying to send ServiceRepairProgress event.");
+        }
+    }
+
     /// 通知前端显示消息队列
     pub fn notice_message<S: Into<String>, M: Into<String>>(status: S, msg: M) {
         let handle = Self::global();
@@ -0,0 +1,474 @@
+//! Linux 桌面环境的系统代理设置
+//!
+//! 按 `XDG_CURRENT_DESKTOP` 选择后端：GNOME 系用 gsettings，KDE 用 kwriteconfig，
+//! XFCE 和没有桌面设置的环境再写入 `environment.d`
+
+use crate::utils::{bypass, dirs, help};
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt, path::PathBuf};
+use tokio::process::Command;
+
+const GNOME_SCHEMA: &str = "org.gnome.system.proxy";
+const KDE_FILE: &str = "kioslaverc";
+const KDE_GROUP: &str = "Proxy Settings";
+const ENV_FILE: &str = "koala-clash-proxy.conf";
+/// 记录写入过代理的后端，异常退出后仍能撤销
+const CHANGED_FILE: &str = "linux_proxy_backends.yaml";
+
+/// 使用 org.gnome.system.proxy 的桌面
+const GNOME_DESKTOPS: &[&str] = &[
+    "gnome",
+    "gnome-classic",
+    "gnome-flashback",
+    "ubuntu",
+    "unity",
+    "cinnamon",
+    "x-cinnamon",
+    "mate",
+    "budgie",
+    "pantheon",
+    "deepin",
+    "lxde",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyBackend {
+    Gsettings,
+    Kde,
+    Env,
+}
+
+impl fmt::Display for ProxyBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gsettings => write!(f, "gsettings"),
+            Self::Kde => write!(f, "kde"),
+            Self::Env => write!(f, "environment.d"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinuxProxy {
+    Off,
+    Manual {
+        host: String,
+        port: u16,
+        bypass: String,
+    },
+    Pac(String),
+}
+
+/// 写入过代理的后端，重置时只撤销这些
+static CHANGED: Lazy<Mutex<BTreeSet<ProxyBackend>>> = Lazy::new(|| Mutex::new(load_changed()));
+
+fn changed_file() -> Result<PathBuf> {
+    Ok(dirs::app_home_dir()?.join(CHANGED_FILE))
+}
+
+fn load_changed() -> BTreeSet<ProxyBackend> {
+    changed_file()
+        .and_then(|path| help::read_yaml(&path))
+        .unwrap_or_default()
+}
+
+fn save_changed(changed: &BTreeSet<ProxyBackend>) {
+    let result = changed_file().and_then(|path| match changed.is_empty() {
+        true => match std::fs::remove_file(&path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        },
+        false => help::save_yaml(&path, changed, None),
+    });
+    if let Err(err) = result {
+        log::warn!(target: "app", "Failed to save changed proxy backends: {err}");
+    }
+}
+
+fn current_desktop() -> String {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .or_else(|_| std::env::var("DESKTOP_SESSION"))
+        .unwrap_or_default()
+}
+
+/// `XDG_CURRENT_DESKTOP` 可能是 `ubuntu:GNOME` 这样的列表
+fn detect_backends(desktop: &str) -> Vec<ProxyBackend> {
+    let mut backends = BTreeSet::new();
+    for name in desktop
+        .split(':')
+        .map(|name| name.trim().to_ascii_lowercase())
+    {
+        match name.as_str() {
+            "kde" | "plasma" => {
+                backends.insert(ProxyBackend::Kde);
+            }
+            // XFCE 没有自己的代理设置，GTK 程序读 gsettings，其余读环境变量
+            "xfce" | "xfce4" => {
+                backends.insert(ProxyBackend::Gsettings);
+                backends.insert(ProxyBackend::Env);
+            }
+            name if GNOME_DESKTOPS.contains(&name) => {
+                backends.insert(ProxyBackend::Gsettings);
+            }
+            _ => {}
+        }
+    }
+    if backends.is_empty() {
+        backends.insert(ProxyBackend::Env);
+    }
+    backends.into_iter().collect()
+}
+
+fn find_program(names: &[&'static str]) -> Option<&'static str> {
+    let paths = std::env::var_os("PATH")?;
+    names
+        .iter()
+        .copied()
+        .find(|name| std::env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+}
+
+async fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program).args(args).output().await?;
+    if !output.status.success() {
+        bail!(
+            "{program} {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 逗号分隔的绕过列表转换为 gsettings 的字符串数组
+fn gsettings_list(bypass: &str) -> String {
+    let items: Vec<String> = bypass
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| format!("'{}'", item.replace('\'', "\\'")))
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+async fn gsettings(args: &[&str]) -> Result<String> {
+    run("gsettings", args).await
+}
+
+async fn apply_gsettings(proxy: &LinuxProxy) -> Result<()> {
+    match proxy {
+        LinuxProxy::Off => {
+            gsettings(&["set", GNOME_SCHEMA, "mode", "none"]).await?;
+        }
+        LinuxProxy::Manual { host, port, bypass } => {
+            let port = port.to_string();
+            for scheme in ["http", "https", "socks"] {
+                let schema = format!("{GNOME_SCHEMA}.{scheme}");
+                gsettings(&["set", &schema, "host", host]).await?;
+                gsettings(&["set", &schema, "port", &port]).await?;
+            }
+            let ignore_hosts = gsettings_list(bypass);
+            gsettings(&["set", GNOME_SCHEMA, "ignore-hosts", &ignore_hosts]).await?;
+            gsettings(&["set", GNOME_SCHEMA, "mode", "manual"]).await?;
+        }
+        LinuxProxy::Pac(url) => {
+            gsettings(&["set", GNOME_SCHEMA, "autoconfig-url", url]).await?;
+            gsettings(&["set", GNOME_SCHEMA, "mode", "auto"]).await?;
+        }
+    }
+    Ok(())
+}
+
+/// kioslaverc 中的代理地址格式为 `http://host port`
+fn kde_proxy_value(scheme: &str, host: &str, port: u16) -> String {
+    format!("{scheme}://{host} {port}")
+}
+
+/// 解析 `http://host port` 或 `http://host:port`
+fn parse_proxy_address(value: &str) -> Option<(String, u16)> {
+    let value = value
+        .split_once("://")
+        .map_or(value, |(_, rest)| rest)
+        .trim();
+    let (host, port) = match value.split_once(char::is_whitespace) {
+        Some((host, port)) => (host, port.trim()),
+        None => value.rsplit_once(':')?,
+    };
+    let port = port.parse().ok()?;
+    (!host.is_empty()).then(|| (host.to_string(), port))
+}
+
+async fn apply_kde(proxy: &LinuxProxy) -> Result<()> {
+    let kwriteconfig = find_program(&["kwriteconfig6", "kwriteconfig5"])
+        .ok_or(anyhow!("kwriteconfig not found"))?;
+    let write = |key: &'static str, value: String| async move {
+        run(
+            kwriteconfig,
+            &[
+                "--file",
+                KDE_FILE,
+                "--group",
+                KDE_GROUP,
+                "--key",
+                key,
+                value.as_str(),
+            ],
+        )
+        .await
+    };
+
+    match proxy {
+        LinuxProxy::Off => {
+            write("ProxyType", "0".into()).await?;
+        }
+        LinuxProxy::Manual { host, port, .. } => {
+            write("httpProxy", kde_proxy_value("http", host, *port)).await?;
+            write("httpsProxy", kde_proxy_value("http", host, *port)).await?;
+            write("socksProxy", kde_proxy_value("socks", host, *port)).await?;
+            write("NoProxyFor", bypass::no_proxy()).await?;
+            write("ProxyType", "1".into()).await?;
+        }
+        LinuxProxy::Pac(url) => {
+            write("Proxy Config Script", url.clone()).await?;
+            write("ProxyType", "2".into()).await?;
+        }
+    }
+
+    // 通知 KIO 重新读取配置，失败时只影响已经打开的程序
+    if let Err(err) = run(
+        "dbus-send",
+        &[
+            "--type=signal",
+            "/KIO/Scheduler",
+            "org.kde.KIO.Scheduler.reparseSlaveConfiguration",
+            "string:",
+        ],
+    )
+    .await
+    {
+        log::warn!(target: "app", "Failed to notify KIO of proxy change: {err}");
+    }
+    Ok(())
+}
+
+fn env_file_path() -> Result<PathBuf> {
+    let config_dir = ::dirs::config_dir().ok_or(anyhow!("config dir not found"))?;
+    Ok(config_dir.join("environment.d").join(ENV_FILE))
+}
+
+/// environment.d 在下次登录时生效，PAC 无法用环境变量表达
+async fn apply_env(proxy: &LinuxProxy) -> Result<()> {
+    let path = env_file_path()?;
+    let (host, port) = match proxy {
+        LinuxProxy::Manual { host, port, .. } => (host, port),
+        LinuxProxy::Pac(_) => bail!("PAC is not supported"),
+        LinuxProxy::Off => {
+            if path.exists() {
+                tokio::fs::remove_file(&path).await?;
+            }
+            return Ok(());
+        }
+    };
+
+    let http = format!("http://{host}:{port}");
+    let socks = format!("socks5://{host}:{port}");
+    let no_proxy = bypass::no_proxy();
+    let mut content = String::from("# Managed by Koala Clash\n");
+    for (key, value) in [
+        ("http_proxy", &http),
+        ("https_proxy", &http),
+        ("all_proxy", &socks),
+        ("no_proxy", &no_proxy),
+    ] {
+        content.push_str(&format!("{key}={value}\n"));
+        content.push_str(&format!("{}={value}\n", key.to_ascii_uppercase()));
+    }
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, content).await?;
+    Ok(())
+}
+
+async fn apply_backend(backend: ProxyBackend, proxy: &LinuxProxy) -> Result<()> {
+    match backend {
+        ProxyBackend::Gsettings => apply_gsettings(proxy).await,
+        ProxyBackend::Kde => apply_kde(proxy).await,
+        ProxyBackend::Env => apply_env(proxy).await,
+    }
+}
+
+async fn apply_backends(
+    backends: Vec<ProxyBackend>,
+    proxy: &LinuxProxy,
+) -> Result<Vec<ProxyBackend>> {
+    let mut changed = Vec::new();
+    let mut errors = Vec::new();
+    for backend in backends {
+        match apply_backend(backend, proxy).await {
+            Ok(()) => {
+                let mut recorded = CHANGED.lock();
+                let updated = match proxy {
+                    LinuxProxy::Off => recorded.remove(&backend),
+                    _ => recorded.insert(backend),
+                };
+                if updated {
+                    save_changed(&recorded);
+                }
+                changed.push(backend);
+            }
+            Err(err) => {
+                log::warn!(target: "app", "Failed to apply {backend} proxy settings: {err}");
+                errors.push(format!("{backend}: {err}"));
+            }
+        }
+    }
+    if changed.is_empty() && !errors.is_empty() {
+        bail!("failed to apply system proxy: {}", errors.join("; "));
+    }
+    Ok(changed)
+}
+
+/// 当前桌面能否设置 PAC，只有 environment.d 时不支持
+pub fn pac_supported() -> bool {
+    detect_backends(&current_desktop()) != [ProxyBackend::Env]
+}
+
+/// 按当前桌面写入代理设置，返回实际修改过的后端
+pub async fn apply(proxy: &LinuxProxy) -> Result<Vec<ProxyBackend>> {
+    let mut backends: BTreeSet<ProxyBackend> =
+        detect_backends(&current_desktop()).into_iter().collect();
+    // 关闭时同时撤销之前写过但当前桌面不再使用的后端
+    if *proxy == LinuxProxy::Off {
+        backends.extend(CHANGED.lock().iter().copied());
+    }
+    apply_backends(backends.into_iter().collect(), proxy).await
+}
+
+/// 撤销写入过的后端，没有记录时按当前桌面关闭
+pub async fn reset() -> Result<Vec<ProxyBackend>> {
+    let recorded: Vec<ProxyBackend> = CHANGED.lock().iter().copied().collect();
+    let backends = match recorded.is_empty() {
+        true => detect_backends(&current_desktop()),
+        false => recorded,
+    };
+    apply_backends(backends, &LinuxProxy::Off).await
+}
+
+/// 读取 KDE 的代理设置，非 KDE 桌面返回 None
+pub async fn read_kde() -> Option<LinuxProxy> {
+    if !detect_backends(&current_desktop()).contains(&ProxyBackend::Kde) {
+        return None;
+    }
+    let kreadconfig = find_program(&["kreadconfig6", "kreadconfig5"])?;
+    let read = |key: &'static str| async move {
+        run(
+            kreadconfig,
+            &["--file", KDE_FILE, "--group", KDE_GROUP, "--key", key],
+        )
+        .await
+        .unwrap_or_default()
+    };
+
+    // NoProxyFor 按 NO_PROXY 的语法写入，与系统代理的绕过列表格式不同，不返回
+    Some(match read("ProxyType").await.as_str() {
+        "1" => {
+            let (host, port) = parse_proxy_address(&read("httpProxy").await)?;
+            LinuxProxy::Manual {
+                host,
+                port,
+                bypass: String::new(),
+            }
+        }
+        "2" => LinuxProxy::Pac(read("Proxy Config Script").await),
+        _ => LinuxProxy::Off,
+    })
+}
+
+fn parse_env_file(content: &str) -> LinuxProxy {
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "http_proxy")
+        .and_then(|(_, value)| parse_proxy_address(value.trim()))
+        .map_or(LinuxProxy::Off, |(host, port)| LinuxProxy::Manual {
+            host,
+            port,
+            bypass: String::new(),
+        })
+}
+
+/// 读取 environment.d 中的代理设置，桌面有自己的代理设置时返回 None
+pub async fn read_env() -> Option<LinuxProxy> {
+    if detect_backends(&current_desktop()) != [ProxyBackend::Env] {
+        return None;
+    }
+    let content = tokio::fs::read_to_string(env_file_path().ok()?)
+        .await
+        .unwrap_or_default();
+    Some(parse_env_file(&content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_backends() {
+        assert_eq!(
+            detect_backends("ubuntu:GNOME"),
+            vec![ProxyBackend::Gsettings]
+        );
+        assert_eq!(detect_backends("KDE"), vec![ProxyBackend::Kde]);
+        assert_eq!(
+            detect_backends("XFCE"),
+            vec![ProxyBackend::Gsettings, ProxyBackend::Env]
+        );
+        assert_eq!(detect_backends("X-Cinnamon"), vec![ProxyBackend::Gsettings]);
+        assert_eq!(detect_backends(""), vec![ProxyBackend::Env]);
+        assert_eq!(detect_backends("sway"), vec![ProxyBackend::Env]);
+    }
+
+    #[test]
+    fn test_format_values() {
+        assert_eq!(
+            gsettings_list("localhost, 127.0.0.1,,192.168.0.0/16"),
+            "['localhost', '127.0.0.1', '192.168.0.0/16']"
+        );
+        assert_eq!(gsettings_list(""), "[]");
+        assert_eq!(
+            kde_proxy_value("http", "127.0.0.1", 7897),
+            "http://127.0.0.1 7897"
+        );
+        assert_eq!(
+            parse_proxy_address("http://127.0.0.1 7897"),
+            Some(("127.0.0.1".to_string(), 7897))
+        );
+        assert_eq!(
+            parse_proxy_address("http://127.0.0.1:7897"),
+            Some(("127.0.0.1".to_string(), 7897))
+        );
+        assert_eq!(parse_proxy_address(""), None);
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let content = "# Managed by Koala Clash\n\
+                       http_proxy=http://127.0.0.1:7897\n\
+                       HTTP_PROXY=http://127.0.0.1:7897\n\
+                       no_proxy=localhost,127.0.0.0/8\n";
+        assert_eq!(
+            parse_env_file(content),
+            LinuxProxy::Manual {
+                host: "127.0.0.1".into(),
+                port: 7897,
+                bypass: String::new(),
+            }
+        );
+        assert_eq!(parse_env_file(""), LinuxProxy::Off);
+    }
+}
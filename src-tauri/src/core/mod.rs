@@ -2,13 +2,19 @@ pub mod async_proxy_query;
 pub mod backup;
 #[allow(clippy::module_inception)]
 mod core;
+pub mod core_caps;
+pub mod core_log;
 pub mod event_driven_proxy;
 pub mod handle;
 pub mod hotkey;
+#[cfg(target_os = "linux")]
+pub mod linux_proxy;
+pub mod port_preflight;
 pub mod service;
 pub mod service_ipc;
 pub mod sysopt;
 pub mod timer;
+pub mod tproxy;
 pub mod tray;
 pub mod win_uwp;
 
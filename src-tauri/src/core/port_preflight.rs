@@ -0,0 +1,402 @@
+//! 启动内核前检查各监听端口是否被其他进程占用，需要时换用空闲端口
+
+use crate::config::{Config, IClashTemp, IVerge};
+use anyhow::Result;
+use serde::Serialize;
+use serde_yaml::Mapping;
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener},
+};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+
+/// 在原端口之后查找空闲端口的范围
+const SEARCH_RANGE: u16 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PortKind {
+    Mixed,
+    Socks,
+    Http,
+    #[cfg(not(target_os = "windows"))]
+    Redir,
+    #[cfg(target_os = "linux")]
+    Tproxy,
+    Controller,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PortConflict {
+    pub kind: PortKind,
+    pub port: u16,
+    pub pid: Option<u32>,
+    /// 占用端口的进程名，查不到时为 None
+    pub process: Option<String>,
+    /// 换用的新端口，未重新分配时为 None
+    pub reassigned: Option<u16>,
+}
+
+/// 当前配置下内核会监听的地址
+fn listeners() -> Vec<(PortKind, SocketAddr)> {
+    let clash = Config::clash().latest().0.clone();
+    let verge = Config::verge();
+    let verge = verge.latest();
+
+    // 未开启局域网时内核只监听本机地址
+    let allow_lan = clash
+        .get("allow-lan")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    let ip = match allow_lan {
+        true => clash
+            .get("bind-address")
+            .and_then(|value| value.as_str())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        false => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    };
+
+    let mut ports = vec![(PortKind::Mixed, IClashTemp::guard_mixed_port(&clash))];
+    if verge.verge_socks_enabled.unwrap_or(false) {
+        ports.push((PortKind::Socks, IClashTemp::guard_socks_port(&clash)));
+    }
+    if verge.verge_http_enabled.unwrap_or(false) {
+        ports.push((PortKind::Http, IClashTemp::guard_port(&clash)));
+    }
+    #[cfg(not(target_os = "windows"))]
+    if verge.verge_redir_enabled.unwrap_or(false) {
+        ports.push((PortKind::Redir, IClashTemp::guard_redir_port(&clash)));
+    }
+    #[cfg(target_os = "linux")]
+    if verge.verge_tproxy_enabled.unwrap_or(false) {
+        ports.push((PortKind::Tproxy, IClashTemp::guard_tproxy_port(&clash)));
+    }
+
+    let mut listeners: Vec<_> = ports
+        .into_iter()
+        .map(|(kind, port)| (kind, SocketAddr::new(ip, port)))
+        .collect();
+    if let Ok(ctrl) = IClashTemp::guard_server_ctrl(&clash).parse() {
+        listeners.push((PortKind::Controller, ctrl));
+    }
+    listeners
+}
+
+/// Windows 上被系统保留的端口返回 PermissionDenied，同样视为占用
+fn is_occupied(addr: SocketAddr) -> bool {
+    match TcpListener::bind(addr) {
+        Ok(_) => false,
+        Err(err) => matches!(
+            err.kind(),
+            ErrorKind::AddrInUse | ErrorKind::PermissionDenied
+        ),
+    }
+}
+
+fn process_name(pid: u32) -> Option<String> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    system
+        .process(pid)
+        .map(|process| process.name().to_string_lossy().into_owned())
+}
+
+/// 占用端口的是本应用的内核时不算冲突，Linux 的进程名最多 15 个字符
+fn is_own_core(name: &str, core: &str) -> bool {
+    let name = name.trim_end_matches(".exe");
+    name == core || (name.len() == 15 && core.starts_with(name))
+}
+
+/// 解析 /proc/net/tcp，返回监听指定端口的 socket inode
+#[cfg(target_os = "linux")]
+fn listening_inodes(table: &str, port: u16) -> Vec<String> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (_, local_port) = fields.get(1)?.rsplit_once(':')?;
+            let listening = *fields.get(3)? == "0A";
+            let matched = u16::from_str_radix(local_port, 16).ok()? == port;
+            (listening && matched).then(|| fields.get(9).map(|inode| inode.to_string()))?
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn owner_pid(port: u16) -> Option<u32> {
+    let sockets: Vec<String> = ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .flat_map(|table| listening_inodes(&table, port))
+        .map(|inode| format!("socket:[{inode}]"))
+        .collect();
+    if sockets.is_empty() {
+        return None;
+    }
+
+    // 其他用户的进程没有权限读取 fd，此时查不到
+    std::fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            Some((pid, std::fs::read_dir(entry.path().join("fd")).ok()?))
+        })
+        .find_map(|(pid, fds)| {
+            fds.flatten()
+                .filter_map(|fd| std::fs::read_link(fd.path()).ok())
+                .any(|link| {
+                    sockets
+                        .iter()
+                        .any(|socket| link.as_os_str() == socket.as_str())
+                })
+                .then_some(pid)
+        })
+}
+
+#[cfg(target_os = "macos")]
+fn owner_pid(port: u16) -> Option<u32> {
+    let output = std::process::Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{port}"), "-sTCP:LISTEN", "-t"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// 解析 `netstat -ano` 的输出，状态列会被本地化，用远端地址的端口 0 判断监听
+#[cfg(target_os = "windows")]
+fn netstat_owner(output: &str, port: u16) -> Option<u32> {
+    output.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 5 || !fields[0].eq_ignore_ascii_case("tcp") {
+            return None;
+        }
+        let (_, local_port) = fields[1].rsplit_once(':')?;
+        let listening = fields[2].ends_with(":0");
+        (listening && local_port.parse::<u16>().ok()? == port).then(|| fields[4].parse().ok())?
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn owner_pid(port: u16) -> Option<u32> {
+    #[allow(unused_imports)] // creation_flags必须
+    use std::os::windows::process::CommandExt;
+
+    let output = std::process::Command::new("netstat")
+        .args(["-ano", "-p", "TCP"])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+    netstat_owner(&String::from_utf8_lossy(&output.stdout), port)
+}
+
+/// 从原端口往后找空闲端口，都被占用时交给系统分配
+fn free_port(ip: IpAddr, from: u16, taken: &[u16]) -> Option<u16> {
+    (from.saturating_add(1)..=from.saturating_add(SEARCH_RANGE))
+        .find(|port| !taken.contains(port) && !is_occupied(SocketAddr::new(ip, *port)))
+        .or_else(|| {
+            TcpListener::bind(SocketAddr::new(ip, 0))
+                .and_then(|listener| listener.local_addr())
+                .map(|addr| addr.port())
+                .ok()
+        })
+}
+
+/// 把新端口写入 verge、clash 配置和运行时配置
+fn apply_ports(reassigned: &[(PortKind, SocketAddr)]) -> Result<()> {
+    let mut verge = IVerge::default();
+    let mut clash = Mapping::new();
+    for &(kind, addr) in reassigned {
+        let port = addr.port();
+        match kind {
+            PortKind::Mixed => {
+                verge.verge_mixed_port = Some(port);
+                clash.insert("mixed-port".into(), port.into());
+            }
+            PortKind::Socks => {
+                verge.verge_socks_port = Some(port);
+                clash.insert("socks-port".into(), port.into());
+            }
+            PortKind::Http => {
+                verge.verge_port = Some(port);
+                clash.insert("port".into(), port.into());
+            }
+            #[cfg(not(target_os = "windows"))]
+            PortKind::Redir => {
+                verge.verge_redir_port = Some(port);
+                clash.insert("redir-port".into(), port.into());
+            }
+            #[cfg(target_os = "linux")]
+            PortKind::Tproxy => {
+                verge.verge_tproxy_port = Some(port);
+                clash.insert("tproxy-port".into(), port.into());
+            }
+            PortKind::Controller => {
+                clash.insert("external-controller".into(), addr.to_string().into());
+            }
+        }
+    }
+
+    {
+        let verge_config = Config::verge();
+        let mut verge_data = verge_config.data();
+        verge_data.patch_config(verge);
+        verge_data.save_file()?;
+    }
+    {
+        let clash_config = Config::clash();
+        let mut clash_data = clash_config.data();
+        clash_data.patch_config(clash.clone());
+        clash_data.save_config()?;
+    }
+    if let Some(config) = Config::runtime().latest().config.as_mut() {
+        for (key, value) in clash {
+            config.insert(key, value);
+        }
+    }
+    Ok(())
+}
+
+/// 是否有本应用的内核进程在运行，按进程名或内核的路径判断
+fn own_core_running(core: &str) -> bool {
+    let core_path = std::env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name(format!("{core}{}", std::env::consts::EXE_SUFFIX)));
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    system.processes().values().any(|process| {
+        is_own_core(&process.name().to_string_lossy(), core)
+            || process
+                .exe()
+                .is_some_and(|exe| Some(exe) == core_path.as_deref())
+    })
+}
+
+/// 端口是否由本应用的内核占用
+///
+/// 服务模式下内核以 root 运行，普通用户查不到占用进程，此时只要内核在运行就视为由它占用
+fn is_managed(pid: Option<u32>, process: Option<&str>, core: &str, core_running: bool) -> bool {
+    match (pid, process) {
+        (_, Some(name)) => is_own_core(name, core),
+        (Some(_), None) => false,
+        (None, None) => core_running,
+    }
+}
+
+/// 检查所有监听端口，`reassign` 为 true 时为冲突的端口换用空闲端口
+pub fn check_ports(reassign: bool) -> Result<Vec<PortConflict>> {
+    let core = Config::verge().latest().get_valid_clash_core();
+    let core_running = own_core_running(&core);
+    let listeners = listeners();
+    let mut taken: Vec<u16> = listeners.iter().map(|(_, addr)| addr.port()).collect();
+    let mut conflicts = Vec::new();
+    let mut reassigned = Vec::new();
+
+    for &(kind, addr) in &listeners {
+        if !is_occupied(addr) {
+            continue;
+        }
+        let pid = owner_pid(addr.port());
+        let process = pid.and_then(process_name);
+        if is_managed(pid, process.as_deref(), &core, core_running) {
+            continue;
+        }
+
+        let new_port = match reassign {
+            true => free_port(addr.ip(), addr.port(), &taken),
+            false => None,
+        };
+        if let Some(port) = new_port {
+            taken.push(port);
+            reassigned.push((kind, SocketAddr::new(addr.ip(), port)));
+        }
+        log::warn!(
+            target: "app",
+            "{kind:?} port {} is used by {} (pid {pid:?}), reassigned to {new_port:?}",
+            addr.port(),
+            process.as_deref().unwrap_or("unknown process"),
+        );
+        conflicts.push(PortConflict {
+            kind,
+            port: addr.port(),
+            pid,
+            process,
+            reassigned: new_port,
+        });
+    }
+
+    if !reassigned.is_empty() {
+        apply_ports(&reassigned)?;
+    }
+    Ok(conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_core() {
+        assert!(is_own_core("koala-mihomo", "koala-mihomo"));
+        assert!(is_own_core("koala-mihomo.exe", "koala-mihomo"));
+        assert!(is_own_core("koala-mihomo-al", "koala-mihomo-alpha"));
+        assert!(!is_own_core("nginx", "koala-mihomo"));
+        assert!(!is_own_core("koala", "koala-mihomo"));
+    }
+
+    #[test]
+    fn test_managed_core() {
+        let core = "koala-mihomo";
+        assert!(is_managed(Some(1), Some("koala-mihomo"), core, false));
+        assert!(is_managed(None, None, core, true));
+        assert!(!is_managed(Some(7), Some("nginx"), core, true));
+        assert!(!is_managed(Some(7), None, core, true));
+        assert!(!is_managed(None, None, core, false));
+    }
+
+    #[test]
+    fn test_free_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert!(is_occupied(addr));
+
+        let port = free_port(addr.ip(), addr.port(), &[addr.port() + 1]).unwrap();
+        assert_ne!(port, addr.port());
+        assert_ne!(port, addr.port() + 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_listening_inodes() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1ED9 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 41235 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:1ED9 0100007F:D2F0 01 00000000:00000000 00:00000000 00000000  1000        0 51234 1 0000000000000000 20 4 30 10 -1
+   2: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1234 1 0000000000000000 100 0 0 10 0";
+        assert_eq!(listening_inodes(table, 7897), vec!["41235"]);
+        assert!(listening_inodes(table, 7898).is_empty());
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_netstat_owner() {
+        let output = "
+Active Connections
+
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:7897           0.0.0.0:0              LISTENING       4321
+  TCP    127.0.0.1:7898         127.0.0.1:50000        ESTABLISHED     1111
+  TCP    [::]:7898              [::]:0                 LISTENING       2222
+";
+        assert_eq!(netstat_owner(output, 7897), Some(4321));
+        assert_eq!(netstat_owner(output, 7898), Some(2222));
+        assert_eq!(netstat_owner(output, 7899), None);
+    }
+}
@@ -1,17 +1,25 @@
 use crate::{
     config::Config,
-    core::service_ipc::{send_ipc_request, IpcCommand},
+    core::{
+        core_log::CoreLogSink,
+        handle,
+        service_ipc::{self, send_ipc_request, IpcClient, IpcCommand, IpcProtocol, IpcResponse},
+        CoreManager, RunningMode,
+    },
     logging,
+    process::AsyncHandler,
     utils::{dirs, logging::Type},
 };
 use anyhow::{bail, Context, Result};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::{
     env::current_exe,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::Command as StdCommand,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
+use tauri::async_runtime::JoinHandle;
 
 const REQUIRED_SERVICE_VERSION: &str = "1.1.0"; // 定义所需的服务版本号
 
@@ -20,6 +28,18 @@ const REINSTALL_COOLDOWN_SECS: u64 = 300; // 5分钟冷却期
 const MAX_REINSTALLS_PER_DAY: u32 = 3; // 每24小时最多重装3次
 const ONE_DAY_SECS: u64 = 86400; // 24小时的秒数
 
+// 服务重启core需要等待旧进程退出，给予更长的超时
+const CORE_RESTART_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 安装后等待服务就绪的次数，每次间隔1秒
+const SERVICE_READY_ATTEMPTS: u32 = 10;
+
+// 日志订阅断开后重新订阅的间隔
+const LOG_RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+// 服务模式下接收 core 输出的后台任务
+static LOG_FOLLOWER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct ServiceState {
     pub last_install_time: u64,     // 上次安装时间戳 (Unix 时间戳，秒)
@@ -146,7 +166,7 @@ pub async fn uninstall_service() -> Result<()> {
 }
 
 #[cfg(target_os = "windows")]
-pub async fn install_service() -> Result<()> {
+async fn run_service_installer(secret_file: Option<&Path>) -> Result<()> {
     logging!(info, Type::Service, true, "install service");
 
     use deelevate::{PrivilegeLevel, Token};
@@ -160,11 +180,20 @@ pub async fn install_service() -> Result<()> {
         bail!(format!("installer not found: {install_path:?}"));
     }
 
+    let args: Vec<&std::ffi::OsStr> = match secret_file {
+        Some(secret_file) => vec!["--secret-file".as_ref(), secret_file.as_os_str()],
+        None => vec![],
+    };
+
     let token = Token::with_current_process()?;
     let level = token.privilege_level()?;
     let status = match level {
-        PrivilegeLevel::NotPrivileged => RunasCommand::new(install_path).show(false).status()?,
+        PrivilegeLevel::NotPrivileged => RunasCommand::new(install_path)
+            .args(&args[..])
+            .show(false)
+            .status()?,
         _ => StdCommand::new(install_path)
+            .args(&args)
             .creation_flags(0x08000000)
             .status()?,
     };
@@ -208,7 +237,7 @@ pub async fn reinstall_service() -> Result<()> {
         );
     }
 
-    // 再安装服务
+    // 再安装服务，同时轮换 IPC 密钥
     match install_service().await {
         Ok(_) => {
             // 记录安装信息并保存
@@ -268,7 +297,7 @@ pub async fn uninstall_service() -> Result<()> {
 }
 
 #[cfg(target_os = "linux")]
-pub async fn install_service() -> Result<()> {
+async fn run_service_installer(secret_file: Option<&Path>) -> Result<()> {
     logging!(info, Type::Service, true, "install service");
     use users::get_effective_uid;
 
@@ -279,14 +308,28 @@ pub async fn install_service() -> Result<()> {
     }
 
     let install_shell: String = install_path.to_string_lossy().replace(" ", "\\ ");
+    let secret_shell = secret_file
+        .map(|file| {
+            format!(
+                " --secret-file {}",
+                file.to_string_lossy().replace(" ", "\\ ")
+            )
+        })
+        .unwrap_or_default();
 
     let elevator = crate::utils::help::linux_elevator();
     let status = match get_effective_uid() {
-        0 => StdCommand::new(install_shell).status()?,
+        0 => {
+            let mut command = StdCommand::new(install_shell);
+            if let Some(secret_file) = secret_file {
+                command.arg("--secret-file").arg(secret_file);
+            }
+            command.status()?
+        }
         _ => StdCommand::new(elevator.clone())
             .arg("sh")
             .arg("-c")
-            .arg(install_shell)
+            .arg(format!("{install_shell}{secret_shell}"))
             .status()?,
     };
     logging!(
@@ -336,7 +379,7 @@ pub async fn reinstall_service() -> Result<()> {
         );
     }
 
-    // 再安装服务
+    // 再安装服务，同时轮换 IPC 密钥
     match install_service().await {
         Ok(_) => {
             // 记录安装信息并保存
@@ -392,7 +435,7 @@ pub async fn uninstall_service() -> Result<()> {
 }
 
 #[cfg(target_os = "macos")]
-pub async fn install_service() -> Result<()> {
+async fn run_service_installer(secret_file: Option<&Path>) -> Result<()> {
     use crate::utils::i18n::t;
 
     logging!(info, Type::Service, true, "install service");
@@ -405,10 +448,13 @@ pub async fn install_service() -> Result<()> {
     }
 
     let install_shell: String = install_path.to_string_lossy().into_owned();
+    let secret_shell = secret_file
+        .map(|file| format!(" --secret-file '{}'", file.to_string_lossy()))
+        .unwrap_or_default();
 
     let prompt = t("Service Administrator Prompt");
     let command = format!(
-        r#"do shell script "sudo '{install_shell}'" with administrator privileges with prompt "{prompt}""#
+        r#"do shell script "sudo '{install_shell}'{secret_shell}" with administrator privileges with prompt "{prompt}""#
     );
 
     // logging!(debug, Type::Service, true, "install command: {}", command);
@@ -456,7 +502,7 @@ pub async fn reinstall_service() -> Result<()> {
         );
     }
 
-    // 再安装服务
+    // 再安装服务，同时轮换 IPC 密钥
     match install_service().await {
         Ok(_) => {
             // 记录安装信息并保存
@@ -475,6 +521,26 @@ pub async fn reinstall_service() -> Result<()> {
     }
 }
 
+/// 安装服务，内置的服务支持时每次安装都会生成新的 IPC 密钥并交给安装程序保存
+/// 安装失败时继续使用原来的密钥
+pub async fn install_service() -> Result<()> {
+    let result = match IpcProtocol::for_version(REQUIRED_SERVICE_VERSION) {
+        IpcProtocol::Legacy => run_service_installer(None).await,
+        IpcProtocol::Secure => {
+            let secret_file = service_ipc::prepare_secret_key()?;
+            match run_service_installer(Some(&secret_file)).await {
+                Ok(()) => service_ipc::commit_secret_key(),
+                Err(err) => {
+                    service_ipc::discard_secret_key();
+                    Err(err)
+                }
+            }
+        }
+    };
+    IpcClient::global().reset_protocol();
+    result
+}
+
 /// 检查服务状态 - 使用IPC通信
 pub async fn check_ipc_service_status() -> Result<JsonResponse> {
     logging!(
@@ -717,82 +783,37 @@ pub async fn check_service_needs_reinstall() -> bool {
         "Checking whether service needs reinstallation"
     );
 
-    let service_state = ServiceState::get();
-
-    if !service_state.can_reinstall() {
+    let mut health = check_service_health().await;
+    if !health.can_reinstall {
         log::info!(target: "app", "Service reinstall check: in cooldown period or max attempts reached");
         return false;
     }
 
-    // 检查版本和可用性
-    match check_service_version().await {
-        Ok(version) => {
-            log::info!(target: "app", "Service version check: current={version}, required={REQUIRED_SERVICE_VERSION}");
-            /*             logging!(
-                info,
-                Type::Service,
-                true,
-                "服务版本检测：当前={}, 要求={}",
-                version,
-                REQUIRED_SERVICE_VERSION
-            ); */
-
-            let needs_reinstall = version != REQUIRED_SERVICE_VERSION;
-            if needs_reinstall {
-                log::warn!(target: "app", "Service version mismatch detected, reinstallation required! current={version}, required={REQUIRED_SERVICE_VERSION}");
-                logging!(
-                    warn,
-                    Type::Service,
-                    true,
-                    "Service version mismatch, reinstallation required"
-                );
-
-                // log::debug!(target: "app", "当前版本字节: {:?}", version.as_bytes());
-                // log::debug!(target: "app", "要求版本字节: {:?}", REQUIRED_SERVICE_VERSION.as_bytes());
-            } else {
-                log::info!(target: "app", "Service version matches, no reinstallation needed");
-                // logging!(info, Type::Service, true, "服务版本匹配，无需重装");
-            }
-
-            needs_reinstall
-        }
-        Err(err) => {
-            logging!(
-                error,
-                Type::Service,
-                true,
-                "Failed to check service version: {}",
-                err
-            );
+    // 服务已安装但暂时连不上时稍等再确认一次
+    if health.installed && !health.socket_reachable {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        health = check_service_health().await;
+    }
 
-            // 检查服务是否可用
-            match is_service_available().await {
-                Ok(()) => {
-                    log::info!(target: "app", "Service is running but version check failed: {err}");
-                    /*                     logging!(
-                        info,
-                        Type::Service,
-                        true,
-                        "服务正在运行但版本检查失败: {}",
-                        err
-                    ); */
-                    false
-                }
-                _ => {
-                    log::info!(target: "app", "Service unavailable or not running, reinstallation needed");
-                    // logging!(info, Type::Service, true, "服务不可用或未运行，需要重装");
-                    true
-                }
-            }
-        }
+    let needs_reinstall = health.needs_auto_reinstall();
+    if needs_reinstall {
+        log::warn!(
+            target: "app",
+            "Service needs {:?}: installed={}, reachable={}, signature_valid={}, version={:?}, required={REQUIRED_SERVICE_VERSION}",
+            health.recommended_action,
+            health.installed,
+            health.socket_reachable,
+            health.signature_valid,
+            health.version
+        );
+    } else {
+        log::info!(target: "app", "Service is healthy, no reinstallation needed");
     }
+    needs_reinstall
 }
 
-/// 尝试使用服务启动core
-pub(super) async fn start_with_existing_service(config_file: &PathBuf) -> Result<()> {
-    log::info!(target:"app", "Attempting to start core with existing service (IPC)");
-    // logging!(info, Type::Service, true, "尝试使用现有服务启动核心");
-
+/// 服务启动和重启core时使用的参数
+fn core_start_payload(config_file: &PathBuf) -> Result<serde_json::Value> {
     let clash_core = Config::verge().latest().get_valid_clash_core();
 
     let bin_ext = if cfg!(windows) { ".exe" } else { "" };
@@ -808,14 +829,49 @@ pub(super) async fn start_with_existing_service(config_file: &PathBuf) -> Result
 
     let config_file = dirs::path_to_str(config_file)?;
 
-    // 构建启动参数
-    let payload = serde_json::json!({
+    Ok(serde_json::json!({
         "core_type": clash_core,
         "bin_path": bin_path,
         "config_dir": config_dir,
         "config_file": config_file,
         "log_file": log_path,
-    });
+    }))
+}
+
+/// 解析服务响应，`code` 不为 0 时视为失败，返回嵌套的 data
+fn service_response_data(response: IpcResponse, action: &str) -> Result<Option<serde_json::Value>> {
+    if !response.success {
+        bail!(response
+            .error
+            .unwrap_or_else(|| format!("Failed to {action}")));
+    }
+
+    let Some(data) = response.data else {
+        return Ok(None);
+    };
+
+    match data.get("code") {
+        Some(code) => {
+            if code.as_u64().unwrap_or(1) != 0 {
+                let msg = data
+                    .get("msg")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("Unknown error");
+                bail!("Failed to {action}: {msg}");
+            }
+            Ok(data.get("data").cloned())
+        }
+        None => Ok(Some(data)),
+    }
+}
+
+/// 尝试使用服务启动core
+pub(super) async fn start_with_existing_service(config_file: &PathBuf) -> Result<()> {
+    log::info!(target:"app", "Attempting to start core with existing service (IPC)");
+    // logging!(info, Type::Service, true, "尝试使用现有服务启动核心");
+
+    // 构建启动参数
+    let payload = core_start_payload(config_file)?;
 
     // log::info!(target:"app", "启动服务参数: {:?}", payload);
     // logging!(info, Type::Service, true, "发送StartClash请求");
@@ -1014,6 +1070,318 @@ pub(super) async fn stop_core_by_service() -> Result<()> {
     Ok(())
 }
 
+/// 当前安装的服务是否支持该命令，旧版本服务只支持启动、停止和查询
+pub async fn service_supports(command: IpcCommand) -> bool {
+    IpcClient::global().supports(&command).await
+}
+
+/// 通过服务重启core
+pub(super) async fn restart_core_by_service(config_file: &PathBuf) -> Result<()> {
+    logging!(
+        info,
+        Type::Service,
+        true,
+        "Restarting core via service (IPC)"
+    );
+
+    let payload = core_start_payload(config_file)?;
+    let response = IpcClient::global()
+        .request_with_timeout(IpcCommand::RestartClash, payload, CORE_RESTART_TIMEOUT)
+        .await
+        .context("Unable to connect to Koala Clash Service")?;
+    service_response_data(response, "restart core")?;
+    Ok(())
+}
+
+/// 获取服务中 core 最近的日志
+pub async fn get_core_logs_by_service(lines: usize) -> Result<Vec<String>> {
+    let payload = serde_json::json!({ "lines": lines });
+    let response = send_ipc_request(IpcCommand::GetClashLogs, payload)
+        .await
+        .context("Unable to connect to Koala Clash Service")?;
+
+    let logs = service_response_data(response, "get core logs")?
+        .and_then(|data| data.get("logs").cloned())
+        .map(serde_json::from_value::<Vec<String>>)
+        .transpose()?
+        .unwrap_or_default();
+    Ok(logs)
+}
+
+/// 订阅服务转发的 core 输出，写入与 sidecar 模式相同的日志管道
+/// 连接断开时只要仍处于服务模式就会重新订阅
+/// 旧版本服务不支持订阅，core 输出仍由服务写入启动参数中的 `log_file`
+pub fn start_core_log_stream() {
+    let follower = AsyncHandler::spawn(|| async {
+        if !service_supports(IpcCommand::SubscribeLogs).await {
+            logging!(
+                info,
+                Type::Service,
+                true,
+                "Service does not support log streaming, core output stays in the service log file"
+            );
+            return;
+        }
+
+        let mut sink = None;
+        loop {
+            match IpcClient::global()
+                .subscribe(IpcCommand::SubscribeLogs, serde_json::json!({}))
+                .await
+            {
+                Ok(mut events) => {
+                    if sink.is_none() {
+                        match CoreLogSink::create("service") {
+                            Ok(created) => sink = Some(created),
+                            Err(e) => {
+                                logging!(
+                                    error,
+                                    Type::Service,
+                                    true,
+                                    "Failed to create core log file: {}",
+                                    e
+                                );
+                                return;
+                            }
+                        }
+                    }
+                    while let Some(event) = events.recv().await {
+                        if let Some(sink) = sink.as_mut() {
+                            sink.write(event.stream, &event.line);
+                        }
+                    }
+                    logging!(
+                        warn,
+                        Type::Service,
+                        true,
+                        "Core log stream from service closed"
+                    );
+                }
+                Err(e) => {
+                    logging!(
+                        warn,
+                        Type::Service,
+                        true,
+                        "Unable to subscribe to core logs from service: {}",
+                        e
+                    );
+                    // 首次订阅失败时不再重试，core 输出仍在服务的日志文件中
+                    if sink.is_none() {
+                        return;
+                    }
+                }
+            }
+
+            tokio::time::sleep(LOG_RESUBSCRIBE_DELAY).await;
+            if CoreManager::global().get_running_mode().await != RunningMode::Service {
+                return;
+            }
+        }
+    });
+
+    if let Some(previous) = LOG_FOLLOWER.lock().replace(follower) {
+        previous.abort();
+    }
+}
+
+/// 停止接收服务转发的 core 输出
+pub fn stop_core_log_stream() {
+    if let Some(follower) = LOG_FOLLOWER.lock().take() {
+        follower.abort();
+    }
+}
+
+/// 通过服务设置系统 DNS，servers 为空时恢复系统默认
+pub async fn set_dns_by_service(servers: Vec<String>) -> Result<()> {
+    logging!(
+        info,
+        Type::Service,
+        true,
+        "Setting system DNS via service: {:?}",
+        servers
+    );
+
+    let payload = serde_json::json!({ "servers": servers });
+    let response = send_ipc_request(IpcCommand::SetDns, payload)
+        .await
+        .context("Unable to connect to Koala Clash Service")?;
+    service_response_data(response, "set DNS")?;
+    Ok(())
+}
+
+/// 服务自身上报的运行状况
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct ServiceHealthReport {
+    pub version: Option<String>,
+    pub core_running: bool,
+    pub core_pid: Option<u32>,
+    pub core_uptime_secs: Option<u64>,
+    pub core_restarts: u32,
+    pub last_error: Option<String>,
+}
+
+/// 获取服务的运行状况报告
+pub async fn get_service_health_report() -> Result<ServiceHealthReport> {
+    let response = send_ipc_request(IpcCommand::GetHealth, serde_json::json!({}))
+        .await
+        .context("Unable to connect to Koala Clash Service")?;
+
+    match service_response_data(response, "get service health")? {
+        Some(data) => Ok(serde_json::from_value(data)?),
+        None => bail!("No data in service health response"),
+    }
+}
+
+/// 建议的修复操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceRepairAction {
+    None,
+    Install,
+    /// 重装服务，同时轮换 IPC 密钥
+    Reinstall,
+}
+
+/// 服务的整体状况
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceHealth {
+    pub installed: bool,
+    pub running: bool,
+    pub socket_reachable: bool,
+    pub signature_valid: bool,
+    /// 新版本服务所需的本机 IPC 密钥缺失或无法读取，只能重装服务
+    pub secret_missing: bool,
+    pub version: Option<String>,
+    pub required_version: String,
+    pub version_match: bool,
+    /// 是否处于重装冷却期之外
+    pub can_reinstall: bool,
+    pub last_error: Option<String>,
+    /// 新版本服务上报的 core 状态
+    pub report: Option<ServiceHealthReport>,
+    pub recommended_action: ServiceRepairAction,
+}
+
+impl ServiceHealth {
+    fn recommend(&self) -> ServiceRepairAction {
+        if !self.installed && !self.socket_reachable {
+            ServiceRepairAction::Install
+        } else if !self.socket_reachable
+            || !self.running
+            || !self.signature_valid
+            || self.secret_missing
+            || !self.version_match
+        {
+            ServiceRepairAction::Reinstall
+        } else {
+            ServiceRepairAction::None
+        }
+    }
+
+    /// 自动重装只处理服务不可用或版本不一致，签名错误等交给手动修复
+    fn needs_auto_reinstall(&self) -> bool {
+        !self.socket_reachable
+            || (self.signature_valid && self.version.is_some() && !self.version_match)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn is_service_installed() -> bool {
+    use std::os::windows::process::CommandExt;
+
+    StdCommand::new("sc")
+        .args(["query", "koala_clash_service"])
+        .creation_flags(0x08000000)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+fn is_service_installed() -> bool {
+    Path::new("/etc/systemd/system/koala_clash_service.service").exists()
+}
+
+#[cfg(target_os = "macos")]
+fn is_service_installed() -> bool {
+    Path::new("/Library/LaunchDaemons/io.github.koala-clash.service.plist").exists()
+}
+
+/// 检查服务的安装、连接、签名和版本状况
+pub async fn check_service_health() -> ServiceHealth {
+    let service_state = ServiceState::get();
+    let mut health = ServiceHealth {
+        installed: is_service_installed(),
+        running: false,
+        socket_reachable: IpcClient::global().is_reachable().await,
+        signature_valid: false,
+        secret_missing: false,
+        version: None,
+        required_version: REQUIRED_SERVICE_VERSION.to_string(),
+        version_match: false,
+        can_reinstall: service_state.can_reinstall(),
+        last_error: None,
+        report: None,
+        recommended_action: ServiceRepairAction::None,
+    };
+
+    let mut probe_error = None;
+    if health.socket_reachable {
+        match send_ipc_request(IpcCommand::GetVersion, serde_json::json!({})).await {
+            Ok(response) => {
+                health.running = true;
+                health.signature_valid = true;
+                match service_response_data(response, "get service version") {
+                    Ok(data) => {
+                        health.version = data
+                            .as_ref()
+                            .and_then(|data| data.get("version"))
+                            .and_then(|version| version.as_str())
+                            .map(str::to_string);
+                    }
+                    Err(e) => probe_error = Some(e.to_string()),
+                }
+            }
+            Err(e) => {
+                // 签名不一致说明服务仍在运行，只是密钥不匹配
+                health.running = e.downcast_ref::<service_ipc::IpcAuthError>().is_some();
+                probe_error = Some(e.to_string());
+            }
+        }
+    }
+    health.version_match = health.version.as_deref() == Some(REQUIRED_SERVICE_VERSION);
+
+    // 版本查询使用共享密钥，新版本服务还需确认本机密钥可用且与服务一致
+    // 旧版本服务不支持 GetHealth
+    let secure = health
+        .version
+        .as_deref()
+        .is_some_and(|version| IpcProtocol::for_version(version) == IpcProtocol::Secure);
+    if health.signature_valid && secure {
+        if let Err(e) = service_ipc::check_secret_key() {
+            health.secret_missing = true;
+            probe_error = Some(e.to_string());
+        } else {
+            match get_service_health_report().await {
+                Ok(report) => health.report = Some(report),
+                Err(e) => {
+                    if e.downcast_ref::<service_ipc::IpcAuthError>().is_some() {
+                        health.signature_valid = false;
+                    }
+                    probe_error = Some(e.to_string());
+                }
+            }
+        }
+    }
+
+    health.last_error = probe_error
+        .or(service_state.last_error)
+        .or_else(|| health.report.as_ref().and_then(|r| r.last_error.clone()));
+    health.recommended_action = health.recommend();
+    health
+}
+
 /// 检查服务是否正在运行
 pub async fn is_service_available() -> Result<()> {
     logging!(
@@ -1053,25 +1421,155 @@ pub async fn is_service_available() -> Result<()> {
     }
 }
 
-/// 强制重装服务（UI修复按钮）
-pub async fn force_reinstall_service() -> Result<()> {
-    log::info!(target: "app", "User requested forced service reinstallation");
+/// 修复服务的步骤
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceRepairStep {
+    Diagnose,
+    Uninstall,
+    Install,
+    WaitForService,
+    Verify,
+    RestartCore,
+}
 
-    let service_state = ServiceState::default();
-    service_state.save()?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceRepairStatus {
+    Running,
+    Done,
+    Skipped,
+    Failed,
+}
 
-    log::info!(target: "app", "Service state reset, starting reinstallation");
+/// 修复进度，通过 `verge://service-repair-progress` 发送给前端
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceRepairProgress {
+    pub step: ServiceRepairStep,
+    pub status: ServiceRepairStatus,
+    pub message: Option<String>,
+}
 
-    match reinstall_service().await {
-        Ok(()) => {
-            log::info!(target: "app", "Service reinstalled successfully");
-            Ok(())
+fn report_repair_progress(
+    step: ServiceRepairStep,
+    status: ServiceRepairStatus,
+    message: Option<String>,
+) {
+    logging!(
+        info,
+        Type::Service,
+        true,
+        "Service repair {:?}: {:?} {}",
+        step,
+        status,
+        message.as_deref().unwrap_or_default()
+    );
+    let progress = ServiceRepairProgress {
+        step,
+        status,
+        message,
+    };
+    match serde_json::to_value(&progress) {
+        Ok(progress) => handle::Handle::notify_service_repair_progress(progress),
+        Err(e) => log::warn!(target: "app", "Failed to serialize repair progress: {e}"),
+    }
+}
+
+// 步骤失败时记录错误并结束修复
+fn fail_repair_step(step: ServiceRepairStep, err: anyhow::Error) -> anyhow::Error {
+    report_repair_progress(step, ServiceRepairStatus::Failed, Some(err.to_string()));
+    let mut service_state = ServiceState::get();
+    service_state.last_error = Some(err.to_string());
+    if let Err(e) = service_state.save() {
+        log::warn!(target: "app", "Failed to save service state: {e}");
+    }
+    err
+}
+
+/// 按诊断结果逐步修复服务（UI修复按钮），每一步都会发送进度事件
+/// 用户主动修复时不受重装冷却期限制
+pub async fn repair_service() -> Result<ServiceHealth> {
+    use ServiceRepairStatus::{Done, Running, Skipped};
+    use ServiceRepairStep::*;
+
+    log::info!(target: "app", "User requested service repair");
+
+    report_repair_progress(Diagnose, Running, None);
+    let health = check_service_health().await;
+    report_repair_progress(
+        Diagnose,
+        Done,
+        Some(format!("{:?}", health.recommended_action)),
+    );
+
+    let action = health.recommended_action;
+    if action == ServiceRepairAction::Reinstall && health.installed {
+        report_repair_progress(Uninstall, Running, None);
+        match uninstall_service().await {
+            Ok(()) => report_repair_progress(Uninstall, Done, None),
+            // 卸载失败时安装程序仍会覆盖旧服务
+            Err(e) => report_repair_progress(Uninstall, Skipped, Some(e.to_string())),
         }
-        Err(err) => {
-            log::error!(target: "app", "Forced service reinstallation failed: {err}");
-            bail!("Forced service reinstallation failed: {}", err)
+    } else {
+        report_repair_progress(Uninstall, Skipped, None);
+    }
+
+    if action == ServiceRepairAction::None {
+        report_repair_progress(Install, Skipped, None);
+    } else {
+        report_repair_progress(Install, Running, None);
+        install_service()
+            .await
+            .map_err(|e| fail_repair_step(Install, e))?;
+
+        let mut service_state = ServiceState::get();
+        service_state.record_install();
+        service_state.last_error = None;
+        service_state.prefer_sidecar = false;
+        service_state.save()?;
+        report_repair_progress(Install, Done, None);
+    }
+
+    report_repair_progress(WaitForService, Running, None);
+    let mut reachable = false;
+    for _ in 0..SERVICE_READY_ATTEMPTS {
+        if IpcClient::global().is_reachable().await {
+            reachable = true;
+            break;
         }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    if !reachable {
+        return Err(fail_repair_step(
+            WaitForService,
+            anyhow::anyhow!("Service did not start after installation"),
+        ));
     }
+    report_repair_progress(WaitForService, Done, None);
+
+    report_repair_progress(Verify, Running, None);
+    let health = check_service_health().await;
+    if health.recommended_action != ServiceRepairAction::None {
+        let reason = health
+            .last_error
+            .clone()
+            .unwrap_or_else(|| format!("{:?}", health.recommended_action));
+        return Err(fail_repair_step(
+            Verify,
+            anyhow::anyhow!("Service is still unhealthy after repair: {reason}"),
+        ));
+    }
+    report_repair_progress(Verify, Done, None);
+
+    report_repair_progress(RestartCore, Running, None);
+    CoreManager::global()
+        .restart_core()
+        .await
+        .map_err(|e| fail_repair_step(RestartCore, e))?;
+    report_repair_progress(RestartCore, Done, None);
+
+    log::info!(target: "app", "Service repaired successfully");
+    Ok(health)
 }
 /*
 /// 彻底诊断服务状态，检查安装状态、IPC通信和服务版本
@@ -1248,3 +1746,99 @@ pub async fn force_reinstall_service() -> Result<()> {
     logging!(info, Type::Service, true, "============= 服务诊断完成 =============");
     Ok(())
 } */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn healthy() -> ServiceHealth {
+        ServiceHealth {
+            installed: true,
+            running: true,
+            socket_reachable: true,
+            signature_valid: true,
+            secret_missing: false,
+            version: Some(REQUIRED_SERVICE_VERSION.to_string()),
+            required_version: REQUIRED_SERVICE_VERSION.to_string(),
+            version_match: true,
+            can_reinstall: true,
+            last_error: None,
+            report: None,
+            recommended_action: ServiceRepairAction::None,
+        }
+    }
+
+    #[test]
+    fn test_recommend_action() {
+        assert_eq!(healthy().recommend(), ServiceRepairAction::None);
+
+        let missing = ServiceHealth {
+            installed: false,
+            socket_reachable: false,
+            running: false,
+            ..healthy()
+        };
+        assert_eq!(missing.recommend(), ServiceRepairAction::Install);
+
+        let stopped = ServiceHealth {
+            socket_reachable: false,
+            running: false,
+            ..healthy()
+        };
+        assert_eq!(stopped.recommend(), ServiceRepairAction::Reinstall);
+
+        let bad_key = ServiceHealth {
+            signature_valid: false,
+            ..healthy()
+        };
+        assert_eq!(bad_key.recommend(), ServiceRepairAction::Reinstall);
+
+        let missing_key = ServiceHealth {
+            secret_missing: true,
+            ..healthy()
+        };
+        assert_eq!(missing_key.recommend(), ServiceRepairAction::Reinstall);
+
+        let outdated = ServiceHealth {
+            version: Some("1.0.0".into()),
+            version_match: false,
+            ..healthy()
+        };
+        assert_eq!(outdated.recommend(), ServiceRepairAction::Reinstall);
+    }
+
+    #[test]
+    fn test_needs_auto_reinstall() {
+        assert!(!healthy().needs_auto_reinstall());
+
+        let outdated = ServiceHealth {
+            version: Some("1.0.0".into()),
+            version_match: false,
+            ..healthy()
+        };
+        assert!(outdated.needs_auto_reinstall());
+
+        let unreachable = ServiceHealth {
+            socket_reachable: false,
+            running: false,
+            ..healthy()
+        };
+        assert!(unreachable.needs_auto_reinstall());
+
+        // 服务在运行但签名不一致或版本未知时不自动重装
+        let bad_key = ServiceHealth {
+            signature_valid: false,
+            version: None,
+            version_match: false,
+            ..healthy()
+        };
+        assert!(!bad_key.needs_auto_reinstall());
+
+        let unknown_version = ServiceHealth {
+            version: None,
+            version_match: false,
+            ..healthy()
+        };
+        assert!(!unknown_version.needs_auto_reinstall());
+    }
+}
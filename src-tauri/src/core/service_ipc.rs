@@ -1,9 +1,30 @@
-use crate::{logging, utils::logging::Type};
+use crate::{
+    core::core_log::CoreLogStream,
+    logging,
+    utils::{dirs, logging::Type},
+};
 use anyhow::{bail, Context, Result};
 use hmac::{Hmac, Mac};
+use once_cell::sync::OnceCell;
+use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
 
 const IPC_SOCKET_NAME: &str = if cfg!(windows) {
     r"\\.\pipe\koala-clash-service"
@@ -11,6 +32,75 @@ const IPC_SOCKET_NAME: &str = if cfg!(windows) {
     "/tmp/koala-clash-service.sock"
 };
 
+/// 请求和响应的时间戳允许的最大偏差
+pub const IPC_TIME_WINDOW_SECS: u64 = 30;
+
+/// 支持每次安装生成的密钥和响应重放校验的最低服务版本，更早的服务只认内置的共享密钥
+/// 该版本同时支持在一条连接上处理多个请求、推送事件以及 RestartClash 等新增命令
+pub const SECURE_IPC_VERSION: &str = "1.2.0";
+
+/// 密钥长度（字节）
+const IPC_SECRET_LEN: usize = 32;
+
+// 缓存的每次安装生成的密钥，安装服务后重新加载
+static SECRET_KEY: RwLock<Option<Vec<u8>>> = RwLock::new(None);
+
+// 记录最近收到的响应，拒绝重放
+static RESPONSE_GUARD: Mutex<Option<ReplayGuard>> = Mutex::new(None);
+
+/// 与服务通信使用的协议，由服务上报的版本决定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpcProtocol {
+    /// 旧版本服务：内置的共享密钥，响应不带时间戳
+    Legacy,
+    /// 每次安装生成的密钥，响应带时间戳并拒绝重放
+    Secure,
+}
+
+impl IpcProtocol {
+    pub fn for_version(version: &str) -> Self {
+        if version_at_least(version, SECURE_IPC_VERSION) {
+            Self::Secure
+        } else {
+            Self::Legacy
+        }
+    }
+
+    /// 旧版本服务只支持最初的几个命令
+    pub fn supports(self, command: &IpcCommand) -> bool {
+        match self {
+            Self::Secure => true,
+            Self::Legacy => matches!(
+                command,
+                IpcCommand::GetClash
+                    | IpcCommand::GetVersion
+                    | IpcCommand::StartClash
+                    | IpcCommand::StopClash
+            ),
+        }
+    }
+}
+
+/// 解析 `x.y.z` 格式的版本号，缺少的部分视为 0
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let mut parts = [0u64; 3];
+    let mut iter = version.trim().trim_start_matches('v').split('.');
+    for part in parts.iter_mut() {
+        if let Some(value) = iter.next() {
+            *part = value.parse().ok()?;
+        }
+    }
+    iter.next().is_none().then_some(parts)
+}
+
+/// 版本号是否不低于 required，无法解析时视为更低
+pub fn version_at_least(version: &str, required: &str) -> bool {
+    match (parse_version(version), parse_version(required)) {
+        (Some(version), Some(required)) => version >= required,
+        _ => false,
+    }
+}
+
 // 定义命令类型
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IpcCommand {
@@ -18,6 +108,12 @@ pub enum IpcCommand {
     GetVersion,
     StartClash,
     StopClash,
+    RestartClash,
+    GetClashLogs,
+    SetDns,
+    GetHealth,
+    /// 订阅 core 的 stdout/stderr 以及服务事件，服务以 [`IpcEvent`] 持续推送
+    SubscribeLogs,
 }
 
 // IPC消息格式
@@ -36,29 +132,178 @@ pub struct IpcResponse {
     pub success: bool,
     pub data: Option<serde_json::Value>,
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
     pub signature: String,
 }
 
-// 密钥派生函数
-fn derive_secret_key() -> Vec<u8> {
-    // to do
-    // 从系统安全存储中获取或从程序安装时生成的密钥文件中读取
+/// 服务推送的事件，id 为订阅请求的 id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpcEvent {
+    pub id: String,
+    pub stream: CoreLogStream,
+    pub line: String,
+    pub timestamp: u64,
+    pub signature: String,
+}
+
+// 连接上收到的消息，事件包含 stream 字段，优先匹配
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum IpcFrame {
+    Event(IpcEvent),
+    Response(IpcResponse),
+}
+
+/// 按时间窗口和请求 id 拒绝过期或重放的消息
+#[derive(Debug)]
+pub struct ReplayGuard {
+    window_secs: u64,
+    seen: HashMap<String, u64>,
+}
+
+impl ReplayGuard {
+    pub fn new(window_secs: u64) -> Self {
+        Self {
+            window_secs,
+            seen: HashMap::new(),
+        }
+    }
+
+    pub fn check(&mut self, id: &str, timestamp: u64, now: u64) -> Result<()> {
+        if now.abs_diff(timestamp) > self.window_secs {
+            bail!("message {id} is outside the time window ({timestamp}, now {now})");
+        }
+
+        // 超出时间窗口的 id 已经会被上面的检查拒绝，无需继续保存
+        let window = self.window_secs;
+        self.seen
+            .retain(|_, seen_at| now.abs_diff(*seen_at) <= window);
+
+        if self.seen.contains_key(id) {
+            bail!("message {id} has already been processed");
+        }
+        self.seen.insert(id.to_string(), timestamp);
+        Ok(())
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn read_secret_file(path: &Path) -> Result<Vec<u8>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read service secret {}", path.display()))?;
+    let key = hex::decode(content.trim()).context("service secret is not valid hex")?;
+    if key.len() != IPC_SECRET_LEN {
+        bail!("service secret has invalid length {}", key.len());
+    }
+    Ok(key)
+}
+
+/// 以仅当前用户可读写的权限写入密钥
+fn write_secret_file(path: &Path, key: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("failed to create service secret {}", path.display()))?;
+    file.write_all(hex::encode(key).as_bytes())?;
+    file.sync_all()?;
+
+    // 文件已存在时 mode 不会生效，这里再收紧一次
+    // Windows 下密钥位于用户目录，默认只有当前用户和管理员可访问
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+fn pending_secret_path() -> Result<PathBuf> {
+    Ok(dirs::service_secret_path()?.with_extension("key.new"))
+}
+
+/// 为新安装的服务生成密钥，返回交给安装程序的密钥文件路径
+/// 安装成功后调用 [`commit_secret_key`]，失败时调用 [`discard_secret_key`]
+pub fn prepare_secret_key() -> Result<PathBuf> {
+    let mut key = vec![0u8; IPC_SECRET_LEN];
+    getrandom::fill(&mut key).map_err(|e| anyhow::anyhow!("failed to generate secret: {e}"))?;
+
+    let path = pending_secret_path()?;
+    write_secret_file(&path, &key)?;
+    Ok(path)
+}
+
+/// 启用新生成的密钥
+pub fn commit_secret_key() -> Result<()> {
+    let pending = pending_secret_path()?;
+    let key = read_secret_file(&pending)?;
+    fs::rename(&pending, dirs::service_secret_path()?)?;
+    *SECRET_KEY.write() = Some(key);
+    *RESPONSE_GUARD.lock() = None;
+    logging!(info, Type::Service, true, "Service IPC secret rotated");
+    Ok(())
+}
+
+/// 安装失败时丢弃新生成的密钥，继续使用原来的密钥
+pub fn discard_secret_key() {
+    if let Ok(pending) = pending_secret_path() {
+        let _ = fs::remove_file(pending);
+    }
+}
+
+// 旧版本服务内置的共享密钥
+fn legacy_secret_key() -> Vec<u8> {
     let unique_app_id = "koala-clash-app-secret-fuck-me-until-daylight";
     let mut hasher = Sha256::new();
     hasher.update(unique_app_id.as_bytes());
     hasher.finalize().to_vec()
 }
 
+// 新版本服务使用本机安装时生成的密钥，缺失时需要重新安装服务
+fn derive_secret_key(protocol: IpcProtocol) -> Result<Vec<u8>> {
+    if protocol == IpcProtocol::Legacy {
+        return Ok(legacy_secret_key());
+    }
+    if let Some(key) = SECRET_KEY.read().as_ref() {
+        return Ok(key.clone());
+    }
+
+    let key = dirs::service_secret_path()
+        .and_then(|path| read_secret_file(&path))
+        .context("Service IPC secret is unavailable, please reinstall the service")?;
+    *SECRET_KEY.write() = Some(key.clone());
+    Ok(key)
+}
+
+/// 检查新版本服务所需的本机密钥是否存在且可读
+pub fn check_secret_key() -> Result<()> {
+    derive_secret_key(IpcProtocol::Secure).map(|_| ())
+}
+
 // 创建带签名的请求
 pub fn create_signed_request(
     command: IpcCommand,
     payload: serde_json::Value,
+    protocol: IpcProtocol,
 ) -> Result<IpcRequest> {
     let id = nanoid::nanoid!(32);
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
+    let timestamp = now_secs();
 
     let unsigned_request = IpcRequest {
         id: id.clone(),
@@ -69,7 +314,7 @@ pub fn create_signed_request(
     };
 
     let unsigned_json = serde_json::to_string(&unsigned_request)?;
-    let signature = sign_message(&unsigned_json)?;
+    let signature = sign_message(&unsigned_json, protocol)?;
 
     Ok(IpcRequest {
         id,
@@ -81,10 +326,10 @@ pub fn create_signed_request(
 }
 
 // 签名消息
-fn sign_message(message: &str) -> Result<String> {
+fn sign_message(message: &str, protocol: IpcProtocol) -> Result<String> {
     type HmacSha256 = Hmac<Sha256>;
 
-    let secret_key = derive_secret_key();
+    let secret_key = derive_secret_key(protocol)?;
     let mut mac = HmacSha256::new_from_slice(&secret_key).context("Failed to initialize HMAC")?;
 
     mac.update(message.as_bytes());
@@ -95,206 +340,537 @@ fn sign_message(message: &str) -> Result<String> {
 }
 
 // 验证响应签名
-pub fn verify_response_signature(response: &IpcResponse) -> Result<bool> {
+pub fn verify_response_signature(response: &IpcResponse, protocol: IpcProtocol) -> Result<bool> {
     let verification_response = IpcResponse {
         id: response.id.clone(),
         success: response.success,
         data: response.data.clone(),
         error: response.error.clone(),
+        timestamp: response.timestamp,
         signature: String::new(),
     };
 
     let message = serde_json::to_string(&verification_response)?;
-    let expected_signature = sign_message(&message)?;
+    let expected_signature = sign_message(&message, protocol)?;
 
     Ok(expected_signature == response.signature)
 }
 
-// IPC连接管理-win
-#[cfg(target_os = "windows")]
-pub async fn send_ipc_request(
-    command: IpcCommand,
-    payload: serde_json::Value,
+// 验证事件签名，事件 id 会重复出现，只检查时间窗口
+// 只有新版本服务会推送事件
+fn verify_event(event: &IpcEvent) -> Result<()> {
+    let unsigned_event = IpcEvent {
+        signature: String::new(),
+        ..event.clone()
+    };
+    let message = serde_json::to_string(&unsigned_event)?;
+    if sign_message(&message, IpcProtocol::Secure)? != event.signature {
+        bail!("event signature verification failed");
+    }
+    if now_secs().abs_diff(event.timestamp) > IPC_TIME_WINDOW_SECS {
+        bail!("event {} is outside the time window", event.id);
+    }
+    Ok(())
+}
+
+/// 创建带签名的事件，供服务端使用
+#[cfg(test)]
+pub fn create_signed_event(id: String, stream: CoreLogStream, line: String) -> Result<IpcEvent> {
+    let mut event = IpcEvent {
+        id,
+        stream,
+        line,
+        timestamp: now_secs(),
+        signature: String::new(),
+    };
+    event.signature = sign_message(&serde_json::to_string(&event)?, IpcProtocol::Secure)?;
+    Ok(event)
+}
+
+/// 创建带签名的响应，供服务端使用，旧版本服务的响应不带时间戳
+#[cfg(test)]
+pub fn create_signed_response(
+    protocol: IpcProtocol,
+    id: String,
+    success: bool,
+    data: Option<serde_json::Value>,
+    error: Option<String>,
 ) -> Result<IpcResponse> {
-    use std::{
-        ffi::CString,
-        fs::File,
-        io::{Read, Write},
-        os::windows::io::{FromRawHandle, RawHandle},
-        ptr,
+    let mut response = IpcResponse {
+        id,
+        success,
+        data,
+        error,
+        timestamp: (protocol == IpcProtocol::Secure).then(now_secs),
+        signature: String::new(),
     };
-    use winapi::um::{
-        fileapi::{CreateFileA, OPEN_EXISTING},
-        handleapi::INVALID_HANDLE_VALUE,
-        winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE},
+    response.signature = sign_message(&serde_json::to_string(&response)?, protocol)?;
+    Ok(response)
+}
+
+/// 验证请求的签名、时间戳以及是否重放，与服务端的校验一致
+#[cfg(test)]
+pub fn verify_request(
+    request: &IpcRequest,
+    protocol: IpcProtocol,
+    guard: &mut ReplayGuard,
+) -> Result<()> {
+    let unsigned_request = IpcRequest {
+        signature: String::new(),
+        ..request.clone()
     };
+    let message = serde_json::to_string(&unsigned_request)?;
+    if sign_message(&message, protocol)? != request.signature {
+        bail!("request signature verification failed");
+    }
+    guard.check(&request.id, request.timestamp, now_secs())
+}
 
-    logging!(
-        info,
-        Type::Service,
-        true,
-        "Connecting to service (Windows)..."
-    );
+/// 从 GetVersion 的响应中取出版本号，兼容嵌套的 `data.data.version`
+pub fn response_version(response: &IpcResponse) -> Option<String> {
+    let data = response.data.as_ref()?;
+    data.get("data")
+        .unwrap_or(data)
+        .get("version")?
+        .as_str()
+        .map(str::to_string)
+}
 
-    let command_type = format!("{:?}", command);
+// 响应必须对应本次请求，带时间戳时还需在时间窗口内且未被处理过
+fn validate_response(request_id: &str, response: &IpcResponse) -> Result<()> {
+    if response.id != request_id {
+        bail!(
+            "service response id mismatch: expected {}, got {}",
+            request_id,
+            response.id
+        );
+    }
 
-    let request = match create_signed_request(command, payload) {
-        Ok(req) => req,
-        Err(e) => {
-            logging!(
-                error,
-                Type::Service,
-                true,
-                "Failed to create signed request: {}",
-                e
-            );
-            return Err(e);
-        }
-    };
+    if let Some(timestamp) = response.timestamp {
+        RESPONSE_GUARD
+            .lock()
+            .get_or_insert_with(|| ReplayGuard::new(IPC_TIME_WINDOW_SECS))
+            .check(&response.id, timestamp, now_secs())?;
+    }
+    Ok(())
+}
 
-    let request_json = serde_json::to_string(&request)?;
+/// 单个消息的最大长度
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
 
-    let result = tokio::task::spawn_blocking(move || -> Result<IpcResponse> {
-        let c_pipe_name = match CString::new(IPC_SOCKET_NAME) {
-            Ok(name) => name,
+type BoxedReader = Box<dyn AsyncRead + Send + Unpin>;
+type BoxedWriter = Box<dyn AsyncWrite + Send + Unpin>;
+type PendingMap = Arc<Mutex<HashMap<String, oneshot::Sender<IpcResponse>>>>;
+type SubscriberMap = Arc<Mutex<HashMap<String, mpsc::UnboundedSender<IpcEvent>>>>;
+
+/// 读取一帧消息：4 字节大端长度 + JSON
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut len_bytes = [0u8; 4];
+    reader.read_exact(&mut len_bytes).await?;
+    let len = u32::from_be_bytes(len_bytes) as usize;
+    if len > MAX_FRAME_LEN {
+        bail!("frame too large: {len} bytes");
+    }
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body).await?;
+    Ok(body)
+}
+
+/// 写入一帧消息
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, body: &[u8]) -> Result<()> {
+    if body.len() > MAX_FRAME_LEN {
+        bail!("frame too large: {} bytes", body.len());
+    }
+    writer.write_all(&(body.len() as u32).to_be_bytes()).await?;
+    writer.write_all(body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn connect(endpoint: &str) -> Result<(BoxedReader, BoxedWriter)> {
+    let stream = tokio::net::UnixStream::connect(endpoint)
+        .await
+        .with_context(|| format!("Unable to connect to service Unix socket {endpoint}"))?;
+    let (reader, writer) = tokio::io::split(stream);
+    Ok((Box::new(reader), Box::new(writer)))
+}
+
+#[cfg(windows)]
+async fn connect(endpoint: &str) -> Result<(BoxedReader, BoxedWriter)> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    // 所有管道实例都在使用中时稍后重试，整体由 CONNECT_TIMEOUT 限制
+    const ERROR_PIPE_BUSY: i32 = 231;
+
+    let client = loop {
+        match ClientOptions::new().open(endpoint) {
+            Ok(client) => break client,
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) => {}
             Err(e) => {
-                logging!(
-                    error,
-                    Type::Service,
-                    true,
-                    "Failed to create CString: {}",
+                return Err(anyhow::anyhow!(
+                    "Unable to connect to service named pipe: {}",
                     e
-                );
-                return Err(anyhow::anyhow!("Failed to create CString: {}", e));
+                ))
             }
-        };
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    };
+    let (reader, writer) = tokio::io::split(client);
+    Ok((Box::new(reader), Box::new(writer)))
+}
 
-        let handle = unsafe {
-            CreateFileA(
-                c_pipe_name.as_ptr(),
-                GENERIC_READ | GENERIC_WRITE,
-                FILE_SHARE_READ | FILE_SHARE_WRITE,
-                ptr::null_mut(),
-                OPEN_EXISTING,
-                0,
-                ptr::null_mut(),
-            )
+/// 与服务之间的一条长连接，按请求 id 分发响应
+struct IpcConnection {
+    writer: tokio::sync::Mutex<BoxedWriter>,
+    pending: PendingMap,
+    subscribers: SubscriberMap,
+    alive: Arc<AtomicBool>,
+    reader: JoinHandle<()>,
+}
+
+impl IpcConnection {
+    fn spawn(reader: BoxedReader, writer: BoxedWriter) -> Self {
+        let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let subscribers: SubscriberMap = Arc::new(Mutex::new(HashMap::new()));
+        let alive = Arc::new(AtomicBool::new(true));
+        let reader = tokio::spawn(Self::read_loop(
+            reader,
+            pending.clone(),
+            subscribers.clone(),
+            alive.clone(),
+        ));
+
+        Self {
+            writer: tokio::sync::Mutex::new(writer),
+            pending,
+            subscribers,
+            alive,
+            reader,
+        }
+    }
+
+    fn dispatch_event(subscribers: &SubscriberMap, event: IpcEvent) {
+        if let Err(e) = verify_event(&event) {
+            logging!(warn, Type::Service, true, "Dropping service event: {}", e);
+            return;
+        }
+
+        let mut subscribers = subscribers.lock();
+        let closed = match subscribers.get(&event.id) {
+            Some(sender) => sender.send(event.clone()).is_err(),
+            None => false,
         };
+        // 订阅方已经不再接收
+        if closed {
+            subscribers.remove(&event.id);
+        }
+    }
 
-        if handle == INVALID_HANDLE_VALUE {
-            let error = std::io::Error::last_os_error();
-            logging!(
-                error,
-                Type::Service,
-                true,
-                "Failed to connect to service named pipe: {}",
-                error
-            );
-            return Err(anyhow::anyhow!("Unable to connect to service named pipe: {}", error));
+    async fn read_loop(
+        mut reader: BoxedReader,
+        pending: PendingMap,
+        subscribers: SubscriberMap,
+        alive: Arc<AtomicBool>,
+    ) {
+        loop {
+            let frame = match read_frame(&mut reader).await {
+                Ok(frame) => frame,
+                Err(e) => {
+                    logging!(
+                        debug,
+                        Type::Service,
+                        false,
+                        "Service connection closed: {}",
+                        e
+                    );
+                    break;
+                }
+            };
+
+            match serde_json::from_slice::<IpcFrame>(&frame) {
+                Ok(IpcFrame::Event(event)) => Self::dispatch_event(&subscribers, event),
+                Ok(IpcFrame::Response(response)) => {
+                    let sender = pending.lock().remove(&response.id);
+                    match sender {
+                        Some(sender) => {
+                            let _ = sender.send(response);
+                        }
+                        None => {
+                            logging!(
+                                warn,
+                                Type::Service,
+                                true,
+                                "Dropping service response with unknown id: {}",
+                                response.id
+                            );
+                        }
+                    }
+                }
+                Err(e) => {
+                    logging!(
+                        error,
+                        Type::Service,
+                        true,
+                        "Failed to parse service response: {}",
+                        e
+                    );
+                }
+            }
+        }
+
+        alive.store(false, Ordering::SeqCst);
+        // 丢弃所有等待中的请求和订阅，调用方会立即收到连接断开的错误
+        pending.lock().clear();
+        subscribers.lock().clear();
+    }
+
+    fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    async fn send(&self, body: &[u8]) -> Result<()> {
+        let mut writer = self.writer.lock().await;
+        write_frame(&mut *writer, body).await
+    }
+}
+
+impl Drop for IpcConnection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// 服务响应未通过签名或重放校验，通常是服务与客户端的密钥不一致
+#[derive(Debug)]
+pub struct IpcAuthError(String);
+
+impl std::fmt::Display for IpcAuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for IpcAuthError {}
+
+/// 当前版本的服务不支持该命令
+#[derive(Debug)]
+pub struct IpcUnsupportedError(pub IpcCommand);
+
+impl std::fmt::Display for IpcUnsupportedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Service does not support {:?}, version {SECURE_IPC_VERSION} or newer is required",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for IpcUnsupportedError {}
+
+/// 服务 IPC 客户端
+/// 新版本服务复用同一条连接并发送多个请求，连接断开后自动重连
+/// 旧版本服务每个连接只处理一个请求，每个请求单独建立连接
+pub struct IpcClient {
+    endpoint: String,
+    connection: tokio::sync::Mutex<Option<Arc<IpcConnection>>>,
+    protocol: Mutex<Option<IpcProtocol>>,
+}
+
+impl IpcClient {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            connection: tokio::sync::Mutex::new(None),
+            protocol: Mutex::new(None),
+        }
+    }
+
+    pub fn global() -> &'static IpcClient {
+        static CLIENT: OnceCell<IpcClient> = OnceCell::new();
+        CLIENT.get_or_init(|| IpcClient::new(IPC_SOCKET_NAME))
+    }
+
+    async fn open(&self) -> Result<(BoxedReader, BoxedWriter)> {
+        match tokio::time::timeout(CONNECT_TIMEOUT, connect(&self.endpoint))
+            .await
+            .context("Timed out connecting to service")
+        {
+            Ok(Ok(stream)) => Ok(stream),
+            Ok(Err(e)) | Err(e) => {
+                // 服务可能已被替换为其他版本
+                self.reset_protocol();
+                Err(e)
+            }
+        }
+    }
+
+    async fn connection(&self) -> Result<Arc<IpcConnection>> {
+        let mut guard = self.connection.lock().await;
+        if let Some(connection) = guard.as_ref().filter(|c| c.is_alive()) {
+            return Ok(connection.clone());
+        }
+
+        let (reader, writer) = self.open().await?;
+        logging!(info, Type::Service, true, "Service connection established");
+
+        let connection = Arc::new(IpcConnection::spawn(reader, writer));
+        *guard = Some(connection.clone());
+        Ok(connection)
+    }
+
+    /// 能否建立到服务的连接，不发送请求
+    pub async fn is_reachable(&self) -> bool {
+        self.open().await.is_ok()
+    }
+
+    /// 服务使用的协议，首次使用时查询服务版本并缓存
+    /// 版本查询始终使用共享密钥签名，任何版本的服务都能应答
+    pub async fn protocol(&self) -> Result<IpcProtocol> {
+        if let Some(protocol) = *self.protocol.lock() {
+            return Ok(protocol);
         }
 
-        let mut pipe = unsafe { File::from_raw_handle(handle as RawHandle) };
+        let response = self
+            .request_with_timeout(
+                IpcCommand::GetVersion,
+                serde_json::json!({}),
+                REQUEST_TIMEOUT,
+            )
+            .await?;
+        let version = response_version(&response).context("Service did not report its version")?;
+        let protocol = IpcProtocol::for_version(&version);
         logging!(
             info,
             Type::Service,
             true,
-            "Service connection successful (Windows)"
+            "Service {} uses {:?} IPC",
+            version,
+            protocol
         );
+        *self.protocol.lock() = Some(protocol);
+        Ok(protocol)
+    }
 
-        let request_bytes = request_json.as_bytes();
-        let len_bytes = (request_bytes.len() as u32).to_be_bytes();
+    /// 重新安装服务后需要重新确认协议
+    pub fn reset_protocol(&self) {
+        *self.protocol.lock() = None;
+    }
 
-        if let Err(e) = pipe.write_all(&len_bytes) {
-            logging!(
-                error,
-                Type::Service,
-                true,
-                "Failed to write request length: {}",
-                e
-            );
-            return Err(anyhow::anyhow!("Failed to write request length: {}", e));
-        }
+    /// 当前服务是否支持该命令，服务不可用时返回 false
+    pub async fn supports(&self, command: &IpcCommand) -> bool {
+        self.protocol()
+            .await
+            .is_ok_and(|protocol| protocol.supports(command))
+    }
 
-        if let Err(e) = pipe.write_all(request_bytes) {
-            logging!(
-                error,
-                Type::Service,
-                true,
-                "Failed to write request body: {}",
-                e
-            );
-            return Err(anyhow::anyhow!("Failed to write request body: {}", e));
+    async fn reset(&self, connection: &Arc<IpcConnection>) {
+        let mut guard = self.connection.lock().await;
+        if guard.as_ref().is_some_and(|c| Arc::ptr_eq(c, connection)) {
+            *guard = None;
         }
+    }
 
-        if let Err(e) = pipe.flush() {
-            logging!(error, Type::Service, true, "Failed to flush pipe: {}", e);
-            return Err(anyhow::anyhow!("Failed to flush pipe: {}", e));
-        }
+    /// 发送请求并等待对应 id 的响应
+    pub async fn request(
+        &self,
+        command: IpcCommand,
+        payload: serde_json::Value,
+    ) -> Result<IpcResponse> {
+        self.request_with_timeout(command, payload, REQUEST_TIMEOUT)
+            .await
+    }
 
-        let mut response_len_bytes = [0u8; 4];
-        if let Err(e) = pipe.read_exact(&mut response_len_bytes) {
-            logging!(
-                error,
-                Type::Service,
-                true,
-                "Failed to read response length: {}",
-                e
-            );
-            return Err(anyhow::anyhow!("Failed to read response length: {}", e));
+    pub async fn request_with_timeout(
+        &self,
+        command: IpcCommand,
+        payload: serde_json::Value,
+        timeout: Duration,
+    ) -> Result<IpcResponse> {
+        let protocol = match command {
+            IpcCommand::GetVersion => IpcProtocol::Legacy,
+            _ => self.protocol().await?,
+        };
+        if !protocol.supports(&command) {
+            return Err(IpcUnsupportedError(command).into());
         }
+        match protocol {
+            IpcProtocol::Legacy => self.request_once(command, payload, timeout).await,
+            IpcProtocol::Secure => self.send_request(command, payload, timeout, None).await,
+        }
+    }
 
-        let response_len = u32::from_be_bytes(response_len_bytes) as usize;
-
-        let mut response_bytes = vec![0u8; response_len];
-        if let Err(e) = pipe.read_exact(&mut response_bytes) {
-            logging!(
-                error,
-                Type::Service,
-                true,
-                "Failed to read response body: {}",
-                e
-            );
-            return Err(anyhow::anyhow!("Failed to read response body: {}", e));
+    /// 订阅服务推送的事件，只有新版本服务支持
+    /// 返回的通道在连接断开后关闭，调用方需要重新订阅
+    pub async fn subscribe(
+        &self,
+        command: IpcCommand,
+        payload: serde_json::Value,
+    ) -> Result<mpsc::UnboundedReceiver<IpcEvent>> {
+        if self.protocol().await? != IpcProtocol::Secure {
+            return Err(IpcUnsupportedError(command).into());
         }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.send_request(command, payload, REQUEST_TIMEOUT, Some(sender))
+            .await?;
+        Ok(receiver)
+    }
 
-        let response: IpcResponse = match serde_json::from_slice::<IpcResponse>(&response_bytes) {
-            Ok(r) => r,
-            Err(e) => {
-                logging!(
-                    error,
-                    Type::Service,
-                    true,
-                    "Failed to parse service response: {}",
-                    e
-                );
-                return Err(anyhow::anyhow!("Failed to parse response: {}", e));
-            }
+    /// 校验响应的签名、id 以及是否重放，未通过时重新确认协议
+    fn check_response(
+        &self,
+        protocol: IpcProtocol,
+        request_id: &str,
+        response: &IpcResponse,
+    ) -> Result<()> {
+        let rejected = if !verify_response_signature(response, protocol)? {
+            "Service response signature verification failed".to_string()
+        } else if let Err(e) = validate_response(request_id, response) {
+            format!("Service response rejected: {e}")
+        } else {
+            return Ok(());
         };
 
-        match verify_response_signature(&response) {
-            Ok(valid) => {
-                if !valid {
-                    logging!(
-                        error,
-                        Type::Service,
-                        true,
-                        "Service response signature verification failed"
-                    );
-                    bail!("Service response signature verification failed");
-                }
-            }
-            Err(e) => {
-                logging!(
-                    error,
-                    Type::Service,
-                    true,
-                    "Error verifying response signature: {}",
-                    e
-                );
-                return Err(e);
-            }
-        }
+        logging!(error, Type::Service, true, "{}", rejected);
+        self.reset_protocol();
+        Err(IpcAuthError(rejected).into())
+    }
+
+    /// 旧版本服务的请求：建立连接，发送一个请求并读取响应后关闭
+    async fn request_once(
+        &self,
+        command: IpcCommand,
+        payload: serde_json::Value,
+        timeout: Duration,
+    ) -> Result<IpcResponse> {
+        let command_type = format!("{command:?}");
+        let request = create_signed_request(command, payload, IpcProtocol::Legacy)?;
+        let body = serde_json::to_vec(&request)?;
 
+        let exchange = async {
+            let (mut reader, mut writer) = self.open().await?;
+            write_frame(&mut writer, &body)
+                .await
+                .context("Failed to send request to service")?;
+            let frame = read_frame(&mut reader)
+                .await
+                .context("Failed to read service response")?;
+            serde_json::from_slice::<IpcResponse>(&frame)
+                .context("Failed to parse service response")
+        };
+        let response = match tokio::time::timeout(timeout, exchange).await {
+            Ok(response) => response?,
+            Err(_) => bail!(
+                "Service did not respond to {} within {:?}",
+                command_type,
+                timeout
+            ),
+        };
+
+        self.check_response(IpcProtocol::Legacy, &request.id, &response)?;
         logging!(
             info,
             Type::Service,
@@ -304,123 +880,435 @@ pub async fn send_ipc_request(
             response.success
         );
         Ok(response)
-    })
-    .await??;
+    }
 
-    Ok(result)
-}
+    /// 新版本服务的请求，在长连接上按 id 等待响应
+    /// 请求未能写入时（例如服务重启导致旧连接失效）会重连并重试一次
+    async fn send_request(
+        &self,
+        command: IpcCommand,
+        payload: serde_json::Value,
+        timeout: Duration,
+        subscriber: Option<mpsc::UnboundedSender<IpcEvent>>,
+    ) -> Result<IpcResponse> {
+        let command_type = format!("{command:?}");
+        let mut retried = false;
 
-// IPC连接管理-unix
-#[cfg(target_family = "unix")]
-pub async fn send_ipc_request(
-    command: IpcCommand,
-    payload: serde_json::Value,
-) -> Result<IpcResponse> {
-    use std::os::unix::net::UnixStream;
+        loop {
+            let connection = self.connection().await?;
+            let request =
+                create_signed_request(command.clone(), payload.clone(), IpcProtocol::Secure)?;
+            let body = serde_json::to_vec(&request)?;
+
+            let (sender, receiver) = oneshot::channel();
+            connection.pending.lock().insert(request.id.clone(), sender);
+            // 事件可能先于响应到达，需要在发送请求前登记
+            if let Some(subscriber) = subscriber.as_ref() {
+                connection
+                    .subscribers
+                    .lock()
+                    .insert(request.id.clone(), subscriber.clone());
+            }
 
-    logging!(info, Type::Service, true, "Connecting to service (Unix)...");
+            if let Err(e) = connection.send(&body).await {
+                connection.pending.lock().remove(&request.id);
+                connection.subscribers.lock().remove(&request.id);
+                self.reset(&connection).await;
+                if !retried {
+                    retried = true;
+                    logging!(
+                        warn,
+                        Type::Service,
+                        true,
+                        "Failed to send {} to service, reconnecting: {}",
+                        command_type,
+                        e
+                    );
+                    continue;
+                }
+                return Err(e.context("Failed to send request to service"));
+            }
 
-    let command_type = format!("{command:?}");
+            let response = match tokio::time::timeout(timeout, receiver).await {
+                Ok(Ok(response)) => response,
+                Ok(Err(_)) => {
+                    self.reset(&connection).await;
+                    bail!("Service connection closed before responding to {command_type}");
+                }
+                Err(_) => {
+                    connection.pending.lock().remove(&request.id);
+                    connection.subscribers.lock().remove(&request.id);
+                    bail!(
+                        "Service did not respond to {} within {:?}",
+                        command_type,
+                        timeout
+                    );
+                }
+            };
 
-    let request = match create_signed_request(command, payload) {
-        Ok(req) => req,
-        Err(e) => {
-            logging!(error, Type::Service, true, "Failed to create signed request: {}", e);
-            return Err(e);
-        }
-    };
+            if let Err(e) = self.check_response(IpcProtocol::Secure, &request.id, &response) {
+                connection.subscribers.lock().remove(&request.id);
+                return Err(e);
+            }
 
-    let request_json = serde_json::to_string(&request)?;
+            if subscriber.is_some() && !response.success {
+                connection.subscribers.lock().remove(&request.id);
+                bail!(response
+                    .error
+                    .unwrap_or_else(|| format!("Service rejected {command_type}")));
+            }
 
-    let mut stream = match UnixStream::connect(IPC_SOCKET_NAME) {
-        Ok(s) => {
             logging!(
                 info,
                 Type::Service,
                 true,
-                "Service connection successful (Unix)"
-            );
-            s
-        }
-        Err(e) => {
-            logging!(
-                error,
-                Type::Service,
-                true,
-                "Failed to connect to Unix socket: {}",
-                e
+                "IPC request completed: command={}, success={}",
+                command_type,
+                response.success
             );
-            return Err(anyhow::anyhow!("Unable to connect to service Unix socket: {}", e));
+            return Ok(response);
         }
-    };
+    }
+}
 
-    let request_bytes = request_json.as_bytes();
-    let len_bytes = (request_bytes.len() as u32).to_be_bytes();
+/// 通过全局客户端向服务发送请求
+pub async fn send_ipc_request(
+    command: IpcCommand,
+    payload: serde_json::Value,
+) -> Result<IpcResponse> {
+    IpcClient::global().request(command, payload).await
+}
 
-    if let Err(e) = std::io::Write::write_all(&mut stream, &len_bytes) {
-        logging!(error, Type::Service, true, "Failed to write request length: {}", e);
-        return Err(anyhow::anyhow!("Failed to write request length: {}", e));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_guard_time_window() {
+        let mut guard = ReplayGuard::new(30);
+        assert!(guard.check("a", 1_000, 1_020).is_ok());
+        assert!(guard.check("b", 1_000, 1_031).is_err());
+        assert!(guard.check("c", 1_050, 1_000).is_err());
     }
 
-    if let Err(e) = std::io::Write::write_all(&mut stream, request_bytes) {
-        logging!(error, Type::Service, true, "Failed to write request body: {}", e);
-        return Err(anyhow::anyhow!("Failed to write request body: {}", e));
+    #[test]
+    fn test_replay_guard_rejects_replayed_id() {
+        let mut guard = ReplayGuard::new(30);
+        assert!(guard.check("a", 1_000, 1_000).is_ok());
+        assert!(guard.check("a", 1_000, 1_001).is_err());
+        assert!(guard.check("b", 1_001, 1_001).is_ok());
+
+        // 过期的 id 会被清理，但仍会因时间戳过期被拒绝
+        assert!(guard.check("a", 1_000, 1_100).is_err());
+        assert_eq!(guard.seen.len(), 0);
     }
 
-    let mut response_len_bytes = [0u8; 4];
-    if let Err(e) = std::io::Read::read_exact(&mut stream, &mut response_len_bytes) {
-        logging!(error, Type::Service, true, "Failed to read response length: {}", e);
-        return Err(anyhow::anyhow!("Failed to read response length: {}", e));
+    fn use_test_key() {
+        *SECRET_KEY.write() = Some(vec![7u8; IPC_SECRET_LEN]);
     }
 
-    let response_len = u32::from_be_bytes(response_len_bytes) as usize;
+    #[test]
+    fn test_verify_request() {
+        use_test_key();
+        let mut guard = ReplayGuard::new(IPC_TIME_WINDOW_SECS);
+        let secure = IpcProtocol::Secure;
+
+        let request =
+            create_signed_request(IpcCommand::GetVersion, serde_json::json!({}), secure).unwrap();
+        assert!(verify_request(&request, secure, &mut guard).is_ok());
+        assert!(verify_request(&request, secure, &mut guard).is_err());
 
-    let mut response_bytes = vec![0u8; response_len];
-    if let Err(e) = std::io::Read::read_exact(&mut stream, &mut response_bytes) {
-        logging!(error, Type::Service, true, "Failed to read response body: {}", e);
-        return Err(anyhow::anyhow!("Failed to read response body: {}", e));
+        let mut tampered =
+            create_signed_request(IpcCommand::StopClash, serde_json::json!({}), secure).unwrap();
+        tampered.command = IpcCommand::StartClash;
+        assert!(verify_request(&tampered, secure, &mut guard).is_err());
+
+        // 旧版本服务只认共享密钥
+        let legacy = create_signed_request(
+            IpcCommand::GetClash,
+            serde_json::json!({}),
+            IpcProtocol::Legacy,
+        )
+        .unwrap();
+        assert!(verify_request(&legacy, secure, &mut guard).is_err());
+        assert!(verify_request(&legacy, IpcProtocol::Legacy, &mut guard).is_ok());
     }
 
-    let response: IpcResponse = match serde_json::from_slice::<IpcResponse>(&response_bytes) {
-        Ok(r) => r,
-        Err(e) => {
-            logging!(
-                error,
-                Type::Service,
-                true,
-                "Failed to parse service response: {}",
-                e,
-            );
-            return Err(anyhow::anyhow!("Failed to parse response: {}", e));
+    #[test]
+    fn test_protocol_for_version() {
+        assert_eq!(IpcProtocol::for_version("1.1.0"), IpcProtocol::Legacy);
+        assert_eq!(IpcProtocol::for_version("1.0"), IpcProtocol::Legacy);
+        assert_eq!(
+            IpcProtocol::for_version(SECURE_IPC_VERSION),
+            IpcProtocol::Secure
+        );
+        assert_eq!(IpcProtocol::for_version("v1.10.0"), IpcProtocol::Secure);
+        assert_eq!(IpcProtocol::for_version("2"), IpcProtocol::Secure);
+        assert_eq!(IpcProtocol::for_version("dev"), IpcProtocol::Legacy);
+        assert!(!version_at_least("1.2.0.1", "1.2.0"));
+    }
+
+    #[test]
+    fn test_response_version() {
+        let response = |data| IpcResponse {
+            id: String::new(),
+            success: true,
+            data: Some(data),
+            error: None,
+            timestamp: None,
+            signature: String::new(),
+        };
+        let nested = response(serde_json::json!({ "code": 0, "data": { "version": "1.1.0" } }));
+        assert_eq!(response_version(&nested).as_deref(), Some("1.1.0"));
+        let flat = response(serde_json::json!({ "version": "1.2.0" }));
+        assert_eq!(response_version(&flat).as_deref(), Some("1.2.0"));
+    }
+
+    /// 进程内的假服务，使用与真实服务相同的帧格式和签名
+    /// payload 中的 `delay_ms` 用于延迟响应，`close` 用于不响应直接断开连接
+    #[cfg(unix)]
+    mod loopback {
+        use super::*;
+        use tokio::net::UnixListener;
+
+        fn spawn_fake_service(path: &Path, legacy: bool) {
+            let listener = UnixListener::bind(path).unwrap();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    match legacy {
+                        true => tokio::spawn(serve_legacy(stream)),
+                        false => tokio::spawn(serve_connection(stream)),
+                    };
+                }
+            });
         }
-    };
 
-    match verify_response_signature(&response) {
-        Ok(valid) => {
-            if !valid {
-                logging!(error, Type::Service, true, "Service response signature verification failed");
-                bail!("Service response signature verification failed");
+        /// 与 1.1.0 服务一致：共享密钥，每个连接只处理一个请求
+        async fn serve_legacy(mut stream: tokio::net::UnixStream) {
+            let Ok(frame) = read_frame(&mut stream).await else {
+                return;
+            };
+            let request: IpcRequest = serde_json::from_slice(&frame).unwrap();
+            let mut guard = ReplayGuard::new(IPC_TIME_WINDOW_SECS);
+            let verified = verify_request(&request, IpcProtocol::Legacy, &mut guard);
+
+            let delay = request.payload.get("delay_ms").and_then(|v| v.as_u64());
+            if let Some(delay) = delay {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
             }
+
+            let data = match request.command {
+                IpcCommand::GetVersion => serde_json::json!({
+                    "code": 0,
+                    "msg": "ok",
+                    "data": { "version": "1.1.0" },
+                }),
+                _ => serde_json::json!({
+                    "command": format!("{:?}", request.command),
+                    "payload": request.payload,
+                }),
+            };
+            let (success, data, error) = match verified {
+                Ok(()) => (true, Some(data), None),
+                Err(e) => (false, None, Some(e.to_string())),
+            };
+            let response =
+                create_signed_response(IpcProtocol::Legacy, request.id, success, data, error)
+                    .unwrap();
+            let body = serde_json::to_vec(&response).unwrap();
+            let _ = write_frame(&mut stream, &body).await;
         }
-        Err(e) => {
-            logging!(
-                error,
-                Type::Service,
-                true,
-                "Error verifying response signature: {}",
-                e
+
+        async fn serve_connection(stream: tokio::net::UnixStream) {
+            let (mut reader, writer) = tokio::io::split(stream);
+            let writer = Arc::new(tokio::sync::Mutex::new(writer));
+            let mut guard = ReplayGuard::new(IPC_TIME_WINDOW_SECS);
+
+            while let Ok(frame) = read_frame(&mut reader).await {
+                let request: IpcRequest = serde_json::from_slice(&frame).unwrap();
+                if request.payload.get("close").is_some() {
+                    break;
+                }
+
+                // 版本查询使用共享密钥签名
+                let protocol = match request.command {
+                    IpcCommand::GetVersion => IpcProtocol::Legacy,
+                    _ => IpcProtocol::Secure,
+                };
+                let verified = verify_request(&request, protocol, &mut guard);
+                let writer = writer.clone();
+                tokio::spawn(async move {
+                    let delay = request.payload.get("delay_ms").and_then(|v| v.as_u64());
+                    if let Some(delay) = delay {
+                        tokio::time::sleep(Duration::from_millis(delay)).await;
+                    }
+
+                    let data = match request.command {
+                        IpcCommand::GetVersion => {
+                            serde_json::json!({ "version": SECURE_IPC_VERSION })
+                        }
+                        _ => serde_json::json!({
+                            "command": format!("{:?}", request.command),
+                            "payload": request.payload,
+                        }),
+                    };
+                    let response = match verified {
+                        Ok(()) => {
+                            create_signed_response(protocol, request.id, true, Some(data), None)
+                        }
+                        Err(e) => create_signed_response(
+                            protocol,
+                            request.id,
+                            false,
+                            None,
+                            Some(e.to_string()),
+                        ),
+                    }
+                    .unwrap();
+
+                    let body = serde_json::to_vec(&response).unwrap();
+                    let mut writer = writer.lock().await;
+                    let _ = write_frame(&mut *writer, &body).await;
+
+                    if matches!(request.command, IpcCommand::SubscribeLogs) && response.success {
+                        for (stream, line) in [
+                            (CoreLogStream::Stdout, "core started"),
+                            (CoreLogStream::Stderr, "core warning"),
+                            (CoreLogStream::Service, "core restarted"),
+                        ] {
+                            let event =
+                                create_signed_event(response.id.clone(), stream, line.into())
+                                    .unwrap();
+                            let body = serde_json::to_vec(&event).unwrap();
+                            let _ = write_frame(&mut *writer, &body).await;
+                        }
+                    }
+                });
+            }
+        }
+
+        fn setup(legacy: bool) -> (tempfile::TempDir, IpcClient) {
+            use_test_key();
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("service.sock");
+            spawn_fake_service(&path, legacy);
+            let client = IpcClient::new(path.to_string_lossy().to_string());
+            (dir, client)
+        }
+
+        #[tokio::test]
+        async fn test_multiplexed_requests() {
+            let (_dir, client) = setup(false);
+
+            let slow = client.request(
+                IpcCommand::GetHealth,
+                serde_json::json!({ "delay_ms": 200 }),
+            );
+            let fast = client.request(IpcCommand::GetClash, serde_json::json!({ "n": 1 }));
+            let (slow, fast) = tokio::join!(slow, fast);
+
+            let slow = slow.unwrap();
+            let fast = fast.unwrap();
+            assert!(slow.success && fast.success);
+            assert_eq!(slow.data.unwrap()["command"], "GetHealth");
+            assert_eq!(fast.data.unwrap()["payload"]["n"], 1);
+            assert_eq!(client.protocol().await.unwrap(), IpcProtocol::Secure);
+        }
+
+        #[tokio::test]
+        async fn test_legacy_service() {
+            let (_dir, client) = setup(true);
+
+            // 每个请求使用单独的连接，并发请求互不影响
+            let slow = client.request(IpcCommand::GetClash, serde_json::json!({ "delay_ms": 200 }));
+            let fast = client.request(IpcCommand::StartClash, serde_json::json!({ "n": 1 }));
+            let (slow, fast) = tokio::join!(slow, fast);
+            assert!(slow.unwrap().success);
+            assert_eq!(fast.unwrap().data.unwrap()["payload"]["n"], 1);
+            assert_eq!(client.protocol().await.unwrap(), IpcProtocol::Legacy);
+
+            let err = client
+                .request(IpcCommand::RestartClash, serde_json::json!({}))
+                .await
+                .unwrap_err();
+            assert!(err.downcast_ref::<IpcUnsupportedError>().is_some());
+            assert!(!client.supports(&IpcCommand::GetHealth).await);
+            assert!(client.supports(&IpcCommand::StopClash).await);
+            assert!(client
+                .subscribe(IpcCommand::SubscribeLogs, serde_json::json!({}))
+                .await
+                .is_err());
+        }
+
+        #[tokio::test]
+        async fn test_request_timeout() {
+            let (_dir, client) = setup(false);
+
+            let result = client
+                .request_with_timeout(
+                    IpcCommand::GetClash,
+                    serde_json::json!({ "delay_ms": 500 }),
+                    Duration::from_millis(100),
+                )
+                .await;
+            assert!(result.is_err());
+
+            // 超时不影响同一连接上的后续请求
+            let response = client
+                .request(IpcCommand::GetClash, serde_json::json!({}))
+                .await
+                .unwrap();
+            assert!(response.success);
+        }
+
+        #[tokio::test]
+        async fn test_subscribe_logs() {
+            let (_dir, client) = setup(false);
+
+            let mut events = client
+                .subscribe(IpcCommand::SubscribeLogs, serde_json::json!({}))
+                .await
+                .unwrap();
+
+            let mut received = Vec::new();
+            for _ in 0..3 {
+                let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                received.push((event.stream, event.line));
+            }
+            assert_eq!(
+                received,
+                vec![
+                    (CoreLogStream::Stdout, "core started".to_string()),
+                    (CoreLogStream::Stderr, "core warning".to_string()),
+                    (CoreLogStream::Service, "core restarted".to_string()),
+                ]
             );
-            return Err(e);
+
+            // 订阅期间普通请求不受影响
+            let response = client
+                .request(IpcCommand::GetVersion, serde_json::json!({}))
+                .await
+                .unwrap();
+            assert!(response.success);
         }
-    }
 
-    logging!(
-        info,
-        Type::Service,
-        true,
-        "IPC request completed: command={}, success={}",
-        command_type,
-        response.success
-    );
-    Ok(response)
+        #[tokio::test]
+        async fn test_reconnect_after_disconnect() {
+            let (_dir, client) = setup(false);
+
+            let result = client
+                .request(IpcCommand::StopClash, serde_json::json!({ "close": true }))
+                .await;
+            assert!(result.is_err());
+
+            let response = client
+                .request(IpcCommand::GetVersion, serde_json::json!({}))
+                .await
+                .unwrap();
+            assert!(response.success);
+        }
+    }
 }
@@ -4,12 +4,12 @@ use crate::{
     config::{Config, IVerge},
     core::{handle::Handle, EventDrivenProxyManager},
     logging, logging_error,
-    utils::logging::Type,
+    utils::{bypass, logging::Type},
 };
 use anyhow::Result;
 use once_cell::sync::OnceCell;
 use std::sync::Arc;
-#[cfg(not(target_os = "windows"))]
+#[cfg(target_os = "macos")]
 use sysproxy::{Autoproxy, Sysproxy};
 use tauri::async_runtime::Mutex as TokioMutex;
 use tauri_plugin_autostart::ManagerExt;
@@ -19,36 +19,6 @@ pub struct Sysopt {
     reset_sysproxy: Arc<TokioMutex<bool>>,
 }
 
-#[cfg(target_os = "windows")]
-static DEFAULT_BYPASS: &str = "localhost;127.*;192.168.*;10.*;172.16.*;172.17.*;172.18.*;172.19.*;172.20.*;172.21.*;172.22.*;172.23.*;172.24.*;172.25.*;172.26.*;172.27.*;172.28.*;172.29.*;172.30.*;172.31.*;<local>";
-#[cfg(target_os = "linux")]
-static DEFAULT_BYPASS: &str =
-    "localhost,127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,172.29.0.0/16,::1";
-#[cfg(target_os = "macos")]
-static DEFAULT_BYPASS: &str =
-    "127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,172.29.0.0/16,localhost,*.local,*.crashlytics.com,<local>";
-
-fn get_bypass() -> String {
-    let use_default = Config::verge().latest().use_default_bypass.unwrap_or(true);
-    let res = {
-        let verge = Config::verge();
-        let verge = verge.latest();
-        verge.system_proxy_bypass.clone()
-    };
-    let custom_bypass = match res {
-        Some(bypass) => bypass,
-        None => "".to_string(),
-    };
-
-    if custom_bypass.is_empty() {
-        DEFAULT_BYPASS.to_string()
-    } else if use_default {
-        format!("{DEFAULT_BYPASS},{custom_bypass}")
-    } else {
-        custom_bypass
-    }
-}
-
 impl Sysopt {
     pub fn global() -> &'static Sysopt {
         static SYSOPT: OnceCell<Sysopt> = OnceCell::new();
@@ -90,13 +60,31 @@ impl Sysopt {
             )
         };
 
-        #[cfg(not(target_os = "windows"))]
+        #[cfg(target_os = "linux")]
+        {
+            use crate::core::linux_proxy::{self, LinuxProxy};
+
+            let proxy = if !sys_enable {
+                LinuxProxy::Off
+            } else if pac_enable {
+                LinuxProxy::Pac(format!("http://{proxy_host}:{pac_port}/commands/pac"))
+            } else {
+                LinuxProxy::Manual {
+                    host: proxy_host,
+                    port,
+                    bypass: bypass::system_bypass(),
+                }
+            };
+            let changed = linux_proxy::apply(&proxy).await?;
+            log::info!(target: "app", "System proxy applied via {changed:?}");
+        }
+        #[cfg(target_os = "macos")]
         {
             let mut sys = Sysproxy {
                 enable: false,
                 host: proxy_host.clone(),
                 port,
-                bypass: get_bypass(),
+                bypass: bypass::system_bypass(),
             };
             let mut auto = Autoproxy {
                 enable: false,
@@ -163,7 +151,7 @@ impl Sysopt {
                 output
             } else {
                 let address = format!("{}:{}", proxy_host, port);
-                let bypass = get_bypass();
+                let bypass = bypass::system_bypass();
                 let output = shell
                     .command(sysproxy_exe.as_path().to_str().unwrap())
                     .args(["global", address.as_str(), bypass.as_ref()])
@@ -187,7 +175,13 @@ impl Sysopt {
     pub async fn reset_sysproxy(&self) -> Result<()> {
         let _lock = self.reset_sysproxy.lock().await;
         //直接关闭所有代理
-        #[cfg(not(target_os = "windows"))]
+        #[cfg(target_os = "linux")]
+        {
+            let reset = crate::core::linux_proxy::reset().await?;
+            log::info!(target: "app", "System proxy reset via {reset:?}");
+        }
+
+        #[cfg(target_os = "macos")]
         {
             let mut sysproxy: Sysproxy = Sysproxy::get_system_proxy()?;
             let mut autoproxy = match Autoproxy::get_auto_proxy() {
@@ -0,0 +1,457 @@
+#![cfg(target_os = "linux")]
+
+use crate::{
+    config::{Config, IClashTemp},
+    core::{core_caps, service, CoreManager, RunningMode},
+    logging,
+    utils::{cidr::IpCidr, dirs, logging::Type},
+};
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+use std::{fmt::Write, path::PathBuf, process::Command};
+
+/// nftables 表名
+const NFT_TABLE: &str = "koala_clash";
+/// 需要透明代理的流量使用的 fwmark，配合策略路由送回本机
+const TPROXY_MARK: u32 = 0x1;
+/// 策略路由表
+const ROUTE_TABLE: u32 = 100;
+/// core 自身出站流量的 routing-mark，避免被重新送回 core
+pub const CORE_ROUTING_MARK: u32 = 0x162;
+
+/// 保留地址和局域网网段，始终直连
+const RESERVED_V4: &[&str] = &[
+    "0.0.0.0/8",
+    "10.0.0.0/8",
+    "100.64.0.0/10",
+    "127.0.0.0/8",
+    "169.254.0.0/16",
+    "172.16.0.0/12",
+    "192.0.0.0/24",
+    "192.0.2.0/24",
+    "192.88.99.0/24",
+    "192.168.0.0/16",
+    "198.18.0.0/15",
+    "198.51.100.0/24",
+    "203.0.113.0/24",
+    "224.0.0.0/4",
+    "240.0.0.0/4",
+];
+const RESERVED_V6: &[&str] = &[
+    "::/128",
+    "::1/128",
+    "64:ff9b::/96",
+    "100::/64",
+    "2001:db8::/32",
+    "fc00::/7",
+    "fe80::/10",
+    "ff00::/8",
+];
+
+// 已经应用的规则，退出时只在应用过的情况下清理，避免无谓的提权
+static APPLIED: Mutex<Option<TransparentProxyPlan>> = Mutex::new(None);
+// 保证应用和清理依次执行，提权等待期间不占用 APPLIED
+static SYNC_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransparentProxyMode {
+    /// TCP + UDP，需要策略路由
+    Tproxy(u16),
+    /// 仅 TCP，使用 nat redirect
+    Redir(u16),
+}
+
+/// 透明代理规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransparentProxyPlan {
+    pub mode: TransparentProxyMode,
+    pub bypass: Vec<IpCidr>,
+}
+
+impl TransparentProxyPlan {
+    /// 根据当前配置生成，未启用透明代理时返回 None
+    pub fn current() -> Option<Self> {
+        let (enabled, redir_enabled, tproxy_enabled) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.enable_transparent_proxy.unwrap_or(false),
+                verge.verge_redir_enabled.unwrap_or(false),
+                verge.verge_tproxy_enabled.unwrap_or(false),
+            )
+        };
+        if !enabled {
+            return None;
+        }
+
+        let clash = Config::clash().latest().0.clone();
+        // tproxy 同时支持 TCP 和 UDP，两者都开启时优先使用
+        let mode = if tproxy_enabled {
+            TransparentProxyMode::Tproxy(IClashTemp::guard_tproxy_port(&clash))
+        } else if redir_enabled {
+            TransparentProxyMode::Redir(IClashTemp::guard_redir_port(&clash))
+        } else {
+            return None;
+        };
+
+        let mut bypass: Vec<IpCidr> = RESERVED_V4
+            .iter()
+            .chain(RESERVED_V6)
+            .filter_map(|cidr| cidr.parse().ok())
+            .collect();
+        for cidr in local_subnets() {
+            if !bypass.iter().any(|b| b.contains(&cidr.addr())) {
+                bypass.push(cidr);
+            }
+        }
+
+        Some(Self { mode, bypass })
+    }
+
+    fn bypass_elements(&self, v4: bool) -> String {
+        self.bypass
+            .iter()
+            .filter(|cidr| cidr.is_ipv4() == v4)
+            .map(|cidr| cidr.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// 生成 nftables 规则集，重复应用时会先删除旧表
+    pub fn render_ruleset(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "table inet {NFT_TABLE}");
+        let _ = writeln!(out, "delete table inet {NFT_TABLE}");
+        let _ = writeln!(out, "table inet {NFT_TABLE} {{");
+        let _ = writeln!(out, "    set bypass_v4 {{");
+        let _ = writeln!(out, "        type ipv4_addr; flags interval; auto-merge;");
+        let _ = writeln!(
+            out,
+            "        elements = {{ {} }}",
+            self.bypass_elements(true)
+        );
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "    set bypass_v6 {{");
+        let _ = writeln!(out, "        type ipv6_addr; flags interval; auto-merge;");
+        let _ = writeln!(
+            out,
+            "        elements = {{ {} }}",
+            self.bypass_elements(false)
+        );
+        let _ = writeln!(out, "    }}");
+
+        let bypass = [
+            "fib daddr type local return",
+            "ip daddr @bypass_v4 return",
+            "ip6 daddr @bypass_v6 return",
+        ];
+        let core_bypass = format!("meta mark {CORE_ROUTING_MARK:#x} return");
+
+        match self.mode {
+            TransparentProxyMode::Tproxy(port) => {
+                let _ = writeln!(out, "    chain prerouting {{");
+                let _ = writeln!(
+                    out,
+                    "        type filter hook prerouting priority mangle; policy accept;"
+                );
+                for rule in bypass {
+                    let _ = writeln!(out, "        {rule}");
+                }
+                let _ = writeln!(
+                    out,
+                    "        meta l4proto {{ tcp, udp }} meta mark set {TPROXY_MARK:#x} tproxy ip to 127.0.0.1:{port} accept"
+                );
+                let _ = writeln!(
+                    out,
+                    "        meta l4proto {{ tcp, udp }} meta mark set {TPROXY_MARK:#x} tproxy ip6 to [::1]:{port} accept"
+                );
+                let _ = writeln!(out, "    }}");
+                // 本机流量打上标记后由策略路由送回 prerouting
+                let _ = writeln!(out, "    chain output {{");
+                let _ = writeln!(
+                    out,
+                    "        type route hook output priority mangle; policy accept;"
+                );
+                let _ = writeln!(out, "        {core_bypass}");
+                for rule in bypass {
+                    let _ = writeln!(out, "        {rule}");
+                }
+                let _ = writeln!(
+                    out,
+                    "        meta l4proto {{ tcp, udp }} meta mark set {TPROXY_MARK:#x}"
+                );
+                let _ = writeln!(out, "    }}");
+            }
+            TransparentProxyMode::Redir(port) => {
+                for (chain, hook) in [("prerouting", "prerouting"), ("output", "output")] {
+                    let _ = writeln!(out, "    chain {chain} {{");
+                    let _ = writeln!(
+                        out,
+                        "        type nat hook {hook} priority dstnat; policy accept;"
+                    );
+                    if hook == "output" {
+                        let _ = writeln!(out, "        {core_bypass}");
+                    }
+                    for rule in bypass {
+                        let _ = writeln!(out, "        {rule}");
+                    }
+                    let _ = writeln!(out, "        meta l4proto tcp redirect to :{port}");
+                    let _ = writeln!(out, "    }}");
+                }
+            }
+        }
+
+        let _ = writeln!(out, "}}");
+        out
+    }
+
+    /// 策略路由命令，仅 tproxy 需要
+    fn routing_commands(&self) -> Vec<String> {
+        match self.mode {
+            TransparentProxyMode::Tproxy(_) => ["ip", "ip -6"]
+                .iter()
+                .flat_map(|ip| {
+                    let default = if *ip == "ip" { "0.0.0.0/0" } else { "::/0" };
+                    let rule = format!("fwmark {TPROXY_MARK:#x} table {ROUTE_TABLE}");
+                    [
+                        format!("{ip} rule del {rule} 2>/dev/null || true"),
+                        format!("{ip} rule add {rule}"),
+                        format!("{ip} route replace local {default} dev lo table {ROUTE_TABLE}"),
+                    ]
+                })
+                .collect(),
+            TransparentProxyMode::Redir(_) => Vec::new(),
+        }
+    }
+
+    /// 完整的应用脚本，规则集通过 here-document 传给 nft
+    pub fn render_script(&self) -> String {
+        let mut script = String::from("set -e\n");
+        let _ = writeln!(script, "nft -f - <<'EOF'");
+        script.push_str(&self.render_ruleset());
+        let _ = writeln!(script, "EOF");
+        for command in self.routing_commands() {
+            let _ = writeln!(script, "{command}");
+        }
+        script
+    }
+}
+
+fn teardown_script() -> String {
+    let mut script = format!("nft delete table inet {NFT_TABLE} 2>/dev/null\n");
+    for (ip, default) in [("ip", "0.0.0.0/0"), ("ip -6", "::/0")] {
+        let _ = writeln!(
+            script,
+            "{ip} rule del fwmark {TPROXY_MARK:#x} table {ROUTE_TABLE} 2>/dev/null"
+        );
+        let _ = writeln!(
+            script,
+            "{ip} route del local {default} dev lo table {ROUTE_TABLE} 2>/dev/null"
+        );
+    }
+    script.push_str("exit 0\n");
+    script
+}
+
+// 本机网卡所在的网段
+fn local_subnets() -> Vec<IpCidr> {
+    use network_interface::{NetworkInterface, NetworkInterfaceConfig};
+
+    let Ok(interfaces) = NetworkInterface::show() else {
+        return Vec::new();
+    };
+    interfaces
+        .iter()
+        .flat_map(|iface| iface.addr.iter())
+        .filter_map(|addr| {
+            let netmask = addr.netmask()?;
+            IpCidr::from_netmask(addr.ip(), netmask).ok()
+        })
+        .filter(|cidr| cidr.prefix() > 0)
+        .collect()
+}
+
+/// 提权执行脚本，会等待用户确认，放到阻塞线程中运行
+async fn run_privileged(script: String) -> Result<()> {
+    tokio::task::spawn_blocking(move || {
+        use users::get_effective_uid;
+
+        let status = match get_effective_uid() {
+            0 => Command::new("sh").arg("-c").arg(&script).status()?,
+            _ => Command::new(crate::utils::help::linux_elevator())
+                .arg("sh")
+                .arg("-c")
+                .arg(&script)
+                .status()?,
+        };
+        if !status.success() {
+            bail!("transparent proxy script failed with status {status}");
+        }
+        Ok(())
+    })
+    .await?
+}
+
+/// 已应用的规则集副本，同时作为规则仍然生效的标记
+fn applied_file() -> Option<PathBuf> {
+    dirs::app_home_dir().ok().map(|dir| dir.join("tproxy.nft"))
+}
+
+/// 上次运行（例如崩溃后）遗留的规则
+fn has_stale_rules() -> bool {
+    if applied_file().is_some_and(|path| path.exists()) {
+        return true;
+    }
+    let table = Command::new("nft")
+        .args(["list", "table", "inet", NFT_TABLE])
+        .output()
+        .is_ok_and(|output| output.status.success());
+    let rule = format!("fwmark {TPROXY_MARK:#x} lookup {ROUTE_TABLE}");
+    let routing = Command::new("ip")
+        .args(["rule", "show"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&rule));
+    table || routing
+}
+
+/// 启动时清理遗留的规则，否则流量会被送往已经不存在的端口
+pub async fn cleanup_stale() -> Result<()> {
+    let _guard = SYNC_LOCK.lock().await;
+    if !tokio::task::spawn_blocking(has_stale_rules).await? {
+        return Ok(());
+    }
+    logging!(
+        info,
+        Type::System,
+        true,
+        "Removing stale transparent proxy rules"
+    );
+    run_privileged(teardown_script()).await?;
+    if let Some(path) = applied_file() {
+        let _ = std::fs::remove_file(path);
+    }
+    *APPLIED.lock() = None;
+    Ok(())
+}
+
+/// 按当前配置应用或清理透明代理规则，配置未变化时不做任何操作
+/// core 能否给自身出站流量打上 routing-mark
+///
+/// SO_MARK 需要 CAP_NET_ADMIN，只有服务以 root 运行或使用已授权的副本时才有，
+/// 否则 core 的流量会被重新送回自己形成回环。内核尚未启动时按 start_core
+/// 的选择顺序推断
+pub async fn core_can_mark() -> bool {
+    let clash_core = Config::verge().latest().get_valid_clash_core();
+    if core_caps::capable_core(&clash_core).is_some() {
+        return true;
+    }
+    match CoreManager::global().get_running_mode().await {
+        RunningMode::Service => true,
+        RunningMode::Sidecar => false,
+        RunningMode::NotRunning => service::is_service_available().await.is_ok(),
+    }
+}
+
+pub async fn sync() -> Result<()> {
+    let _guard = SYNC_LOCK.lock().await;
+    let plan = TransparentProxyPlan::current();
+    if *APPLIED.lock() == plan {
+        return Ok(());
+    }
+
+    match plan {
+        Some(_) if !core_can_mark().await => {
+            logging!(
+                warn,
+                Type::System,
+                true,
+                "Core cannot set its routing mark; skipping transparent proxy rules"
+            );
+            remove_rules().await?;
+            bail!("transparent proxy requires service mode or core capabilities")
+        }
+        Some(plan) => {
+            logging!(
+                info,
+                Type::System,
+                true,
+                "Applying transparent proxy rules: {:?}",
+                plan.mode
+            );
+            if let Some(path) = applied_file() {
+                let _ = std::fs::write(path, plan.render_ruleset());
+            }
+            run_privileged(plan.render_script()).await?;
+            *APPLIED.lock() = Some(plan);
+            Ok(())
+        }
+        None => remove_rules().await,
+    }
+}
+
+/// 删除透明代理规则和策略路由
+pub async fn teardown() -> Result<()> {
+    let _guard = SYNC_LOCK.lock().await;
+    remove_rules().await
+}
+
+async fn remove_rules() -> Result<()> {
+    if APPLIED.lock().is_none() {
+        return Ok(());
+    }
+    logging!(info, Type::System, true, "Removing transparent proxy rules");
+    run_privileged(teardown_script()).await?;
+    if let Some(path) = applied_file() {
+        let _ = std::fs::remove_file(path);
+    }
+    *APPLIED.lock() = None;
+    Ok(())
+}
+
+/// 返回将要应用的脚本，不做任何修改
+pub fn dry_run() -> Result<String> {
+    match TransparentProxyPlan::current() {
+        Some(plan) => Ok(plan.render_script()),
+        None => bail!("transparent proxy is disabled or neither redir nor tproxy port is enabled"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(mode: TransparentProxyMode) -> TransparentProxyPlan {
+        TransparentProxyPlan {
+            mode,
+            bypass: vec![
+                "192.168.0.0/16".parse().unwrap(),
+                "fc00::/7".parse().unwrap(),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render_tproxy() {
+        let plan = plan(TransparentProxyMode::Tproxy(7896));
+        let ruleset = plan.render_ruleset();
+        assert!(ruleset.contains("elements = { 192.168.0.0/16 }"));
+        assert!(ruleset.contains("elements = { fc00::/7 }"));
+        assert!(ruleset.contains("tproxy ip to 127.0.0.1:7896"));
+        assert!(ruleset.contains("tproxy ip6 to [::1]:7896"));
+        assert!(ruleset.contains("meta mark 0x162 return"));
+
+        let script = plan.render_script();
+        assert!(script.contains("ip rule add fwmark 0x1 table 100"));
+        assert!(script.contains("ip -6 route replace local ::/0 dev lo table 100"));
+    }
+
+    #[test]
+    fn test_render_redir() {
+        let plan = plan(TransparentProxyMode::Redir(7895));
+        let ruleset = plan.render_ruleset();
+        assert!(ruleset.contains("type nat hook prerouting priority dstnat"));
+        assert!(ruleset.contains("meta l4proto tcp redirect to :7895"));
+        assert!(!ruleset.contains("tproxy"));
+        assert!(!plan.render_script().contains("ip rule"));
+    }
+}
@@ -6,7 +6,10 @@ pub mod seq;
 mod tun;
 
 use self::{chain::*, field::*, merge::*, script::*, seq::*, tun::*};
-use crate::{config::Config, utils::tmpl};
+use crate::{
+    config::{Config, IDnsConfig},
+    utils::tmpl,
+};
 use serde_yaml::Mapping;
 use std::collections::{HashMap, HashSet};
 
@@ -30,6 +33,7 @@ pub async fn enhance() -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
             verge.enable_dns_settings.unwrap_or(false),
         )
     };
+    let tun_settings = { Config::verge().latest().tun_settings.clone() };
     #[cfg(not(target_os = "windows"))]
     let redir_enabled = {
         let verge = Config::verge();
@@ -255,40 +259,29 @@ pub async fn enhance() -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
             });
     }
 
-    config = use_tun(config, enable_tun).await;
+    config = use_tun(config, enable_tun, tun_settings).await;
+
+    // 透明代理规则依赖 routing-mark 放行 core 自身的流量，
+    // core 没有 CAP_NET_ADMIN 时设置 routing-mark 会导致出站失败
+    #[cfg(target_os = "linux")]
+    let enable_tproxy = Config::verge()
+        .latest()
+        .enable_transparent_proxy
+        .unwrap_or(false);
+    #[cfg(target_os = "linux")]
+    if enable_tproxy && crate::core::tproxy::core_can_mark().await {
+        config.insert(
+            "routing-mark".into(),
+            crate::core::tproxy::CORE_ROUTING_MARK.into(),
+        );
+    }
     config = use_sort(config);
 
     // 应用独立的DNS配置（如果启用）
     if enable_dns_settings {
-        use crate::utils::dirs;
-        use std::fs;
-
-        if let Ok(app_dir) = dirs::app_home_dir() {
-            let dns_path = app_dir.join("dns_config.yaml");
-
-            if dns_path.exists() {
-                if let Ok(dns_yaml) = fs::read_to_string(&dns_path) {
-                    if let Ok(dns_config) = serde_yaml::from_str::<serde_yaml::Mapping>(&dns_yaml) {
-                        // 处理hosts配置
-                        if let Some(hosts_value) = dns_config.get("hosts") {
-                            if hosts_value.is_mapping() {
-                                config.insert("hosts".into(), hosts_value.clone());
-                                log::info!(target: "app", "apply hosts configuration");
-                            }
-                        }
-
-                        if let Some(dns_value) = dns_config.get("dns") {
-                            if let Some(dns_mapping) = dns_value.as_mapping() {
-                                config.insert("dns".into(), dns_mapping.clone().into());
-                                log::info!(target: "app", "apply dns_config.yaml (dns section)");
-                            }
-                        } else {
-                            config.insert("dns".into(), dns_config.into());
-                            log::info!(target: "app", "apply dns_config.yaml");
-                        }
-                    }
-                }
-            }
+        match IDnsConfig::load().and_then(|dns_config| dns_config.apply(&mut config)) {
+            Ok(()) => log::info!(target: "app", "apply dns_config.yaml"),
+            Err(err) => log::error!(target: "app", "skip invalid dns_config.yaml `{err}`"),
         }
     }
 
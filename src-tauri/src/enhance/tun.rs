@@ -1,3 +1,4 @@
+use crate::config::IVergeTun;
 use serde_yaml::{Mapping, Value};
 
 macro_rules! revise {
@@ -18,7 +19,39 @@ macro_rules! append {
     };
 }
 
-pub async fn use_tun(mut config: Mapping, enable: bool) -> Mapping {
+/// 各平台不支持的 TUN 字段
+fn unsupported_tun_keys() -> Vec<&'static str> {
+    let mut keys = vec![];
+    if !cfg!(target_os = "linux") {
+        keys.extend(["include-uid", "exclude-uid", "gso"]);
+    }
+    if !cfg!(target_os = "android") {
+        keys.extend(["include-package", "exclude-package"]);
+    }
+    keys
+}
+
+/// 将 verge 中的 TUN 设置写入 tun 配置，未设置的字段保留订阅中的值
+fn apply_tun_settings(tun_val: &mut Mapping, settings: &IVergeTun) {
+    let Ok(Value::Mapping(fields)) = serde_yaml::to_value(settings) else {
+        return;
+    };
+    let unsupported = unsupported_tun_keys();
+
+    for (key, value) in fields {
+        let (Some(key), false) = (key.as_str(), value.is_null()) else {
+            continue;
+        };
+        let key = key.replace('_', "-");
+        if unsupported.contains(&key.as_str()) {
+            log::warn!(target: "app", "TUN option `{key}` is not supported on this platform");
+            continue;
+        }
+        revise!(tun_val, key, value);
+    }
+}
+
+pub async fn use_tun(mut config: Mapping, enable: bool, settings: Option<IVergeTun>) -> Mapping {
     let tun_key = Value::from("tun");
     let tun_val = config.get(&tun_key);
     let mut tun_val = tun_val.map_or(Mapping::new(), |val| {
@@ -72,9 +105,101 @@ pub async fn use_tun(mut config: Mapping, enable: bool) -> Mapping {
         crate::utils::resolve::restore_public_dns().await;
     }
 
+    if let Some(settings) = settings {
+        let base_stack = tun_val.get("stack").and_then(Value::as_str);
+        match settings.validate(base_stack) {
+            Ok(()) => apply_tun_settings(&mut tun_val, &settings),
+            Err(err) => log::error!(target: "app", "invalid TUN settings `{err}`"),
+        }
+    }
+
     // 更新TUN配置
     revise!(tun_val, "enable", enable);
     revise!(config, "tun", tun_val);
 
     config
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_tun_settings() {
+        let mut tun_val: Mapping =
+            serde_yaml::from_str("stack: gvisor\nmtu: 1500\nauto-route: true").unwrap();
+        let settings = IVergeTun {
+            stack: Some("mixed".into()),
+            mtu: Some(9000),
+            route_exclude_address: Some(vec!["192.168.0.0/16".into()]),
+            endpoint_independent_nat: Some(true),
+            gso: Some(true),
+            ..IVergeTun::default()
+        };
+        assert!(settings.validate(None).is_ok());
+        apply_tun_settings(&mut tun_val, &settings);
+
+        assert_eq!(tun_val["stack"], Value::from("mixed"));
+        assert_eq!(tun_val["mtu"], Value::from(9000));
+        assert_eq!(tun_val["auto-route"], Value::from(true));
+        assert_eq!(
+            tun_val["route-exclude-address"][0],
+            Value::from("192.168.0.0/16")
+        );
+        assert_eq!(tun_val["endpoint-independent-nat"], Value::from(true));
+        assert_eq!(tun_val.contains_key("gso"), cfg!(target_os = "linux"));
+        assert!(!tun_val.contains_key("strict-route"));
+    }
+
+    #[test]
+    fn test_validate_tun_settings() {
+        let gso_on_gvisor = IVergeTun {
+            gso: Some(true),
+            ..IVergeTun::default()
+        };
+        assert!(gso_on_gvisor.validate(None).is_err());
+
+        let ein_on_system = IVergeTun {
+            stack: Some("system".into()),
+            endpoint_independent_nat: Some(true),
+            ..IVergeTun::default()
+        };
+        assert!(ein_on_system.validate(None).is_err());
+
+        let wrong_family = IVergeTun {
+            inet4_route_address: Some(vec!["fd00::/8".into()]),
+            ..IVergeTun::default()
+        };
+        assert!(wrong_family.validate(None).is_err());
+
+        let routes_without_auto_route = IVergeTun {
+            auto_route: Some(false),
+            exclude_uid: Some(vec![1000]),
+            ..IVergeTun::default()
+        };
+        assert!(routes_without_auto_route.validate(None).is_err());
+
+        assert!(IVergeTun {
+            mtu: Some(100),
+            ..IVergeTun::default()
+        }
+        .validate(None)
+        .is_err());
+        assert!(IVergeTun::default().validate(None).is_ok());
+
+        // 未设置时以订阅中的协议栈为准
+        let gso_only = IVergeTun {
+            gso: Some(true),
+            ..IVergeTun::default()
+        };
+        assert!(gso_only.validate(Some("system")).is_ok());
+        assert!(gso_only.validate(Some("gvisor")).is_err());
+        assert!(ein_on_system.validate(Some("gvisor")).is_err());
+        assert!(IVergeTun {
+            endpoint_independent_nat: Some(true),
+            ..IVergeTun::default()
+        }
+        .validate(Some("System"))
+        .is_err());
+    }
+}
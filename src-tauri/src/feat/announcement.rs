@@ -0,0 +1,161 @@
+use crate::{
+    config::Config,
+    core::handle,
+    utils::{dirs, help},
+};
+use anyhow::Result;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 最多保留的公告数量
+const MAX_ANNOUNCEMENTS: usize = 100;
+
+// 防止并发读写公告文件
+static ANNOUNCEMENTS_LOCK: Mutex<()> = Mutex::new(());
+
+/// 订阅提供方下发的公告
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Announcement {
+    pub id: String,
+    pub profile_uid: Option<String>,
+    pub profile_name: Option<String>,
+    pub message: String,
+    pub url: Option<String>,
+    pub received_at: i64,
+    pub read: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct AnnouncementStore {
+    items: Vec<Announcement>,
+}
+
+impl AnnouncementStore {
+    fn load() -> Self {
+        dirs::announcements_path()
+            .and_then(|path| help::read_yaml::<AnnouncementStore>(&path))
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<()> {
+        help::save_yaml(
+            &dirs::announcements_path()?,
+            self,
+            Some("# Subscription announcements"),
+        )
+    }
+}
+
+fn announcement_id(profile_uid: Option<&str>, message: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(profile_uid.unwrap_or_default().as_bytes());
+    hasher.update(message.as_bytes());
+    hex::encode(&hasher.finalize()[..8])
+}
+
+/// 保存公告，已存在的公告保持原有的已读状态
+/// 返回是否为新公告
+pub fn record_announcement(
+    profile_uid: Option<&str>,
+    profile_name: Option<&str>,
+    message: &str,
+    url: Option<&str>,
+) -> bool {
+    let message = message.trim();
+    if message.is_empty() {
+        return false;
+    }
+
+    let id = announcement_id(profile_uid, message);
+
+    let _guard = ANNOUNCEMENTS_LOCK.lock();
+    let mut store = AnnouncementStore::load();
+    if store.items.iter().any(|item| item.id == id) {
+        return false;
+    }
+
+    store.items.push(Announcement {
+        id,
+        profile_uid: profile_uid.map(str::to_string),
+        profile_name: profile_name.map(str::to_string),
+        message: message.to_string(),
+        url: url.filter(|u| !u.is_empty()).map(str::to_string),
+        received_at: chrono::Local::now().timestamp(),
+        read: false,
+    });
+    if store.items.len() > MAX_ANNOUNCEMENTS {
+        let overflow = store.items.len() - MAX_ANNOUNCEMENTS;
+        store.items.drain(..overflow);
+    }
+
+    if let Err(err) = store.save() {
+        log::warn!(target: "app", "failed to save announcements: {err}");
+        return false;
+    }
+
+    handle::Handle::notify_announcements_updated();
+    true
+}
+
+/// 保存订阅当前的公告
+pub fn record_profile_announcement(uid: &str) {
+    let (name, announce, announce_url) = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        match profiles.get_item(&uid.to_string()) {
+            Ok(item) => (
+                item.name.clone(),
+                item.announce.clone(),
+                item.announce_url.clone(),
+            ),
+            Err(_) => return,
+        }
+    };
+
+    if let Some(announce) = announce {
+        record_announcement(
+            Some(uid),
+            name.as_deref(),
+            &announce,
+            announce_url.as_deref(),
+        );
+    }
+}
+
+/// 获取所有公告，最新的在前
+pub fn get_announcements() -> Vec<Announcement> {
+    let _guard = ANNOUNCEMENTS_LOCK.lock();
+    let mut items = AnnouncementStore::load().items;
+    items.reverse();
+    items
+}
+
+/// 标记公告为已读，id 为空时标记全部
+pub fn mark_announcement_read(id: Option<String>) -> Result<()> {
+    let _guard = ANNOUNCEMENTS_LOCK.lock();
+    let mut store = AnnouncementStore::load();
+    for item in store.items.iter_mut() {
+        if id.as_ref().is_none_or(|id| *id == item.id) {
+            item.read = true;
+        }
+    }
+    store.save()?;
+    handle::Handle::notify_announcements_updated();
+    Ok(())
+}
+
+/// 删除订阅时一并删除它的公告
+pub fn remove_profile_announcements(uid: &str) {
+    let _guard = ANNOUNCEMENTS_LOCK.lock();
+    let mut store = AnnouncementStore::load();
+    let before = store.items.len();
+    store
+        .items
+        .retain(|item| item.profile_uid.as_deref() != Some(uid));
+    if store.items.len() != before {
+        if let Err(err) = store.save() {
+            log::warn!(target: "app", "failed to save announcements: {err}");
+        }
+    }
+}
@@ -3,15 +3,23 @@ use crate::{
     core::{handle, hotkey, sysopt, tray, CoreManager},
     logging_error,
     module::lightweight,
-    utils::logging::Type,
+    utils::{bypass::BypassList, control_api, logging::Type},
 };
 use anyhow::Result;
 use serde_yaml::Mapping;
 
+/// 修改后需要重新启动局域网 PAC 服务的字段
+const LAN_KEYS: [&str; 2] = ["allow-lan", "bind-address"];
+
 /// Patch Clash configuration
 pub async fn patch_clash(patch: Mapping) -> Result<()> {
     Config::clash().draft().patch_config(patch.clone());
+    if let Err(err) = Config::clash().latest().validate() {
+        Config::clash().discard();
+        return Err(err);
+    }
 
+    let lan_changed = LAN_KEYS.iter().any(|key| patch.contains_key(*key));
     let res = {
         // 激活订阅
         if patch.get("secret").is_some() || patch.get("external-controller").is_some() {
@@ -32,6 +40,9 @@ pub async fn patch_clash(patch: Mapping) -> Result<()> {
         Ok(()) => {
             Config::clash().apply();
             Config::clash().data().save_config()?;
+            if lan_changed {
+                super::sync_lan_pac();
+            }
             Ok(())
         }
         Err(err) => {
@@ -56,13 +67,24 @@ enum UpdateFlags {
     SystrayTooltip = 1 << 8,
     SystrayClickBehavior = 1 << 9,
     LighteWeight = 1 << 10,
+    #[cfg(target_os = "linux")]
+    TransparentProxy = 1 << 11,
+    ShellEnv = 1 << 12,
 }
 
 /// Patch Verge configuration
 pub async fn patch_verge(patch: IVerge, not_save_file: bool) -> Result<()> {
+    if let Some(tun_settings) = &patch.tun_settings {
+        let base_stack = Config::profiles().latest().current_tun_stack();
+        tun_settings.validate(base_stack.as_deref())?;
+    }
+    if let Some(bypass) = &patch.system_proxy_bypass {
+        BypassList::parse(bypass)?;
+    }
     Config::verge().draft().patch_config(patch.clone());
 
     let tun_mode = patch.enable_tun_mode;
+    let tun_settings = patch.tun_settings.clone();
     let auto_launch = patch.enable_auto_launch;
     let system_proxy = patch.enable_system_proxy;
     let pac = patch.proxy_auto_config;
@@ -85,6 +107,8 @@ pub async fn patch_verge(patch: IVerge, not_save_file: bool) -> Result<()> {
     let tproxy_enabled = patch.verge_tproxy_enabled;
     #[cfg(target_os = "linux")]
     let tproxy_port = patch.verge_tproxy_port;
+    #[cfg(target_os = "linux")]
+    let transparent_proxy = patch.enable_transparent_proxy;
     let socks_enabled = patch.verge_socks_enabled;
     let socks_port = patch.verge_socks_port;
     let http_enabled = patch.verge_http_enabled;
@@ -95,6 +119,8 @@ pub async fn patch_verge(patch: IVerge, not_save_file: bool) -> Result<()> {
     let tray_event = patch.tray_event;
     let home_cards = patch.home_cards.clone();
     let enable_auto_light_weight = patch.enable_auto_light_weight_mode;
+    let shell_env = patch.enable_shell_env;
+    let shell_env_socks_only = patch.shell_env_socks_only;
     let res: std::result::Result<(), anyhow::Error> = {
         // Initialize with no flags set
         let mut update_flags: i32 = UpdateFlags::None as i32;
@@ -105,6 +131,9 @@ pub async fn patch_verge(patch: IVerge, not_save_file: bool) -> Result<()> {
             update_flags |= UpdateFlags::SystrayTooltip as i32;
             update_flags |= UpdateFlags::SystrayIcon as i32;
         }
+        if tun_settings.is_some() {
+            update_flags |= UpdateFlags::ClashConfig as i32;
+        }
         if enable_global_hotkey.is_some() || home_cards.is_some() {
             update_flags |= UpdateFlags::VergeConfig as i32;
         }
@@ -116,6 +145,19 @@ pub async fn patch_verge(patch: IVerge, not_save_file: bool) -> Result<()> {
         if tproxy_enabled.is_some() || tproxy_port.is_some() {
             update_flags |= UpdateFlags::RestartCore as i32;
         }
+        #[cfg(target_os = "linux")]
+        if tproxy_enabled.is_some()
+            || tproxy_port.is_some()
+            || redir_enabled.is_some()
+            || redir_port.is_some()
+            || transparent_proxy.is_some()
+        {
+            update_flags |= UpdateFlags::TransparentProxy as i32;
+        }
+        #[cfg(target_os = "linux")]
+        if transparent_proxy.is_some() {
+            update_flags |= UpdateFlags::RestartCore as i32;
+        }
         if socks_enabled.is_some()
             || http_enabled.is_some()
             || socks_port.is_some()
@@ -165,11 +207,26 @@ pub async fn patch_verge(patch: IVerge, not_save_file: bool) -> Result<()> {
             update_flags |= UpdateFlags::LighteWeight as i32;
         }
 
+        if shell_env.is_some()
+            || shell_env_socks_only.is_some()
+            || system_proxy.is_some()
+            || mixed_port.is_some()
+            || proxy_bypass.is_some()
+            || patch.proxy_host.is_some()
+            || patch.use_default_bypass.is_some()
+        {
+            update_flags |= UpdateFlags::ShellEnv as i32;
+        }
+
         // Process updates based on flags
         if (update_flags & (UpdateFlags::RestartCore as i32)) != 0 {
             Config::generate().await?;
             CoreManager::global().restart_core().await?;
         }
+        #[cfg(target_os = "linux")]
+        if (update_flags & (UpdateFlags::TransparentProxy as i32)) != 0 {
+            crate::core::tproxy::sync().await?;
+        }
         if (update_flags & (UpdateFlags::ClashConfig as i32)) != 0 {
             CoreManager::global().update_config().await?;
             handle::Handle::refresh_clash();
@@ -199,6 +256,9 @@ pub async fn patch_verge(patch: IVerge, not_save_file: bool) -> Result<()> {
         if (update_flags & (UpdateFlags::SystrayClickBehavior as i32)) != 0 {
             tray::Tray::global().update_click_behavior()?;
         }
+        if (update_flags & (UpdateFlags::ShellEnv as i32)) != 0 {
+            super::sync_shell_env()?;
+        }
         if (update_flags & (UpdateFlags::LighteWeight as i32)) != 0 {
             if enable_auto_light_weight.unwrap() {
                 lightweight::enable_auto_light_weight_mode();
@@ -224,3 +284,17 @@ pub async fn patch_verge(patch: IVerge, not_save_file: bool) -> Result<()> {
         }
     }
 }
+
+/// 重新生成本地控制接口的访问令牌
+pub async fn regenerate_control_api_token() -> Result<String> {
+    let token = control_api::generate_token()?;
+    patch_verge(
+        IVerge {
+            control_api_token: Some(token.clone()),
+            ..IVerge::default()
+        },
+        false,
+    )
+    .await?;
+    Ok(token)
+}
@@ -0,0 +1,451 @@
+use crate::{
+    config::{Config, HostsImportReport, IDnsConfig},
+    core::{handle, CoreManager},
+    logging,
+    module::mihomo::MihomoManager,
+    utils::{
+        cidr::IpCidr,
+        dirs,
+        dns_client::{DnsClient, DnsRoute, Nameserver},
+        logging::Type,
+    },
+};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, net::IpAddr, path::PathBuf, time::Duration};
+use tokio::time::Instant;
+
+const DEFAULT_DOMAINS: &[&str] = &[
+    "www.google.com",
+    "www.youtube.com",
+    "github.com",
+    "www.cloudflare.com",
+    "www.wikipedia.org",
+    "telegram.org",
+];
+/// 经代理查询，作为判断污染的基准
+const DEFAULT_TRUSTED_NAMESERVER: &str = "https://1.1.1.1/dns-query";
+const DEFAULT_ROUNDS: u32 = 3;
+const DEFAULT_TIMEOUT_MS: u64 = 3000;
+/// 写回配置时保留的服务器数量
+const FASTEST_COUNT: usize = 3;
+const MAX_FAILURE_RATE: f64 = 0.1;
+
+/// 不应该出现在公网域名解析结果中的地址
+const BOGON_RANGES: &[&str] = &[
+    "0.0.0.0/8",
+    "10.0.0.0/8",
+    "127.0.0.0/8",
+    "169.254.0.0/16",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "198.18.0.0/15",
+    "240.0.0.0/4",
+    "::/128",
+    "::1/128",
+    "fc00::/7",
+    "fe80::/10",
+];
+
+/// `/dns/query` 支持的记录类型
+const QUERY_TYPES: &[&str] = &[
+    "A", "AAAA", "CNAME", "MX", "NS", "TXT", "SRV", "PTR", "SOA", "HTTPS", "SVCB", "CAA",
+];
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct DnsBenchmarkOptions {
+    /// 默认使用 DNS 设置中的全部服务器
+    pub nameservers: Option<Vec<String>>,
+    pub domains: Option<Vec<String>>,
+    pub trusted: Option<String>,
+    /// 计入统计的轮数，另有一轮用于建立连接
+    pub rounds: Option<u32>,
+    pub timeout_ms: Option<u64>,
+    /// 同时经过内核的代理端口测试
+    pub via_proxy: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsBenchmarkResult {
+    pub nameserver: String,
+    pub route: DnsRoute,
+    pub queries: u32,
+    pub failures: u32,
+    pub failure_rate: f64,
+    pub median_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    /// 返回了保留地址，而基准服务器没有
+    pub poisoned: Vec<String>,
+    /// 与基准服务器的结果完全不同，CDN 域名也可能出现
+    pub mismatched: Vec<String>,
+    /// 无法测试的服务器，例如 quic、dhcp
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsBenchmarkReport {
+    pub trusted: String,
+    pub domains: Vec<String>,
+    pub results: Vec<DnsBenchmarkResult>,
+}
+
+fn configured_nameservers() -> Vec<String> {
+    let dns = IDnsConfig::load()
+        .unwrap_or_else(|_| IDnsConfig::template())
+        .dns;
+    let mut servers: Vec<String> = vec![];
+    for server in [
+        dns.nameserver,
+        dns.fallback,
+        dns.proxy_server_nameserver,
+        dns.direct_nameserver,
+    ]
+    .into_iter()
+    .flatten()
+    .flatten()
+    {
+        if !servers.contains(&server) {
+            servers.push(server);
+        }
+    }
+    servers
+}
+
+/// 最近秩法计算分位数，`sorted` 需已排序
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+fn is_bogon(addr: &IpAddr) -> bool {
+    BOGON_RANGES
+        .iter()
+        .filter_map(|range| range.parse::<IpCidr>().ok())
+        .any(|range| range.contains(addr))
+}
+
+/// 返回 (被污染, 与基准不一致)
+fn compare_answers(answer: &[IpAddr], trusted: &[IpAddr]) -> (bool, bool) {
+    if answer.is_empty() || trusted.is_empty() {
+        return (false, false);
+    }
+    let poisoned = answer.iter().any(is_bogon) && !trusted.iter().any(is_bogon);
+    // 按 /24 或 /48 比较，减少 CDN 轮换地址造成的误报
+    let same_network = |a: &IpAddr, b: &IpAddr| {
+        let prefix = if a.is_ipv4() { 24 } else { 48 };
+        IpCidr::new(*a, prefix).is_ok_and(|net| net.contains(b))
+    };
+    let mismatched = !answer
+        .iter()
+        .any(|a| trusted.iter().any(|b| same_network(a, b)));
+    (poisoned, mismatched)
+}
+
+async fn benchmark_nameserver(
+    server: String,
+    route: DnsRoute,
+    domains: &[String],
+    trusted: &HashMap<String, Vec<IpAddr>>,
+    rounds: u32,
+    timeout: Duration,
+) -> DnsBenchmarkResult {
+    let mut result = DnsBenchmarkResult {
+        nameserver: server.clone(),
+        route,
+        queries: 0,
+        failures: 0,
+        failure_rate: 0.0,
+        median_ms: None,
+        p95_ms: None,
+        poisoned: vec![],
+        mismatched: vec![],
+        error: None,
+    };
+    let client = match Nameserver::parse(&server) {
+        Ok(nameserver) => DnsClient::new(&nameserver, route, timeout).await,
+        Err(err) => Err(err),
+    };
+    let client = match client {
+        Ok(client) => client,
+        Err(err) => {
+            result.error = Some(err.to_string());
+            return result;
+        }
+    };
+
+    // 第 0 轮用于建立连接，延迟包含握手时间，不计入统计
+    let mut latencies = vec![];
+    for round in 0..=rounds {
+        for domain in domains {
+            result.queries += 1;
+            let start = Instant::now();
+            match client.resolve(domain).await {
+                Ok(answer) => {
+                    if round > 0 {
+                        latencies.push(start.elapsed().as_millis() as u64);
+                        continue;
+                    }
+                    let trusted = trusted.get(domain).map(Vec::as_slice).unwrap_or_default();
+                    let (poisoned, mismatched) = compare_answers(&answer, trusted);
+                    if poisoned {
+                        result.poisoned.push(domain.clone());
+                    }
+                    if mismatched {
+                        result.mismatched.push(domain.clone());
+                    }
+                }
+                Err(err) => {
+                    log::debug!(target: "app", "dns benchmark {server} {domain} failed: {err}");
+                    result.failures += 1;
+                }
+            }
+        }
+    }
+
+    latencies.sort_unstable();
+    result.failure_rate = result.failures as f64 / result.queries.max(1) as f64;
+    result.median_ms = percentile(&latencies, 0.5);
+    result.p95_ms = percentile(&latencies, 0.95);
+    result
+}
+
+/// 分别直连和经代理查询每个服务器，统计延迟、失败率和污染情况
+pub async fn benchmark_dns(options: DnsBenchmarkOptions) -> Result<DnsBenchmarkReport> {
+    let nameservers = options
+        .nameservers
+        .filter(|servers| !servers.is_empty())
+        .unwrap_or_else(configured_nameservers);
+    let domains = options
+        .domains
+        .filter(|domains| !domains.is_empty())
+        .unwrap_or_else(|| DEFAULT_DOMAINS.iter().map(|s| s.to_string()).collect());
+    let trusted_server = options
+        .trusted
+        .unwrap_or_else(|| DEFAULT_TRUSTED_NAMESERVER.into());
+    let rounds = options.rounds.unwrap_or(DEFAULT_ROUNDS).clamp(1, 10);
+    let timeout = Duration::from_millis(options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    let mut routes = vec![DnsRoute::Direct];
+    if options.via_proxy.unwrap_or(true) {
+        routes.push(DnsRoute::Proxy);
+    }
+
+    // 基准服务器优先经代理查询，避免本地网络的污染
+    let trusted_nameserver = Nameserver::parse(&trusted_server)?;
+    let mut trusted_clients = vec![];
+    for route in routes.iter().rev() {
+        if let Ok(client) = DnsClient::new(&trusted_nameserver, *route, timeout).await {
+            trusted_clients.push(client);
+        }
+    }
+    let mut trusted = HashMap::new();
+    for domain in &domains {
+        for client in &trusted_clients {
+            if let Ok(answer) = client.resolve(domain).await {
+                trusted.insert(domain.clone(), answer);
+                break;
+            }
+        }
+    }
+    if trusted.is_empty() {
+        logging!(
+            warn,
+            Type::Network,
+            true,
+            "Trusted nameserver {} is unreachable, poisoning checks are skipped",
+            trusted_server
+        );
+    }
+
+    let pairs: Vec<(String, DnsRoute)> = nameservers
+        .iter()
+        .flat_map(|server| routes.iter().map(move |route| (server.clone(), *route)))
+        .collect();
+    let tasks = pairs.into_iter().map(|(server, route)| {
+        benchmark_nameserver(server, route, &domains, &trusted, rounds, timeout)
+    });
+    let mut results = futures::future::join_all(tasks).await;
+    results.sort_by_key(|result| {
+        (
+            result.route,
+            result.error.is_some(),
+            result.failures,
+            result.median_ms.unwrap_or(u64::MAX),
+        )
+    });
+
+    Ok(DnsBenchmarkReport {
+        trusted: trusted_server,
+        domains,
+        results,
+    })
+}
+
+/// 将直连测试中最快且没有污染的服务器写入 `nameserver`
+///
+/// mihomo 默认直连 DNS 服务器，因此只参考直连的结果
+pub async fn apply_fastest_nameservers(report: DnsBenchmarkReport) -> Result<Vec<String>> {
+    let mut candidates: Vec<_> = report
+        .results
+        .into_iter()
+        .filter(|result| {
+            result.route == DnsRoute::Direct
+                && result.error.is_none()
+                && result.poisoned.is_empty()
+                && result.failure_rate <= MAX_FAILURE_RATE
+                && result.median_ms.is_some()
+        })
+        .collect();
+    candidates.sort_by_key(|result| (result.median_ms, result.p95_ms));
+    let fastest: Vec<String> = candidates
+        .into_iter()
+        .take(FASTEST_COUNT)
+        .map(|result| result.nameserver)
+        .collect();
+    if fastest.is_empty() {
+        bail!("no nameserver passed the benchmark");
+    }
+
+    let mut dns_config = load_dns_config()?;
+    let previous = dns_config.clone();
+    dns_config.dns.nameserver = Some(fastest.clone());
+    dns_config.validate()?;
+    dns_config.save()?;
+    logging!(
+        info,
+        Type::Config,
+        true,
+        "Applied fastest nameservers: {:?}",
+        fastest
+    );
+
+    // 内核拒绝新配置时恢复原来的服务器
+    if let Err(err) = reload_dns_settings().await {
+        previous.save()?;
+        return Err(err);
+    }
+    Ok(fastest)
+}
+
+/// 配置文件无效时不覆盖，交给用户处理
+fn load_dns_config() -> Result<IDnsConfig> {
+    match dirs::dns_config_path()?.exists() {
+        true => IDnsConfig::load(),
+        false => Ok(IDnsConfig::template()),
+    }
+}
+
+/// 启用了 DNS 覆写时重新生成配置
+async fn reload_dns_settings() -> Result<()> {
+    if Config::verge()
+        .latest()
+        .enable_dns_settings
+        .unwrap_or(false)
+    {
+        let (valid, msg) = CoreManager::global().update_config().await?;
+        if !valid {
+            bail!("failed to apply dns settings: {msg}");
+        }
+        handle::Handle::refresh_clash();
+    }
+    Ok(())
+}
+
+fn system_hosts_path() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".into());
+        PathBuf::from(root).join(r"System32\drivers\etc\hosts")
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        PathBuf::from("/etc/hosts")
+    }
+}
+
+/// 将系统 hosts 导入 DNS 配置的 `hosts`
+pub async fn import_system_hosts(overwrite: bool) -> Result<HostsImportReport> {
+    let path = system_hosts_path();
+    let content = std::fs::read_to_string(&path)
+        .map_err(|err| anyhow!("failed to read {}: {err}", path.display()))?;
+
+    let mut dns_config = load_dns_config()?;
+    let report = dns_config.import_hosts(&content, overwrite);
+    dns_config.validate()?;
+    dns_config.save()?;
+    logging!(
+        info,
+        Type::Config,
+        true,
+        "Imported system hosts: {} new, {} conflicts, {} skipped",
+        report.imported.len(),
+        report.conflicts.len(),
+        report.skipped.len()
+    );
+
+    if !report.imported.is_empty() || (overwrite && !report.conflicts.is_empty()) {
+        reload_dns_settings().await?;
+    }
+    Ok(report)
+}
+
+/// 清空内核的 fake-ip 缓存，修改 fake-ip 过滤后需要执行
+pub async fn flush_fakeip_cache() -> Result<()> {
+    MihomoManager::global()
+        .flush_fakeip_cache()
+        .await
+        .map_err(|err| anyhow!("failed to flush fake-ip cache: {err}"))?;
+    logging!(info, Type::Network, true, "Fake-ip cache flushed");
+    Ok(())
+}
+
+/// 通过内核的 DNS 模块查询，返回原始的应答
+pub async fn query_core_dns(name: String, query_type: Option<String>) -> Result<serde_json::Value> {
+    let name = name.trim().trim_end_matches('.');
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    if name.is_empty() || !name.chars().all(valid_char) {
+        bail!("invalid domain \"{name}\"");
+    }
+    let query_type = query_type
+        .unwrap_or_else(|| "A".into())
+        .trim()
+        .to_ascii_uppercase();
+    if !QUERY_TYPES.contains(&query_type.as_str()) {
+        bail!("unsupported query type \"{query_type}\"");
+    }
+
+    MihomoManager::global()
+        .dns_query(name, &query_type)
+        .await
+        .map_err(|err| anyhow!("failed to query {name}: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let samples = [10, 20, 30, 40, 50, 60, 70, 80, 90, 100];
+        assert_eq!(percentile(&samples, 0.5), Some(50));
+        assert_eq!(percentile(&samples, 0.95), Some(100));
+        assert_eq!(percentile(&[7], 0.95), Some(7));
+        assert_eq!(percentile(&[], 0.5), None);
+    }
+
+    #[test]
+    fn test_compare_answers() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let trusted = [ip("142.250.74.68")];
+
+        assert_eq!(
+            compare_answers(&[ip("142.250.74.100")], &trusted),
+            (false, false)
+        );
+        assert_eq!(compare_answers(&[ip("127.0.0.1")], &trusted), (true, true));
+        assert_eq!(
+            compare_answers(&[ip("31.13.64.1")], &trusted),
+            (false, true)
+        );
+        assert_eq!(compare_answers(&[], &trusted), (false, false));
+    }
+}
@@ -0,0 +1,157 @@
+//! 局域网网关模式：把本机代理分享给同一网络中的设备
+
+use crate::{
+    config::{Config, IClashTemp, IVerge},
+    utils::{cidr::IpCidr, server},
+};
+use anyhow::Result;
+use network_interface::{Addr, NetworkInterface, NetworkInterfaceConfig};
+use qrcode::{render::svg, QrCode};
+use serde::Serialize;
+use serde_yaml::Mapping;
+use std::net::{IpAddr, Ipv4Addr};
+
+/// 其他设备接入本机代理需要的信息
+#[derive(Debug, Clone, Serialize)]
+pub struct LanGatewayInfo {
+    pub allow_lan: bool,
+    /// 其他设备可以访问到的本机地址
+    pub addresses: Vec<String>,
+    pub mixed_port: u16,
+    pub pac_port: u16,
+    /// 连接代理是否需要用户名和密码
+    pub authentication: bool,
+}
+
+fn allow_lan(config: &Mapping) -> bool {
+    config
+        .get("allow-lan")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+/// bind-address 为 `*` 或未设置时监听所有地址
+fn bind_address(config: &Mapping) -> IpAddr {
+    config
+        .get("bind-address")
+        .and_then(|value| value.as_str())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+}
+
+/// 本机的局域网 IPv4 地址，私有网段排在前面
+pub fn lan_addresses() -> Vec<IpAddr> {
+    let bind = bind_address(&Config::clash().latest().0);
+    if !bind.is_unspecified() {
+        return vec![bind];
+    }
+
+    let interfaces = match NetworkInterface::show() {
+        Ok(interfaces) => interfaces,
+        Err(err) => {
+            log::warn!(target: "app", "Failed to list network interfaces: {err}");
+            return vec![];
+        }
+    };
+    let mut addresses: Vec<Ipv4Addr> = interfaces
+        .iter()
+        .flat_map(|interface| &interface.addr)
+        .filter_map(|addr| match addr {
+            Addr::V4(v4) => Some(v4.ip),
+            Addr::V6(_) => None,
+        })
+        .filter(|ip| !ip.is_loopback() && !ip.is_link_local() && !ip.is_unspecified())
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    addresses.sort_by_key(|ip| !ip.is_private());
+    addresses.into_iter().map(IpAddr::V4).collect()
+}
+
+pub fn lan_gateway_info() -> LanGatewayInfo {
+    let (allow_lan, authentication, mixed_port) = {
+        let clash = Config::clash();
+        let clash = clash.latest();
+        let authentication = IClashTemp::string_list(&clash.0, "authentication")
+            .is_ok_and(|users| !users.is_empty());
+        (allow_lan(&clash.0), authentication, clash.get_mixed_port())
+    };
+    let mixed_port = Config::verge()
+        .latest()
+        .verge_mixed_port
+        .unwrap_or(mixed_port);
+
+    LanGatewayInfo {
+        allow_lan,
+        addresses: lan_addresses().iter().map(ToString::to_string).collect(),
+        mixed_port,
+        pac_port: IVerge::get_lan_pac_port(),
+        authentication,
+    }
+}
+
+/// 按 lan-allowed-ips 和 lan-disallowed-ips 判断设备能否访问，未设置白名单时允许所有设备
+pub fn lan_client_allowed(config: &Mapping, ip: IpAddr) -> bool {
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    };
+    let networks = |key: &str| -> Vec<IpCidr> {
+        IClashTemp::string_list(config, key)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|item| item.parse().ok())
+            .collect()
+    };
+    let allowed = networks("lan-allowed-ips");
+    let disallowed = networks("lan-disallowed-ips");
+
+    (allowed.is_empty() || allowed.iter().any(|cidr| cidr.contains(&ip)))
+        && !disallowed.iter().any(|cidr| cidr.contains(&ip))
+}
+
+/// allow-lan 开启时为局域网设备提供 PAC，关闭时停止
+pub fn sync_lan_pac() {
+    let bind = {
+        let clash = Config::clash();
+        let clash = clash.latest();
+        allow_lan(&clash.0).then(|| bind_address(&clash.0))
+    };
+    server::lan_pac_server(bind);
+}
+
+/// 生成二维码 SVG，供其他设备扫码
+pub fn qr_code_svg(text: &str) -> Result<String> {
+    let code = QrCode::new(text.as_bytes())?;
+    Ok(code
+        .render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .quiet_zone(true)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lan_client_allowed() {
+        let config: Mapping = serde_yaml::from_str(
+            r#"
+lan-allowed-ips: [192.168.1.0/24]
+lan-disallowed-ips: [192.168.1.13]
+"#,
+        )
+        .unwrap();
+        let allowed = |ip: &str| lan_client_allowed(&config, ip.parse().unwrap());
+
+        assert!(allowed("192.168.1.2"));
+        assert!(allowed("::ffff:192.168.1.2"));
+        assert!(!allowed("192.168.1.13"));
+        assert!(!allowed("10.0.0.2"));
+        assert!(lan_client_allowed(
+            &Mapping::new(),
+            "10.0.0.2".parse().unwrap()
+        ));
+    }
+}
@@ -1,14 +1,24 @@
+mod announcement;
 mod backup;
 mod clash;
 mod config;
+mod dns;
+mod lan_gateway;
 mod profile;
 mod proxy;
+mod shell_env;
+mod subscription;
 mod window;
 
 // Re-export all functions from modules
+pub use announcement::*;
 pub use backup::*;
 pub use clash::*;
 pub use config::*;
+pub use dns::*;
+pub use lan_gateway::*;
 pub use profile::*;
 pub use proxy::*;
+pub use shell_env::*;
+pub use subscription::*;
 pub use window::*;
@@ -1,18 +1,45 @@
 use crate::{
-    cmd,
-    config::{Config, PrfItem, PrfOption},
+    config::{Config, DeviceLimitError, IProfiles, PrfItem, PrfOption},
     core::{handle, CoreManager, *},
     logging,
     process::AsyncHandler,
-    utils::logging::Type,
+    utils::{dirs, logging::Type},
 };
 use anyhow::{bail, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+
+// 全局互斥锁防止并发配置更新
+static PROFILE_UPDATE_MUTEX: Mutex<()> = Mutex::const_new(());
+
+// 全局请求序列号跟踪，用于避免队列化执行
+static CURRENT_REQUEST_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+static CURRENT_PROCESSING_PROFILE: RwLock<Option<String>> = RwLock::const_new(None);
+
+/// 清理配置处理状态
+async fn cleanup_processing_state(sequence: u64, reason: &str) {
+    *CURRENT_PROCESSING_PROFILE.write().await = None;
+    logging!(
+        info,
+        Type::Cmd,
+        true,
+        "{}，Cleanup status, serial number: {}",
+        reason,
+        sequence
+    );
+}
 
 /// Toggle proxy profile
 pub fn toggle_proxy_profile(profile_index: String) {
     AsyncHandler::spawn(|| async move {
-        let app_handle = handle::Handle::global().app_handle().unwrap();
-        match cmd::patch_profiles_config_by_profile_index(app_handle, profile_index).await {
+        let profiles = IProfiles {
+            current: Some(profile_index),
+            items: None,
+            ..IProfiles::default()
+        };
+        match patch_profiles_config(profiles).await {
             Ok(_) => {
                 let _ = tray::Tray::global().update_menu();
             }
@@ -23,6 +50,419 @@ pub fn toggle_proxy_profile(profile_index: String) {
     });
 }
 
+/// Switch to a profile and wait until it is applied
+pub async fn switch_profile(uid: String) -> Result<bool> {
+    Config::profiles().latest().get_item(&uid)?;
+    let profiles = IProfiles {
+        current: Some(uid),
+        items: None,
+        ..IProfiles::default()
+    };
+    let switched = patch_profiles_config(profiles).await?;
+    if switched {
+        let _ = tray::Tray::global().update_menu();
+    }
+    Ok(switched)
+}
+
+/// 修改profiles的配置
+pub async fn patch_profiles_config(profiles: IProfiles) -> Result<bool> {
+    // 为当前请求分配序列号
+    let current_sequence = CURRENT_REQUEST_SEQUENCE.fetch_add(1, Ordering::SeqCst) + 1;
+    let target_profile = profiles.current.clone();
+
+    logging!(
+        info,
+        Type::Cmd,
+        true,
+        "Starting to modify profiles, sequence: {}, target profile: {:?}",
+        current_sequence,
+        target_profile
+    );
+
+    let mutex_result =
+        tokio::time::timeout(Duration::from_millis(100), PROFILE_UPDATE_MUTEX.lock()).await;
+
+    let _guard = match mutex_result {
+        Ok(guard) => guard,
+        Err(_) => {
+            let latest_sequence = CURRENT_REQUEST_SEQUENCE.load(Ordering::SeqCst);
+            if current_sequence < latest_sequence {
+                logging!(
+                    info,
+                    Type::Cmd,
+                    true,
+                    "Newer request detected (seq: {} < {}), abandoning current",
+                    current_sequence,
+                    latest_sequence
+                );
+                return Ok(false);
+            }
+            logging!(
+                info,
+                Type::Cmd,
+                true,
+                "Force acquiring lock to process latest request: {}",
+                current_sequence
+            );
+            PROFILE_UPDATE_MUTEX.lock().await
+        }
+    };
+
+    let latest_sequence = CURRENT_REQUEST_SEQUENCE.load(Ordering::SeqCst);
+    if current_sequence < latest_sequence {
+        logging!(
+            info,
+            Type::Cmd,
+            true,
+            "After acquiring lock, found newer request (seq: {} < {}), abandoning current",
+            current_sequence,
+            latest_sequence
+        );
+        return Ok(false);
+    }
+
+    // 保存当前配置，以便在验证失败时恢复
+    let current_profile = Config::profiles().latest().current.clone();
+    logging!(info, Type::Cmd, true, "Current profile: {:?}", current_profile);
+
+    // 如果要切换配置，先检查目标配置文件是否有语法错误
+    if let Some(new_profile) = profiles.current.as_ref() {
+        if current_profile.as_ref() != Some(new_profile) {
+            logging!(info, Type::Cmd, true, "Switching to new profile: {}", new_profile);
+
+            // 获取目标配置文件路径
+            let config_file_result = {
+                let profiles_config = Config::profiles();
+                let profiles_data = profiles_config.latest();
+                match profiles_data.get_item(new_profile) {
+                    Ok(item) => {
+                        if let Some(file) = &item.file {
+                            let path = dirs::app_profiles_dir().map(|dir| dir.join(file));
+                            path.ok()
+                        } else {
+                            None
+                        }
+                    }
+                    Err(e) => {
+                        logging!(error, Type::Cmd, true, "Failed to get target profile info: {}", e);
+                        None
+                    }
+                }
+            };
+
+            // 如果获取到文件路径，检查YAML语法
+            if let Some(file_path) = config_file_result {
+                if !file_path.exists() {
+                    logging!(
+                        error,
+                        Type::Cmd,
+                        true,
+                        "Target profile does not exist: {}",
+                        file_path.display()
+                    );
+                    handle::Handle::notice_message(
+                        "config_validate::file_not_found",
+                        format!("{}", file_path.display()),
+                    );
+                    return Ok(false);
+                }
+
+                // 超时保护
+                let file_read_result = tokio::time::timeout(
+                    Duration::from_secs(5),
+                    tokio::fs::read_to_string(&file_path),
+                )
+                .await;
+
+                match file_read_result {
+                    Ok(Ok(content)) => {
+                        let yaml_parse_result = tokio::task::spawn_blocking(move || {
+                            serde_yaml::from_str::<serde_yaml::Value>(&content)
+                        })
+                        .await;
+
+                        match yaml_parse_result {
+                            Ok(Ok(_)) => {
+                                logging!(info, Type::Cmd, true, "Target profile file syntax is correct");
+                            }
+                            Ok(Err(err)) => {
+                                let error_msg = format!(" {err}");
+                                logging!(
+                                    error,
+                                    Type::Cmd,
+                                    true,
+                                    "YAML syntax error in target profile file: {}",
+                                    error_msg
+                                );
+                                handle::Handle::notice_message(
+                                    "config_validate::yaml_syntax_error",
+                                    &error_msg,
+                                );
+                                return Ok(false);
+                            }
+                            Err(join_err) => {
+                                let error_msg = format!("YAML parse task failed: {join_err}");
+                                logging!(error, Type::Cmd, true, "{}", error_msg);
+                                handle::Handle::notice_message(
+                                    "config_validate::yaml_parse_error",
+                                    &error_msg,
+                                );
+                                return Ok(false);
+                            }
+                        }
+                    }
+                    Ok(Err(err)) => {
+                        let error_msg = format!("Failed to read target profile file: {err}");
+                        logging!(error, Type::Cmd, true, "{}", error_msg);
+                        handle::Handle::notice_message(
+                            "config_validate::file_read_error",
+                            &error_msg,
+                        );
+                        return Ok(false);
+                    }
+                    Err(_) => {
+                        let error_msg = "Reading config file timed out (5s)".to_string();
+                        logging!(error, Type::Cmd, true, "{}", error_msg);
+                        handle::Handle::notice_message(
+                            "config_validate::file_read_timeout",
+                            &error_msg,
+                        );
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+    }
+
+    // 检查请求有效性
+    let latest_sequence = CURRENT_REQUEST_SEQUENCE.load(Ordering::SeqCst);
+    if current_sequence < latest_sequence {
+        logging!(
+            info,
+            Type::Cmd,
+            true,
+            "Found newer request before core operation (seq: {} < {}), abandoning current",
+            current_sequence,
+            latest_sequence
+        );
+        return Ok(false);
+    }
+
+    if let Some(ref profile) = target_profile {
+        *CURRENT_PROCESSING_PROFILE.write().await = Some(profile.clone());
+        logging!(
+            info,
+            Type::Cmd,
+            true,
+            "Set current processing profile: {}, serial number: {}",
+            profile,
+            current_sequence
+        );
+    }
+
+    // 更新profiles配置
+    logging!(
+        info,
+        Type::Cmd,
+        true,
+        "Updating draft profiles, sequence: {}",
+        current_sequence
+    );
+
+    let current_value = profiles.current.clone();
+
+    let _ = Config::profiles().draft().patch_config(profiles);
+
+    // 在调用内核前再次验证请求有效性
+    let latest_sequence = CURRENT_REQUEST_SEQUENCE.load(Ordering::SeqCst);
+    if current_sequence < latest_sequence {
+        logging!(
+            info,
+            Type::Cmd,
+            true,
+            "Detect updated requests before kernel interaction (sequence number: {} < {}) and abandon the current request.",
+            current_sequence,
+            latest_sequence
+        );
+        Config::profiles().discard();
+        return Ok(false);
+    }
+
+    // 为配置更新添加超时保护
+    logging!(
+        info,
+        Type::Cmd,
+        true,
+        "Starting kernel config update, sequence: {}",
+        current_sequence
+    );
+    let update_result = tokio::time::timeout(
+        Duration::from_secs(30), // 30秒超时
+        CoreManager::global().update_config(),
+    )
+    .await;
+
+    // 更新配置并进行验证
+    match update_result {
+        Ok(Ok((true, _))) => {
+            // 内核操作完成后再次检查请求有效性
+            let latest_sequence = CURRENT_REQUEST_SEQUENCE.load(Ordering::SeqCst);
+            if current_sequence < latest_sequence {
+                logging!(
+                    info,
+                    Type::Cmd,
+                    true,
+                    "After kernel operation, an updated request was found (sequence number: {} < {}), ignore the current result.",
+                    current_sequence,
+                    latest_sequence
+                );
+                Config::profiles().discard();
+                return Ok(false);
+            }
+
+            logging!(
+                info,
+                Type::Cmd,
+                true,
+                "Configuration update successful, serial number: {}",
+                current_sequence
+            );
+            Config::profiles().apply();
+            handle::Handle::refresh_clash();
+
+            // 强制刷新代理缓存，确保profile切换后立即获取最新节点数据
+            crate::process::AsyncHandler::spawn(|| async move {
+                if let Err(e) = super::force_refresh_proxies().await {
+                    log::warn!(target: "app", "Force refresh proxy cache failed: {e}");
+                }
+            });
+
+            crate::process::AsyncHandler::spawn(|| async move {
+                if let Err(e) = tray::Tray::global().update_tooltip() {
+                    log::warn!(target: "app", "Async tray tooltip update failed: {e}");
+                }
+
+                if let Err(e) = tray::Tray::global().update_menu() {
+                    log::warn!(target: "app", "Async tray menu update failed: {e}");
+                }
+
+                // 保存配置文件
+                if let Err(e) = Config::profiles().data().save_file() {
+                    log::warn!(target: "app", "Async save profiles file failed: {e}");
+                }
+            });
+
+            // 立即通知前端配置变更
+            if let Some(current) = &current_value {
+                logging!(
+                    info,
+                    Type::Cmd,
+                    true,
+                    "Sending profile change event to frontend: {}, sequence: {}",
+                    current,
+                    current_sequence
+                );
+                handle::Handle::notify_profile_changed(current.clone());
+            }
+
+            cleanup_processing_state(current_sequence, "Profile switch completed").await;
+
+            Ok(true)
+        }
+        Ok(Ok((false, error_msg))) => {
+            logging!(warn, Type::Cmd, true, "Profile validation failed: {}", error_msg);
+            Config::profiles().discard();
+            // 如果验证失败，恢复到之前的配置
+            if let Some(prev_profile) = current_profile {
+                logging!(
+                    info,
+                    Type::Cmd,
+                    true,
+                    "Attempting to restore previous profile: {}",
+                    prev_profile
+                );
+                let restore_profiles = IProfiles {
+                    current: Some(prev_profile),
+                    items: None,
+                    ..IProfiles::default()
+                };
+                // 静默恢复，不触发验证
+                Config::profiles().draft().patch_config(restore_profiles)?;
+                Config::profiles().apply();
+
+                crate::process::AsyncHandler::spawn(|| async move {
+                    if let Err(e) = Config::profiles().data().save_file() {
+                        log::warn!(target: "app", "Failed to save and restore configuration file asynchronously: {e}");
+                    }
+                });
+
+                logging!(info, Type::Cmd, true, "Successfully restored previous profile");
+            }
+
+            // 发送验证错误通知
+            handle::Handle::notice_message("config_validate::error", &error_msg);
+
+            cleanup_processing_state(current_sequence, "Profile validation failed").await;
+
+            Ok(false)
+        }
+        Ok(Err(e)) => {
+            logging!(
+                warn,
+                Type::Cmd,
+                true,
+                "Error occurred during update: {}, sequence: {}",
+                e,
+                current_sequence
+            );
+            Config::profiles().discard();
+            handle::Handle::notice_message("config_validate::boot_error", e.to_string());
+
+            cleanup_processing_state(current_sequence, "Update process error").await;
+
+            Ok(false)
+        }
+        Err(_) => {
+            // 超时处理
+            let timeout_msg = "Profile update timed out (30s), possibly due to validation or kernel communication";
+            logging!(
+                error,
+                Type::Cmd,
+                true,
+                "{}, sequence: {}",
+                timeout_msg,
+                current_sequence
+            );
+            Config::profiles().discard();
+
+            if let Some(prev_profile) = current_profile {
+                logging!(
+                    info,
+                    Type::Cmd,
+                    true,
+                    "After timeout, attempting to restore previous profile: {}, sequence: {}",
+                    prev_profile,
+                    current_sequence
+                );
+                let restore_profiles = IProfiles {
+                    current: Some(prev_profile),
+                    items: None,
+                    ..IProfiles::default()
+                };
+                Config::profiles().draft().patch_config(restore_profiles)?;
+                Config::profiles().apply();
+            }
+
+            handle::Handle::notice_message("config_validate::timeout", timeout_msg);
+
+            cleanup_processing_state(current_sequence, "Profile update timeout").await;
+
+            Ok(false)
+        }
+    }
+}
+
 /// Update a profile
 /// If updating current profile, activate it
 /// auto_refresh: 是否自动更新配置和刷新前端
@@ -62,6 +502,7 @@ pub async fn update_profile(
         }
     };
 
+    let is_remote = url_opt.is_some();
     let should_update = match url_opt {
         Some((url, opt)) => {
             log::info!(target: "app", "[Subscription Update] Start downloading new subscription content");
@@ -79,6 +520,12 @@ pub async fn update_profile(
                     log::info!(target: "app", "[Subscription Update] Is current active subscription: {is_current}");
                     is_current && auto_refresh
                 }
+                Err(err) if err.downcast_ref::<DeviceLimitError>().is_some() => {
+                    // 设备数量超限，使用代理重试没有意义
+                    log::warn!(target: "app", "[Subscription Update] Device limit reached for {uid}: {err}");
+                    notify_device_limit(Some(&uid), &err);
+                    return Err(err);
+                }
                 Err(err) => {
                     // 首次更新失败，尝试使用Clash代理
                     log::warn!(target: "app", "[Subscription Update] Normal update failed: {err}, trying to update via Clash proxy");
@@ -136,6 +583,11 @@ pub async fn update_profile(
         None => auto_refresh,
     };
 
+    if is_remote {
+        super::record_subscription_usage(&uid);
+        super::record_profile_announcement(&uid);
+    }
+
     if should_update {
         logging!(
             info,
@@ -170,6 +622,90 @@ pub async fn update_profile(
     Ok(())
 }
 
+/// 导入订阅的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileImport {
+    /// 已存在相同 URL 的订阅，已更新
+    Updated(String),
+    /// 新建的订阅
+    Created(String),
+}
+
+/// 导入订阅：已存在相同 URL 时更新，否则新建并记录流量和公告
+/// auto_refresh: 更新已有订阅时是否刷新配置和前端
+pub async fn import_profile(
+    url: &str,
+    name: Option<String>,
+    option: Option<PrfOption>,
+    auto_refresh: bool,
+) -> Result<ProfileImport> {
+    let existing_uid = Config::profiles()
+        .latest()
+        .get_items()
+        .into_iter()
+        .flatten()
+        .find(|item| item.url.as_deref() == Some(url))
+        .and_then(|item| item.uid.clone());
+
+    if let Some(uid) = existing_uid {
+        logging!(
+            info,
+            Type::Config,
+            true,
+            "The profile with URL {} already exists (UID: {}). Running the update...",
+            url,
+            uid
+        );
+        update_profile(uid.clone(), option, Some(auto_refresh)).await?;
+        return Ok(ProfileImport::Updated(uid));
+    }
+
+    logging!(
+        info,
+        Type::Config,
+        true,
+        "Profile with URL {} not found. Create a new one...",
+        url
+    );
+    let item = match PrfItem::from_url(url, name, None, option).await {
+        Ok(item) => item,
+        Err(err) => {
+            notify_device_limit(None, &err);
+            return Err(err);
+        }
+    };
+    let uid = item.uid.clone().unwrap_or_default();
+    Config::profiles().data().append_item(item)?;
+    if !uid.is_empty() {
+        super::record_subscription_usage(&uid);
+        super::record_profile_announcement(&uid);
+    }
+    Ok(ProfileImport::Created(uid))
+}
+
+/// 通知前端订阅因设备数量超限被拒绝，并保存提供方的公告
+pub fn notify_device_limit(uid: Option<&str>, err: &anyhow::Error) {
+    let Some(limit) = err.downcast_ref::<DeviceLimitError>() else {
+        return;
+    };
+
+    let profile_name = uid.and_then(|uid| {
+        Config::profiles()
+            .latest()
+            .get_item(&uid.to_string())
+            .ok()
+            .and_then(|item| item.name.clone())
+    });
+    super::record_announcement(
+        uid,
+        profile_name.as_deref(),
+        &limit.announce,
+        limit.announce_url.as_deref(),
+    );
+
+    handle::Handle::notice_message("profile::device_limit", limit.announce.clone());
+}
+
 /// 增强配置
 pub async fn enhance_profiles() -> Result<()> {
     crate::core::CoreManager::global()
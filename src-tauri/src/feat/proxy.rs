@@ -1,11 +1,41 @@
+use super::{proxy_endpoint, shell_env_text, ShellKind};
 use crate::{
     config::{Config, IVerge},
     core::handle,
+    module::mihomo::MihomoManager,
     process::AsyncHandler,
+    state::proxy::CmdProxyState,
 };
-use std::env;
+use anyhow::{anyhow, Result};
+use std::{sync::Mutex, time::Instant};
+use tauri::Manager;
 use tauri_plugin_clipboard_manager::ClipboardExt;
 
+/// 强制刷新代理缓存，用于切换订阅后立即获取最新节点
+pub async fn force_refresh_proxies() -> Result<serde_json::Value> {
+    let app_handle = handle::Handle::global()
+        .app_handle()
+        .ok_or_else(|| anyhow!("app handle is not ready"))?;
+
+    log::debug!(target: "app", "Force refresh proxy cache");
+
+    let proxies = MihomoManager::global()
+        .get_refresh_proxies()
+        .await
+        .map_err(|err| anyhow!(err))?;
+
+    {
+        let cmd_proxy_state = app_handle.state::<Mutex<CmdProxyState>>();
+        let mut state = cmd_proxy_state.lock().unwrap();
+        state.proxies = Box::new(proxies.clone());
+        state.need_refresh = false;
+        state.last_refresh_time = Instant::now();
+    }
+
+    log::debug!(target: "app", "Force refresh proxy cache completed");
+    Ok(proxies)
+}
+
 /// Toggle system proxy on/off
 pub fn toggle_system_proxy() {
     let enable = Config::verge().draft().enable_system_proxy;
@@ -64,20 +94,7 @@ pub fn toggle_tun_mode(not_save_file: Option<bool>) {
 
 /// Copy proxy environment variables to clipboard
 pub fn copy_clash_env() {
-    // 从环境变量获取IP地址，如果没有则从配置中获取 proxy_host，默认为 127.0.0.1
-    let clash_verge_rev_ip = env::var("CLASH_VERGE_REV_IP").unwrap_or_else(|_| {
-        Config::verge()
-            .latest()
-            .proxy_host
-            .clone()
-            .unwrap_or_else(|| "127.0.0.1".to_string())
-    });
-
     let app_handle = handle::Handle::global().app_handle().unwrap();
-    let port = { Config::verge().latest().verge_mixed_port.unwrap_or(7897) };
-    let http_proxy = format!("http://{clash_verge_rev_ip}:{port}");
-    let socks5_proxy = format!("socks5://{clash_verge_rev_ip}:{port}");
-
     let cliboard = app_handle.clipboard();
     let env_type = { Config::verge().latest().env_type.clone() };
     let env_type = match env_type {
@@ -92,10 +109,12 @@ pub fn copy_clash_env() {
         }
     };
 
+    let (host, port) = proxy_endpoint();
+    let http_proxy = format!("http://{host}:{port}");
+
     let export_text = match env_type.as_str() {
-        "bash" => format!(
-            "export https_proxy={http_proxy} http_proxy={http_proxy} all_proxy={socks5_proxy}"
-        ),
+        "bash" => shell_env_text(ShellKind::Posix),
+        "fish" => shell_env_text(ShellKind::Fish),
         "cmd" => format!("set http_proxy={http_proxy}\r\nset https_proxy={http_proxy}"),
         "powershell" => {
             format!("$env:HTTP_PROXY=\"{http_proxy}\"; $env:HTTPS_PROXY=\"{http_proxy}\"")
@@ -103,7 +122,6 @@ pub fn copy_clash_env() {
         "nushell" => {
             format!("load-env {{ http_proxy: \"{http_proxy}\", https_proxy: \"{http_proxy}\" }}")
         }
-        "fish" => format!("set -x http_proxy {http_proxy}; set -x https_proxy {http_proxy}"),
         _ => {
             log::error!(target: "app", "copy_clash_env: Invalid env type! {env_type}");
             return;
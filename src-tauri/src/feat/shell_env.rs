@@ -0,0 +1,302 @@
+//! 把代理环境变量接入 shell
+//!
+//! 变量写在应用目录下由程序维护的文件中，shell 配置里只加入一段带标记的 source 语句
+
+use crate::{
+    config::Config,
+    utils::{bypass, dirs},
+};
+use anyhow::Result;
+use std::{fs, path::PathBuf};
+
+const MARKER_BEGIN: &str = "# >>> koala-clash proxy >>>";
+const MARKER_END: &str = "# <<< koala-clash proxy <<<";
+const ENV_DIR: &str = "shell";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+    Posix,
+    Fish,
+}
+
+/// 代理环境变量，小写和大写各写一份
+fn proxy_vars(host: &str, port: u16, no_proxy: &str, socks_only: bool) -> Vec<(String, String)> {
+    let http = format!("http://{host}:{port}");
+    let socks = format!("socks5://{host}:{port}");
+    let mut vars = Vec::new();
+    if !socks_only {
+        vars.push(("http_proxy", http.clone()));
+        vars.push(("https_proxy", http));
+    }
+    vars.push(("all_proxy", socks));
+    if !no_proxy.is_empty() {
+        vars.push(("no_proxy", no_proxy.to_string()));
+    }
+
+    vars.into_iter()
+        .flat_map(|(key, value)| {
+            [
+                (key.to_string(), value.clone()),
+                (key.to_ascii_uppercase(), value),
+            ]
+        })
+        .collect()
+}
+
+/// 单引号内的转义
+fn quote(shell: ShellKind, value: &str) -> String {
+    match shell {
+        ShellKind::Posix => format!("'{}'", value.replace('\'', r"'\''")),
+        ShellKind::Fish => format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'")),
+    }
+}
+
+fn render(shell: ShellKind, vars: &[(String, String)]) -> String {
+    let lines: Vec<String> = vars
+        .iter()
+        .map(|(key, value)| match shell {
+            ShellKind::Posix => format!("{key}={}", quote(shell, value)),
+            ShellKind::Fish => format!("set -gx {key} {}", quote(shell, value)),
+        })
+        .collect();
+    match shell {
+        ShellKind::Posix => format!("export {}", lines.join(" ")),
+        ShellKind::Fish => lines.join("; "),
+    }
+}
+
+/// 代理地址，优先使用环境变量中的地址，其次是配置中的 proxy_host
+pub fn proxy_endpoint() -> (String, u16) {
+    let verge = Config::verge();
+    let verge = verge.latest();
+    let host = std::env::var("CLASH_VERGE_REV_IP").unwrap_or_else(|_| {
+        verge
+            .proxy_host
+            .clone()
+            .unwrap_or_else(|| "127.0.0.1".to_string())
+    });
+    let port = verge
+        .verge_mixed_port
+        .unwrap_or_else(|| Config::clash().latest().get_mixed_port());
+    (host, port)
+}
+
+/// 当前配置对应的导出语句
+pub fn shell_env_text(shell: ShellKind) -> String {
+    let (host, port) = proxy_endpoint();
+    let socks_only = Config::verge()
+        .latest()
+        .shell_env_socks_only
+        .unwrap_or(false);
+    let vars = proxy_vars(&host, port, &bypass::no_proxy(), socks_only);
+    render(shell, &vars)
+}
+
+fn hook_line(shell: ShellKind, file: &str) -> String {
+    let file = quote(shell, file);
+    match shell {
+        ShellKind::Fish => format!("test -f {file}; and source {file}"),
+        _ => format!("[ -f {file} ] && . {file}"),
+    }
+}
+
+/// 替换已有的标记段，没有时追加到末尾
+fn upsert_block(content: &str, line: &str) -> String {
+    let block = format!("{MARKER_BEGIN}\n{line}\n{MARKER_END}\n");
+    let base = remove_block(content).unwrap_or_else(|| content.to_string());
+    let mut result = base.trim_end_matches('\n').to_string();
+    if !result.is_empty() {
+        result.push_str("\n\n");
+    }
+    result.push_str(&block);
+    result
+}
+
+/// 删除标记段，没有标记时返回 None
+fn remove_block(content: &str) -> Option<String> {
+    let start = content.find(MARKER_BEGIN)?;
+    let end = content[start..].find(MARKER_END)? + start + MARKER_END.len();
+    let before = content[..start].trim_end_matches('\n');
+    let after = content[end..].trim_start_matches('\n');
+    let mut result = before.to_string();
+    if !before.is_empty() && !after.is_empty() {
+        result.push_str("\n\n");
+    }
+    result.push_str(after);
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    Some(result)
+}
+
+/// 需要接入的 shell 配置文件，只处理已经存在的 shell
+fn shell_profiles() -> Vec<(PathBuf, ShellKind)> {
+    let Some(home) = ::dirs::home_dir() else {
+        return vec![];
+    };
+    let zdotdir = std::env::var_os("ZDOTDIR").map_or_else(|| home.clone(), PathBuf::from);
+    let config_home =
+        std::env::var_os("XDG_CONFIG_HOME").map_or_else(|| home.join(".config"), PathBuf::from);
+    vec![
+        (home.join(".bashrc"), ShellKind::Posix),
+        (zdotdir.join(".zshrc"), ShellKind::Posix),
+        (
+            config_home.join("fish").join("config.fish"),
+            ShellKind::Fish,
+        ),
+    ]
+}
+
+fn env_file(shell: ShellKind) -> Result<PathBuf> {
+    let name = match shell {
+        ShellKind::Fish => "proxy.fish",
+        _ => "proxy.sh",
+    };
+    Ok(dirs::app_home_dir()?.join(ENV_DIR).join(name))
+}
+
+/// 写入环境变量文件，未启用系统代理时只保留说明，新开的终端不再走代理
+fn write_env_files(active: bool) -> Result<()> {
+    for shell in [ShellKind::Posix, ShellKind::Fish] {
+        let path = env_file(shell)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = "# Managed by Koala Clash, changes will be overwritten\n".to_string();
+        if active {
+            content.push_str(&shell_env_text(shell));
+            content.push('\n');
+        }
+        fs::write(&path, content)?;
+    }
+    Ok(())
+}
+
+/// 清空已写入的环境变量文件，退出应用时调用
+pub fn clear_shell_env() -> Result<()> {
+    if dirs::app_home_dir()?.join(ENV_DIR).exists() {
+        write_env_files(false)?;
+    }
+    Ok(())
+}
+
+/// 写入环境变量文件并接入 shell 配置，返回修改过的配置文件
+pub fn install_shell_env() -> Result<Vec<PathBuf>> {
+    let active = Config::verge()
+        .latest()
+        .enable_system_proxy
+        .unwrap_or(false);
+    write_env_files(active)?;
+
+    let mut hooked = Vec::new();
+    for (profile, shell) in shell_profiles() {
+        // fish 的配置文件可能还不存在，只要配置目录存在就接入
+        let exists = match shell {
+            ShellKind::Fish => profile.parent().is_some_and(|dir| dir.is_dir()),
+            _ => profile.is_file(),
+        };
+        if !exists {
+            continue;
+        }
+        let env_file = env_file(shell)?;
+        let line = hook_line(shell, &env_file.to_string_lossy());
+        let content = fs::read_to_string(&profile).unwrap_or_default();
+        let updated = upsert_block(&content, &line);
+        if updated != content {
+            fs::write(&profile, updated)?;
+            hooked.push(profile);
+        }
+    }
+    Ok(hooked)
+}
+
+/// 删除标记段和环境变量文件，返回修改过的配置文件
+pub fn uninstall_shell_env() -> Result<Vec<PathBuf>> {
+    let mut cleaned = Vec::new();
+    for (profile, _) in shell_profiles() {
+        let Ok(content) = fs::read_to_string(&profile) else {
+            continue;
+        };
+        if let Some(updated) = remove_block(&content) {
+            fs::write(&profile, updated)?;
+            cleaned.push(profile);
+        }
+    }
+
+    let env_dir = dirs::app_home_dir()?.join(ENV_DIR);
+    if env_dir.exists() {
+        fs::remove_dir_all(env_dir)?;
+    }
+    Ok(cleaned)
+}
+
+/// 按配置安装或卸载 shell 集成
+pub fn sync_shell_env() -> Result<()> {
+    let enabled = Config::verge().latest().enable_shell_env.unwrap_or(false);
+    let changed = match enabled {
+        true => install_shell_env()?,
+        false => uninstall_shell_env()?,
+    };
+    if !changed.is_empty() {
+        log::info!(target: "app", "Shell profiles updated: {changed:?}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_shells() {
+        let vars = proxy_vars("127.0.0.1", 7890, "localhost,.lan", false);
+        assert_eq!(
+            render(ShellKind::Posix, &vars),
+            "export http_proxy='http://127.0.0.1:7890' HTTP_PROXY='http://127.0.0.1:7890' \
+             https_proxy='http://127.0.0.1:7890' HTTPS_PROXY='http://127.0.0.1:7890' \
+             all_proxy='socks5://127.0.0.1:7890' ALL_PROXY='socks5://127.0.0.1:7890' \
+             no_proxy='localhost,.lan' NO_PROXY='localhost,.lan'"
+        );
+
+        let vars = proxy_vars("127.0.0.1", 7890, "", true);
+        assert_eq!(
+            render(ShellKind::Fish, &vars),
+            "set -gx all_proxy 'socks5://127.0.0.1:7890'; set -gx ALL_PROXY 'socks5://127.0.0.1:7890'"
+        );
+    }
+
+    #[test]
+    fn test_quote_value() {
+        let value = r"it's\here";
+        assert_eq!(quote(ShellKind::Posix, value), r"'it'\''s\here'");
+        assert_eq!(quote(ShellKind::Fish, value), r"'it\'s\\here'");
+    }
+
+    #[test]
+    fn test_hook_line() {
+        assert_eq!(
+            hook_line(ShellKind::Posix, "/home/o'neil/proxy.sh"),
+            r"[ -f '/home/o'\''neil/proxy.sh' ] && . '/home/o'\''neil/proxy.sh'"
+        );
+        assert_eq!(
+            hook_line(ShellKind::Fish, "/home/o'neil/proxy.fish"),
+            r"test -f '/home/o\'neil/proxy.fish'; and source '/home/o\'neil/proxy.fish'"
+        );
+    }
+
+    #[test]
+    fn test_profile_block() {
+        let line = "[ -f '/tmp/proxy.sh' ] && . '/tmp/proxy.sh'";
+        let block = format!("{MARKER_BEGIN}\n{line}\n{MARKER_END}\n");
+
+        assert_eq!(upsert_block("", line), block);
+        let installed = upsert_block("alias ll='ls -l'\n", line);
+        assert_eq!(installed, format!("alias ll='ls -l'\n\n{block}"));
+        assert_eq!(upsert_block(&installed, line), installed);
+
+        let surrounded = format!("a=1\n\n{block}\nb=2\n");
+        assert_eq!(remove_block(&surrounded).unwrap(), "a=1\n\nb=2\n");
+        assert_eq!(remove_block(&installed).unwrap(), "alias ll='ls -l'\n");
+        assert_eq!(remove_block("a=1\n"), None);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::{
+    config::{Config, PrfExtra},
+    core::handle,
+    logging,
+    utils::{
+        dirs, help,
+        logging::Type,
+        notification::{notify_event, NotificationEvent},
+    },
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// 每个订阅最多保留的历史采样数量
+const MAX_USAGE_SAMPLES: usize = 500;
+
+const DEFAULT_QUOTA_THRESHOLDS: [u8; 2] = [80, 95];
+const DEFAULT_EXPIRE_ALERT_DAYS: u64 = 3;
+const ONE_DAY_SECS: i64 = 86400;
+
+/// 一次订阅刷新时记录的流量采样
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct UsageSample {
+    pub timestamp: i64,
+    pub upload: u64,
+    pub download: u64,
+    pub total: u64,
+    pub expire: u64,
+}
+
+impl UsageSample {
+    fn used(&self) -> u64 {
+        self.upload.saturating_add(self.download)
+    }
+}
+
+/// 单个订阅的流量历史以及已触发的提醒
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UsageHistory {
+    pub samples: Vec<UsageSample>,
+
+    /// 已经提醒过的阈值，例如 `quota-80`、`expire-3`
+    #[serde(default)]
+    pub fired_alerts: Vec<String>,
+}
+
+/// 需要推送给用户的订阅提醒
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsageAlert {
+    Quota { percent: u8 },
+    Expire { days: u64 },
+}
+
+impl UsageAlert {
+    fn key(&self) -> String {
+        match self {
+            UsageAlert::Quota { percent } => format!("quota-{percent}"),
+            UsageAlert::Expire { days } => format!("expire-{days}"),
+        }
+    }
+}
+
+impl UsageHistory {
+    fn path(uid: &str) -> Result<std::path::PathBuf> {
+        Ok(dirs::app_usage_dir()?.join(format!("{uid}.yaml")))
+    }
+
+    pub fn load(uid: &str) -> Self {
+        Self::path(uid)
+            .and_then(|path| help::read_yaml::<UsageHistory>(&path))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, uid: &str) -> Result<()> {
+        let path = Self::path(uid)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        help::save_yaml(&path, self, Some("# Subscription usage history"))
+    }
+
+    pub fn remove(uid: &str) -> Result<()> {
+        let path = Self::path(uid)?;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// 追加采样；流量被重置或到期时间延后时清空已触发的提醒
+    fn push(&mut self, sample: UsageSample) {
+        if let Some(last) = self.samples.last() {
+            if sample.used() < last.used() || sample.expire > last.expire {
+                self.fired_alerts.clear();
+            }
+        }
+
+        self.samples.push(sample);
+        if self.samples.len() > MAX_USAGE_SAMPLES {
+            let overflow = self.samples.len() - MAX_USAGE_SAMPLES;
+            self.samples.drain(..overflow);
+        }
+    }
+
+    /// 计算尚未触发过的提醒，每类只取最严重的一条
+    fn pending_alerts(
+        &self,
+        extra: &PrfExtra,
+        now: i64,
+        thresholds: &[u8],
+        expire_days: u64,
+    ) -> Vec<UsageAlert> {
+        let mut alerts = Vec::new();
+
+        if extra.total > 0 {
+            let used = extra.upload.saturating_add(extra.download);
+            let percent = used.saturating_mul(100) / extra.total;
+            if let Some(threshold) = thresholds
+                .iter()
+                .copied()
+                .filter(|t| *t > 0 && percent >= *t as u64)
+                .max()
+            {
+                alerts.push(UsageAlert::Quota { percent: threshold });
+            }
+        }
+
+        if extra.expire > 0 && expire_days > 0 {
+            let remaining = extra.expire as i64 - now;
+            if remaining > 0 && remaining <= expire_days as i64 * ONE_DAY_SECS {
+                alerts.push(UsageAlert::Expire { days: expire_days });
+            }
+        }
+
+        alerts.retain(|alert| !self.fired_alerts.contains(&alert.key()));
+        alerts
+    }
+}
+
+/// 在订阅刷新后记录流量采样，并在达到阈值时提醒用户
+pub fn record_subscription_usage(uid: &str) {
+    let (name, extra) = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        match profiles.get_item(&uid.to_string()) {
+            Ok(item) => (
+                item.name.clone().unwrap_or_else(|| uid.to_string()),
+                item.extra,
+            ),
+            Err(_) => return,
+        }
+    };
+
+    let Some(extra) = extra else {
+        return;
+    };
+
+    let now = chrono::Local::now().timestamp();
+    let mut history = UsageHistory::load(uid);
+    history.push(UsageSample {
+        timestamp: now,
+        upload: extra.upload,
+        download: extra.download,
+        total: extra.total,
+        expire: extra.expire,
+    });
+
+    let (enabled, thresholds, expire_days) = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        (
+            verge.enable_subscription_alerts.unwrap_or(true),
+            verge
+                .subscription_quota_thresholds
+                .clone()
+                .unwrap_or_else(|| DEFAULT_QUOTA_THRESHOLDS.to_vec()),
+            verge
+                .subscription_expire_alert_days
+                .unwrap_or(DEFAULT_EXPIRE_ALERT_DAYS),
+        )
+    };
+
+    if enabled {
+        for alert in history.pending_alerts(&extra, now, &thresholds, expire_days) {
+            logging!(
+                info,
+                Type::Config,
+                true,
+                "[Subscription Usage] {} reached alert {}",
+                uid,
+                alert.key()
+            );
+            send_usage_alert(&name, &alert);
+            history.fired_alerts.push(alert.key());
+        }
+    }
+
+    if let Err(err) = history.save(uid) {
+        logging!(
+            warn,
+            Type::Config,
+            true,
+            "[Subscription Usage] Failed to save usage history for {}: {}",
+            uid,
+            err
+        );
+    }
+}
+
+fn send_usage_alert(name: &str, alert: &UsageAlert) {
+    let (status, event) = match alert {
+        UsageAlert::Quota { percent } => (
+            "subscription_quota_alert",
+            NotificationEvent::SubscriptionQuotaAlert {
+                name,
+                percent: *percent,
+            },
+        ),
+        UsageAlert::Expire { days } => (
+            "subscription_expire_alert",
+            NotificationEvent::SubscriptionExpireAlert { name, days: *days },
+        ),
+    };
+
+    let payload = match alert {
+        UsageAlert::Quota { percent } => serde_json::json!({ "name": name, "percent": percent }),
+        UsageAlert::Expire { days } => serde_json::json!({ "name": name, "days": days }),
+    };
+    handle::Handle::notice_message(status, payload.to_string());
+
+    if let Some(app_handle) = handle::Handle::global().app_handle() {
+        notify_event(&app_handle, event);
+    }
+}
+
+/// 获取订阅的流量历史
+pub fn get_subscription_usage_history(uid: &str) -> Vec<UsageSample> {
+    UsageHistory::load(uid).samples
+}
+
+/// 删除订阅时一并删除流量历史
+pub fn remove_subscription_usage_history(uid: &str) {
+    if let Err(err) = UsageHistory::remove(uid) {
+        log::warn!(target: "app", "failed to remove usage history for {uid}: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(used: u64, expire: u64) -> UsageSample {
+        UsageSample {
+            timestamp: 0,
+            upload: 0,
+            download: used,
+            total: 100,
+            expire,
+        }
+    }
+
+    fn extra(used: u64, expire: u64) -> PrfExtra {
+        PrfExtra {
+            upload: 0,
+            download: used,
+            total: 100,
+            expire,
+        }
+    }
+
+    #[test]
+    fn test_push_resets_fired_alerts() {
+        let mut history = UsageHistory::default();
+        history.push(sample(50, 1000));
+        history.fired_alerts.push("quota-80".into());
+
+        history.push(sample(60, 1000));
+        assert_eq!(history.fired_alerts, vec!["quota-80".to_string()]);
+
+        // 流量被重置
+        history.push(sample(10, 1000));
+        assert!(history.fired_alerts.is_empty());
+
+        // 到期时间延后
+        history.fired_alerts.push("expire-3".into());
+        history.push(sample(10, 2000));
+        assert!(history.fired_alerts.is_empty());
+        assert_eq!(history.samples.len(), 4);
+    }
+
+    #[test]
+    fn test_push_caps_samples() {
+        let mut history = UsageHistory::default();
+        for used in 0..(MAX_USAGE_SAMPLES as u64 + 10) {
+            history.push(sample(used, 0));
+        }
+        assert_eq!(history.samples.len(), MAX_USAGE_SAMPLES);
+        assert_eq!(history.samples[0].download, 10);
+    }
+
+    #[test]
+    fn test_pending_alerts() {
+        let now = 1_000_000;
+        let history = UsageHistory::default();
+
+        // 只取最严重的流量阈值
+        let alerts = history.pending_alerts(&extra(96, 0), now, &[80, 95], 3);
+        assert_eq!(alerts, vec![UsageAlert::Quota { percent: 95 }]);
+
+        let alerts = history.pending_alerts(&extra(50, 0), now, &[80, 95], 3);
+        assert!(alerts.is_empty());
+
+        let expire = (now + ONE_DAY_SECS) as u64;
+        let alerts = history.pending_alerts(&extra(0, expire), now, &[80], 3);
+        assert_eq!(alerts, vec![UsageAlert::Expire { days: 3 }]);
+
+        // 已过期或未到提醒时间
+        let alerts = history.pending_alerts(&extra(0, (now - 1) as u64), now, &[80], 3);
+        assert!(alerts.is_empty());
+        let expire = (now + 5 * ONE_DAY_SECS) as u64;
+        assert!(history
+            .pending_alerts(&extra(0, expire), now, &[80], 3)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_pending_alerts_skips_fired() {
+        let history = UsageHistory {
+            samples: Vec::new(),
+            fired_alerts: vec!["quota-95".into()],
+        };
+        let alerts = history.pending_alerts(&extra(96, 0), 0, &[80, 95], 3);
+        assert!(alerts.is_empty());
+
+        // 总量为 0 时不计算流量提醒
+        let mut unlimited = extra(96, 0);
+        unlimited.total = 0;
+        assert!(UsageHistory::default()
+            .pending_alerts(&unlimited, 0, &[80], 3)
+            .is_empty());
+    }
+}
@@ -107,6 +107,11 @@ async fn clean_async() -> bool {
         "Start executing asynchronous cleanup..."
     );
 
+    // 清空 shell 环境变量文件，退出后新开的终端不再走代理
+    if let Err(e) = super::clear_shell_env() {
+        log::warn!(target: "app", "Failed to clear shell env: {e}");
+    }
+
     // 1. 处理TUN模式
     let tun_task = async {
         if Config::verge().data().enable_tun_mode.unwrap_or(false) {
@@ -168,7 +173,23 @@ async fn clean_async() -> bool {
         }
     };
 
-    // 4. DNS恢复（仅macOS）
+    // 4. 透明代理规则清理（仅Linux）
+    #[cfg(target_os = "linux")]
+    let tproxy_task = async {
+        match timeout(Duration::from_secs(3), crate::core::tproxy::teardown()).await {
+            Ok(Ok(())) => true,
+            Ok(Err(e)) => {
+                log::warn!(target: "app", "Failed to remove transparent proxy rules: {e}");
+                false
+            }
+            Err(_) => {
+                log::warn!(target: "app", "Timeout removing transparent proxy rules");
+                false
+            }
+        }
+    };
+
+    // 5. DNS恢复（仅macOS）
     #[cfg(target_os = "macos")]
     let dns_task = async {
         match timeout(
@@ -189,23 +210,30 @@ async fn clean_async() -> bool {
     };
 
     // 并行执行所有清理任务
+    #[cfg(target_os = "linux")]
+    let (tun_success, proxy_success, core_success, tproxy_success) =
+        tokio::join!(tun_task, proxy_task, core_task, tproxy_task);
+    #[cfg(not(target_os = "linux"))]
     let (tun_success, proxy_success, core_success) = tokio::join!(tun_task, proxy_task, core_task);
+    #[cfg(not(target_os = "linux"))]
+    let tproxy_success = true;
 
     #[cfg(target_os = "macos")]
     let dns_success = dns_task.await;
     #[cfg(not(target_os = "macos"))]
     let dns_success = true;
 
-    let all_success = tun_success && proxy_success && core_success && dns_success;
+    let all_success = tun_success && proxy_success && core_success && tproxy_success && dns_success;
 
     logging!(
         info,
         Type::System,
         true,
-        "Asynchronous cleanup completed - TUN: {}, Proxy: {}, Core: {}, DNS: {}, Overall: {}",
+        "Asynchronous cleanup completed - TUN: {}, Proxy: {}, Core: {}, Transparent proxy: {}, DNS: {}, Overall: {}",
         tun_success,
         proxy_success,
         core_success,
+        tproxy_success,
         dns_success,
         all_success
     );
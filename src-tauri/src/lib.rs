@@ -1,3 +1,4 @@
+mod cli;
 mod cmd;
 mod config;
 mod core;
@@ -82,6 +83,11 @@ impl AppHandleManager {
 
 #[allow(clippy::panic)]
 pub fn run() {
+	// Headless subcommands run without creating the webview
+	if let Some(code) = cli::run_from_args() {
+		std::process::exit(code);
+	}
+
 	// Capture early deep link before any async setup (cold start on macOS)
 	utils::resolve::capture_early_deep_link_from_args();
 
@@ -254,6 +260,8 @@ pub fn run() {
             // common
             cmd::get_sys_proxy,
             cmd::get_auto_proxy,
+            cmd::get_proxy_guard_status,
+            cmd::reset_proxy_guard,
             cmd::open_app_dir,
             cmd::open_logs_dir,
             cmd::open_web_url,
@@ -266,6 +274,7 @@ pub fn run() {
             cmd::start_core,
             cmd::stop_core,
             cmd::restart_core,
+            cmd::reassign_port_conflicts,
             // 启动命令
             cmd::notify_ui_ready,
             cmd::update_ui_stage,
@@ -274,6 +283,9 @@ pub fn run() {
             cmd::get_app_uptime,
             cmd::get_auto_launch_status,
             cmd::is_admin,
+            cmd::get_core_capability_state,
+            cmd::grant_core_capabilities,
+            cmd::revoke_core_capabilities,
             // 添加轻量模式相关命令
             cmd::entry_lightweight_mode,
             cmd::exit_lightweight_mode,
@@ -283,6 +295,10 @@ pub fn run() {
             cmd::reinstall_service,
             cmd::repair_service,
             cmd::is_service_available,
+            cmd::get_service_core_logs,
+            cmd::set_service_dns,
+            cmd::get_service_health_report,
+            cmd::get_service_health,
             // clash
             cmd::get_clash_info,
             cmd::patch_clash_config,
@@ -293,6 +309,7 @@ pub fn run() {
             cmd::get_runtime_exists,
             cmd::get_runtime_logs,
             cmd::invoke_uwp_tool,
+            cmd::get_transparent_proxy_script,
             cmd::copy_clash_env,
             cmd::get_proxies,
             cmd::force_refresh_proxies,
@@ -302,9 +319,15 @@ pub fn run() {
             cmd::check_dns_config_exists,
             cmd::get_dns_config_content,
             cmd::validate_dns_config,
+            cmd::benchmark_dns,
+            cmd::apply_fastest_dns,
+            cmd::flush_fakeip_cache,
+            cmd::query_dns,
+            cmd::import_system_hosts,
             // verge
             cmd::get_verge_config,
             cmd::patch_verge_config,
+            cmd::regenerate_control_api_token,
             cmd::test_delay,
             cmd::get_app_dir,
             cmd::copy_icon_file,
@@ -312,12 +335,19 @@ pub fn run() {
             cmd::open_devtools,
             cmd::exit_app,
             cmd::get_network_interfaces_info,
+            cmd::get_lan_gateway_info,
+            cmd::get_qr_code_svg,
+            cmd::get_network_snapshot,
             // profile
             cmd::get_profiles,
             cmd::enhance_profiles,
             cmd::patch_profiles_config,
             cmd::view_profile,
             cmd::patch_profile,
+            cmd::get_profile_auth,
+            cmd::patch_profile_auth,
+            cmd::patch_profile_groups,
+            cmd::patch_network_rules,
             cmd::create_profile,
             cmd::import_profile,
             cmd::reorder_profile,
@@ -326,6 +356,9 @@ pub fn run() {
             cmd::read_profile_file,
             cmd::save_profile_file,
             cmd::get_next_update_time,
+            cmd::get_profile_usage_history,
+            cmd::get_announcements,
+            cmd::mark_announcement_read,
             cmd::update_profiles_on_startup,
             cmd::create_profile_from_share_link,
             // script validation
@@ -333,6 +366,10 @@ pub fn run() {
             cmd::validate_script_file,
             // clash api
             cmd::clash_api_get_proxy_delay,
+            cmd::get_connection_stats,
+            cmd::get_closed_connections,
+            cmd::clear_closed_connections,
+            cmd::close_connections,
             // backup
             cmd::create_webdav_backup,
             cmd::save_webdav_config,
@@ -343,6 +380,8 @@ pub fn run() {
             cmd::export_diagnostic_info,
             // get system info for display
             cmd::get_system_info,
+            cmd::get_hwid,
+            cmd::reset_hwid,
             // media unlock checker
             cmd::get_unlock_items,
             cmd::check_media_unlock,
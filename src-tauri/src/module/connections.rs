@@ -0,0 +1,462 @@
+//! 连接管理：订阅核心的 /connections 推送，按主机、进程、规则和代理链统计流量
+//!
+//! 推送中消失的连接记入最近关闭的历史，便于排查问题
+
+use crate::{
+    config::Config,
+    module::{lightweight::is_in_lightweight_mode, mihomo::MihomoManager},
+    process::AsyncHandler,
+};
+use anyhow::{anyhow, bail, Result};
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{
+        client::IntoClientRequest,
+        http::{header::AUTHORIZATION, HeaderValue},
+        Message,
+    },
+};
+
+/// 保留的已关闭连接数量
+const CLOSED_HISTORY_LIMIT: usize = 500;
+/// 核心推送连接列表的间隔（毫秒）
+const PUSH_INTERVAL_MS: u64 = 1000;
+/// 推送断开后重新订阅的间隔
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(3);
+
+static WATCHER_STARTED: AtomicBool = AtomicBool::new(false);
+static STORE: Lazy<RwLock<ConnectionStore>> = Lazy::new(|| RwLock::new(ConnectionStore::default()));
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConnectionMetadata {
+    pub network: String,
+    #[serde(rename = "type")]
+    pub conn_type: String,
+    pub host: String,
+    #[serde(rename = "sourceIP")]
+    pub source_ip: String,
+    pub source_port: String,
+    #[serde(rename = "destinationIP")]
+    pub destination_ip: String,
+    pub destination_port: String,
+    pub remote_destination: String,
+    pub process: String,
+    pub process_path: String,
+}
+
+/// 核心返回的单个连接，字段与前端的 IConnectionsItem 一致
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Connection {
+    pub id: String,
+    pub metadata: ConnectionMetadata,
+    pub upload: u64,
+    pub download: u64,
+    pub start: String,
+    pub chains: Vec<String>,
+    pub rule: String,
+    pub rule_payload: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClosedConnection {
+    #[serde(flatten)]
+    pub connection: Connection,
+    /// 发现连接关闭的时间（毫秒时间戳）
+    pub closed_at: i64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConnectionsSnapshot {
+    /// 没有连接时核心返回 null
+    #[serde(default)]
+    connections: Option<Vec<Connection>>,
+}
+
+/// 统计连接时的分组方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionGroup {
+    Host,
+    Process,
+    Rule,
+    Chain,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConnectionStat {
+    pub key: String,
+    pub upload: u64,
+    pub download: u64,
+    /// 当前活动的连接数
+    pub active: usize,
+    /// 历史中已关闭的连接数
+    pub closed: usize,
+}
+
+/// 批量关闭连接的条件，所有已设置的条件都满足才会关闭
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ConnectionFilter {
+    /// 域名或 IP，域名同时匹配其子域名
+    pub host: Option<String>,
+    pub process: Option<String>,
+    pub rule: Option<String>,
+    /// 代理链中任意一个节点或策略组
+    pub chain: Option<String>,
+}
+
+impl Connection {
+    fn host(&self) -> &str {
+        let metadata = &self.metadata;
+        [
+            &metadata.host,
+            &metadata.destination_ip,
+            &metadata.remote_destination,
+        ]
+        .into_iter()
+        .find(|value| !value.is_empty())
+        .map_or("", String::as_str)
+    }
+
+    /// 与连接详情中的显示一致，例如 `RuleSet(ru-bundle)`
+    fn rule_name(&self) -> String {
+        match self.rule_payload.is_empty() {
+            true => self.rule.clone(),
+            false => format!("{}({})", self.rule, self.rule_payload),
+        }
+    }
+
+    /// 核心中的代理链从出站节点开始，显示时从策略组开始
+    fn chain(&self) -> String {
+        let chains: Vec<&str> = self.chains.iter().rev().map(String::as_str).collect();
+        chains.join(" / ")
+    }
+
+    fn group_key(&self, group: ConnectionGroup) -> String {
+        match group {
+            ConnectionGroup::Host => self.host().to_string(),
+            ConnectionGroup::Process => self.metadata.process.clone(),
+            ConnectionGroup::Rule => self.rule_name(),
+            ConnectionGroup::Chain => self.chain(),
+        }
+    }
+}
+
+/// 去掉空白，空字符串视为未设置
+fn condition(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+impl ConnectionFilter {
+    pub fn is_empty(&self) -> bool {
+        [&self.host, &self.process, &self.rule, &self.chain]
+            .into_iter()
+            .all(|value| condition(value).is_none())
+    }
+
+    pub fn matches(&self, connection: &Connection) -> bool {
+        if let Some(host) = condition(&self.host) {
+            let current = connection.host();
+            // IP 不做后缀匹配，避免 `3.4` 匹配到 `1.2.3.4`
+            let matched = match (host.parse::<IpAddr>(), current.parse::<IpAddr>()) {
+                (Ok(ip), current_ip) => current_ip.ok() == Some(ip),
+                (Err(_), Ok(_)) => false,
+                (Err(_), Err(_)) => {
+                    let suffix = format!(".{}", host.trim_start_matches('.'));
+                    current.eq_ignore_ascii_case(host)
+                        || current
+                            .to_ascii_lowercase()
+                            .ends_with(&suffix.to_ascii_lowercase())
+                }
+            };
+            if !matched {
+                return false;
+            }
+        }
+
+        if let Some(process) = condition(&self.process) {
+            if !connection.metadata.process.eq_ignore_ascii_case(process) {
+                return false;
+            }
+        }
+
+        if let Some(rule) = condition(&self.rule) {
+            if connection.rule != rule && connection.rule_name() != rule {
+                return false;
+            }
+        }
+
+        if let Some(chain) = condition(&self.chain) {
+            if !connection.chains.iter().any(|each| each == chain) && connection.chain() != chain {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[derive(Debug, Default)]
+struct ConnectionStore {
+    active: HashMap<String, Connection>,
+    /// 最早关闭的在前
+    closed: VecDeque<ClosedConnection>,
+}
+
+impl ConnectionStore {
+    /// 用最新的连接列表替换活动连接，消失的连接记入历史
+    fn update(&mut self, connections: Vec<Connection>, now: i64) {
+        let active: HashMap<String, Connection> = connections
+            .into_iter()
+            .map(|connection| (connection.id.clone(), connection))
+            .collect();
+
+        let gone: Vec<Connection> = self
+            .active
+            .drain()
+            .filter(|(id, _)| !active.contains_key(id))
+            .map(|(_, connection)| connection)
+            .collect();
+
+        for connection in gone {
+            if self.closed.len() >= CLOSED_HISTORY_LIMIT {
+                self.closed.pop_front();
+            }
+            self.closed.push_back(ClosedConnection {
+                connection,
+                closed_at: now,
+            });
+        }
+        self.active = active;
+    }
+
+    /// 汇总活动连接和历史中的连接，按总流量从大到小排序
+    fn stats(&self, group: ConnectionGroup) -> Vec<ConnectionStat> {
+        let mut stats: HashMap<String, ConnectionStat> = HashMap::new();
+        let connections = self
+            .active
+            .values()
+            .map(|connection| (connection, true))
+            .chain(self.closed.iter().map(|closed| (&closed.connection, false)));
+
+        for (connection, active) in connections {
+            let key = connection.group_key(group);
+            let stat = stats.entry(key.clone()).or_insert_with(|| ConnectionStat {
+                key,
+                ..ConnectionStat::default()
+            });
+            stat.upload += connection.upload;
+            stat.download += connection.download;
+            match active {
+                true => stat.active += 1,
+                false => stat.closed += 1,
+            }
+        }
+
+        let mut stats: Vec<ConnectionStat> = stats.into_values().collect();
+        stats.sort_by(|a, b| {
+            (b.upload + b.download)
+                .cmp(&(a.upload + a.download))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        stats
+    }
+}
+
+/// 按分组统计连接流量
+pub fn connection_stats(group: ConnectionGroup) -> Vec<ConnectionStat> {
+    STORE.read().stats(group)
+}
+
+/// 最近关闭的连接，最新的在前
+pub fn closed_connections() -> Vec<ClosedConnection> {
+    STORE.read().closed.iter().rev().cloned().collect()
+}
+
+pub fn clear_closed_connections() {
+    STORE.write().closed.clear();
+}
+
+/// 关闭所有符合条件的连接，返回关闭的数量
+pub async fn close_connections(filter: &ConnectionFilter) -> Result<usize> {
+    // 空条件会匹配所有连接，关闭全部连接应使用 close_all_connections
+    if filter.is_empty() {
+        bail!("Connection filter is empty");
+    }
+
+    // 直接向核心获取最新列表，避免漏掉推送间隔内新建的连接
+    let manager = MihomoManager::global();
+    let snapshot = manager
+        .get_connections()
+        .await
+        .map_err(|err| anyhow!(err))?;
+    let snapshot: ConnectionsSnapshot = serde_json::from_value(snapshot)?;
+    let ids: Vec<String> = snapshot
+        .connections
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|connection| filter.matches(connection))
+        .map(|connection| connection.id)
+        .collect();
+
+    let results =
+        futures::future::join_all(ids.iter().map(|id| manager.delete_connection(id))).await;
+    if let Some(Err(err)) = results.iter().find(|result| result.is_err()) {
+        log::warn!(target: "app", "Failed to close some connections: {err}");
+    }
+    Ok(results.iter().filter(|result| result.is_ok()).count())
+}
+
+async fn watch_connections() -> Result<()> {
+    let (server, secret) = {
+        let client = Config::clash().latest().get_client_info();
+        (client.server, client.secret)
+    };
+    let mut request =
+        format!("ws://{server}/connections?interval={PUSH_INTERVAL_MS}").into_client_request()?;
+    if let Some(secret) = secret {
+        let value = HeaderValue::from_str(&format!("Bearer {secret}"))?;
+        request.headers_mut().insert(AUTHORIZATION, value);
+    }
+
+    let (mut stream, _) = connect_async(request).await?;
+    while let Some(message) = stream.next().await {
+        // 轻量模式下没有界面，断开订阅
+        if is_in_lightweight_mode() {
+            return Ok(());
+        }
+        let Message::Text(text) = message? else {
+            continue;
+        };
+        let snapshot: ConnectionsSnapshot = serde_json::from_str(&text)?;
+        let now = chrono::Local::now().timestamp_millis();
+        STORE
+            .write()
+            .update(snapshot.connections.unwrap_or_default(), now);
+    }
+    Ok(())
+}
+
+/// 启动连接订阅，核心重启或断开后自动重新订阅，轻量模式下暂停
+pub fn connections_init() {
+    if WATCHER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    AsyncHandler::spawn(|| async {
+        loop {
+            if is_in_lightweight_mode() {
+                tokio::time::sleep(RECONNECT_INTERVAL).await;
+                continue;
+            }
+            if let Err(err) = watch_connections().await {
+                log::debug!(target: "app", "Connections stream closed: {err}");
+            }
+            // 因进入轻量模式而暂停时保留现有记录，恢复后由下一次推送更新
+            if is_in_lightweight_mode() {
+                continue;
+            }
+            // 推送断开说明核心已停止，现有连接都已关闭
+            let now = chrono::Local::now().timestamp_millis();
+            STORE.write().update(Vec::new(), now);
+            tokio::time::sleep(RECONNECT_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(id: &str, host: &str, process: &str, chains: &[&str], bytes: u64) -> Connection {
+        Connection {
+            id: id.to_string(),
+            metadata: ConnectionMetadata {
+                host: host.to_string(),
+                process: process.to_string(),
+                ..ConnectionMetadata::default()
+            },
+            upload: bytes,
+            download: bytes,
+            chains: chains.iter().map(ToString::to_string).collect(),
+            rule: "RuleSet".to_string(),
+            rule_payload: "ru-bundle".to_string(),
+            ..Connection::default()
+        }
+    }
+
+    #[test]
+    fn test_store_history_and_stats() {
+        let mut store = ConnectionStore::default();
+        store.update(
+            vec![
+                connection("1", "a.example.com", "curl", &["node-1", "Proxy"], 10),
+                connection("2", "b.example.com", "curl", &["DIRECT"], 5),
+            ],
+            1,
+        );
+        store.update(
+            vec![connection("2", "b.example.com", "curl", &["DIRECT"], 7)],
+            2,
+        );
+
+        assert_eq!(store.closed.len(), 1);
+        assert_eq!(store.closed[0].connection.id, "1");
+        assert_eq!(store.closed[0].closed_at, 2);
+
+        let stats = store.stats(ConnectionGroup::Process);
+        assert_eq!(
+            stats,
+            vec![ConnectionStat {
+                key: "curl".to_string(),
+                upload: 17,
+                download: 17,
+                active: 1,
+                closed: 1,
+            }]
+        );
+        let stats = store.stats(ConnectionGroup::Chain);
+        assert_eq!(stats[0].key, "Proxy / node-1");
+        assert_eq!(stats[1].key, "DIRECT");
+        let stats = store.stats(ConnectionGroup::Rule);
+        assert_eq!(stats[0].key, "RuleSet(ru-bundle)");
+    }
+
+    #[test]
+    fn test_filter() {
+        let conn = connection("1", "a.example.com", "Chrome.exe", &["node-1", "Proxy"], 1);
+        let filter = |json: &str| serde_json::from_str::<ConnectionFilter>(json).unwrap();
+
+        assert!(filter(r#"{}"#).is_empty());
+        assert!(filter(r#"{"host": " "}"#).is_empty());
+        assert!(filter(r#"{"host": "example.com"}"#).matches(&conn));
+        assert!(filter(r#"{"host": "A.example.com"}"#).matches(&conn));
+        assert!(!filter(r#"{"host": "ample.com"}"#).matches(&conn));
+        assert!(filter(r#"{"process": "chrome.exe", "chain": "Proxy"}"#).matches(&conn));
+        assert!(!filter(r#"{"process": "chrome.exe", "chain": "DIRECT"}"#).matches(&conn));
+        assert!(filter(r#"{"rule": "RuleSet(ru-bundle)"}"#).matches(&conn));
+        assert!(filter(r#"{"rule": "RuleSet"}"#).matches(&conn));
+
+        let ip_conn = connection("2", "1.2.3.4", "curl", &["DIRECT"], 1);
+        assert!(filter(r#"{"host": "1.2.3.4"}"#).matches(&ip_conn));
+        assert!(!filter(r#"{"host": "3.4"}"#).matches(&ip_conn));
+        assert!(!filter(r#"{"host": "2.3.4"}"#).matches(&ip_conn));
+        assert!(!filter(r#"{"host": "1.2.3.4"}"#).matches(&conn));
+    }
+}
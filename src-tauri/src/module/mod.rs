@@ -1,3 +1,5 @@
+pub mod connections;
 pub mod lightweight;
 pub mod mihomo;
+pub mod network_switch;
 pub mod sysinfo;
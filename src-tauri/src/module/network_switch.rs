@@ -0,0 +1,523 @@
+use crate::{
+    cmd,
+    config::{Config, IProfiles, PrfNetworkRule},
+    core::handle,
+    feat, logging,
+    process::AsyncHandler,
+    utils::{cidr::IpCidr, logging::Type},
+};
+use serde::Serialize;
+use std::{
+    net::IpAddr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tokio::process::Command;
+
+/// 网络状态轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+static WATCHER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// 当前网络环境，用于匹配自动切换规则
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct NetworkSnapshot {
+    pub ssid: Option<String>,
+    pub gateway: Option<String>,
+    pub gateway_interface: Option<String>,
+    pub interfaces: Vec<NetworkSnapshotInterface>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct NetworkSnapshotInterface {
+    pub name: String,
+    pub addrs: Vec<String>,
+    pub subnets: Vec<String>,
+}
+
+impl NetworkSnapshot {
+    /// collect the current network state
+    pub async fn collect() -> Self {
+        let (gateway_interface, gateway) = match default_gateway().await {
+            Some((iface, gateway)) => (iface, Some(gateway.to_string())),
+            None => (None, None),
+        };
+        let interfaces = local_interfaces();
+        // Windows 的路由表只给出网卡地址，换成对应的网卡名称
+        let gateway_interface = gateway_interface.map(|iface| {
+            interfaces
+                .iter()
+                .find(|each| each.addrs.contains(&iface))
+                .map_or(iface, |each| each.name.clone())
+        });
+        let ssid = current_ssid(gateway_interface.as_deref()).await;
+
+        Self {
+            ssid,
+            gateway,
+            gateway_interface,
+            interfaces,
+        }
+    }
+
+    /// 所有条件都满足时才算匹配
+    pub fn matches(&self, rule: &PrfNetworkRule) -> bool {
+        let condition = |v: &Option<String>| {
+            v.as_ref()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        if let Some(ssid) = condition(&rule.ssid) {
+            if self.ssid.as_deref() != Some(ssid.as_str()) {
+                return false;
+            }
+        }
+
+        // 只匹配默认路由所在的网卡
+        if let Some(interface) = condition(&rule.interface) {
+            if !self
+                .gateway_interface
+                .as_ref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(&interface))
+            {
+                return false;
+            }
+        }
+
+        if let Some(gateway) = condition(&rule.gateway) {
+            let expected = gateway.parse::<IpAddr>().ok();
+            let current = self.gateway.as_ref().and_then(|g| g.parse::<IpAddr>().ok());
+            if expected.is_none() || expected != current {
+                return false;
+            }
+        }
+
+        if let Some(subnet) = condition(&rule.subnet) {
+            let Ok(cidr) = subnet.parse::<IpCidr>() else {
+                return false;
+            };
+            if !self
+                .interfaces
+                .iter()
+                .flat_map(|each| each.addrs.iter())
+                .filter_map(|addr| addr.parse::<IpAddr>().ok())
+                .any(|addr| cidr.contains(&addr))
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// 过滤掉回环和 TUN 网卡，避免开关 TUN 时被误判为网络变化
+fn local_interfaces() -> Vec<NetworkSnapshotInterface> {
+    let tun_device = Config::clash()
+        .latest()
+        .0
+        .get("tun")
+        .and_then(|tun| tun.get("device"))
+        .and_then(|device| device.as_str())
+        .map(str::to_string);
+
+    let interfaces = match cmd::get_network_interfaces_info() {
+        Ok(interfaces) => interfaces,
+        Err(err) => {
+            logging!(
+                warn,
+                Type::Network,
+                true,
+                "[Network Switch] Failed to list network interfaces: {}",
+                err
+            );
+            return vec![];
+        }
+    };
+
+    let mut result: Vec<NetworkSnapshotInterface> = interfaces
+        .into_iter()
+        .filter(|iface| tun_device.as_deref() != Some(iface.name.as_str()))
+        .filter_map(|iface| {
+            let mut addrs = vec![];
+            let mut subnets = vec![];
+            for addr in iface.addr.iter() {
+                let ip = addr.ip();
+                if ip.is_loopback() || ip.is_unspecified() {
+                    continue;
+                }
+                addrs.push(ip.to_string());
+                if let Some(cidr) = addr
+                    .netmask()
+                    .and_then(|mask| IpCidr::from_netmask(ip, mask).ok())
+                {
+                    subnets.push(cidr.to_string());
+                }
+            }
+            if addrs.is_empty() {
+                return None;
+            }
+            addrs.sort();
+            subnets.sort();
+            Some(NetworkSnapshotInterface {
+                name: iface.name,
+                addrs,
+                subnets,
+            })
+        })
+        .collect();
+
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    result.dedup_by(|a, b| a.name == b.name);
+    result
+}
+
+async fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(program);
+    command.args(args);
+
+    #[cfg(target_os = "windows")]
+    {
+        #[allow(unused_imports)] // creation_flags必须
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = command.output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 获取默认网关以及对应的网卡
+#[cfg(target_os = "linux")]
+async fn default_gateway() -> Option<(Option<String>, IpAddr)> {
+    let content = tokio::fs::read_to_string("/proc/net/route").await.ok()?;
+    content.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || fields[1] != "00000000" {
+            return None;
+        }
+        let gateway = u32::from_str_radix(fields[2], 16).ok()?;
+        if gateway == 0 {
+            return None;
+        }
+        // 按主机字节序存储
+        let ip = std::net::Ipv4Addr::from(gateway.to_ne_bytes());
+        Some((Some(fields[0].to_string()), IpAddr::V4(ip)))
+    })
+}
+
+#[cfg(target_os = "macos")]
+async fn default_gateway() -> Option<(Option<String>, IpAddr)> {
+    let output = command_output("route", &["-n", "get", "default"]).await?;
+    let mut gateway = None;
+    let mut interface = None;
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("gateway:") {
+            gateway = value.trim().parse::<IpAddr>().ok();
+        } else if let Some(value) = line.strip_prefix("interface:") {
+            interface = Some(value.trim().to_string());
+        }
+    }
+    gateway.map(|gateway| (interface, gateway))
+}
+
+#[cfg(target_os = "windows")]
+async fn default_gateway() -> Option<(Option<String>, IpAddr)> {
+    let output = command_output("route", &["print", "-4", "0.0.0.0"]).await?;
+    // Network Destination  Netmask  Gateway  Interface  Metric
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 5 || fields[0] != "0.0.0.0" || fields[1] != "0.0.0.0" {
+                return None;
+            }
+            let gateway = fields[2].parse::<IpAddr>().ok()?;
+            let metric = fields[4].parse::<u32>().unwrap_or(u32::MAX);
+            Some((metric, gateway, fields[3].to_string()))
+        })
+        .min_by_key(|(metric, ..)| *metric)
+        .map(|(_, gateway, interface)| (Some(interface), gateway))
+}
+
+/// 获取当前连接的 WiFi 名称
+#[cfg(target_os = "linux")]
+async fn current_ssid(interface: Option<&str>) -> Option<String> {
+    let mut args = vec!["-r"];
+    if let Some(interface) = interface {
+        args.insert(0, interface);
+    }
+    if let Some(ssid) = command_output("iwgetid", &args)
+        .await
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+    {
+        return Some(ssid);
+    }
+
+    let output = command_output("nmcli", &["-t", "-f", "active,ssid", "dev", "wifi"]).await?;
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("yes:"))
+        .map(|ssid| ssid.replace("\\:", ":"))
+        .filter(|ssid| !ssid.is_empty())
+}
+
+#[cfg(target_os = "macos")]
+async fn current_ssid(interface: Option<&str>) -> Option<String> {
+    let interface = interface.unwrap_or("en0");
+    let output = command_output("networksetup", &["-getairportnetwork", interface]).await?;
+    output
+        .trim()
+        .split_once(": ")
+        .map(|(_, ssid)| ssid.trim().to_string())
+        .filter(|ssid| !ssid.is_empty())
+}
+
+#[cfg(target_os = "windows")]
+async fn current_ssid(_interface: Option<&str>) -> Option<String> {
+    let output = command_output("netsh", &["wlan", "show", "interfaces"]).await?;
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        if key.trim() != "SSID" {
+            return None;
+        }
+        let ssid = value.trim();
+        (!ssid.is_empty()).then(|| ssid.to_string())
+    })
+}
+
+/// 按规则切换订阅和代理模式，只在网络变化后执行一次，
+/// 不会覆盖用户在同一网络下的手动切换
+async fn apply_rule(rule: &PrfNetworkRule) {
+    let (current, target) = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        let current = profiles.get_current();
+        let target = match (rule.profile.as_ref(), rule.group.as_ref()) {
+            (Some(uid), _) => Some(uid.clone()),
+            (None, Some(group)) => {
+                profiles
+                    .get_group(group)
+                    .ok()
+                    .and_then(|group| match current.as_ref() {
+                        Some(current) if group.items.contains(current) => Some(current.clone()),
+                        _ => group.items.first().cloned(),
+                    })
+            }
+            (None, None) => None,
+        };
+        (current, target)
+    };
+
+    let mut switched = false;
+
+    if let Some(target) = target.filter(|target| current.as_ref() != Some(target)) {
+        logging!(
+            info,
+            Type::Network,
+            true,
+            "[Network Switch] Rule {} activates profile {}",
+            rule.id,
+            target
+        );
+        match feat::patch_profiles_config(IProfiles {
+            current: Some(target),
+            ..IProfiles::default()
+        })
+        .await
+        {
+            Ok(true) => switched = true,
+            Ok(false) => {}
+            Err(err) => {
+                logging!(
+                    error,
+                    Type::Network,
+                    true,
+                    "[Network Switch] Failed to switch profile: {}",
+                    err
+                );
+            }
+        }
+    }
+
+    if let Some(mode) = rule.mode.as_ref().filter(|mode| !mode.is_empty()) {
+        let current_mode = Config::clash()
+            .latest()
+            .0
+            .get("mode")
+            .and_then(|mode| mode.as_str())
+            .map(str::to_string);
+        if current_mode.as_ref() != Some(mode) {
+            logging!(
+                info,
+                Type::Network,
+                true,
+                "[Network Switch] Rule {} switches clash mode to {}",
+                rule.id,
+                mode
+            );
+            feat::change_clash_mode(mode.clone());
+            switched = true;
+        }
+    }
+
+    if switched {
+        let name = rule.name.clone().unwrap_or_else(|| rule.id.clone());
+        handle::Handle::notice_message("network_auto_switch", name);
+    }
+}
+
+async fn on_network_changed(snapshot: &NetworkSnapshot) {
+    let rules = { Config::profiles().latest().network_rules.clone() }.unwrap_or_default();
+
+    match rules
+        .iter()
+        .filter(|rule| rule.is_enabled())
+        .find(|rule| snapshot.matches(rule))
+    {
+        Some(rule) => apply_rule(rule).await,
+        None => {
+            logging!(
+                debug,
+                Type::Network,
+                true,
+                "[Network Switch] No rule matches the current network"
+            );
+        }
+    }
+}
+
+/// 启动网络变化监听，启用自动切换时按规则切换订阅
+pub fn network_switch_init() {
+    if WATCHER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    AsyncHandler::spawn(|| async {
+        let mut last: Option<NetworkSnapshot> = None;
+        loop {
+            // 没有启用的规则时不采集网络状态，避免频繁启动子进程
+            let enabled = { Config::verge().latest().enable_network_auto_switch }.unwrap_or(false)
+                && { Config::profiles().latest().network_rules.clone() }
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|rule| rule.is_enabled());
+            if !enabled {
+                last = None;
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+
+            let snapshot = NetworkSnapshot::collect().await;
+            if last.as_ref() != Some(&snapshot) {
+                logging!(
+                    info,
+                    Type::Network,
+                    true,
+                    "[Network Switch] Network changed: ssid={:?}, gateway={:?}",
+                    snapshot.ssid,
+                    snapshot.gateway
+                );
+                on_network_changed(&snapshot).await;
+                last = Some(snapshot);
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> NetworkSnapshot {
+        NetworkSnapshot {
+            ssid: Some("Office".into()),
+            gateway: Some("192.168.1.1".into()),
+            gateway_interface: Some("wlan0".into()),
+            interfaces: vec![
+                NetworkSnapshotInterface {
+                    name: "eth0".into(),
+                    addrs: vec!["10.1.0.5".into()],
+                    subnets: vec!["10.1.0.0/24".into()],
+                },
+                NetworkSnapshotInterface {
+                    name: "wlan0".into(),
+                    addrs: vec!["192.168.1.23".into()],
+                    subnets: vec!["192.168.1.0/24".into()],
+                },
+            ],
+        }
+    }
+
+    fn rule() -> PrfNetworkRule {
+        PrfNetworkRule {
+            id: "r".into(),
+            profile: Some("p1".into()),
+            ..PrfNetworkRule::default()
+        }
+    }
+
+    #[test]
+    fn test_snapshot_matches() {
+        let snapshot = snapshot();
+        let matches = |rule: PrfNetworkRule| snapshot.matches(&rule);
+
+        assert!(matches(PrfNetworkRule {
+            ssid: Some(" Office ".into()),
+            interface: Some("WLAN0".into()),
+            gateway: Some("192.168.1.1".into()),
+            subnet: Some("192.168.0.0/16".into()),
+            ..rule()
+        }));
+        // 空条件会被忽略
+        assert!(matches(PrfNetworkRule {
+            ssid: Some("".into()),
+            gateway: Some("192.168.1.1".into()),
+            ..rule()
+        }));
+
+        assert!(!matches(PrfNetworkRule {
+            ssid: Some("Home".into()),
+            ..rule()
+        }));
+        // eth0 有地址但不是默认路由所在的网卡
+        assert!(!matches(PrfNetworkRule {
+            interface: Some("eth0".into()),
+            ..rule()
+        }));
+        let no_gateway = NetworkSnapshot {
+            gateway_interface: None,
+            ..snapshot.clone()
+        };
+        assert!(!no_gateway.matches(&PrfNetworkRule {
+            interface: Some("wlan0".into()),
+            ..rule()
+        }));
+        assert!(!matches(PrfNetworkRule {
+            gateway: Some("10.0.0.1".into()),
+            ..rule()
+        }));
+        assert!(!matches(PrfNetworkRule {
+            gateway: Some("router".into()),
+            ..rule()
+        }));
+        assert!(!matches(PrfNetworkRule {
+            subnet: Some("172.16.0.0/12".into()),
+            ..rule()
+        }));
+        // 任一条件不满足即不匹配
+        assert!(!matches(PrfNetworkRule {
+            ssid: Some("Office".into()),
+            gateway: Some("10.0.0.1".into()),
+            ..rule()
+        }));
+    }
+}
@@ -0,0 +1,369 @@
+//! 系统代理的绕过列表
+//!
+//! 配置中保存为逗号或分号分隔的字符串，解析为结构化条目后再按平台的语法输出
+
+use crate::{config::Config, utils::cidr::IpCidr};
+use anyhow::{bail, Result};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    str::FromStr,
+};
+
+const DEFAULT_BYPASS: &[&str] = &[
+    "localhost",
+    "127.0.0.0/8",
+    "192.168.0.0/16",
+    "10.0.0.0/8",
+    "172.16.0.0/12",
+    "::1",
+    "*.local",
+    "<local>",
+];
+const MACOS_DEFAULT_BYPASS: &[&str] = &["*.crashlytics.com"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BypassPlatform {
+    Windows,
+    Linux,
+    Macos,
+    /// `NO_PROXY` 环境变量
+    Env,
+}
+
+impl BypassPlatform {
+    pub fn current() -> Self {
+        match std::env::consts::OS {
+            "windows" => Self::Windows,
+            "macos" => Self::Macos,
+            _ => Self::Linux,
+        }
+    }
+
+    fn separator(self) -> &'static str {
+        match self {
+            Self::Windows => ";",
+            Self::Linux | Self::Macos | Self::Env => ",",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BypassEntry {
+    /// 不含点的主机名
+    Local,
+    /// 域名，可以包含 `*` 通配符
+    Domain(String),
+    Ip(IpAddr),
+    Cidr(IpCidr),
+}
+
+impl BypassEntry {
+    fn render(&self, platform: BypassPlatform) -> Vec<String> {
+        match (self, platform) {
+            // gsettings、KDE 和 NO_PROXY 都不认识 <local>
+            (Self::Local, BypassPlatform::Linux | BypassPlatform::Env) => vec![],
+            (Self::Local, _) => vec!["<local>".into()],
+            // NO_PROXY 用前导的点匹配子域名，不支持其他位置的通配符
+            (Self::Domain(domain), BypassPlatform::Env) => match domain.strip_prefix('*') {
+                Some(suffix) if suffix.starts_with('.') && !suffix.contains('*') => {
+                    vec![suffix.to_string()]
+                }
+                _ if domain.contains('*') => vec![],
+                _ => vec![domain.clone()],
+            },
+            (Self::Domain(domain), _) => vec![domain.clone()],
+            (Self::Ip(IpAddr::V6(ip)), BypassPlatform::Windows) => vec![format!("[{ip}]")],
+            (Self::Ip(ip), _) => vec![ip.to_string()],
+            (Self::Cidr(cidr), BypassPlatform::Windows) => windows_wildcards(cidr),
+            (Self::Cidr(cidr), _) => vec![cidr.to_string()],
+        }
+    }
+}
+
+impl FromStr for BypassEntry {
+    type Err = anyhow::Error;
+
+    /// 兼容 Windows 的 `172.16.*` 写法，解析为对应的网段
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("<local>") {
+            return Ok(Self::Local);
+        }
+        if let Some(cidr) = parse_ipv4_wildcard(s) {
+            return Ok(Self::Cidr(cidr));
+        }
+        if s.contains('/') {
+            return Ok(Self::Cidr(s.parse::<IpCidr>()?.network()));
+        }
+        let host = s
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(s);
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(Self::Ip(ip));
+        }
+        if is_valid_domain(s) {
+            return Ok(Self::Domain(s.to_ascii_lowercase()));
+        }
+        bail!("invalid bypass entry \"{s}\"")
+    }
+}
+
+fn parse_ipv4_wildcard(s: &str) -> Option<IpCidr> {
+    let prefix = s.strip_suffix(".*")?;
+    let octets = prefix
+        .split('.')
+        .map(|octet| octet.parse::<u8>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    if octets.len() > 3 {
+        return None;
+    }
+    let mut addr = [0u8; 4];
+    addr[..octets.len()].copy_from_slice(&octets);
+    IpCidr::new(IpAddr::V4(addr.into()), octets.len() as u8 * 8).ok()
+}
+
+fn is_valid_domain(s: &str) -> bool {
+    let domain = s.strip_prefix('.').unwrap_or(s);
+    !domain.is_empty()
+        && domain.len() <= 253
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '*'))
+        })
+}
+
+/// WinINet 只支持 `*` 通配符，网段按字节边界展开，IPv6 网段无法表示
+fn windows_wildcards(cidr: &IpCidr) -> Vec<String> {
+    let IpAddr::V4(network) = cidr.network().addr() else {
+        return vec![];
+    };
+    let prefix = cidr.prefix() as u32;
+    let fixed = prefix.div_ceil(8) as usize;
+    if fixed == 0 {
+        return vec!["*".into()];
+    }
+
+    let base = u32::from(network);
+    let step = 1u32 << (32 - fixed * 8);
+    (0..1u32 << (fixed as u32 * 8 - prefix))
+        .map(|i| {
+            let octets = Ipv4Addr::from(base + i * step).octets();
+            let fixed_part = octets[..fixed]
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<_>>()
+                .join(".");
+            match fixed {
+                4 => fixed_part,
+                _ => format!("{fixed_part}.*"),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BypassList(Vec<BypassEntry>);
+
+impl BypassList {
+    fn items(value: &str) -> impl Iterator<Item = &str> {
+        value
+            .split([',', ';', '\n'])
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+    }
+
+    /// 存在无效条目时返回全部无效条目
+    pub fn parse(value: &str) -> Result<Self> {
+        let mut list = Self::default();
+        let mut invalid = Vec::new();
+        for item in Self::items(value) {
+            match item.parse() {
+                Ok(entry) => list.push(entry),
+                Err(_) => invalid.push(item),
+            }
+        }
+        if !invalid.is_empty() {
+            bail!("invalid bypass entries: {}", invalid.join(", "));
+        }
+        Ok(list)
+    }
+
+    /// 跳过无效条目，用于读取旧版本保存的配置
+    fn parse_lossy(value: &str) -> Self {
+        let mut list = Self::default();
+        for item in Self::items(value) {
+            match item.parse() {
+                Ok(entry) => list.push(entry),
+                Err(err) => log::warn!(target: "app", "Skip bypass entry: {err}"),
+            }
+        }
+        list
+    }
+
+    pub fn defaults(platform: BypassPlatform) -> Self {
+        let mut list = Self::parse_lossy(&DEFAULT_BYPASS.join(","));
+        if platform == BypassPlatform::Macos {
+            list.extend(Self::parse_lossy(&MACOS_DEFAULT_BYPASS.join(",")));
+        }
+        list
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn push(&mut self, entry: BypassEntry) {
+        if !self.0.contains(&entry) {
+            self.0.push(entry);
+        }
+    }
+
+    fn extend(&mut self, other: Self) {
+        for entry in other.0 {
+            self.push(entry);
+        }
+    }
+
+    pub fn render(&self, platform: BypassPlatform) -> String {
+        self.0
+            .iter()
+            .flat_map(|entry| entry.render(platform))
+            .collect::<Vec<_>>()
+            .join(platform.separator())
+    }
+}
+
+/// 按 verge 配置组合默认与自定义的绕过列表
+fn configured_bypass(platform: BypassPlatform) -> BypassList {
+    let (use_default, custom) = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        (
+            verge.use_default_bypass.unwrap_or(true),
+            verge.system_proxy_bypass.clone().unwrap_or_default(),
+        )
+    };
+    let custom = BypassList::parse_lossy(&custom);
+
+    if custom.is_empty() {
+        BypassList::defaults(platform)
+    } else if use_default {
+        let mut list = BypassList::defaults(platform);
+        list.extend(custom);
+        list
+    } else {
+        custom
+    }
+}
+
+/// 当前平台系统代理的绕过列表
+pub fn system_bypass() -> String {
+    let platform = BypassPlatform::current();
+    configured_bypass(platform).render(platform)
+}
+
+/// shell 环境变量中的 `NO_PROXY`
+pub fn no_proxy() -> String {
+    configured_bypass(BypassPlatform::current()).render(BypassPlatform::Env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entries() {
+        assert_eq!(
+            "<LOCAL>".parse::<BypassEntry>().unwrap(),
+            BypassEntry::Local
+        );
+        assert_eq!(
+            "*.Example.com".parse::<BypassEntry>().unwrap(),
+            BypassEntry::Domain("*.example.com".into())
+        );
+        assert_eq!(
+            "[::1]".parse::<BypassEntry>().unwrap(),
+            BypassEntry::Ip("::1".parse().unwrap())
+        );
+        assert_eq!(
+            "172.16.*".parse::<BypassEntry>().unwrap(),
+            BypassEntry::Cidr("172.16.0.0/16".parse().unwrap())
+        );
+        assert_eq!(
+            "10.1.2.3/8".parse::<BypassEntry>().unwrap(),
+            BypassEntry::Cidr("10.0.0.0/8".parse().unwrap())
+        );
+        assert!("10.0.0.0/33".parse::<BypassEntry>().is_err());
+        assert!("exa mple.com".parse::<BypassEntry>().is_err());
+        assert!("a..b".parse::<BypassEntry>().is_err());
+
+        let list = BypassList::parse("localhost; 10.*,localhost\n*.lan").unwrap();
+        assert_eq!(
+            list.render(BypassPlatform::Linux),
+            "localhost,10.0.0.0/8,*.lan"
+        );
+        let err = BypassList::parse("localhost,bad host,a/b").unwrap_err();
+        assert_eq!(err.to_string(), "invalid bypass entries: bad host, a/b");
+    }
+
+    #[test]
+    fn test_render_windows() {
+        let list =
+            BypassList::parse("<local>,::1,fc00::/7,192.168.1.0/30,10.0.0.0/14,0.0.0.0/0").unwrap();
+        assert_eq!(
+            list.render(BypassPlatform::Windows),
+            "<local>;[::1];192.168.1.0;192.168.1.1;192.168.1.2;192.168.1.3;10.0.*;10.1.*;10.2.*;\
+             10.3.*;*"
+        );
+        assert_eq!(
+            BypassList::defaults(BypassPlatform::Windows).render(BypassPlatform::Windows),
+            "localhost;127.*;192.168.*;10.*;172.16.*;172.17.*;172.18.*;172.19.*;172.20.*;\
+             172.21.*;172.22.*;172.23.*;172.24.*;172.25.*;172.26.*;172.27.*;172.28.*;172.29.*;\
+             172.30.*;172.31.*;[::1];*.local;<local>"
+        );
+    }
+
+    #[test]
+    fn test_render_linux() {
+        let list = BypassList::parse("<local>,[::1],fc00::/7,172.16.*").unwrap();
+        assert_eq!(
+            list.render(BypassPlatform::Linux),
+            "::1,fc00::/7,172.16.0.0/16"
+        );
+        assert_eq!(
+            BypassList::defaults(BypassPlatform::Linux).render(BypassPlatform::Linux),
+            "localhost,127.0.0.0/8,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,::1,*.local"
+        );
+    }
+
+    #[test]
+    fn test_render_macos() {
+        let list = BypassList::parse("<local>,[::1],fc00::/7,172.16.*").unwrap();
+        assert_eq!(
+            list.render(BypassPlatform::Macos),
+            "<local>,::1,fc00::/7,172.16.0.0/16"
+        );
+        assert_eq!(
+            BypassList::defaults(BypassPlatform::Macos).render(BypassPlatform::Macos),
+            "localhost,127.0.0.0/8,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,::1,*.local,<local>,\
+             *.crashlytics.com"
+        );
+    }
+
+    #[test]
+    fn test_render_env() {
+        let list = BypassList::parse("<local>,*.lan,foo*.com,example.com,[::1],172.16.*").unwrap();
+        assert_eq!(
+            list.render(BypassPlatform::Env),
+            ".lan,example.com,::1,172.16.0.0/16"
+        );
+        assert_eq!(
+            BypassList::defaults(BypassPlatform::Linux).render(BypassPlatform::Env),
+            "localhost,127.0.0.0/8,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,::1,.local"
+        );
+    }
+}
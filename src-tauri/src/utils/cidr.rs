@@ -0,0 +1,168 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fmt, net::IpAddr, str::FromStr};
+
+/// IPv4/IPv6 网段，例如 `192.168.1.0/24`、`fd00::/8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpCidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpCidr {
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Self> {
+        let max = Self::max_prefix(&addr);
+        if prefix > max {
+            bail!("invalid prefix length {prefix} for {addr}, max is {max}");
+        }
+        Ok(Self { addr, prefix })
+    }
+
+    /// build the network that contains `addr` with the given netmask
+    pub fn from_netmask(addr: IpAddr, netmask: IpAddr) -> Result<Self> {
+        let prefix = match netmask {
+            IpAddr::V4(mask) => u32::from(mask).leading_ones(),
+            IpAddr::V6(mask) => u128::from(mask).leading_ones(),
+        };
+        Ok(Self::new(addr, prefix as u8)?.network())
+    }
+
+    fn max_prefix(addr: &IpAddr) -> u8 {
+        match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        self.addr.is_ipv4()
+    }
+
+    /// the same cidr with host bits cleared
+    pub fn network(&self) -> Self {
+        let addr = match self.addr {
+            IpAddr::V4(v4) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                IpAddr::V4((u32::from(v4) & mask).into())
+            }
+            IpAddr::V6(v6) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                IpAddr::V6((u128::from(v6) & mask).into())
+            }
+        };
+        Self {
+            addr,
+            prefix: self.prefix,
+        }
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = anyhow::Error;
+
+    /// accepts `addr/prefix` or a bare address (host route)
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match s.split_once('/') {
+            Some((addr, prefix)) => {
+                let addr = addr
+                    .parse::<IpAddr>()
+                    .with_context(|| format!("invalid ip address in \"{s}\""))?;
+                let prefix = prefix
+                    .parse::<u8>()
+                    .with_context(|| format!("invalid prefix length in \"{s}\""))?;
+                Self::new(addr, prefix)
+            }
+            None => {
+                let addr = s
+                    .parse::<IpAddr>()
+                    .with_context(|| format!("invalid ip address \"{s}\""))?;
+                let prefix = Self::max_prefix(&addr);
+                Self::new(addr, prefix)
+            }
+        }
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl Serialize for IpCidr {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpCidr {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_contains() {
+        let cidr: IpCidr = "192.168.1.0/24".parse().unwrap();
+        assert!(cidr.contains(&"192.168.1.42".parse().unwrap()));
+        assert!(!cidr.contains(&"192.168.2.1".parse().unwrap()));
+        assert!(!cidr.contains(&"::1".parse().unwrap()));
+
+        let v6: IpCidr = "fd00::/8".parse().unwrap();
+        assert!(v6.contains(&"fd12:3456::1".parse().unwrap()));
+        assert!(!v6.contains(&"fe80::1".parse().unwrap()));
+
+        let any: IpCidr = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains(&"8.8.8.8".parse().unwrap()));
+
+        let host: IpCidr = "10.0.0.1".parse().unwrap();
+        assert_eq!(host.prefix(), 32);
+        assert_eq!(host.to_string(), "10.0.0.1/32");
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("10.0.0/8".parse::<IpCidr>().is_err());
+        assert!("example.com".parse::<IpCidr>().is_err());
+        assert!("fd00::/129".parse::<IpCidr>().is_err());
+    }
+
+    #[test]
+    fn test_netmask() {
+        let cidr = IpCidr::from_netmask(
+            "172.16.5.9".parse().unwrap(),
+            "255.255.0.0".parse().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(cidr.to_string(), "172.16.0.0/16");
+    }
+}
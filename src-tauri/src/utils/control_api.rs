@@ -0,0 +1,390 @@
+//! 本地 HTTP 控制接口，挂在单例服务器的 `/api` 下
+//!
+//! 所有请求需要携带 `Authorization: Bearer <token>`，令牌保存在 verge 配置中
+
+use crate::{
+    config::{Config, IVerge},
+    core::{handle, CoreManager},
+    feat,
+};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use warp::{
+    filters::BoxedFilter,
+    http::StatusCode,
+    reject::{Reject, Rejection},
+    reply::{self, Reply},
+    Filter,
+};
+
+const CLASH_MODES: &[&str] = &["rule", "global", "direct"];
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+#[derive(Debug, Deserialize)]
+struct EnableBody {
+    enable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModeBody {
+    mode: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ApiStatus {
+    mode: String,
+    running_mode: String,
+    profile: Option<String>,
+    profile_name: Option<String>,
+    mixed_port: u16,
+    socks_port: u16,
+    http_port: u16,
+    system_proxy: bool,
+    tun_mode: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ApiProfile {
+    uid: String,
+    name: Option<String>,
+    current: bool,
+}
+
+/// 按字节比较全部内容，避免比较耗时泄露令牌
+fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn authorize(header: Option<String>) -> bool {
+    let verge = Config::verge();
+    let verge = verge.latest();
+    check_authorization(
+        verge.enable_control_api.unwrap_or(false),
+        verge.control_api_token.as_deref(),
+        header.as_deref(),
+    )
+}
+
+/// 未启用或没有设置令牌时拒绝所有请求
+fn check_authorization(enabled: bool, expected: Option<&str>, header: Option<&str>) -> bool {
+    let Some(expected) = expected.filter(|t| enabled && !t.is_empty()) else {
+        return false;
+    };
+    header
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| token_matches(expected, token.trim()))
+}
+
+fn with_auth() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(|header: Option<String>| async move {
+            match authorize(header) {
+                true => Ok(()),
+                false => Err(warp::reject::custom(Unauthorized)),
+            }
+        })
+        .untuple_one()
+}
+
+fn respond<T: Serialize>(result: Result<T>) -> Result<reply::Response, Infallible> {
+    Ok(match result {
+        Ok(data) => reply::json(&data).into_response(),
+        Err(err) => reply::with_status(
+            reply::json(&serde_json::json!({ "error": err.to_string() })),
+            StatusCode::BAD_REQUEST,
+        )
+        .into_response(),
+    })
+}
+
+async fn recover(rejection: Rejection) -> Result<reply::Response, Rejection> {
+    if rejection.find::<Unauthorized>().is_some() {
+        return Ok(reply::with_status(
+            reply::json(&serde_json::json!({ "error": "unauthorized" })),
+            StatusCode::UNAUTHORIZED,
+        )
+        .into_response());
+    }
+    Err(rejection)
+}
+
+async fn get_status() -> Result<reply::Response, Infallible> {
+    let running_mode = CoreManager::global().get_running_mode().await;
+    let clash_info = Config::clash().latest().get_client_info();
+    let mode = Config::clash()
+        .latest()
+        .0
+        .get("mode")
+        .and_then(|mode| mode.as_str())
+        .unwrap_or("rule")
+        .to_owned();
+    let verge = Config::verge().latest().clone();
+    let profiles = Config::profiles();
+    let profiles = profiles.latest();
+    let profile = profiles.get_current();
+    let profile_name = profile
+        .as_ref()
+        .and_then(|uid| profiles.get_item(uid).ok())
+        .and_then(|item| item.name.clone());
+
+    respond(Ok(ApiStatus {
+        mode,
+        running_mode: running_mode.to_string(),
+        profile,
+        profile_name,
+        mixed_port: verge.verge_mixed_port.unwrap_or(clash_info.mixed_port),
+        socks_port: verge.verge_socks_port.unwrap_or(clash_info.socks_port),
+        http_port: verge.verge_port.unwrap_or(clash_info.port),
+        system_proxy: verge.enable_system_proxy.unwrap_or(false),
+        tun_mode: verge.enable_tun_mode.unwrap_or(false),
+    }))
+}
+
+async fn list_profiles() -> Result<reply::Response, Infallible> {
+    let profiles = Config::profiles();
+    let profiles = profiles.latest();
+    let current = profiles.get_current();
+    let items = profiles
+        .get_items()
+        .into_iter()
+        .flatten()
+        .filter(|item| matches!(item.itype.as_deref(), Some("remote" | "local")))
+        .filter_map(|item| {
+            let uid = item.uid.clone()?;
+            Some(ApiProfile {
+                current: current.as_ref() == Some(&uid),
+                uid,
+                name: item.name.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    respond(Ok(items))
+}
+
+async fn activate_profile(uid: String) -> Result<reply::Response, Infallible> {
+    respond(feat::switch_profile(uid).await)
+}
+
+async fn update_profile(uid: String) -> Result<reply::Response, Infallible> {
+    let result = feat::update_profile(uid, None, Some(true)).await;
+    respond(result.map(|_| true))
+}
+
+async fn patch_verge(patch: IVerge) -> Result<reply::Response, Infallible> {
+    let result = feat::patch_verge(patch, false).await;
+    if result.is_ok() {
+        handle::Handle::refresh_verge();
+    }
+    respond(result.map(|_| true))
+}
+
+async fn set_system_proxy(body: EnableBody) -> Result<reply::Response, Infallible> {
+    patch_verge(IVerge {
+        enable_system_proxy: Some(body.enable),
+        ..IVerge::default()
+    })
+    .await
+}
+
+async fn set_tun_mode(body: EnableBody) -> Result<reply::Response, Infallible> {
+    patch_verge(IVerge {
+        enable_tun_mode: Some(body.enable),
+        ..IVerge::default()
+    })
+    .await
+}
+
+async fn set_mode(body: ModeBody) -> Result<reply::Response, Infallible> {
+    let mode = body.mode.to_ascii_lowercase();
+    let result = match CLASH_MODES.contains(&mode.as_str()) {
+        true => {
+            feat::change_clash_mode(mode);
+            Ok(true)
+        }
+        false => Err(anyhow!("invalid mode, expected one of {CLASH_MODES:?}")),
+    };
+    respond(result)
+}
+
+/// 控制接口的路由，未启用或令牌不正确时返回 401
+pub fn routes() -> BoxedFilter<(reply::Response,)> {
+    let api = warp::path("api").and(with_auth());
+
+    let status = api
+        .and(warp::path!("status"))
+        .and(warp::get())
+        .and_then(get_status);
+    let profiles = api
+        .and(warp::path!("profiles"))
+        .and(warp::get())
+        .and_then(list_profiles);
+    let activate = api
+        .and(warp::path!("profiles" / String / "activate"))
+        .and(warp::post())
+        .and_then(activate_profile);
+    let update = api
+        .and(warp::path!("profiles" / String / "update"))
+        .and(warp::post())
+        .and_then(update_profile);
+    let system_proxy = api
+        .and(warp::path!("system-proxy"))
+        .and(warp::put())
+        .and(warp::body::json())
+        .and_then(set_system_proxy);
+    let tun = api
+        .and(warp::path!("tun"))
+        .and(warp::put())
+        .and(warp::body::json())
+        .and_then(set_tun_mode);
+    let mode = api
+        .and(warp::path!("mode"))
+        .and(warp::put())
+        .and(warp::body::json())
+        .and_then(set_mode);
+
+    status
+        .or(profiles)
+        .unify()
+        .or(activate)
+        .unify()
+        .or(update)
+        .unify()
+        .or(system_proxy)
+        .unify()
+        .or(tun)
+        .unify()
+        .or(mode)
+        .unify()
+        .recover(recover)
+        .unify()
+        .boxed()
+}
+
+/// 生成新的访问令牌
+pub fn generate_token() -> Result<String> {
+    let mut token = [0u8; 32];
+    if let Err(err) = getrandom::fill(&mut token) {
+        bail!("failed to generate control api token: {err}");
+    }
+    Ok(hex::encode(token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn enable_api() {
+        let verge = Config::verge();
+        let mut verge = verge.data();
+        verge.enable_control_api = Some(true);
+        verge.control_api_token = Some(TOKEN.into());
+    }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches(TOKEN, TOKEN));
+        assert!(!token_matches(TOKEN, "0123456789abcdeF"));
+        assert!(!token_matches(TOKEN, "0123456789abcde"));
+        assert!(!token_matches(TOKEN, ""));
+        assert!(token_matches("", ""));
+    }
+
+    #[test]
+    fn test_check_authorization() {
+        let header = format!("Bearer {TOKEN}");
+        assert!(check_authorization(true, Some(TOKEN), Some(&header)));
+        assert!(check_authorization(
+            true,
+            Some(TOKEN),
+            Some(&format!("Bearer {TOKEN} "))
+        ));
+
+        // 未启用、没有令牌或令牌为空
+        assert!(!check_authorization(false, Some(TOKEN), Some(&header)));
+        assert!(!check_authorization(true, None, Some(&header)));
+        assert!(!check_authorization(true, Some(""), Some("Bearer ")));
+
+        // 缺少或格式错误的请求头
+        assert!(!check_authorization(true, Some(TOKEN), None));
+        assert!(!check_authorization(true, Some(TOKEN), Some(TOKEN)));
+        assert!(!check_authorization(
+            true,
+            Some(TOKEN),
+            Some(&format!("Basic {TOKEN}"))
+        ));
+        assert!(!check_authorization(
+            true,
+            Some(TOKEN),
+            Some("Bearer wrong")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_routes_reject_unauthorized() {
+        enable_api();
+        let api = routes();
+
+        let res = warp::test::request()
+            .method("GET")
+            .path("/api/profiles")
+            .reply(&api)
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = warp::test::request()
+            .method("PUT")
+            .path("/api/mode")
+            .header("authorization", "Bearer wrong")
+            .json(&serde_json::json!({ "mode": "global" }))
+            .reply(&api)
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_routes_authorized() {
+        enable_api();
+        let api = routes();
+        let auth = format!("Bearer {TOKEN}");
+
+        let res = warp::test::request()
+            .method("GET")
+            .path("/api/profiles")
+            .header("authorization", &auth)
+            .reply(&api)
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(res.body()).unwrap();
+        assert!(body.is_array());
+
+        // 非法模式不会改动配置
+        let res = warp::test::request()
+            .method("PUT")
+            .path("/api/mode")
+            .header("authorization", &auth)
+            .json(&serde_json::json!({ "mode": "bogus" }))
+            .reply(&api)
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = warp::test::request()
+            .method("GET")
+            .path("/api/unknown")
+            .header("authorization", &auth)
+            .reply(&api)
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -19,6 +19,9 @@ pub static PORTABLE_FLAG: OnceCell<bool> = OnceCell::new();
 pub static CLASH_CONFIG: &str = "config.yaml";
 pub static VERGE_CONFIG: &str = "verge.yaml";
 pub static PROFILE_YAML: &str = "profiles.yaml";
+pub static ANNOUNCEMENTS_YAML: &str = "announcements.yaml";
+pub static SERVICE_SECRET: &str = "service.key";
+pub static DNS_CONFIG: &str = "dns_config.yaml";
 
 /// init portable flag
 pub fn init_portable_flag() -> Result<()> {
@@ -61,35 +64,9 @@ pub fn app_home_dir() -> Result<PathBuf> {
                 .parent()
                 .ok_or(anyhow::anyhow!("failed to get executable directory"))?;
 
-            // 使用系统临时目录 + 应用ID
-            #[cfg(target_os = "windows")]
-            {
-                if let Some(local_app_data) = std::env::var_os("LOCALAPPDATA") {
-                    let path = PathBuf::from(local_app_data).join(APP_ID);
-                    return Ok(path);
-                }
-            }
-
-            #[cfg(target_os = "macos")]
-            {
-                if let Some(home) = std::env::var_os("HOME") {
-                    let path = PathBuf::from(home)
-                        .join("Library")
-                        .join("Application Support")
-                        .join(APP_ID);
-                    return Ok(path);
-                }
-            }
-
-            #[cfg(target_os = "linux")]
-            {
-                if let Some(home) = std::env::var_os("HOME") {
-                    let path = PathBuf::from(home)
-                        .join(".local")
-                        .join("share")
-                        .join(APP_ID);
-                    return Ok(path);
-                }
+            // 与 tauri 的 data_dir 保持一致（Windows 下为 Roaming AppData）
+            if let Some(data_dir) = ::dirs::data_dir() {
+                return Ok(data_dir.join(APP_ID));
             }
 
             // 如果无法获取系统目录，则回退到可执行文件目录
@@ -168,6 +145,11 @@ pub fn find_target_icons(target: &str) -> Result<Option<String>> {
     }
 }
 
+/// subscription usage history dir
+pub fn app_usage_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("usage"))
+}
+
 /// logs dir
 pub fn app_logs_dir() -> Result<PathBuf> {
     Ok(app_home_dir()?.join("logs"))
@@ -185,6 +167,19 @@ pub fn profiles_path() -> Result<PathBuf> {
     Ok(app_home_dir()?.join(PROFILE_YAML))
 }
 
+pub fn announcements_path() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join(ANNOUNCEMENTS_YAML))
+}
+
+pub fn dns_config_path() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join(DNS_CONFIG))
+}
+
+/// 服务 IPC 通信使用的密钥文件
+pub fn service_secret_path() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join(SERVICE_SECRET))
+}
+
 #[cfg(target_os = "macos")]
 pub fn service_path() -> Result<PathBuf> {
     let res_dir = app_resources_dir()?;
@@ -211,6 +206,12 @@ pub fn service_log_file() -> Result<PathBuf> {
     Ok(log_file)
 }
 
+/// 与主程序同目录的 sidecar，无界面运行时直接启动
+pub fn sidecar_path(name: &str) -> Result<PathBuf> {
+    let exe = tauri::utils::platform::current_exe()?;
+    Ok(exe.with_file_name(format!("{name}{}", std::env::consts::EXE_SUFFIX)))
+}
+
 pub fn path_to_str(path: &PathBuf) -> Result<&str> {
     let path_str = path
         .as_os_str()
@@ -0,0 +1,305 @@
+use crate::config::Config;
+use anyhow::{bail, Context, Result};
+use hickory_resolver::{
+    config::{NameServerConfig, ResolverConfig, ResolverOpts},
+    name_server::GenericConnector,
+    proto::{
+        runtime::{
+            iocompat::AsyncIoTokioAsStd, RuntimeProvider, TokioHandle, TokioRuntimeProvider,
+            TokioTime,
+        },
+        xfer::Protocol,
+    },
+    Resolver,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    io,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{lookup_host, TcpStream, UdpSocket},
+};
+
+const MAX_PROXY_HEADER_SIZE: usize = 4096;
+
+/// 查询是直连 DNS 服务器还是经过内核的代理端口
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsRoute {
+    Direct,
+    Proxy,
+}
+
+/// 可以直接查询的 DNS 服务器
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Nameserver {
+    Udp {
+        host: String,
+        port: u16,
+    },
+    Tcp {
+        host: String,
+        port: u16,
+    },
+    Tls {
+        host: String,
+        port: u16,
+    },
+    Https {
+        host: String,
+        port: u16,
+        path: String,
+    },
+}
+
+impl Nameserver {
+    /// 解析 mihomo 格式的服务器地址，`#` 后的出站参数会被忽略
+    pub fn parse(server: &str) -> Result<Self> {
+        let server = server.split('#').next().unwrap_or_default().trim();
+        if server == "system" {
+            bail!("querying system nameservers is not supported");
+        }
+        if let Ok(ip) = server.parse::<IpAddr>() {
+            return Ok(Self::Udp {
+                host: ip.to_string(),
+                port: 53,
+            });
+        }
+
+        let url = match server.contains("://") {
+            true => url::Url::parse(server),
+            false => url::Url::parse(&format!("udp://{server}")),
+        }?;
+        let host = url
+            .host_str()
+            .filter(|host| !host.is_empty())
+            .context("missing host")?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+
+        match url.scheme() {
+            "udp" => Ok(Self::Udp {
+                host,
+                port: url.port().unwrap_or(53),
+            }),
+            "tcp" => Ok(Self::Tcp {
+                host,
+                port: url.port().unwrap_or(53),
+            }),
+            "tls" => Ok(Self::Tls {
+                host,
+                port: url.port().unwrap_or(853),
+            }),
+            "https" => {
+                let path = match url.path() {
+                    "/" => "/dns-query".to_string(),
+                    path => path.to_string(),
+                };
+                let path = match url.query() {
+                    Some(query) => format!("{path}?{query}"),
+                    None => path,
+                };
+                Ok(Self::Https {
+                    host,
+                    port: url.port().unwrap_or(443),
+                    path,
+                })
+            }
+            scheme => bail!("querying {scheme} nameservers is not supported"),
+        }
+    }
+
+    /// 代理端口只能转发 TCP，普通 DNS 经代理时改用 DNS over TCP
+    async fn config(&self, route: DnsRoute) -> Result<NameServerConfig> {
+        let (host, port, protocol) = match self {
+            Self::Udp { host, port } if route == DnsRoute::Direct => (host, *port, Protocol::Udp),
+            Self::Udp { host, port } | Self::Tcp { host, port } => (host, *port, Protocol::Tcp),
+            Self::Tls { host, port } => (host, *port, Protocol::Tls),
+            Self::Https { host, port, .. } => (host, *port, Protocol::Https),
+        };
+        let addr = lookup_host((host.as_str(), port))
+            .await?
+            .next()
+            .with_context(|| format!("failed to resolve {host}"))?;
+
+        let mut config = NameServerConfig::new(addr, protocol);
+        if matches!(protocol, Protocol::Tls | Protocol::Https) {
+            config.tls_dns_name = Some(host.clone());
+        }
+        if let Self::Https { path, .. } = self {
+            config.http_endpoint = Some(path.clone());
+        }
+        Ok(config)
+    }
+}
+
+/// 通过 mixed 端口的 HTTP CONNECT 建立 TCP 连接，其余沿用 tokio 的实现
+#[derive(Clone)]
+struct ProxyRuntimeProvider(TokioRuntimeProvider);
+
+impl RuntimeProvider for ProxyRuntimeProvider {
+    type Handle = TokioHandle;
+    type Timer = TokioTime;
+    type Udp = UdpSocket;
+    type Tcp = AsyncIoTokioAsStd<TcpStream>;
+
+    fn create_handle(&self) -> Self::Handle {
+        self.0.create_handle()
+    }
+
+    fn connect_tcp(
+        &self,
+        server_addr: SocketAddr,
+        _bind_addr: Option<SocketAddr>,
+        timeout: Option<Duration>,
+    ) -> Pin<Box<dyn Send + Future<Output = io::Result<Self::Tcp>>>> {
+        Box::pin(async move {
+            let connect = http_connect(server_addr);
+            let stream = match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, connect)
+                    .await
+                    .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??,
+                None => connect.await?,
+            };
+            Ok(AsyncIoTokioAsStd(stream))
+        })
+    }
+
+    fn bind_udp(
+        &self,
+        local_addr: SocketAddr,
+        server_addr: SocketAddr,
+    ) -> Pin<Box<dyn Send + Future<Output = io::Result<Self::Udp>>>> {
+        self.0.bind_udp(local_addr, server_addr)
+    }
+}
+
+async fn http_connect(target: SocketAddr) -> io::Result<TcpStream> {
+    let mixed_port = Config::verge()
+        .latest()
+        .verge_mixed_port
+        .unwrap_or(Config::clash().data().get_mixed_port());
+    let mut stream = TcpStream::connect(("127.0.0.1", mixed_port)).await?;
+
+    let request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // 逐字节读取响应头，避免读走后续的 DNS 数据
+    let mut header = Vec::new();
+    while !header.ends_with(b"\r\n\r\n") {
+        if header.len() > MAX_PROXY_HEADER_SIZE {
+            return Err(io::Error::other("invalid proxy response"));
+        }
+        header.push(stream.read_u8().await?);
+    }
+    let status = String::from_utf8_lossy(&header);
+    let status = status.lines().next().unwrap_or_default();
+    if !status.contains(" 200") {
+        return Err(io::Error::other(format!("proxy refused CONNECT: {status}")));
+    }
+    Ok(stream)
+}
+
+fn build_resolver<P: RuntimeProvider>(
+    nameserver: NameServerConfig,
+    provider: P,
+    timeout: Duration,
+) -> Resolver<GenericConnector<P>> {
+    let mut config = ResolverConfig::new();
+    config.add_name_server(nameserver);
+    let mut opts = ResolverOpts::default();
+    opts.timeout = timeout;
+    opts.attempts = 0;
+    opts.cache_size = 0;
+
+    Resolver::builder_with_config(config, GenericConnector::new(provider))
+        .with_options(opts)
+        .build()
+}
+
+enum ClientResolver {
+    Direct(Resolver<GenericConnector<TokioRuntimeProvider>>),
+    Proxy(Resolver<GenericConnector<ProxyRuntimeProvider>>),
+}
+
+/// 单个服务器和线路的查询客户端，服务器地址只解析一次，后续查询复用连接
+pub struct DnsClient(ClientResolver);
+
+impl DnsClient {
+    pub async fn new(server: &Nameserver, route: DnsRoute, timeout: Duration) -> Result<Self> {
+        let nameserver = server.config(route).await?;
+        let provider = TokioRuntimeProvider::new();
+        let resolver = match route {
+            DnsRoute::Direct => {
+                ClientResolver::Direct(build_resolver(nameserver, provider, timeout))
+            }
+            DnsRoute::Proxy => ClientResolver::Proxy(build_resolver(
+                nameserver,
+                ProxyRuntimeProvider(provider),
+                timeout,
+            )),
+        };
+        Ok(Self(resolver))
+    }
+
+    /// 查询域名的 A 记录
+    pub async fn resolve(&self, domain: &str) -> Result<Vec<IpAddr>> {
+        let lookup = match &self.0 {
+            ClientResolver::Direct(resolver) => resolver.ipv4_lookup(domain).await,
+            ClientResolver::Proxy(resolver) => resolver.ipv4_lookup(domain).await,
+        };
+        match lookup {
+            Ok(lookup) => Ok(lookup.iter().map(|a| IpAddr::V4(a.0)).collect()),
+            // NXDOMAIN 和空应答视为空结果
+            Err(err) if err.is_no_records_found() => Ok(vec![]),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nameserver() {
+        assert_eq!(
+            Nameserver::parse("8.8.8.8").unwrap(),
+            Nameserver::Udp {
+                host: "8.8.8.8".into(),
+                port: 53
+            }
+        );
+        assert_eq!(
+            Nameserver::parse("tls://[2606:4700::1111]#PROXY").unwrap(),
+            Nameserver::Tls {
+                host: "2606:4700::1111".into(),
+                port: 853
+            }
+        );
+        assert_eq!(
+            Nameserver::parse("https://1.1.1.1").unwrap(),
+            Nameserver::Https {
+                host: "1.1.1.1".into(),
+                port: 443,
+                path: "/dns-query".into()
+            }
+        );
+        assert_eq!(
+            Nameserver::parse("https://dns.example.com:8443/resolve?ct=1").unwrap(),
+            Nameserver::Https {
+                host: "dns.example.com".into(),
+                port: 8443,
+                path: "/resolve?ct=1".into()
+            }
+        );
+        assert!(Nameserver::parse("quic://dns.adguard.com").is_err());
+        assert!(Nameserver::parse("system").is_err());
+    }
+}
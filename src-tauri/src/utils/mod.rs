@@ -1,5 +1,9 @@
 pub mod autostart;
+pub mod bypass;
+pub mod cidr;
+pub mod control_api;
 pub mod dirs;
+pub mod dns_client;
 pub mod help;
 pub mod i18n;
 pub mod init;
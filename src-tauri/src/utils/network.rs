@@ -1,13 +1,21 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use lazy_static::lazy_static;
-use reqwest::{Client, ClientBuilder, Proxy, RequestBuilder, Response};
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Client, ClientBuilder, Proxy, RequestBuilder, Response,
+};
 use std::{
     sync::{Arc, Mutex, Once},
     time::{Duration, Instant},
 };
 use tokio::runtime::{Builder, Runtime};
 
-use crate::{config::Config, logging, utils::logging::Type, utils::sys_info};
+use crate::{
+    config::{Config, PrfAuth},
+    logging,
+    utils::logging::Type,
+    utils::sys_info,
+};
 
 // HTTP2 相关
 const H2_CONNECTION_WINDOW_SIZE: u32 = 1024 * 1024;
@@ -241,6 +249,7 @@ impl NetworkManager {
        }
     */
     /// 创建带有自定义选项的HTTP请求
+    #[allow(clippy::too_many_arguments)]
     pub fn create_request(
         &self,
         url: &str,
@@ -249,6 +258,7 @@ impl NetworkManager {
         user_agent: Option<String>,
         accept_invalid_certs: bool,
         use_hwid: bool,
+        auth: Option<&PrfAuth>,
     ) -> RequestBuilder {
         if self.should_reset_clients() {
             self.reset_clients();
@@ -338,11 +348,48 @@ impl NetworkManager {
             let sys_info = sys_info::get_system_info();
             logging!(info, Type::Network, true, "Adding HWID headers to request");
             request_builder = request_builder
-                .header("x-hwid", &sys_info.hwid)
+                .header("x-hwid", sys_info::get_hwid())
                 .header("x-device-os", &sys_info.os_type)
                 .header("x-ver-os", &sys_info.os_ver);
         }
 
+        if let Some(auth) = auth {
+            request_builder = Self::apply_auth(request_builder, auth);
+        }
+
+        request_builder
+    }
+
+    /// 添加订阅的自定义请求头和认证信息
+    fn apply_auth(mut request_builder: RequestBuilder, auth: &PrfAuth) -> RequestBuilder {
+        if let Some(headers) = &auth.headers {
+            for (name, value) in headers {
+                match (
+                    HeaderName::from_bytes(name.trim().as_bytes()),
+                    HeaderValue::from_str(value.trim()),
+                ) {
+                    (Ok(name), Ok(value)) => {
+                        request_builder = request_builder.header(name, value);
+                    }
+                    _ => {
+                        logging!(
+                            warn,
+                            Type::Network,
+                            true,
+                            "Skipping invalid custom header: {}",
+                            name
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(token) = auth.bearer_token.as_ref().filter(|t| !t.is_empty()) {
+            request_builder = request_builder.bearer_auth(token);
+        } else if let Some(username) = auth.username.as_ref().filter(|u| !u.is_empty()) {
+            request_builder = request_builder.basic_auth(username, auth.password.as_ref());
+        }
+
         request_builder
     }
 
@@ -383,6 +430,7 @@ impl NetworkManager {
         }
     } */
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn get_with_interrupt(
         &self,
         url: &str,
@@ -391,6 +439,7 @@ impl NetworkManager {
         user_agent: Option<String>,
         accept_invalid_certs: bool,
         use_hwid: bool,
+        auth: Option<&PrfAuth>,
     ) -> Result<Response> {
         let request = self.create_request(
             url,
@@ -399,6 +448,7 @@ impl NetworkManager {
             user_agent,
             accept_invalid_certs,
             use_hwid,
+            auth,
         );
 
         let timeout_duration = timeout_secs.unwrap_or(20);
@@ -429,6 +479,92 @@ impl NetworkManager {
             }
         }
     }
+
+    /// 从多个镜像地址获取订阅，按顺序依次尝试或并发竞速
+    /// 返回第一个成功的响应以及对应的地址
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_from_mirrors(
+        &self,
+        urls: &[String],
+        race: bool,
+        proxy_type: ProxyType,
+        timeout_secs: Option<u64>,
+        user_agent: Option<String>,
+        accept_invalid_certs: bool,
+        use_hwid: bool,
+        auth: Option<&PrfAuth>,
+    ) -> Result<(Response, String)> {
+        if urls.is_empty() {
+            bail!("no subscription url to fetch");
+        }
+
+        if race && urls.len() > 1 {
+            logging!(
+                info,
+                Type::Network,
+                true,
+                "Racing {} subscription mirrors",
+                urls.len()
+            );
+
+            let requests = urls.iter().map(|url| {
+                let user_agent = user_agent.clone();
+                Box::pin(async move {
+                    let resp = self
+                        .get_with_interrupt(
+                            url,
+                            proxy_type,
+                            timeout_secs,
+                            user_agent,
+                            accept_invalid_certs,
+                            use_hwid,
+                            auth,
+                        )
+                        .await?;
+                    Self::ensure_success(resp, url)
+                })
+            });
+
+            return futures::future::select_ok(requests)
+                .await
+                .map(|(result, _)| result)
+                .map_err(|e| anyhow!("all subscription mirrors failed, last error: {}", e));
+        }
+
+        let mut errors = Vec::new();
+        for url in urls {
+            let result = self
+                .get_with_interrupt(
+                    url,
+                    proxy_type,
+                    timeout_secs,
+                    user_agent.clone(),
+                    accept_invalid_certs,
+                    use_hwid,
+                    auth,
+                )
+                .await
+                .and_then(|resp| Self::ensure_success(resp, url));
+
+            match result {
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    logging!(warn, Type::Network, true, "Failed to fetch {}: {}", url, e);
+                    errors.push(format!("{url}: {e}"));
+                }
+            }
+        }
+
+        bail!("all subscription mirrors failed: {}", errors.join("; "))
+    }
+
+    fn ensure_success(resp: Response, url: &str) -> Result<(Response, String)> {
+        let status_code = resp.status();
+        if !status_code.is_success() {
+            bail!("failed to fetch remote profile with status {status_code}");
+        }
+        Ok((resp, url.to_string()))
+    }
 }
 
 /// 代理类型
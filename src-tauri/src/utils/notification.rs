@@ -12,6 +12,20 @@ pub enum NotificationEvent<'a> {
     AppQuit,
     #[cfg(target_os = "macos")]
     AppHidden,
+    SubscriptionQuotaAlert {
+        name: &'a str,
+        percent: u8,
+    },
+    SubscriptionExpireAlert {
+        name: &'a str,
+        days: u64,
+    },
+    ProxyTampered {
+        count: u32,
+    },
+    ProxyGuardPaused {
+        minutes: u64,
+    },
 }
 
 fn notify(app: &AppHandle, title: &str, body: &str) {
@@ -33,7 +47,7 @@ pub fn notify_event(app: &AppHandle, event: NotificationEvent) {
             notify(
                 app,
                 &t("ClashModeChangedTitle"),
-                &t_with_args("ClashModeChangedBody", mode),
+                &t_with_args("ClashModeChangedBody", &[("mode", mode)]),
             );
         }
         NotificationEvent::SystemProxyToggled => {
@@ -60,11 +74,47 @@ pub fn notify_event(app: &AppHandle, event: NotificationEvent) {
         NotificationEvent::AppHidden => {
             notify(app, &t("AppHiddenTitle"), &t("AppHiddenBody"));
         }
+        NotificationEvent::SubscriptionQuotaAlert { name, percent } => {
+            notify(
+                app,
+                &t("SubscriptionQuotaAlertTitle"),
+                &t_with_args(
+                    "SubscriptionQuotaAlertBody",
+                    &[("name", name), ("percent", &percent.to_string())],
+                ),
+            );
+        }
+        NotificationEvent::SubscriptionExpireAlert { name, days } => {
+            notify(
+                app,
+                &t("SubscriptionExpireAlertTitle"),
+                &t_with_args(
+                    "SubscriptionExpireAlertBody",
+                    &[("name", name), ("days", &days.to_string())],
+                ),
+            );
+        }
+        NotificationEvent::ProxyTampered { count } => {
+            notify(
+                app,
+                &t("ProxyTamperedTitle"),
+                &t_with_args("ProxyTamperedBody", &[("count", &count.to_string())]),
+            );
+        }
+        NotificationEvent::ProxyGuardPaused { minutes } => {
+            notify(
+                app,
+                &t("ProxyGuardPausedTitle"),
+                &t_with_args("ProxyGuardPausedBody", &[("minutes", &minutes.to_string())]),
+            );
+        }
     }
 }
 
 // 辅助函数，带参数的i18n
-fn t_with_args(key: &str, mode: &str) -> String {
+fn t_with_args(key: &str, args: &[(&str, &str)]) -> String {
     use crate::utils::i18n::t;
-    t(key).replace("{mode}", mode)
+    args.iter().fold(t(key), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), value)
+    })
 }
@@ -1,11 +1,15 @@
 #[cfg(target_os = "macos")]
 use crate::AppHandleManager;
 use crate::{
-    config::{Config, IVerge, PrfItem},
+    config::{Config, DeviceLimitError, IVerge, PrfItem},
     core::*,
     core::handle::Handle,
-    logging, logging_error,
-    module::lightweight::{self, auto_lightweight_mode_init},
+    feat, logging, logging_error,
+    module::{
+        connections::connections_init,
+        lightweight::{self, auto_lightweight_mode_init},
+        network_switch::network_switch_init,
+    },
     process::AsyncHandler,
     utils::{init, logging::Type, server, window_manager::WindowManager},
     wrap_err,
@@ -285,6 +289,9 @@ pub async fn resolve_setup_async(app_handle: &AppHandle) {
 
     log::trace!(target: "app", "Starting embedded server...");
     server::embed_server();
+    feat::sync_lan_pac();
+    // 退出时清理了 shell 环境变量文件，启动时按配置重新写入
+    logging_error!(Type::System, true, feat::sync_shell_env());
 
     logging_error!(Type::Tray, true, tray::Tray::global().init());
 
@@ -317,6 +324,17 @@ pub async fn resolve_setup_async(app_handle: &AppHandle) {
         sysopt::Sysopt::global().init_guard_sysproxy()
     );
 
+    // 透明代理规则，先清理上次异常退出遗留的规则
+    #[cfg(target_os = "linux")]
+    {
+        logging_error!(
+            Type::System,
+            true,
+            crate::core::tproxy::cleanup_stale().await
+        );
+        logging_error!(Type::System, true, crate::core::tproxy::sync().await);
+    }
+
     // 创建窗口
     let is_silent_start = { Config::verge().data().enable_silent_start }.unwrap_or(false);
     #[cfg(target_os = "macos")]
@@ -335,6 +353,12 @@ pub async fn resolve_setup_async(app_handle: &AppHandle) {
     // 自动进入轻量模式
     auto_lightweight_mode_init();
 
+    // 按网络自动切换订阅
+    network_switch_init();
+
+    // 订阅连接列表，统计流量并记录关闭的连接
+    connections_init();
+
     logging_error!(Type::Tray, true, tray::Tray::global().update_part());
 
     logging!(trace, Type::System, true, "Initializing hotkeys...");
@@ -701,6 +725,9 @@ pub async fn resolve_scheme(param: String) -> Result<()> {
                         // If UI not ready yet, message will be queued and flushed on ready
                         handle::Handle::notice_message("import_sub_url::ok", uid);
                     }
+                    Err(e) if e.downcast_ref::<DeviceLimitError>().is_some() => {
+                        feat::notify_device_limit(None, &e);
+                    }
                     Err(e) => {
                         handle::Handle::notice_message("import_sub_url::error", e.to_string());
                     }
@@ -1,23 +1,92 @@
 extern crate warp;
 
-use super::resolve;
+use super::{control_api, resolve};
 use crate::{
     config::{Config, IVerge, DEFAULT_PAC},
-    logging_error,
+    feat, logging_error,
     process::AsyncHandler,
     utils::logging::Type,
 };
 use anyhow::Result;
-use std::convert::Infallible;
-use warp::Filter;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+use tokio::sync::oneshot;
+use warp::{
+    http::{Response, StatusCode},
+    Filter,
+};
+
+/// 正在运行的局域网 PAC 服务的监听地址和停止信号
+static LAN_PAC: Lazy<Mutex<Option<(IpAddr, oneshot::Sender<()>)>>> = Lazy::new(|| Mutex::new(None));
 
 #[derive(serde::Deserialize, Debug)]
 struct QueryParam {
     param: String,
 }
 
-/// The embed server only be used to implement singleton process
-/// maybe it can be used as pac server later
+/// 未自定义 PAC 时使用由规则生成的 PAC，并跟随当前的代理模式
+fn pac_content() -> String {
+    let custom = Config::verge()
+        .latest()
+        .pac_file_content
+        .clone()
+        .filter(|content| content.trim() != DEFAULT_PAC.trim());
+    if let Some(content) = custom {
+        return content;
+    }
+
+    let mode = Config::clash()
+        .latest()
+        .0
+        .get("mode")
+        .and_then(|mode| mode.as_str())
+        .unwrap_or("rule")
+        .to_owned();
+    match mode.as_str() {
+        "direct" => "function FindProxyForURL(url, host) {\n  return \"DIRECT\";\n}\n".into(),
+        "global" => DEFAULT_PAC.into(),
+        _ => Config::runtime()
+            .latest()
+            .pac
+            .clone()
+            .unwrap_or_else(|| DEFAULT_PAC.into()),
+    }
+}
+
+/// PAC 中的代理地址换成 `proxy_host`，端口换成当前的混合端口
+fn pac_response(proxy_host: &str) -> Response<String> {
+    let port = Config::verge()
+        .latest()
+        .verge_mixed_port
+        .unwrap_or(Config::clash().data().get_mixed_port());
+    let content = pac_content()
+        .replace(
+            "127.0.0.1:%mixed-port%",
+            &format!("{proxy_host}:%mixed-port%"),
+        )
+        .replace("%proxy_host%", proxy_host)
+        .replace("%mixed-port%", &format!("{port}"));
+    Response::builder()
+        .header("Content-Type", "application/x-ns-proxy-autoconfig")
+        .body(content)
+        .unwrap_or_default()
+}
+
+/// 去掉 Host 头中的端口，IPv6 地址保留方括号
+fn host_without_port(host: &str) -> &str {
+    match host.strip_prefix('[') {
+        Some(rest) => rest.find(']').map_or(host, |end| &host[..end + 2]),
+        None => host.split(':').next().unwrap_or(host),
+    }
+}
+
+/// The embed server implements the singleton process, serves the pac file
+/// and the local control api
 pub fn embed_server() {
     let port = IVerge::get_singleton_port();
 
@@ -28,20 +97,12 @@ pub fn embed_server() {
         });
 
         let pac = warp::path!("commands" / "pac").map(move || {
-            let content = Config::verge()
+            let proxy_host = Config::verge()
                 .latest()
-                .pac_file_content
+                .proxy_host
                 .clone()
-                .unwrap_or(DEFAULT_PAC.to_string());
-            let port = Config::verge()
-                .latest()
-                .verge_mixed_port
-                .unwrap_or(Config::clash().data().get_mixed_port());
-            let content = content.replace("%mixed-port%", &format!("{port}"));
-            warp::http::Response::builder()
-                .header("Content-Type", "application/x-ns-proxy-autoconfig")
-                .body(content)
-                .unwrap_or_default()
+                .unwrap_or_else(|| "127.0.0.1".into());
+            pac_response(&proxy_host)
         });
         async fn scheme_handler(query: QueryParam) -> Result<impl warp::Reply, Infallible> {
             logging_error!(
@@ -55,7 +116,84 @@ pub fn embed_server() {
         let scheme = warp::path!("commands" / "scheme")
             .and(warp::query::<QueryParam>())
             .and_then(scheme_handler);
-        let commands = visible.or(scheme).or(pac);
+        let commands = visible.or(scheme).or(pac).or(control_api::routes());
         warp::serve(commands).run(([127, 0, 0, 1], port)).await;
     });
 }
+
+/// 局域网 PAC 服务只提供 PAC，不包含控制接口，传入 None 时停止
+///
+/// PAC 中的代理地址使用设备访问本机时的地址
+pub fn lan_pac_server(bind: Option<IpAddr>) {
+    let mut current = LAN_PAC.lock();
+    if current.as_ref().map(|(ip, _)| *ip) == bind {
+        return;
+    }
+    let restarting = match current.take() {
+        Some((_, shutdown)) => {
+            let _ = shutdown.send(());
+            true
+        }
+        None => false,
+    };
+    let Some(ip) = bind else {
+        log::info!(target: "app", "LAN PAC server stopped");
+        return;
+    };
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    *current = Some((ip, shutdown_tx));
+    let port = IVerge::get_lan_pac_port();
+
+    AsyncHandler::spawn(move || async move {
+        // 等待旧的服务释放端口
+        if restarting {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+        }
+        let pac = warp::path!("commands" / "pac")
+            .and(warp::addr::remote())
+            .and(warp::header::optional::<String>("host"))
+            .map(|remote: Option<SocketAddr>, host: Option<String>| {
+                let allowed = remote.is_some_and(|remote| {
+                    feat::lan_client_allowed(&Config::clash().latest().0, remote.ip())
+                });
+                if !allowed {
+                    return Response::builder()
+                        .status(StatusCode::FORBIDDEN)
+                        .body(String::new())
+                        .unwrap_or_default();
+                }
+                let proxy_host = match host {
+                    Some(host) => host_without_port(&host).to_string(),
+                    None => feat::lan_addresses()
+                        .first()
+                        .map_or_else(|| "127.0.0.1".into(), ToString::to_string),
+                };
+                pac_response(&proxy_host)
+            });
+        let shutdown = async {
+            shutdown_rx.await.ok();
+        };
+        match warp::serve(pac).try_bind_with_graceful_shutdown((ip, port), shutdown) {
+            Ok((addr, server)) => {
+                log::info!(target: "app", "LAN PAC server listening on {addr}");
+                server.await;
+            }
+            Err(err) => {
+                log::error!(target: "app", "Failed to start LAN PAC server on {ip}:{port}: {err}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_without_port() {
+        assert_eq!(host_without_port("192.168.1.2:33332"), "192.168.1.2");
+        assert_eq!(host_without_port("192.168.1.2"), "192.168.1.2");
+        assert_eq!(host_without_port("[fd00::2]:33332"), "[fd00::2]");
+        assert_eq!(host_without_port("[fd00::2]"), "[fd00::2]");
+    }
+}
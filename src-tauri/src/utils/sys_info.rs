@@ -20,3 +20,20 @@ pub static SYSTEM_INFO: Lazy<SystemInfo> = Lazy::new(|| {
 pub fn get_system_info() -> &'static SystemInfo {
     &SYSTEM_INFO
 }
+
+/// 获取发送给订阅提供方的 HWID，优先使用用户重置后的值
+pub fn get_hwid() -> String {
+    crate::config::Config::verge()
+        .latest()
+        .custom_hwid
+        .clone()
+        .filter(|hwid| !hwid.is_empty())
+        .unwrap_or_else(|| SYSTEM_INFO.hwid.clone())
+}
+
+/// 生成新的随机 HWID
+pub fn generate_hwid() -> anyhow::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes)?;
+    Ok(hex::encode(bytes))
+}
@@ -36,7 +36,7 @@ impl MihomoManager {
             .map_err(|e| e.to_string())?;
 
         let response = match method {
-            Method::PATCH => {
+            Method::PATCH | Method::POST | Method::DELETE => {
                 let status = client_response.status();
                 if status.as_u16() == 204 {
                     json!({"code": 204})
@@ -144,4 +144,26 @@ impl MihomoManager {
                 .to_string())
         }
     }
+
+    pub async fn flush_fakeip_cache(&self) -> Result<(), String> {
+        let url = format!("{}/cache/fakeip/flush", self.mihomo_server);
+        let response = self.send_request(Method::POST, url, None).await?;
+        if response["code"] == 204 {
+            Ok(())
+        } else {
+            Err(response["message"]
+                .as_str()
+                .unwrap_or("unknown error")
+                .to_string())
+        }
+    }
+
+    pub async fn dns_query(&self, name: &str, query_type: &str) -> Result<Value, String> {
+        let url = format!(
+            "{}/dns/query?name={}&type={}",
+            self.mihomo_server, name, query_type
+        );
+        let response = self.send_request(Method::GET, url, None).await?;
+        Ok(response)
+    }
 }
@@ -8,6 +8,8 @@ import {
   SheetTitle,
 } from "@/components/ui/sheet";
 import { deleteConnection } from "@/services/api";
+import { closeConnections } from "@/services/cmds";
+import { showNotice } from "@/services/noticeService";
 import parseTraffic from "@/utils/parse-traffic";
 import { t } from "i18next";
 import { Button } from "@/components/ui/button";
@@ -106,6 +108,17 @@ const InnerConnectionDetail = ({ data, onClose }: InnerProps) => {
 
   const onDelete = useLockFn(async () => deleteConnection(data.id));
 
+  // Закрыть все соединения того же процесса или того же прокси-узла
+  const onCloseMatching = useLockFn(async (filter: IConnectionFilter) => {
+    try {
+      const count = await closeConnections(filter);
+      showNotice("success", t("Connections Closed", { count }));
+      onClose?.();
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
   return (
     <div className="select-text text-muted-foreground">
       {information.map((each) => (
@@ -115,7 +128,23 @@ const InnerConnectionDetail = ({ data, onClose }: InnerProps) => {
         </div>
       ))}
 
-      <div className="text-right mt-4">
+      <div className="flex flex-wrap justify-end gap-2 mt-4">
+        {metadata.process && (
+          <Button
+            variant="outline"
+            onClick={() => onCloseMatching({ process: metadata.process })}
+          >
+            {t("Close Process Connections")}
+          </Button>
+        )}
+        {data.chains.length > 0 && (
+          <Button
+            variant="outline"
+            onClick={() => onCloseMatching({ chain: data.chains[0] })}
+          >
+            {t("Close Proxy Connections")}
+          </Button>
+        )}
         <Button
           title={t("Close Connection")}
           onClick={() => {
@@ -0,0 +1,203 @@
+import dayjs from "dayjs";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useLockFn } from "ahooks";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+import {
+  clearClosedConnections,
+  closeConnections,
+  getClosedConnections,
+  getConnectionStats,
+} from "@/services/cmds";
+import { showNotice } from "@/services/noticeService";
+import parseTraffic from "@/utils/parse-traffic";
+
+import { BaseEmpty } from "@/components/base";
+import { Button } from "@/components/ui/button";
+import {
+  Sheet,
+  SheetContent,
+  SheetHeader,
+  SheetTitle,
+} from "@/components/ui/sheet";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { ArrowDown, ArrowUp, X } from "lucide-react";
+
+export interface ConnectionStatsRef {
+  open: () => void;
+}
+
+type View = IConnectionGroup | "closed";
+
+const VIEWS: { value: View; label: string }[] = [
+  { value: "host", label: "Host" },
+  { value: "process", label: "Process" },
+  { value: "rule", label: "Rule" },
+  { value: "chain", label: "Chains" },
+  { value: "closed", label: "Closed Connections" },
+];
+
+const traffic = (value: number) => parseTraffic(value).join(" ");
+
+export const ConnectionStats = forwardRef<ConnectionStatsRef>((props, ref) => {
+  const { t } = useTranslation();
+  const [open, setOpen] = useState(false);
+  const [view, setView] = useState<View>("host");
+
+  useImperativeHandle(ref, () => ({
+    open: () => setOpen(true),
+  }));
+
+  const { data: stats, mutate: mutateStats } = useSWR(
+    open && view !== "closed" ? ["getConnectionStats", view] : null,
+    ([, group]) => getConnectionStats(group as IConnectionGroup),
+    { refreshInterval: 2000 },
+  );
+  const { data: closed, mutate: mutateClosed } = useSWR(
+    open && view === "closed" ? "getClosedConnections" : null,
+    getClosedConnections,
+    { refreshInterval: 2000 },
+  );
+
+  const onCloseGroup = useLockFn(async (key: string) => {
+    try {
+      const count = await closeConnections({ [view]: key });
+      showNotice("success", t("Connections Closed", { count }));
+      await mutateStats();
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
+  const onClearHistory = useLockFn(async () => {
+    await clearClosedConnections();
+    await mutateClosed();
+  });
+
+  const list = view === "closed" ? closed : stats;
+
+  return (
+    <Sheet open={open} onOpenChange={setOpen}>
+      <SheetContent
+        side="right"
+        className="w-full max-w-[520px] overflow-y-auto p-0 flex flex-col"
+      >
+        <SheetHeader className="p-6 pb-4">
+          <SheetTitle>{t("Connection Statistics")}</SheetTitle>
+        </SheetHeader>
+
+        <div className="flex items-center gap-2 px-6 pb-4">
+          <Select value={view} onValueChange={(v) => setView(v as View)}>
+            <SelectTrigger className="flex-1">
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              {VIEWS.map((item) => (
+                <SelectItem key={item.value} value={item.value}>
+                  {t(item.label)}
+                </SelectItem>
+              ))}
+            </SelectContent>
+          </Select>
+          {view === "closed" && (
+            <Button
+              variant="outline"
+              size="sm"
+              onClick={onClearHistory}
+              disabled={!closed?.length}
+            >
+              {t("Clear")}
+            </Button>
+          )}
+        </div>
+
+        <div className="flex-grow overflow-y-auto px-6 pb-6">
+          {!list?.length && <BaseEmpty />}
+
+          {view !== "closed" &&
+            stats?.map((item) => (
+              <div
+                key={item.key}
+                className="flex items-center gap-2 py-2 border-b border-border"
+              >
+                <div className="flex-grow min-w-0">
+                  <div className="text-sm font-medium break-all select-text">
+                    {item.key || "-"}
+                  </div>
+                  <div className="flex gap-3 text-xs text-muted-foreground">
+                    <span className="flex items-center gap-1">
+                      <ArrowDown className="h-3 w-3" />
+                      {traffic(item.download)}
+                    </span>
+                    <span className="flex items-center gap-1">
+                      <ArrowUp className="h-3 w-3" />
+                      {traffic(item.upload)}
+                    </span>
+                    <span>
+                      {t("Active")}: {item.active}
+                    </span>
+                    <span>
+                      {t("Closed")}: {item.closed}
+                    </span>
+                  </div>
+                </div>
+                <Button
+                  variant="ghost"
+                  size="icon"
+                  title={t("Close Matching Connections")}
+                  disabled={!item.key || item.active === 0}
+                  onClick={() => onCloseGroup(item.key)}
+                >
+                  <X className="h-4 w-4" />
+                </Button>
+              </div>
+            ))}
+
+          {view === "closed" &&
+            closed?.map((item) => (
+              <div
+                key={`${item.id}-${item.closedAt}`}
+                className="py-2 border-b border-border select-text"
+              >
+                <div className="text-sm font-medium break-all">
+                  {item.metadata.host || item.metadata.destinationIP}:
+                  {item.metadata.destinationPort}
+                </div>
+                <div className="text-xs text-muted-foreground break-all">
+                  {[
+                    item.metadata.process,
+                    [...item.chains].reverse().join(" / "),
+                    item.rulePayload
+                      ? `${item.rule}(${item.rulePayload})`
+                      : item.rule,
+                  ]
+                    .filter(Boolean)
+                    .join(" · ")}
+                </div>
+                <div className="flex gap-3 text-xs text-muted-foreground">
+                  <span className="flex items-center gap-1">
+                    <ArrowDown className="h-3 w-3" />
+                    {traffic(item.download)}
+                  </span>
+                  <span className="flex items-center gap-1">
+                    <ArrowUp className="h-3 w-3" />
+                    {traffic(item.upload)}
+                  </span>
+                  <span>
+                    {dayjs(item.start).format("HH:mm:ss")} –{" "}
+                    {dayjs(item.closedAt).format("HH:mm:ss")}
+                  </span>
+                </div>
+              </div>
+            ))}
+        </div>
+      </SheetContent>
+    </Sheet>
+  );
+});
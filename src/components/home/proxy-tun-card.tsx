@@ -145,7 +145,7 @@ export const ProxyTunCard: FC = () => {
   const [localServiceOk, setLocalServiceOk] = useState(false);
 
   const { verge } = useVerge();
-  const { isAdminMode } = useSystemState();
+  const { isAdminMode, isCoreCapable } = useSystemState();
   const { indicator: systemProxyIndicator } = useSystemProxyState();
 
   const { enable_tun_mode } = verge ?? {};
@@ -165,7 +165,7 @@ export const ProxyTunCard: FC = () => {
     updateLocalStatus();
   }, []);
 
-  const isTunAvailable = localServiceOk || isAdminMode;
+  const isTunAvailable = localServiceOk || isAdminMode || isCoreCapable;
 
   const handleError = (err: Error) => {
     showNotice("error", err.message || err.toString());
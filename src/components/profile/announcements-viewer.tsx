@@ -0,0 +1,133 @@
+import dayjs from "dayjs";
+import { forwardRef, useEffect, useImperativeHandle, useState } from "react";
+import { useLockFn } from "ahooks";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+import { listen } from "@tauri-apps/api/event";
+import {
+  getAnnouncements,
+  markAnnouncementRead,
+  openWebUrl,
+} from "@/services/cmds";
+
+import { BaseEmpty, DialogRef } from "@/components/base";
+import { Button } from "@/components/ui/button";
+import {
+  Sheet,
+  SheetContent,
+  SheetHeader,
+  SheetTitle,
+} from "@/components/ui/sheet";
+import { ExternalLink } from "lucide-react";
+import { cn } from "@root/lib/utils";
+
+export const useAnnouncements = () => {
+  const { data, mutate } = useSWR("getAnnouncements", getAnnouncements);
+
+  // Бэкенд сообщает о новых объявлениях после обновления подписки
+  useEffect(() => {
+    const unlisten = listen("verge://announcements-updated", () => mutate());
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [mutate]);
+
+  const announcements = data ?? [];
+  const unread = announcements.filter((item) => !item.read).length;
+  return { announcements, unread, mutate };
+};
+
+export const AnnouncementsViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const [open, setOpen] = useState(false);
+  const { announcements, unread, mutate } = useAnnouncements();
+
+  useImperativeHandle(ref, () => ({
+    open: () => setOpen(true),
+    close: () => setOpen(false),
+  }));
+
+  const onMarkRead = useLockFn(async (id?: string) => {
+    await markAnnouncementRead(id);
+    await mutate();
+  });
+
+  const onOpenUrl = (item: IAnnouncement) => {
+    if (!item.url) return;
+    openWebUrl(item.url);
+    if (!item.read) onMarkRead(item.id);
+  };
+
+  return (
+    <Sheet open={open} onOpenChange={setOpen}>
+      <SheetContent
+        side="right"
+        className="w-full max-w-[520px] overflow-y-auto p-0 flex flex-col"
+      >
+        <SheetHeader className="p-6 pb-4">
+          <SheetTitle>{t("Announcements")}</SheetTitle>
+        </SheetHeader>
+
+        <div className="flex justify-end px-6 pb-4">
+          <Button
+            variant="outline"
+            size="sm"
+            onClick={() => onMarkRead()}
+            disabled={unread === 0}
+          >
+            {t("Mark All Read")}
+          </Button>
+        </div>
+
+        <div className="flex-grow overflow-y-auto px-6 pb-6">
+          {announcements.length === 0 && <BaseEmpty />}
+
+          {announcements.map((item) => (
+            <div
+              key={item.id}
+              className={cn(
+                "py-3 border-b border-border",
+                !item.read && "cursor-pointer",
+              )}
+              onClick={() => !item.read && onMarkRead(item.id)}
+            >
+              <div className="flex items-center gap-2 text-xs">
+                {!item.read && (
+                  <span className="h-2 w-2 rounded-full bg-primary" />
+                )}
+                <span className="font-medium">
+                  {item.profile_name || t("Unknown Profile")}
+                </span>
+                <span className="text-muted-foreground">
+                  {dayjs(item.received_at * 1000).format("YYYY-MM-DD HH:mm")}
+                </span>
+              </div>
+              <div
+                className={cn(
+                  "mt-1 text-sm whitespace-pre-wrap break-words select-text",
+                  item.read && "text-muted-foreground",
+                )}
+              >
+                {item.message}
+              </div>
+              {item.url && (
+                <Button
+                  variant="link"
+                  size="sm"
+                  className="h-auto p-0 mt-1"
+                  onClick={(e) => {
+                    e.stopPropagation();
+                    onOpenUrl(item);
+                  }}
+                >
+                  <ExternalLink className="h-3.5 w-3.5" />
+                  {t("Open Link")}
+                </Button>
+              )}
+            </div>
+          ))}
+        </div>
+      </SheetContent>
+    </Sheet>
+  );
+});
@@ -0,0 +1,377 @@
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useLockFn } from "ahooks";
+import { useTranslation } from "react-i18next";
+import {
+  getNetworkSnapshot,
+  patchNetworkRules,
+  patchProfileGroups,
+} from "@/services/cmds";
+import { showNotice } from "@/services/noticeService";
+import { useProfiles } from "@/hooks/use-profiles";
+import { useVerge } from "@/hooks/use-verge";
+
+import { DialogRef } from "@/components/base";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogHeader,
+  DialogTitle,
+  DialogFooter,
+  DialogClose,
+} from "@/components/ui/dialog";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { Switch } from "@/components/ui/switch";
+import { Separator } from "@/components/ui/separator";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { Loader2, Plus, Trash2, Wifi } from "lucide-react";
+import { cn } from "@root/lib/utils";
+
+// Radix Select не допускает пустое значение
+const NONE = "__none";
+
+type Condition = "ssid" | "interface" | "gateway" | "subnet";
+
+const CONDITIONS: { key: Condition; label: string; placeholder: string }[] = [
+  { key: "ssid", label: "SSID", placeholder: "Office Wi-Fi" },
+  { key: "interface", label: "Interface", placeholder: "wlan0" },
+  { key: "gateway", label: "Gateway", placeholder: "192.168.1.1" },
+  { key: "subnet", label: "Subnet", placeholder: "192.168.1.0/24" },
+];
+
+// id пустой — бэкенд сгенерирует его при сохранении
+const newKey = () => `new-${Date.now()}-${Math.random()}`;
+
+export const NetworkRulesViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { profiles, mutateProfiles } = useProfiles();
+  const { verge, patchVerge } = useVerge();
+  const [open, setOpen] = useState(false);
+  const [saving, setSaving] = useState(false);
+  const [groups, setGroups] = useState<IProfileGroup[]>([]);
+  const [rules, setRules] = useState<INetworkRule[]>([]);
+
+  const profileItems = (profiles?.items ?? []).filter(
+    (item) => item.type === "remote" || item.type === "local",
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setGroups(profiles?.groups ?? []);
+      setRules(profiles?.network_rules ?? []);
+      setOpen(true);
+    },
+    close: () => setOpen(false),
+  }));
+
+  const updateGroup = (index: number, patch: Partial<IProfileGroup>) =>
+    setGroups((list) =>
+      list.map((item, i) => (i === index ? { ...item, ...patch } : item)),
+    );
+
+  const updateRule = (index: number, patch: Partial<INetworkRule>) =>
+    setRules((list) =>
+      list.map((item, i) => (i === index ? { ...item, ...patch } : item)),
+    );
+
+  const toggleGroupItem = (index: number, uid: string) => {
+    const items = groups[index].items;
+    updateGroup(index, {
+      items: items.includes(uid)
+        ? items.filter((item) => item !== uid)
+        : [...items, uid],
+    });
+  };
+
+  // Заполнить условия по текущей сети
+  const onUseCurrent = useLockFn(async (index: number) => {
+    try {
+      const snapshot = await getNetworkSnapshot();
+      updateRule(index, {
+        ssid: snapshot.ssid,
+        gateway: snapshot.gateway,
+        interface: snapshot.ssid ? undefined : snapshot.gateway_interface,
+      });
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
+  const onSave = useLockFn(async () => {
+    setSaving(true);
+    try {
+      const keyed = groups.map((group) => ({
+        ...group,
+        id: group.id.startsWith("new-") ? "" : group.id,
+      }));
+      await patchProfileGroups(keyed);
+      // id новых групп выдаёт бэкенд, сопоставляем их по порядку
+      const saved = await mutateProfiles();
+      const groupId = (id?: string) => {
+        if (!id?.startsWith("new-")) return id;
+        const index = groups.findIndex((group) => group.id === id);
+        return saved?.groups?.[index]?.id;
+      };
+      await patchNetworkRules(
+        rules.map((rule) => ({
+          ...rule,
+          id: rule.id.startsWith("new-") ? "" : rule.id,
+          group: groupId(rule.group),
+        })),
+      );
+      await mutateProfiles();
+      showNotice("success", t("Network Rules Saved"));
+      setOpen(false);
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    } finally {
+      setSaving(false);
+    }
+  });
+
+  return (
+    <Dialog open={open} onOpenChange={setOpen}>
+      <DialogContent className="sm:max-w-2xl">
+        <DialogHeader>
+          <DialogTitle>{t("Network Rules")}</DialogTitle>
+        </DialogHeader>
+
+        <div className="max-h-[65vh] overflow-y-auto -mx-6 px-6 space-y-4">
+          <div className="flex items-center justify-between">
+            <Label htmlFor="network-auto-switch">
+              {t("Network Auto Switch")}
+            </Label>
+            <Switch
+              id="network-auto-switch"
+              checked={verge?.enable_network_auto_switch ?? false}
+              onCheckedChange={(checked) =>
+                patchVerge({ enable_network_auto_switch: checked })
+              }
+            />
+          </div>
+
+          <Separator />
+
+          <div className="space-y-3">
+            <div className="flex items-center justify-between">
+              <h4 className="font-semibold">{t("Profile Groups")}</h4>
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={() =>
+                  setGroups((list) => [
+                    ...list,
+                    { id: newKey(), name: "", items: [] },
+                  ])
+                }
+              >
+                <Plus className="h-4 w-4" />
+                {t("Add Group")}
+              </Button>
+            </div>
+            {groups.map((group, index) => (
+              <div key={group.id} className="rounded-md border p-3 space-y-2">
+                <div className="flex items-center gap-2">
+                  <Input
+                    value={group.name}
+                    placeholder={t("Group Name")}
+                    onChange={(e) =>
+                      updateGroup(index, { name: e.target.value })
+                    }
+                  />
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    onClick={() =>
+                      setGroups((list) => list.filter((_, i) => i !== index))
+                    }
+                  >
+                    <Trash2 className="h-4 w-4" />
+                  </Button>
+                </div>
+                <div className="flex flex-wrap gap-1">
+                  {profileItems.map((item) => (
+                    <Button
+                      key={item.uid}
+                      variant="outline"
+                      size="sm"
+                      className={cn(
+                        "h-7 text-xs",
+                        group.items.includes(item.uid) &&
+                          "border-primary text-primary",
+                      )}
+                      onClick={() => toggleGroupItem(index, item.uid)}
+                    >
+                      {item.name || item.uid}
+                    </Button>
+                  ))}
+                </div>
+              </div>
+            ))}
+          </div>
+
+          <Separator />
+
+          <div className="space-y-3">
+            <div className="flex items-center justify-between">
+              <h4 className="font-semibold">{t("Network Rules")}</h4>
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={() =>
+                  setRules((list) => [...list, { id: newKey(), enabled: true }])
+                }
+              >
+                <Plus className="h-4 w-4" />
+                {t("Add Rule")}
+              </Button>
+            </div>
+            {rules.map((rule, index) => (
+              <div key={rule.id} className="rounded-md border p-3 space-y-3">
+                <div className="flex items-center gap-2">
+                  <Switch
+                    checked={rule.enabled ?? true}
+                    onCheckedChange={(enabled) =>
+                      updateRule(index, { enabled })
+                    }
+                  />
+                  <Input
+                    value={rule.name ?? ""}
+                    placeholder={t("Rule Name")}
+                    onChange={(e) =>
+                      updateRule(index, { name: e.target.value })
+                    }
+                  />
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    title={t("Use Current Network")}
+                    onClick={() => onUseCurrent(index)}
+                  >
+                    <Wifi className="h-4 w-4" />
+                  </Button>
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    onClick={() =>
+                      setRules((list) => list.filter((_, i) => i !== index))
+                    }
+                  >
+                    <Trash2 className="h-4 w-4" />
+                  </Button>
+                </div>
+
+                <div className="grid gap-2 sm:grid-cols-2">
+                  {CONDITIONS.map(({ key, label, placeholder }) => (
+                    <div key={key} className="grid gap-1">
+                      <Label className="text-xs">{t(label)}</Label>
+                      <Input
+                        value={rule[key] ?? ""}
+                        placeholder={placeholder}
+                        onChange={(e) =>
+                          updateRule(index, { [key]: e.target.value })
+                        }
+                      />
+                    </div>
+                  ))}
+                </div>
+
+                <div className="grid gap-2 sm:grid-cols-3">
+                  <div className="grid gap-1">
+                    <Label className="text-xs">{t("Profile")}</Label>
+                    <Select
+                      value={rule.profile ?? NONE}
+                      onValueChange={(value) =>
+                        updateRule(index, {
+                          profile: value === NONE ? undefined : value,
+                        })
+                      }
+                    >
+                      <SelectTrigger>
+                        <SelectValue />
+                      </SelectTrigger>
+                      <SelectContent>
+                        <SelectItem value={NONE}>{t("None")}</SelectItem>
+                        {profileItems.map((item) => (
+                          <SelectItem key={item.uid} value={item.uid}>
+                            {item.name || item.uid}
+                          </SelectItem>
+                        ))}
+                      </SelectContent>
+                    </Select>
+                  </div>
+                  <div className="grid gap-1">
+                    <Label className="text-xs">{t("Profile Group")}</Label>
+                    <Select
+                      value={rule.group ?? NONE}
+                      onValueChange={(value) =>
+                        updateRule(index, {
+                          group: value === NONE ? undefined : value,
+                        })
+                      }
+                    >
+                      <SelectTrigger>
+                        <SelectValue />
+                      </SelectTrigger>
+                      <SelectContent>
+                        <SelectItem value={NONE}>{t("None")}</SelectItem>
+                        {groups.map((group) => (
+                          <SelectItem key={group.id} value={group.id}>
+                            {group.name || t("Group Name")}
+                          </SelectItem>
+                        ))}
+                      </SelectContent>
+                    </Select>
+                  </div>
+                  <div className="grid gap-1">
+                    <Label className="text-xs">{t("Mode")}</Label>
+                    <Select
+                      value={rule.mode ?? NONE}
+                      onValueChange={(value) =>
+                        updateRule(index, {
+                          mode:
+                            value === NONE
+                              ? undefined
+                              : (value as INetworkRule["mode"]),
+                        })
+                      }
+                    >
+                      <SelectTrigger>
+                        <SelectValue />
+                      </SelectTrigger>
+                      <SelectContent>
+                        <SelectItem value={NONE}>{t("None")}</SelectItem>
+                        <SelectItem value="rule">{t("rule")}</SelectItem>
+                        <SelectItem value="global">{t("global")}</SelectItem>
+                        <SelectItem value="direct">{t("direct")}</SelectItem>
+                      </SelectContent>
+                    </Select>
+                  </div>
+                </div>
+              </div>
+            ))}
+          </div>
+        </div>
+
+        <DialogFooter>
+          <DialogClose asChild>
+            <Button type="button" variant="outline">
+              {t("Cancel")}
+            </Button>
+          </DialogClose>
+          <Button onClick={onSave} disabled={saving}>
+            {saving && <Loader2 className="mr-2 h-4 w-4 animate-spin" />}
+            {t("Save")}
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+});
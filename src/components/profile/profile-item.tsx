@@ -21,6 +21,7 @@ import parseTraffic from "@/utils/parse-traffic";
 import { ConfirmViewer } from "@/components/profile/confirm-viewer";
 import { open } from "@tauri-apps/plugin-shell";
 import { ProxiesEditorViewer } from "./proxies-editor-viewer";
+import { UsageHistoryViewer } from "./usage-history-viewer";
 import { cn } from "@root/lib/utils";
 
 import { Card } from "@/components/ui/card";
@@ -67,6 +68,7 @@ import {
   Infinity,
   RefreshCw,
   Network,
+  TrendingUp,
 } from "lucide-react";
 import { t } from "i18next";
 
@@ -180,6 +182,7 @@ export const ProfileItem = (props: Props) => {
   const [proxiesOpen, setProxiesOpen] = useState(false);
   const [groupsOpen, setGroupsOpen] = useState(false);
   const [confirmOpen, setConfirmOpen] = useState(false);
+  const [usageOpen, setUsageOpen] = useState(false);
 
   const onOpenHome = () => open(home ?? "");
   const onEditInfo = onEdit;
@@ -188,6 +191,7 @@ export const ProfileItem = (props: Props) => {
   const onEditProxies = () => setProxiesOpen(true);
   const onEditGroups = () => setGroupsOpen(true);
   const onForceSelect = () => onSelect(true);
+  const onShowUsage = () => setUsageOpen(true);
 
   const onOpenFile = useLockFn(async () => {
     try {
@@ -269,6 +273,12 @@ export const ProfileItem = (props: Props) => {
     { label: "Edit Info", handler: onEditInfo, icon: Edit3 },
     { label: "Edit File", handler: onEditFile, icon: FileTextIcon },
     { label: "Open File", handler: onOpenFile, icon: FolderOpen },
+    {
+      label: "Usage History",
+      handler: onShowUsage,
+      disabled: !hasUrl,
+      icon: TrendingUp,
+    },
   ];
 
   const editMenuItems: MenuItemAction[] = [
@@ -507,6 +517,15 @@ export const ProfileItem = (props: Props) => {
         />
       )}
 
+      {usageOpen && (
+        <UsageHistoryViewer
+          open={true}
+          uid={uid}
+          name={name}
+          onClose={() => setUsageOpen(false)}
+        />
+      )}
+
       <ConfirmViewer
         open={confirmOpen}
         onOpenChange={setConfirmOpen}
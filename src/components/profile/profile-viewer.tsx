@@ -15,6 +15,8 @@ import {
   enhanceProfiles,
   createProfileFromShareLink,
   getProfiles,
+  getProfileAuth,
+  patchProfileAuth,
 } from "@/services/cmds";
 import { useProfiles } from "@/hooks/use-profiles";
 import { showNotice } from "@/services/noticeService";
@@ -56,6 +58,22 @@ interface Props {
   onChange: (isActivating?: boolean) => void;
 }
 
+// Заголовки редактируются построчно в виде "Name: value"
+const parseHeaders = (text: string) => {
+  const headers: Record<string, string> = {};
+  for (const line of text.split("\n")) {
+    const index = line.indexOf(":");
+    const key = line.slice(0, index).trim();
+    if (index > 0 && key) headers[key] = line.slice(index + 1).trim();
+  }
+  return Object.keys(headers).length > 0 ? headers : undefined;
+};
+
+const formatHeaders = (headers?: Record<string, string>) =>
+  Object.entries(headers ?? {})
+    .map(([key, value]) => `${key}: ${value}`)
+    .join("\n");
+
 export interface ProfileViewerRef {
   create: () => void;
   edit: (item: IProfileItem) => void;
@@ -70,6 +88,8 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
     const fileDataRef = useRef<string | null>(null);
 
     const [showAdvanced, setShowAdvanced] = useState(false);
+    const [auth, setAuth] = useState<IProfileAuth>({});
+    const [headersText, setHeadersText] = useState("");
     const [importUrl, setImportUrl] = useState("");
     const [isUrlValid, setIsUrlValid] = useState(true);
     const [isCheckingUrl, setIsCheckingUrl] = useState(false);
@@ -108,6 +128,8 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
         });
         fileDataRef.current = null;
         setImportUrl("");
+        setAuth({});
+        setHeadersText("");
         setShowAdvanced(false);
         setOpenType("new");
         setOpen(true);
@@ -115,6 +137,17 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
       edit: (item) => {
         reset(item);
         fileDataRef.current = null;
+        setAuth({});
+        setHeadersText("");
+        // В списке профилей auth зашифрован, читаем его отдельно
+        if (item.type === "remote" && item.uid) {
+          getProfileAuth(item.uid)
+            .then((value) => {
+              setAuth(value ?? {});
+              setHeadersText(formatHeaders(value?.headers));
+            })
+            .catch(() => {});
+        }
         setImportUrl(item.url || "");
         setShowAdvanced(true);
         setOpenType("edit");
@@ -171,15 +204,9 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
       } catch (err: any) {
         const errorMessage =
           typeof err === "string" ? err : err.message || String(err);
-        const lowerErrorMessage = errorMessage.toLowerCase();
-        if (
-          lowerErrorMessage.includes("device") ||
-          lowerErrorMessage.includes("устройств")
-        ) {
-          window.dispatchEvent(
-            new CustomEvent("show-hwid-error", { detail: errorMessage }),
-          );
-        } else if (!isShareLink && errorMessage.includes("failed to fetch")) {
+        // Диалог лимита устройств открывает уведомление бэкенда
+        if (errorMessage === "profile::device_limit") return;
+        if (!isShareLink && errorMessage.includes("failed to fetch")) {
           showNotice("info", t("Import failed, retrying with Clash proxy..."));
           try {
             await importProfile(importUrl, {
@@ -209,6 +236,17 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
       if (text) setImportUrl(text);
     };
 
+    const buildAuth = (): IProfileAuth | null => {
+      const value: IProfileAuth = {
+        headers: parseHeaders(headersText),
+        username: auth.username?.trim() || undefined,
+        password: auth.password || undefined,
+        bearer_token: auth.bearer_token?.trim() || undefined,
+      };
+      const isEmpty = Object.values(value).every((v) => v === undefined);
+      return isEmpty ? null : value;
+    };
+
     const handleSaveAdvanced = useLockFn(
       handleSubmit(async (formData) => {
         const form = { ...formData, url: formData.url || importUrl } as Partial<IProfileItem>;
@@ -231,6 +269,16 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
             if (typeof option.user_agent === "string" && option.user_agent.trim() === "") {
               delete (option as any).user_agent;
             }
+            // auth сохраняется через patchProfileAuth
+            delete option.auth;
+            if (form.type === "remote" && openType === "new") {
+              option.auth = buildAuth() ?? undefined;
+            }
+            if (option.mirrors) {
+              option.mirrors = option.mirrors
+                .map((mirror) => mirror.trim())
+                .filter(Boolean);
+            }
           }
 
           const providedName = (form as any).name && String((form as any).name).trim();
@@ -281,6 +329,9 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
           } else {
             if (!form.uid) throw new Error("UID not found");
             await patchProfile(form.uid as string, item);
+            if (form.type === "remote") {
+              await patchProfileAuth(form.uid as string, buildAuth());
+            }
             showNotice("success", t("Profile Updated Successfully"));
           }
 
@@ -540,6 +591,92 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
                         </FormItem>
                       )}
                     />
+                    <FormField
+                      control={control}
+                      name="option.mirrors"
+                      render={({ field }) => (
+                        <FormItem>
+                          <FormLabel>{t("Mirror URLs")}</FormLabel>
+                          <FormControl>
+                            <Textarea
+                              rows={2}
+                              placeholder={t("One URL per line")}
+                              value={(field.value ?? []).join("\n")}
+                              onChange={(e) =>
+                                field.onChange(e.target.value.split("\n"))
+                              }
+                            />
+                          </FormControl>
+                        </FormItem>
+                      )}
+                    />
+                    <FormField
+                      control={control}
+                      name="option.mirror_race"
+                      render={({ field }) => (
+                        <FormItem className="flex items-center justify-between">
+                          <FormLabel>{t("Race Mirrors")}</FormLabel>
+                          <FormControl>
+                            <Switch
+                              checked={field.value ?? false}
+                              onCheckedChange={field.onChange}
+                            />
+                          </FormControl>
+                        </FormItem>
+                      )}
+                    />
+                    <div className="space-y-2">
+                      <Label htmlFor="profile-auth-headers">
+                        {t("Request Headers")}
+                      </Label>
+                      <Textarea
+                        id="profile-auth-headers"
+                        rows={2}
+                        placeholder="X-Token: value"
+                        value={headersText}
+                        onChange={(e) => setHeadersText(e.target.value)}
+                      />
+                    </div>
+                    <div className="grid grid-cols-2 gap-2">
+                      <div className="space-y-2">
+                        <Label htmlFor="profile-auth-username">
+                          {t("Username")}
+                        </Label>
+                        <Input
+                          id="profile-auth-username"
+                          value={auth.username ?? ""}
+                          onChange={(e) =>
+                            setAuth({ ...auth, username: e.target.value })
+                          }
+                        />
+                      </div>
+                      <div className="space-y-2">
+                        <Label htmlFor="profile-auth-password">
+                          {t("Password")}
+                        </Label>
+                        <Input
+                          id="profile-auth-password"
+                          type="password"
+                          value={auth.password ?? ""}
+                          onChange={(e) =>
+                            setAuth({ ...auth, password: e.target.value })
+                          }
+                        />
+                      </div>
+                    </div>
+                    <div className="space-y-2">
+                      <Label htmlFor="profile-auth-token">
+                        {t("Bearer Token")}
+                      </Label>
+                      <Input
+                        id="profile-auth-token"
+                        type="password"
+                        value={auth.bearer_token ?? ""}
+                        onChange={(e) =>
+                          setAuth({ ...auth, bearer_token: e.target.value })
+                        }
+                      />
+                    </div>
                     <FormField
                       control={control}
                       name="option.update_always"
@@ -0,0 +1,137 @@
+import dayjs from "dayjs";
+import { useMemo } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+import { Line } from "react-chartjs-2";
+import {
+  Chart as ChartJS,
+  CategoryScale,
+  LinearScale,
+  PointElement,
+  LineElement,
+  Tooltip,
+  Filler,
+} from "chart.js";
+import { getProfileUsageHistory } from "@/services/cmds";
+import parseTraffic from "@/utils/parse-traffic";
+
+import { BaseEmpty } from "@/components/base";
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+
+ChartJS.register(
+  CategoryScale,
+  LinearScale,
+  PointElement,
+  LineElement,
+  Tooltip,
+  Filler,
+);
+
+interface Props {
+  open: boolean;
+  uid: string;
+  name: string;
+  onClose: () => void;
+}
+
+const formatTraffic = (value: number) => parseTraffic(value).join(" ");
+
+export const UsageHistoryViewer = (props: Props) => {
+  const { open, uid, name, onClose } = props;
+  const { t } = useTranslation();
+
+  const { data: samples = [] } = useSWR(
+    open ? ["getProfileUsageHistory", uid] : null,
+    () => getProfileUsageHistory(uid),
+  );
+
+  const last = samples[samples.length - 1];
+
+  // Canvas не понимает CSS-переменные, берём вычисленное значение
+  const color = useMemo(
+    () =>
+      getComputedStyle(document.documentElement)
+        .getPropertyValue("--primary")
+        .trim() || "#3b82f6",
+    [open],
+  );
+
+  const data = useMemo(
+    () => ({
+      labels: samples.map((item) =>
+        dayjs(item.timestamp * 1000).format("MM-DD HH:mm"),
+      ),
+      datasets: [
+        {
+          label: t("Used"),
+          data: samples.map((item) => item.upload + item.download),
+          borderColor: color,
+          backgroundColor: color,
+          fill: false,
+          tension: 0.2,
+          pointRadius: samples.length > 50 ? 0 : 2,
+        },
+      ],
+    }),
+    [samples, color, t],
+  );
+
+  const options = useMemo(
+    () => ({
+      responsive: true,
+      maintainAspectRatio: false,
+      animation: false as const,
+      scales: {
+        y: {
+          beginAtZero: true,
+          suggestedMax: last?.total || undefined,
+          ticks: {
+            callback: (value: string | number) => formatTraffic(+value),
+          },
+        },
+        x: { ticks: { maxTicksLimit: 6 } },
+      },
+      plugins: {
+        tooltip: {
+          callbacks: {
+            label: (ctx: { parsed: { y: number } }) =>
+              formatTraffic(ctx.parsed.y),
+          },
+        },
+      },
+    }),
+    [last?.total],
+  );
+
+  return (
+    <Dialog open={open} onOpenChange={(value) => !value && onClose()}>
+      <DialogContent className="sm:max-w-2xl">
+        <DialogHeader>
+          <DialogTitle className="truncate">
+            {t("Usage History")}: {name}
+          </DialogTitle>
+          {last && (
+            <DialogDescription>
+              {formatTraffic(last.upload + last.download)}
+              {last.total > 0 && ` / ${formatTraffic(last.total)}`}
+            </DialogDescription>
+          )}
+        </DialogHeader>
+
+        <div className="h-64">
+          {samples.length === 0 ? (
+            <BaseEmpty text="No Usage History" />
+          ) : (
+            <Line data={data} options={options} />
+          )}
+        </div>
+      </DialogContent>
+    </Dialog>
+  );
+};
@@ -20,7 +20,11 @@ import { cn } from "@root/lib/utils";
 
 // Логика и сервисы
 import { useVerge } from "@/hooks/use-verge";
-import { changeClashCore, restartCore } from "@/services/cmds";
+import {
+  changeClashCore,
+  getCoreCapabilityState,
+  restartCore,
+} from "@/services/cmds";
 import { closeAllConnections, upgradeCore } from "@/services/api";
 import { showNotice } from "@/services/noticeService";
 
@@ -87,6 +91,14 @@ export const ClashCoreViewer = forwardRef<DialogRef>((props, ref) => {
       setUpgrading(true);
       await upgradeCore();
       showNotice("success", t(`Core Version Updated`));
+      // 升级会替换 core 副本，capabilities 随之丢失
+      if (verge?.enable_core_capabilities) {
+        const capState = await getCoreCapabilityState();
+        mutate("getCoreCapabilityState", capState, false);
+        if (capState !== "granted") {
+          showNotice("error", t("Core Capabilities Lost"));
+        }
+      }
     } catch (err: any) {
       const errMsg = err.response?.data?.message || err.toString();
       const showMsg = errMsg.includes("already using latest version")
@@ -4,6 +4,7 @@ import { useLockFn, useRequest } from "ahooks";
 import { useClashInfo } from "@/hooks/use-clash";
 import { useVerge } from "@/hooks/use-verge";
 import { showNotice } from "@/services/noticeService";
+import { getTransparentProxyScript } from "@/services/cmds";
 import getSystem from "@/utils/get-system";
 
 // Новые импорты
@@ -24,7 +25,7 @@ import {
   TooltipProvider,
   TooltipTrigger,
 } from "@/components/ui/tooltip";
-import { Shuffle, Loader2 } from "lucide-react";
+import { Shuffle, Loader2, FileText } from "lucide-react";
 
 const OS = getSystem();
 
@@ -121,6 +122,9 @@ export const ClashPortViewer = forwardRef<ClashPortViewerRef>((props, ref) => {
   const [redirEnabled, setRedirEnabled] = useState(false);
   const [tproxyPort, setTproxyPort] = useState(0);
   const [tproxyEnabled, setTproxyEnabled] = useState(false);
+  const [transparentProxy, setTransparentProxy] = useState(false);
+  const [autoReassign, setAutoReassign] = useState(false);
+  const [rulesPreview, setRulesPreview] = useState<string | null>(null);
 
   const { loading, run: saveSettings } = useRequest(
     async (params: { clashConfig: any; vergeConfig: any }) => {
@@ -150,6 +154,9 @@ export const ClashPortViewer = forwardRef<ClashPortViewerRef>((props, ref) => {
       setRedirEnabled(verge?.verge_redir_enabled ?? false);
       setTproxyPort(verge?.verge_tproxy_port ?? 7894);
       setTproxyEnabled(verge?.verge_tproxy_enabled ?? false);
+      setTransparentProxy(verge?.enable_transparent_proxy ?? false);
+      setAutoReassign(verge?.auto_reassign_ports ?? false);
+      setRulesPreview(null);
       setOpen(true);
     },
     close: () => setOpen(false),
@@ -193,11 +200,22 @@ export const ClashPortViewer = forwardRef<ClashPortViewerRef>((props, ref) => {
       verge_redir_enabled: redirEnabled,
       verge_tproxy_port: tproxyPort,
       verge_tproxy_enabled: tproxyEnabled,
+      auto_reassign_ports: autoReassign,
+      ...(OS === "linux" && { enable_transparent_proxy: transparentProxy }),
     };
 
     await saveSettings({ clashConfig, vergeConfig });
   });
 
+  // 预览当前已保存配置对应的规则
+  const onPreviewRules = useLockFn(async () => {
+    try {
+      setRulesPreview(await getTransparentProxyScript());
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
   return (
     <Dialog open={open} onOpenChange={setOpen}>
       <DialogContent className="sm:max-w-md">
@@ -245,6 +263,45 @@ export const ClashPortViewer = forwardRef<ClashPortViewerRef>((props, ref) => {
               setIsEnabled={setTproxyEnabled}
             />
           )}
+          {OS === "linux" && (
+            <div className="flex items-center justify-between py-2">
+              <p className="text-sm font-medium">{t("Transparent Proxy")}</p>
+              <div className="flex items-center gap-2">
+                <TooltipProvider delayDuration={100}>
+                  <Tooltip>
+                    <TooltipTrigger asChild>
+                      <Button
+                        type="button"
+                        variant="ghost"
+                        size="icon"
+                        className="h-8 w-8"
+                        onClick={onPreviewRules}
+                      >
+                        <FileText className="h-4 w-4" />
+                      </Button>
+                    </TooltipTrigger>
+                    <TooltipContent>
+                      <p>{t("Preview Transparent Proxy Rules")}</p>
+                    </TooltipContent>
+                  </Tooltip>
+                </TooltipProvider>
+                <Switch
+                  checked={transparentProxy}
+                  onCheckedChange={setTransparentProxy}
+                  disabled={!redirEnabled && !tproxyEnabled}
+                />
+              </div>
+            </div>
+          )}
+          <div className="flex items-center justify-between py-2">
+            <p className="text-sm font-medium">{t("Auto Reassign Ports")}</p>
+            <Switch checked={autoReassign} onCheckedChange={setAutoReassign} />
+          </div>
+          {rulesPreview && (
+            <pre className="max-h-48 overflow-auto rounded-md bg-muted p-2 text-xs">
+              {rulesPreview}
+            </pre>
+          )}
         </div>
 
         <DialogFooter>
@@ -0,0 +1,135 @@
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useLockFn } from "ahooks";
+import { useTranslation } from "react-i18next";
+import { writeText } from "@tauri-apps/plugin-clipboard-manager";
+import { useVerge } from "@/hooks/use-verge";
+import { regenerateControlApiToken } from "@/services/cmds";
+import { showNotice } from "@/services/noticeService";
+
+import { DialogRef, Switch } from "@/components/base";
+import { TooltipIcon } from "@/components/base/base-tooltip-icon";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogHeader,
+  DialogTitle,
+  DialogFooter,
+  DialogClose,
+} from "@/components/ui/dialog";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { Copy, RefreshCw } from "lucide-react";
+
+// Адрес API совпадает с портом встроенного сервера
+const API_PORT = import.meta.env.DEV ? 11233 : 33331;
+const API_URL = `http://127.0.0.1:${API_PORT}/api`;
+
+export const ControlApiViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge, mutateVerge } = useVerge();
+  const [open, setOpen] = useState(false);
+
+  const enabled = verge?.enable_control_api ?? false;
+  const token = verge?.control_api_token ?? "";
+
+  useImperativeHandle(ref, () => ({
+    open: () => setOpen(true),
+    close: () => setOpen(false),
+  }));
+
+  const onRegenerate = useLockFn(async () => {
+    try {
+      await regenerateControlApiToken();
+      mutateVerge();
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
+  const onToggle = useLockFn(async (enable: boolean) => {
+    try {
+      await patchVerge({ enable_control_api: enable });
+      // Токен создаётся при первом включении
+      if (enable && !token) {
+        await regenerateControlApiToken();
+        mutateVerge();
+      }
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
+  const onCopy = useLockFn(async (text: string) => {
+    await writeText(text);
+    showNotice("success", t("Copy Success"), 1000);
+  });
+
+  return (
+    <Dialog open={open} onOpenChange={setOpen}>
+      <DialogContent className="sm:max-w-lg">
+        <DialogHeader>
+          <DialogTitle>{t("Control API")}</DialogTitle>
+        </DialogHeader>
+
+        <div className="py-4 space-y-4">
+          <div className="flex items-center justify-between">
+            <div className="flex items-center gap-2">
+              <Label>{t("Enable Control API")}</Label>
+              <TooltipIcon tooltip={t("Control API Info")} />
+            </div>
+            <Switch checked={enabled} onCheckedChange={onToggle} />
+          </div>
+
+          <div className="grid gap-2">
+            <Label>{t("Address")}</Label>
+            <div className="flex gap-2">
+              <Input readOnly value={API_URL} className="font-mono" />
+              <Button
+                variant="outline"
+                size="icon"
+                onClick={() => onCopy(API_URL)}
+              >
+                <Copy className="h-4 w-4" />
+              </Button>
+            </div>
+          </div>
+
+          <div className="grid gap-2">
+            <Label>{t("Access Token")}</Label>
+            <div className="flex gap-2">
+              <Input
+                readOnly
+                type="password"
+                value={token}
+                className="font-mono"
+              />
+              <Button
+                variant="outline"
+                size="icon"
+                disabled={!token}
+                onClick={() => onCopy(token)}
+              >
+                <Copy className="h-4 w-4" />
+              </Button>
+              <Button variant="outline" size="icon" onClick={onRegenerate}>
+                <RefreshCw className="h-4 w-4" />
+              </Button>
+            </div>
+            <p className="text-xs text-muted-foreground font-mono">
+              curl -H "Authorization: Bearer $TOKEN" {API_URL}/status
+            </p>
+          </div>
+        </div>
+
+        <DialogFooter>
+          <DialogClose asChild>
+            <Button type="button" variant="outline">
+              {t("Close")}
+            </Button>
+          </DialogClose>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+});
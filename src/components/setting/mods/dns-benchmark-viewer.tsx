@@ -0,0 +1,190 @@
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useLockFn } from "ahooks";
+import { useTranslation } from "react-i18next";
+import { applyFastestDns, benchmarkDns } from "@/services/cmds";
+import { showNotice } from "@/services/noticeService";
+
+import { DialogRef, Switch } from "@/components/base";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogHeader,
+  DialogTitle,
+  DialogFooter,
+  DialogClose,
+} from "@/components/ui/dialog";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Table,
+  TableBody,
+  TableCell,
+  TableHead,
+  TableHeader,
+  TableRow,
+} from "@/components/ui/table";
+import { Gauge, Loader2, Zap } from "lucide-react";
+
+const parseList = (value: string) =>
+  value
+    .split(",")
+    .map((item) => item.trim())
+    .filter(Boolean);
+
+const formatMs = (value?: number) => (value === undefined ? "-" : `${value}ms`);
+
+export const DnsBenchmarkViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const [open, setOpen] = useState(false);
+  const [running, setRunning] = useState(false);
+  const [nameservers, setNameservers] = useState("");
+  const [domains, setDomains] = useState("");
+  const [viaProxy, setViaProxy] = useState(true);
+  const [report, setReport] = useState<IDnsBenchmarkReport | null>(null);
+
+  useImperativeHandle(ref, () => ({
+    open: () => setOpen(true),
+    close: () => setOpen(false),
+  }));
+
+  const onRun = useLockFn(async () => {
+    setRunning(true);
+    try {
+      const result = await benchmarkDns({
+        nameservers: parseList(nameservers),
+        domains: parseList(domains),
+        via_proxy: viaProxy,
+      });
+      setReport(result);
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    } finally {
+      setRunning(false);
+    }
+  });
+
+  const onApply = useLockFn(async () => {
+    if (!report) return;
+    try {
+      const fastest = await applyFastestDns(report);
+      showNotice("success", `${t("Nameserver")}: ${fastest.join(", ")}`);
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
+  return (
+    <Dialog open={open} onOpenChange={setOpen}>
+      <DialogContent className="max-w-3xl max-h-[90vh] flex flex-col">
+        <DialogHeader>
+          <DialogTitle>{t("DNS Benchmark")}</DialogTitle>
+        </DialogHeader>
+
+        <div className="space-y-3">
+          <div className="grid gap-2">
+            <Label htmlFor="dns-benchmark-servers">{t("Nameserver")}</Label>
+            <Input
+              id="dns-benchmark-servers"
+              value={nameservers}
+              placeholder={t("DNS Benchmark Servers Placeholder")}
+              onChange={(e) => setNameservers(e.target.value)}
+            />
+          </div>
+          <div className="grid gap-2">
+            <Label htmlFor="dns-benchmark-domains">
+              {t("DNS Benchmark Domains")}
+            </Label>
+            <Input
+              id="dns-benchmark-domains"
+              value={domains}
+              placeholder="www.google.com, github.com"
+              onChange={(e) => setDomains(e.target.value)}
+            />
+          </div>
+          <div className="flex items-center justify-between">
+            <Label>{t("Also Test Through Proxy")}</Label>
+            <Switch checked={viaProxy} onCheckedChange={setViaProxy} />
+          </div>
+        </div>
+
+        <div className="flex-1 min-h-0 overflow-y-auto">
+          {report && (
+            <Table>
+              <TableHeader>
+                <TableRow>
+                  <TableHead>{t("Nameserver")}</TableHead>
+                  <TableHead>{t("Route")}</TableHead>
+                  <TableHead>{t("Median")}</TableHead>
+                  <TableHead>P95</TableHead>
+                  <TableHead>{t("Failure Rate")}</TableHead>
+                  <TableHead>{t("Poisoned")}</TableHead>
+                </TableRow>
+              </TableHeader>
+              <TableBody>
+                {report.results.map((result) => (
+                  <TableRow key={`${result.nameserver}-${result.route}`}>
+                    <TableCell className="max-w-56 truncate">
+                      {result.nameserver}
+                    </TableCell>
+                    <TableCell>
+                      {t(result.route === "proxy" ? "Proxy" : "Direct")}
+                    </TableCell>
+                    {result.error ? (
+                      <TableCell colSpan={4} className="text-muted-foreground">
+                        {result.error}
+                      </TableCell>
+                    ) : (
+                      <>
+                        <TableCell>{formatMs(result.median_ms)}</TableCell>
+                        <TableCell>{formatMs(result.p95_ms)}</TableCell>
+                        <TableCell>
+                          {Math.round(result.failure_rate * 100)}%
+                        </TableCell>
+                        <TableCell
+                          className={
+                            result.poisoned.length > 0 ? "text-destructive" : ""
+                          }
+                          title={result.mismatched.join(", ")}
+                        >
+                          {result.poisoned.length > 0
+                            ? result.poisoned.join(", ")
+                            : "-"}
+                        </TableCell>
+                      </>
+                    )}
+                  </TableRow>
+                ))}
+              </TableBody>
+            </Table>
+          )}
+        </div>
+
+        <DialogFooter>
+          <DialogClose asChild>
+            <Button type="button" variant="outline">
+              {t("Close")}
+            </Button>
+          </DialogClose>
+          <Button
+            type="button"
+            variant="secondary"
+            disabled={!report || running}
+            onClick={onApply}
+          >
+            <Zap className="mr-2 h-4 w-4" />
+            {t("Apply Fastest")}
+          </Button>
+          <Button type="button" disabled={running} onClick={onRun}>
+            {running ? (
+              <Loader2 className="mr-2 h-4 w-4 animate-spin" />
+            ) : (
+              <Gauge className="mr-2 h-4 w-4" />
+            )}
+            {t("Run Benchmark")}
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+});
@@ -9,6 +9,11 @@ import { invoke } from "@tauri-apps/api/core";
 import { DialogRef } from "@/components/base";
 import { useThemeMode } from "@/services/states";
 import { showNotice } from "@/services/noticeService";
+import {
+  flushFakeIpCache,
+  importSystemHosts,
+  queryDns,
+} from "@/services/cmds";
 import getSystem from "@/utils/get-system";
 import { Button } from "@/components/ui/button";
 import {
@@ -32,14 +37,23 @@ import {
 import { Textarea } from "@/components/ui/textarea";
 import { Alert, AlertDescription, AlertTitle } from "@/components/ui/alert";
 import { Separator } from "@/components/ui/separator";
-import { AlertTriangle, RotateCcw, Code } from "lucide-react";
+import {
+  AlertTriangle,
+  RotateCcw,
+  Code,
+  FileInput,
+  Eraser,
+  Search,
+} from "lucide-react";
+
+const DNS_QUERY_TYPES = ["A", "AAAA", "CNAME", "MX", "TXT", "HTTPS"];
 
 const DEFAULT_DNS_CONFIG = {
   enable: true,
   listen: ":53",
   "enhanced-mode": "fake-ip" as "fake-ip" | "redir-host",
   "fake-ip-range": "198.18.0.1/16",
-  "fake-ip-filter-mode": "blacklist" as "blacklist" | "whitelist",
+  "fake-ip-filter-mode": "blacklist" as "blacklist" | "whitelist" | "rule",
   "prefer-h3": false,
   "respect-rules": false,
   "use-hosts": false,
@@ -120,7 +134,7 @@ const formatValues = (config: any = {}): any => {
     ? enhancedMode
     : DEFAULT_DNS_CONFIG["enhanced-mode"];
   const fakeIpFilterMode = dnsConfig["fake-ip-filter-mode"];
-  const validFakeIpFilterMode = ["blacklist", "whitelist"].includes(
+  const validFakeIpFilterMode = ["blacklist", "whitelist", "rule"].includes(
     fakeIpFilterMode,
   )
     ? fakeIpFilterMode
@@ -189,6 +203,9 @@ export const DnsViewer = forwardRef<DialogRef, Props>(({ onSave }, ref) => {
   );
   const [yamlContent, setYamlContent] = useState("");
   const [prevData, setPrevData] = useState("");
+  const [queryName, setQueryName] = useState("");
+  const [queryType, setQueryType] = useState("A");
+  const [queryResult, setQueryResult] = useState("");
 
   const parseList = (str: string = ""): string[] =>
     str
@@ -306,6 +323,59 @@ export const DnsViewer = forwardRef<DialogRef, Props>(({ onSave }, ref) => {
     }
   };
 
+  const onImportHosts = useLockFn(async () => {
+    try {
+      let report = await importSystemHosts(false);
+      if (report.conflicts.length > 0) {
+        const list = report.conflicts
+          .map(
+            (c) =>
+              `${c.domain}: ${c.current.join(", ")} → ${c.system.join(", ")}`,
+          )
+          .join("\n");
+        if (window.confirm(`${t("Hosts Import Conflicts")}\n\n${list}`)) {
+          report = await importSystemHosts(true);
+        }
+      }
+      await initDnsConfig();
+      showNotice(
+        "success",
+        t("Hosts Imported", {
+          imported: report.imported.length,
+          conflicts: report.conflicts.length,
+          skipped: report.skipped.length,
+        }),
+      );
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
+  const onFlushFakeIp = useLockFn(async () => {
+    try {
+      await flushFakeIpCache();
+      showNotice("success", t("Fake-IP Cache Flushed"));
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
+  const onQueryDns = useLockFn(async () => {
+    if (!queryName.trim()) return;
+    try {
+      const response = await queryDns(queryName.trim(), queryType);
+      const answers = response.Answer?.map((a) => `${a.data} (TTL ${a.TTL})`);
+      setQueryResult(
+        answers && answers.length > 0
+          ? answers.join("\n")
+          : JSON.stringify(response, null, 2),
+      );
+    } catch (err: any) {
+      setQueryResult("");
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
   const handleSave = useLockFn(async () => {
     try {
       let finalConfig: Record<string, any>;
@@ -322,7 +392,13 @@ export const DnsViewer = forwardRef<DialogRef, Props>(({ onSave }, ref) => {
       }
 
       const currentData = yaml.dump(finalConfig, { forceQuotes: true });
-      await invoke("save_dns_config", { dnsConfig: finalConfig });
+      // Бэкенд проверяет каждое поле и не сохраняет некорректный конфиг
+      try {
+        await invoke("save_dns_config", { dnsConfig: finalConfig });
+      } catch (err: any) {
+        showNotice("error", t("DNS configuration error") + ": " + err);
+        return;
+      }
 
       const [isValid, errorMsg] = await invoke<[boolean, string]>(
         "validate_dns_config",
@@ -467,6 +543,7 @@ export const DnsViewer = forwardRef<DialogRef, Props>(({ onSave }, ref) => {
                     <SelectContent>
                       <SelectItem value="blacklist">blacklist</SelectItem>
                       <SelectItem value="whitelist">whitelist</SelectItem>
+                      <SelectItem value="rule">rule</SelectItem>
                     </SelectContent>
                   </Select>
                 </div>
@@ -636,7 +713,18 @@ export const DnsViewer = forwardRef<DialogRef, Props>(({ onSave }, ref) => {
               <Separator />
 
               <div className="space-y-4">
-                <h4 className="font-semibold">{t("Hosts Settings")}</h4>
+                <div className="flex items-center justify-between">
+                  <h4 className="font-semibold">{t("Hosts Settings")}</h4>
+                  <Button
+                    type="button"
+                    variant="outline"
+                    size="sm"
+                    onClick={onImportHosts}
+                  >
+                    <FileInput className="mr-2 h-4 w-4" />
+                    {t("Import System Hosts")}
+                  </Button>
+                </div>
                 <div className="grid gap-2">
                   <Label htmlFor="hosts-settings">{t("Hosts")}</Label>
                   <Textarea
@@ -647,6 +735,56 @@ export const DnsViewer = forwardRef<DialogRef, Props>(({ onSave }, ref) => {
                   />
                 </div>
               </div>
+
+              <Separator />
+
+              <div className="space-y-4">
+                <div className="flex items-center justify-between">
+                  <h4 className="font-semibold">{t("DNS Debug")}</h4>
+                  <Button
+                    type="button"
+                    variant="outline"
+                    size="sm"
+                    onClick={onFlushFakeIp}
+                  >
+                    <Eraser className="mr-2 h-4 w-4" />
+                    {t("Flush Fake-IP Cache")}
+                  </Button>
+                </div>
+                <div className="flex gap-2">
+                  <Input
+                    value={queryName}
+                    placeholder="www.google.com"
+                    onChange={(e) => setQueryName(e.target.value)}
+                    onKeyDown={(e) => e.key === "Enter" && onQueryDns()}
+                  />
+                  <Select value={queryType} onValueChange={setQueryType}>
+                    <SelectTrigger className="w-28">
+                      <SelectValue />
+                    </SelectTrigger>
+                    <SelectContent>
+                      {DNS_QUERY_TYPES.map((type) => (
+                        <SelectItem key={type} value={type}>
+                          {type}
+                        </SelectItem>
+                      ))}
+                    </SelectContent>
+                  </Select>
+                  <Button
+                    type="button"
+                    variant="secondary"
+                    onClick={onQueryDns}
+                  >
+                    <Search className="mr-2 h-4 w-4" />
+                    {t("Query")}
+                  </Button>
+                </div>
+                {queryResult && (
+                  <pre className="max-h-48 overflow-auto rounded-md bg-muted p-3 text-xs">
+                    {queryResult}
+                  </pre>
+                )}
+              </div>
             </div>
           ) : (
             <div className="h-full rounded-md border">
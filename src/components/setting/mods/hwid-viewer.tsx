@@ -0,0 +1,86 @@
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { useLockFn } from "ahooks";
+import useSWR from "swr";
+import { writeText } from "@tauri-apps/plugin-clipboard-manager";
+
+import { getHwid, resetHwid } from "@/services/cmds";
+import { showNotice } from "@/services/noticeService";
+import { DialogRef } from "@/components/base";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogHeader,
+  DialogTitle,
+  DialogFooter,
+  DialogClose,
+} from "@/components/ui/dialog";
+import { Copy } from "lucide-react";
+
+export const HwidViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const [open, setOpen] = useState(false);
+
+  useImperativeHandle(ref, () => ({
+    open: () => setOpen(true),
+    close: () => setOpen(false),
+  }));
+
+  const { data: hwid, mutate } = useSWR(open ? "getHwid" : null, getHwid);
+
+  const onCopy = useLockFn(async () => {
+    if (!hwid) return;
+    await writeText(hwid);
+    showNotice("success", t("Copy Success"));
+  });
+
+  // random: новый случайный HWID, иначе возврат к ID машины
+  const onReset = useLockFn(async (random: boolean) => {
+    try {
+      await mutate(await resetHwid(random), false);
+      showNotice("success", t("HWID Reset"));
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
+  return (
+    <Dialog open={open} onOpenChange={setOpen}>
+      <DialogContent className="sm:max-w-lg">
+        <DialogHeader>
+          <DialogTitle>HWID</DialogTitle>
+          <DialogDescription>{t("HWID Info")}</DialogDescription>
+        </DialogHeader>
+
+        <div className="flex items-center gap-2 rounded-md bg-muted px-2 py-1">
+          <span className="flex-grow font-mono text-sm break-all select-text">
+            {hwid ?? "-"}
+          </span>
+          <Button
+            variant="ghost"
+            size="icon"
+            className="h-6 w-6"
+            title={t("Copy to clipboard")}
+            onClick={onCopy}
+          >
+            <Copy className="h-3.5 w-3.5" />
+          </Button>
+        </div>
+
+        <DialogFooter>
+          <Button variant="outline" onClick={() => onReset(false)}>
+            {t("Use Machine ID")}
+          </Button>
+          <Button variant="outline" onClick={() => onReset(true)}>
+            {t("Generate New HWID")}
+          </Button>
+          <DialogClose asChild>
+            <Button type="button">{t("Close")}</Button>
+          </DialogClose>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+});
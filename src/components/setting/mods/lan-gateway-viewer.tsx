@@ -0,0 +1,250 @@
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useLockFn } from "ahooks";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+import { writeText } from "@tauri-apps/plugin-clipboard-manager";
+import { useClash } from "@/hooks/use-clash";
+import { getLanGatewayInfo, getQrCodeSvg } from "@/services/cmds";
+import { showNotice } from "@/services/noticeService";
+
+import { DialogRef } from "@/components/base";
+import { TooltipIcon } from "@/components/base/base-tooltip-icon";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogHeader,
+  DialogTitle,
+  DialogFooter,
+  DialogClose,
+} from "@/components/ui/dialog";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { Textarea } from "@/components/ui/textarea";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { Copy, Loader2 } from "lucide-react";
+
+// Пустой белый список в ядре означает «разрешить всем»
+const ALLOW_ALL = ["0.0.0.0/0", "::/0"];
+
+const toLines = (list?: string[]) => (list ?? []).join("\n");
+const fromLines = (text: string) =>
+  text
+    .split(/[\n,]/)
+    .map((item) => item.trim())
+    .filter(Boolean);
+
+export const LanGatewayViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { clash, patchClash } = useClash();
+  const [open, setOpen] = useState(false);
+  const [saving, setSaving] = useState(false);
+  const [address, setAddress] = useState("");
+  const [value, setValue] = useState({
+    bind: "*",
+    allowed: "",
+    disallowed: "",
+    users: "",
+  });
+
+  const { data: info, mutate: mutateInfo } = useSWR(
+    open ? "getLanGatewayInfo" : null,
+    getLanGatewayInfo,
+  );
+
+  const host = address || info?.addresses[0] || "";
+  const proxyAddress = host ? `${host}:${info?.mixed_port}` : "";
+  const pacUrl = host ? `http://${host}:${info?.pac_port}/commands/pac` : "";
+
+  const { data: qrCode } = useSWR(
+    pacUrl ? ["getQrCodeSvg", pacUrl] : null,
+    ([, text]) => getQrCodeSvg(text),
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      const allowed = clash?.["lan-allowed-ips"] ?? [];
+      setValue({
+        bind: clash?.["bind-address"] || "*",
+        allowed: allowed.every((item) => ALLOW_ALL.includes(item))
+          ? ""
+          : toLines(allowed),
+        disallowed: toLines(clash?.["lan-disallowed-ips"]),
+        users: toLines(clash?.authentication),
+      });
+      setAddress("");
+      setOpen(true);
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onCopy = useLockFn(async (text: string) => {
+    await writeText(text);
+    showNotice("success", t("Copy Success"), 1000);
+  });
+
+  const onSave = useLockFn(async () => {
+    setSaving(true);
+    try {
+      const allowed = fromLines(value.allowed);
+      await patchClash({
+        "allow-lan": true,
+        "bind-address": value.bind.trim() || "*",
+        "lan-allowed-ips": allowed.length ? allowed : ALLOW_ALL,
+        "lan-disallowed-ips": fromLines(value.disallowed),
+        authentication: fromLines(value.users),
+      });
+      await mutateInfo();
+      showNotice("success", t("LAN Gateway Enabled"));
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    } finally {
+      setSaving(false);
+    }
+  });
+
+  return (
+    <Dialog open={open} onOpenChange={setOpen}>
+      <DialogContent className="sm:max-w-2xl">
+        <DialogHeader>
+          <DialogTitle>{t("LAN Gateway")}</DialogTitle>
+        </DialogHeader>
+
+        <div className="py-4 grid gap-6 sm:grid-cols-2">
+          <div className="space-y-4">
+            <div className="grid gap-2">
+              <div className="flex items-center gap-2">
+                <Label>{t("Bind Address")}</Label>
+                <TooltipIcon tooltip={t("Bind Address Info")} />
+              </div>
+              <Input
+                value={value.bind}
+                placeholder="*"
+                onChange={(e) =>
+                  setValue((v) => ({ ...v, bind: e.target.value }))
+                }
+              />
+            </div>
+            <div className="grid gap-2">
+              <Label>{t("LAN Allowed IPs")}</Label>
+              <Textarea
+                rows={2}
+                value={value.allowed}
+                placeholder="192.168.0.0/16"
+                onChange={(e) =>
+                  setValue((v) => ({ ...v, allowed: e.target.value }))
+                }
+              />
+            </div>
+            <div className="grid gap-2">
+              <Label>{t("LAN Disallowed IPs")}</Label>
+              <Textarea
+                rows={2}
+                value={value.disallowed}
+                placeholder="192.168.1.13/32"
+                onChange={(e) =>
+                  setValue((v) => ({ ...v, disallowed: e.target.value }))
+                }
+              />
+            </div>
+            <div className="grid gap-2">
+              <div className="flex items-center gap-2">
+                <Label>{t("Proxy Authentication")}</Label>
+                <TooltipIcon tooltip={t("Proxy Authentication Info")} />
+              </div>
+              <Textarea
+                rows={2}
+                value={value.users}
+                placeholder="user:password"
+                className="font-mono"
+                onChange={(e) =>
+                  setValue((v) => ({ ...v, users: e.target.value }))
+                }
+              />
+            </div>
+          </div>
+
+          <div className="space-y-4">
+            {!info?.allow_lan && (
+              <p className="text-sm text-muted-foreground">
+                {t("LAN Gateway Disabled Info")}
+              </p>
+            )}
+            {info?.allow_lan && !host && (
+              <p className="text-sm text-muted-foreground">
+                {t("No LAN Address")}
+              </p>
+            )}
+            {info?.allow_lan && host && (
+              <>
+                <div className="grid gap-2">
+                  <Label>{t("LAN Address")}</Label>
+                  <Select value={host} onValueChange={setAddress}>
+                    <SelectTrigger>
+                      <SelectValue />
+                    </SelectTrigger>
+                    <SelectContent>
+                      {info.addresses.map((item) => (
+                        <SelectItem key={item} value={item}>
+                          {item}
+                        </SelectItem>
+                      ))}
+                    </SelectContent>
+                  </Select>
+                </div>
+                {[
+                  { label: t("Proxy Address"), text: proxyAddress },
+                  { label: t("PAC URL"), text: pacUrl },
+                ].map(({ label, text }) => (
+                  <div key={label} className="grid gap-2">
+                    <Label>{label}</Label>
+                    <div className="flex gap-2">
+                      <Input readOnly value={text} className="font-mono" />
+                      <Button
+                        variant="outline"
+                        size="icon"
+                        onClick={() => onCopy(text)}
+                      >
+                        <Copy className="h-4 w-4" />
+                      </Button>
+                    </div>
+                  </div>
+                ))}
+                {qrCode && (
+                  <img
+                    src={`data:image/svg+xml;utf8,${encodeURIComponent(qrCode)}`}
+                    alt={pacUrl}
+                    className="mx-auto h-44 w-44 rounded-md bg-white"
+                  />
+                )}
+                {info.authentication && (
+                  <p className="text-xs text-muted-foreground">
+                    {t("Proxy Authentication Required")}
+                  </p>
+                )}
+              </>
+            )}
+          </div>
+        </div>
+
+        <DialogFooter>
+          <DialogClose asChild>
+            <Button type="button" variant="outline">
+              {t("Close")}
+            </Button>
+          </DialogClose>
+          <Button type="button" onClick={onSave} disabled={saving}>
+            {saving && <Loader2 className="mr-2 h-4 w-4 animate-spin" />}
+            {t("Enable LAN Gateway")}
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+});
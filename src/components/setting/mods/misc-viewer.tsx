@@ -33,6 +33,8 @@ import {
   ArchiveRestore,
   Link as LinkIcon,
   Timer,
+  Terminal,
+  Network,
 } from "lucide-react";
 
 interface Props {}
@@ -87,6 +89,8 @@ export const MiscViewer = forwardRef<DialogRef>((props, ref) => {
     defaultLatencyTest: "",
     autoLogClean: 2,
     defaultLatencyTimeout: 10000,
+    shellEnv: false,
+    shellEnvSocksOnly: false,
   });
 
   useImperativeHandle(ref, () => ({
@@ -101,6 +105,8 @@ export const MiscViewer = forwardRef<DialogRef>((props, ref) => {
         defaultLatencyTest: verge?.default_latency_test || "",
         autoLogClean: verge?.auto_log_clean || 0,
         defaultLatencyTimeout: verge?.default_latency_timeout || 10000,
+        shellEnv: verge?.enable_shell_env ?? false,
+        shellEnvSocksOnly: verge?.shell_env_socks_only ?? false,
       });
     },
     close: () => setOpen(false),
@@ -117,6 +123,8 @@ export const MiscViewer = forwardRef<DialogRef>((props, ref) => {
         default_latency_test: values.defaultLatencyTest,
         default_latency_timeout: Number(values.defaultLatencyTimeout),
         auto_log_clean: values.autoLogClean as any,
+        enable_shell_env: values.shellEnv,
+        shell_env_socks_only: values.shellEnvSocksOnly,
       });
       setOpen(false);
       showNotice("success", t("Saved Successfully"));
@@ -198,6 +206,31 @@ export const MiscViewer = forwardRef<DialogRef>((props, ref) => {
             />
           </SettingRow>
 
+          <SettingRow
+            label={
+              <LabelWithIcon
+                icon={Terminal}
+                text={t("Shell Proxy Integration")}
+              />
+            }
+            extra={<TooltipIcon tooltip={t("Shell Proxy Integration Info")} />}
+          >
+            <Switch
+              checked={values.shellEnv}
+              onCheckedChange={(c) => handleValueChange("shellEnv", c)}
+            />
+          </SettingRow>
+
+          <SettingRow
+            label={<LabelWithIcon icon={Network} text={t("SOCKS Only")} />}
+          >
+            <Switch
+              checked={values.shellEnvSocksOnly}
+              disabled={!values.shellEnv}
+              onCheckedChange={(c) => handleValueChange("shellEnvSocksOnly", c)}
+            />
+          </SettingRow>
+
           <SettingRow
             label={
               <LabelWithIcon icon={Columns} text={t("Proxy Layout Columns")} />
@@ -0,0 +1,173 @@
+import { forwardRef, useEffect, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { useLockFn } from "ahooks";
+import useSWR, { mutate } from "swr";
+import { listen } from "@tauri-apps/api/event";
+
+import { getServiceHealth, repairService } from "@/services/cmds";
+import { showNotice } from "@/services/noticeService";
+import { useSystemState } from "@/hooks/use-system-state";
+import { DialogRef } from "@/components/base";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogHeader,
+  DialogTitle,
+  DialogFooter,
+  DialogClose,
+} from "@/components/ui/dialog";
+import {
+  CheckCircle2,
+  Circle,
+  Loader2,
+  MinusCircle,
+  XCircle,
+} from "lucide-react";
+
+type RepairStep = IServiceRepairProgress["step"];
+
+const STEPS: { key: RepairStep; label: string }[] = [
+  { key: "diagnose", label: "Diagnose Service" },
+  { key: "uninstall", label: "Uninstall Service" },
+  { key: "install", label: "Install Service" },
+  { key: "wait_for_service", label: "Wait For Service" },
+  { key: "verify", label: "Verify Service" },
+  { key: "restart_core", label: "Restart Core" },
+];
+
+type RepairStatus = IServiceRepairProgress["status"];
+
+const StatusIcon = ({ status }: { status?: RepairStatus }) => {
+  switch (status) {
+    case "running":
+      return <Loader2 className="h-4 w-4 animate-spin" />;
+    case "done":
+      return <CheckCircle2 className="h-4 w-4 text-green-500" />;
+    case "skipped":
+      return <MinusCircle className="h-4 w-4 text-muted-foreground" />;
+    case "failed":
+      return <XCircle className="h-4 w-4 text-destructive" />;
+    default:
+      return <Circle className="h-4 w-4 text-muted-foreground" />;
+  }
+};
+
+export const ServiceRepairViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { mutateRunningMode } = useSystemState();
+  const [open, setOpen] = useState(false);
+  const [repairing, setRepairing] = useState(false);
+  const [progress, setProgress] = useState<
+    Partial<Record<RepairStep, IServiceRepairProgress>>
+  >({});
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setProgress({});
+      setOpen(true);
+    },
+    close: () => setOpen(false),
+  }));
+
+  const { data: health, mutate: mutateHealth } = useSWR(
+    open ? "getServiceHealth" : null,
+    getServiceHealth,
+  );
+
+  // Бэкенд сообщает о каждом шаге восстановления
+  useEffect(() => {
+    if (!open) return;
+    const unlisten = listen<IServiceRepairProgress>(
+      "verge://service-repair-progress",
+      ({ payload }) =>
+        setProgress((prev) => ({ ...prev, [payload.step]: payload })),
+    );
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [open]);
+
+  const onRepair = useLockFn(async () => {
+    setProgress({});
+    setRepairing(true);
+    try {
+      await mutateHealth(await repairService(), false);
+      await mutateRunningMode();
+      mutate("isServiceAvailable");
+      showNotice("success", t("Service Repaired"));
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+      mutateHealth();
+    } finally {
+      setRepairing(false);
+    }
+  });
+
+  const rows: [string, string][] = health
+    ? [
+        ["Installed", health.installed ? t("Yes") : t("No")],
+        ["Running", health.running ? t("Yes") : t("No")],
+        ["Signature Valid", health.signature_valid ? t("Yes") : t("No")],
+        ["IPC Key Available", health.secret_missing ? t("No") : t("Yes")],
+        [
+          "Service Version",
+          `${health.version ?? "-"} / ${health.required_version}`,
+        ],
+      ]
+    : [];
+
+  return (
+    <Dialog open={open} onOpenChange={(value) => !repairing && setOpen(value)}>
+      <DialogContent className="sm:max-w-md">
+        <DialogHeader>
+          <DialogTitle>{t("Service Health")}</DialogTitle>
+        </DialogHeader>
+
+        <div className="space-y-1 text-sm">
+          {rows.map(([label, value]) => (
+            <div key={label} className="flex justify-between">
+              <span className="text-muted-foreground">{t(label)}</span>
+              <span>{value}</span>
+            </div>
+          ))}
+          {health?.last_error && (
+            <div className="pt-2 text-xs text-destructive break-words">
+              {health.last_error}
+            </div>
+          )}
+        </div>
+
+        {Object.keys(progress).length > 0 && (
+          <div className="space-y-2 rounded-md border p-3">
+            {STEPS.map(({ key, label }) => (
+              <div key={key} className="flex items-start gap-2 text-sm">
+                <StatusIcon status={progress[key]?.status} />
+                <div className="min-w-0">
+                  <div>{t(label)}</div>
+                  {progress[key]?.message && (
+                    <div className="text-xs text-muted-foreground break-words">
+                      {progress[key]?.message}
+                    </div>
+                  )}
+                </div>
+              </div>
+            ))}
+          </div>
+        )}
+
+        <DialogFooter>
+          <DialogClose asChild>
+            <Button type="button" variant="outline" disabled={repairing}>
+              {t("Close")}
+            </Button>
+          </DialogClose>
+          <Button onClick={onRepair} disabled={repairing}>
+            {repairing && <Loader2 className="mr-2 h-4 w-4 animate-spin" />}
+            {t("Repair Service")}
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+});
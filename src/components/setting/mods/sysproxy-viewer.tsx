@@ -21,9 +21,11 @@ import { getClashConfig } from "@/services/api";
 import {
   getAutotemProxy,
   getNetworkInterfacesInfo,
+  getProxyGuardStatus,
   getSystemHostname,
   getSystemProxy,
   patchVergeConfig,
+  resetProxyGuard,
 } from "@/services/cmds";
 import { showNotice } from "@/services/noticeService";
 import getSystem from "@/utils/get-system";
@@ -194,6 +196,22 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
     return "127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,172.29.0.0/16,localhost,*.local,*.crashlytics.com,<local>";
   };
 
+  // Журнал изменений прокси другими приложениями, обновляется пока окно открыто
+  const { data: guardStatus, mutate: mutateGuardStatus } = useSWR(
+    open ? "getProxyGuardStatus" : null,
+    getProxyGuardStatus,
+    { refreshInterval: 5000 },
+  );
+
+  const onResetGuard = useLockFn(async () => {
+    try {
+      await resetProxyGuard();
+      await mutateGuardStatus();
+    } catch (err: any) {
+      showNotice("error", err.toString());
+    }
+  });
+
   const { data: clashConfig } = useSWR("getClashConfig", getClashConfig, {
     revalidateOnFocus: false,
     revalidateIfStale: true,
@@ -532,6 +550,51 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
                 <span className="text-sm text-muted-foreground">s</span>
               </div>
             </SettingRow>
+            {!!guardStatus?.backoff_secs && (
+              <SettingRow
+                label={t("Proxy Guard Paused Left", {
+                  minutes: Math.ceil(guardStatus.backoff_secs / 60),
+                })}
+              >
+                <Button variant="outline" size="sm" onClick={onResetGuard}>
+                  {t("Resume Guard")}
+                </Button>
+              </SettingRow>
+            )}
+            {!!guardStatus?.history.length && (
+              <div className="space-y-2">
+                <div className="flex items-center justify-between">
+                  <Label>{t("Proxy Guard History")}</Label>
+                  {!guardStatus.backoff_secs && (
+                    <Button variant="ghost" size="sm" onClick={onResetGuard}>
+                      {t("Clear")}
+                    </Button>
+                  )}
+                </div>
+                <div className="max-h-32 overflow-y-auto rounded-md border p-2 text-xs space-y-1">
+                  {[...guardStatus.history].reverse().map((item) => (
+                    <div
+                      key={item.time}
+                      className={cn(
+                        "flex gap-2",
+                        !item.restored && "text-muted-foreground",
+                      )}
+                    >
+                      <span className="shrink-0">
+                        {new Date(item.time).toLocaleTimeString()}
+                      </span>
+                      <span className="break-all">
+                        {item.changes
+                          .map(
+                            (c) => `${c.field}: ${c.expected} → ${c.actual}`,
+                          )
+                          .join(", ")}
+                      </span>
+                    </div>
+                  ))}
+                </div>
+              </div>
+            )}
             {!value.pac && (
               <SettingRow label={t("Always use Default Bypass")}>
                 <Switch
@@ -4,7 +4,7 @@ import { useLockFn, useRequest } from "ahooks";
 import { mutate } from "swr";
 import { useClash, useClashInfo } from "@/hooks/use-clash";
 import { useVerge } from "@/hooks/use-verge";
-import { enhanceProfiles, restartCore } from "@/services/cmds";
+import { restartCore } from "@/services/cmds";
 import { showNotice } from "@/services/noticeService";
 import getSystem from "@/utils/get-system";
 
@@ -31,11 +31,42 @@ import {
   Network,
   Dna,
   Gauge,
+  Split,
+  Users,
+  Zap,
 } from "lucide-react";
 
 const OS = getSystem();
 type StackMode = "mixed" | "gvisor" | "system";
 
+const DEFAULT_VALUES = {
+  stack: "gvisor" as StackMode,
+  device: OS === "macos" ? "utun1024" : "Mihomo",
+  autoRoute: true,
+  autoDetectInterface: true,
+  dnsHijack: ["any:53"],
+  strictRoute: false,
+  mtu: 1500,
+  routeExcludeAddress: "",
+  inet4RouteAddress: "",
+  inet6RouteAddress: "",
+  includeUid: "",
+  excludeUid: "",
+  endpointIndependentNat: false,
+  gso: false,
+};
+
+const splitList = (value: string) =>
+  value
+    .split(",")
+    .map((item) => item.trim())
+    .filter(Boolean);
+
+const splitUids = (value: string) =>
+  splitList(value)
+    .map((item) => parseInt(item, 10))
+    .filter((uid) => !isNaN(uid));
+
 // Компоненты-хелперы
 const SettingRow = ({
   label,
@@ -69,80 +100,85 @@ const LabelWithIcon = ({
 
 export const TunViewer = forwardRef<DialogRef>((props, ref) => {
   const { t } = useTranslation();
-  const { clash, mutateClash, patchClash } = useClash();
+  const { clash, mutateClash } = useClash();
+  const { verge, patchVerge } = useVerge();
 
   const [open, setOpen] = useState(false);
-  const [values, setValues] = useState({
-    stack: "gvisor" as StackMode,
-    device: OS === "macos" ? "utun1024" : "Mihomo",
-    autoRoute: true,
-    autoDetectInterface: true,
-    dnsHijack: ["any:53"],
-    strictRoute: false,
-    mtu: 1500,
-  });
+  const [values, setValues] = useState(DEFAULT_VALUES);
 
   useImperativeHandle(ref, () => ({
     open: () => {
       setOpen(true);
+      const tun = verge?.tun_settings ?? {};
       setValues({
-        // --- НАЧАЛО ИСПРАВЛЕНИЯ ---
-        // Добавляем утверждение типа, чтобы TypeScript был уверен в значении
-        stack: (clash?.tun.stack as StackMode) ?? "gvisor",
-        // --- КОНЕЦ ИСПРАВЛЕНИЯ ---
-        device: clash?.tun.device ?? (OS === "macos" ? "utun1024" : "Mihomo"),
-        autoRoute: clash?.tun["auto-route"] ?? true,
-        autoDetectInterface: clash?.tun["auto-detect-interface"] ?? true,
-        dnsHijack: clash?.tun["dns-hijack"] ?? ["any:53"],
-        strictRoute: clash?.tun["strict-route"] ?? false,
-        mtu: clash?.tun.mtu ?? 1500,
+        stack: tun.stack ?? (clash?.tun.stack as StackMode) ?? "gvisor",
+        device: tun.device ?? clash?.tun.device ?? DEFAULT_VALUES.device,
+        autoRoute: tun.auto_route ?? clash?.tun["auto-route"] ?? true,
+        autoDetectInterface:
+          tun.auto_detect_interface ??
+          clash?.tun["auto-detect-interface"] ??
+          true,
+        dnsHijack: tun.dns_hijack ?? clash?.tun["dns-hijack"] ?? ["any:53"],
+        strictRoute: tun.strict_route ?? clash?.tun["strict-route"] ?? false,
+        mtu: tun.mtu ?? clash?.tun.mtu ?? 1500,
+        routeExcludeAddress: (tun.route_exclude_address ?? []).join(","),
+        inet4RouteAddress: (tun.inet4_route_address ?? []).join(","),
+        inet6RouteAddress: (tun.inet6_route_address ?? []).join(","),
+        includeUid: (tun.include_uid ?? []).join(","),
+        excludeUid: (tun.exclude_uid ?? []).join(","),
+        endpointIndependentNat: tun.endpoint_independent_nat ?? false,
+        gso: tun.gso ?? false,
       });
     },
     close: () => setOpen(false),
   }));
 
   const resetToDefaults = () => {
-    setValues({
-      stack: "gvisor",
-      device: OS === "macos" ? "utun1024" : "Mihomo",
-      autoRoute: true,
-      autoDetectInterface: true,
-      dnsHijack: ["any:53"],
-      strictRoute: false,
-      mtu: 1500,
-    });
+    setValues(DEFAULT_VALUES);
   };
 
   const onSave = useLockFn(async () => {
     try {
-      const tun = {
+      const tun_settings: IVergeTunSettings = {
         stack: values.stack,
-        device:
-          values.device === ""
-            ? OS === "macos"
-              ? "utun1024"
-              : "Mihomo"
-            : values.device,
-        "auto-route": values.autoRoute,
-        "auto-detect-interface": values.autoDetectInterface,
-        "dns-hijack": values.dnsHijack[0] === "" ? [] : values.dnsHijack,
-        "strict-route": values.strictRoute,
+        device: values.device === "" ? DEFAULT_VALUES.device : values.device,
+        auto_route: values.autoRoute,
+        auto_detect_interface: values.autoDetectInterface,
+        dns_hijack: values.dnsHijack[0] === "" ? [] : values.dnsHijack,
+        strict_route: values.strictRoute,
         mtu: values.mtu ?? 1500,
+        route_exclude_address: splitList(values.routeExcludeAddress),
+        inet4_route_address: splitList(values.inet4RouteAddress),
+        inet6_route_address: splitList(values.inet6RouteAddress),
+        endpoint_independent_nat: values.endpointIndependentNat,
       };
-      await patchClash({ tun });
-      await mutateClash((old) => ({ ...(old! || {}), tun }), false);
-      try {
-        await enhanceProfiles();
-        showNotice("success", t("Settings Applied"));
-      } catch (err: any) {
-        showNotice("error", err.message || err.toString());
+      if (OS === "linux") {
+        tun_settings.include_uid = splitUids(values.includeUid);
+        tun_settings.exclude_uid = splitUids(values.excludeUid);
+        tun_settings.gso = values.gso;
       }
+      // Бэкенд проверяет настройки для выбранного стека и вернёт ошибку
+      await patchVerge({ tun_settings });
+      await mutateClash();
+      showNotice("success", t("Settings Applied"));
       setOpen(false);
     } catch (err: any) {
       showNotice("error", err.message || err.toString());
     }
   });
 
+  const listInput = (
+    key: "routeExcludeAddress" | "inet4RouteAddress" | "inet6RouteAddress",
+    placeholder: string,
+  ) => (
+    <Input
+      className="h-8 w-40"
+      value={values[key]}
+      placeholder={placeholder}
+      onChange={(e) => setValues((v) => ({ ...v, [key]: e.target.value }))}
+    />
+  );
+
   return (
     <Dialog open={open} onOpenChange={setOpen}>
       <DialogContent className="sm:max-w-md">
@@ -236,6 +272,80 @@ export const TunViewer = forwardRef<DialogRef>((props, ref) => {
               }
             />
           </SettingRow>
+          <SettingRow
+            label={
+              <LabelWithIcon icon={Split} text={t("Route Exclude Address")} />
+            }
+          >
+            {listInput("routeExcludeAddress", "192.168.0.0/16")}
+          </SettingRow>
+          <SettingRow
+            label={
+              <LabelWithIcon icon={Route} text={t("Inet4 Route Address")} />
+            }
+          >
+            {listInput("inet4RouteAddress", "0.0.0.0/1")}
+          </SettingRow>
+          <SettingRow
+            label={
+              <LabelWithIcon icon={Route} text={t("Inet6 Route Address")} />
+            }
+          >
+            {listInput("inet6RouteAddress", "::/1")}
+          </SettingRow>
+          {OS === "linux" && (
+            <>
+              <SettingRow
+                label={<LabelWithIcon icon={Users} text={t("Include UID")} />}
+              >
+                <Input
+                  className="h-8 w-40"
+                  value={values.includeUid}
+                  placeholder="1000"
+                  onChange={(e) =>
+                    setValues((v) => ({ ...v, includeUid: e.target.value }))
+                  }
+                />
+              </SettingRow>
+              <SettingRow
+                label={<LabelWithIcon icon={Users} text={t("Exclude UID")} />}
+              >
+                <Input
+                  className="h-8 w-40"
+                  value={values.excludeUid}
+                  placeholder="1000"
+                  onChange={(e) =>
+                    setValues((v) => ({ ...v, excludeUid: e.target.value }))
+                  }
+                />
+              </SettingRow>
+            </>
+          )}
+          <SettingRow
+            label={
+              <LabelWithIcon
+                icon={Network}
+                text={t("Endpoint Independent NAT")}
+              />
+            }
+          >
+            <Switch
+              checked={values.endpointIndependentNat}
+              disabled={values.stack === "system"}
+              onCheckedChange={(c) =>
+                setValues((v) => ({ ...v, endpointIndependentNat: c }))
+              }
+            />
+          </SettingRow>
+          {OS === "linux" && (
+            <SettingRow label={<LabelWithIcon icon={Zap} text={t("GSO")} />}>
+              <Switch
+                checked={values.gso}
+                disabled={values.stack === "gvisor"}
+                onCheckedChange={(c) => setValues((v) => ({ ...v, gso: c }))}
+              />
+            </SettingRow>
+          )}
         </div>
 
         <DialogFooter>
@@ -40,6 +40,8 @@ import {
   Cog,
   Repeat,
   Map as MapIcon,
+  Gauge,
+  Share2,
 } from "lucide-react";
 
 // Модальные окна
@@ -47,6 +49,8 @@ import { ClashCoreViewer } from "./mods/clash-core-viewer";
 import { ClashPortViewer } from "./mods/clash-port-viewer";
 import { ControllerViewer } from "./mods/controller-viewer";
 import { DnsViewer } from "./mods/dns-viewer";
+import { DnsBenchmarkViewer } from "./mods/dns-benchmark-viewer";
+import { LanGatewayViewer } from "./mods/lan-gateway-viewer";
 import { NetworkInterfaceViewer } from "./mods/network-interface-viewer";
 import { WebUIViewer } from "./mods/web-ui-viewer";
 
@@ -121,7 +125,9 @@ const SettingClash = ({ onError }: Props) => {
   const ctrlRef = useRef<DialogRef>(null);
   const coreRef = useRef<DialogRef>(null);
   const networkRef = useRef<DialogRef>(null);
+  const lanGatewayRef = useRef<DialogRef>(null);
   const dnsRef = useRef<DialogRef>(null);
+  const dnsBenchmarkRef = useRef<DialogRef>(null);
 
   const onSwitchFormat = (value: boolean) => value;
   const onSelectFormat = (value: string) => value;
@@ -166,16 +172,25 @@ const SettingClash = ({ onError }: Props) => {
         <ControllerViewer ref={ctrlRef} />
         <ClashCoreViewer ref={coreRef} />
         <NetworkInterfaceViewer ref={networkRef} />
+        <LanGatewayViewer ref={lanGatewayRef} />
         <DnsViewer ref={dnsRef} />
+        <DnsBenchmarkViewer ref={dnsBenchmarkRef} />
 
         <SettingRow
           label={<LabelWithIcon icon={Network} text={t("Allow Lan")} />}
           extra={
-            <TooltipIcon
-              tooltip={t("Network Interface")}
-              icon={<Settings className="h-4 w-4" />}
-              onClick={() => networkRef.current?.open()}
-            />
+            <div className="flex items-center gap-1">
+              <TooltipIcon
+                tooltip={t("Network Interface")}
+                icon={<Settings className="h-4 w-4" />}
+                onClick={() => networkRef.current?.open()}
+              />
+              <TooltipIcon
+                tooltip={t("LAN Gateway")}
+                icon={<Share2 className="h-4 w-4" />}
+                onClick={() => lanGatewayRef.current?.open()}
+              />
+            </div>
           }
         >
           <GuardState
@@ -194,11 +209,18 @@ const SettingClash = ({ onError }: Props) => {
         <SettingRow
           label={<LabelWithIcon icon={Dna} text={t("DNS Overwrite")} />}
           extra={
-            <TooltipIcon
-              tooltip={t("DNS Settings")}
-              icon={<Settings className="h-4 w-4" />}
-              onClick={() => dnsRef.current?.open()}
-            />
+            <div className="flex items-center gap-1">
+              <TooltipIcon
+                tooltip={t("DNS Settings")}
+                icon={<Settings className="h-4 w-4" />}
+                onClick={() => dnsRef.current?.open()}
+              />
+              <TooltipIcon
+                tooltip={t("DNS Benchmark")}
+                icon={<Gauge className="h-4 w-4" />}
+                onClick={() => dnsBenchmarkRef.current?.open()}
+              />
+            </div>
           }
         >
           <Switch
@@ -15,6 +15,7 @@ import {
   restartCore,
   stopCore,
   invoke_uwp_tool,
+  grantCoreCapabilities,
 } from "@/services/cmds";
 import { showNotice } from "@/services/noticeService";
 
@@ -44,11 +45,15 @@ import {
   BellOff,
   Repeat,
   Fingerprint,
+  KeyRound,
+  Stethoscope,
 } from "lucide-react";
 
 // Модальные окна
 import { SysproxyViewer } from "./mods/sysproxy-viewer";
 import { TunViewer } from "./mods/tun-viewer";
+import { HwidViewer } from "./mods/hwid-viewer";
+import { ServiceRepairViewer } from "./mods/service-repair-viewer";
 import {
   Select,
   SelectContent,
@@ -59,6 +64,7 @@ import {
 import { useProfiles } from "@/hooks/use-profiles";
 
 const isWIN = getSystem() === "windows";
+const isLINUX = getSystem() === "linux";
 interface Props {
   onError?: (err: Error) => void;
 }
@@ -119,11 +125,14 @@ const SettingSystem = ({ onError }: Props) => {
     toggleSystemProxy,
   } = useSystemProxyState();
 
-  const { isAdminMode, isServiceMode, mutateRunningMode } = useSystemState();
-  const isTunAvailable = isServiceMode || isAdminMode;
+  const { isAdminMode, isServiceMode, isCoreCapable, mutateRunningMode } =
+    useSystemState();
+  const isTunAvailable = isServiceMode || isAdminMode || isCoreCapable;
 
   const sysproxyRef = useRef<DialogRef>(null);
   const tunRef = useRef<DialogRef>(null);
+  const hwidRef = useRef<DialogRef>(null);
+  const serviceRepairRef = useRef<DialogRef>(null);
 
   const { enable_tun_mode, enable_auto_launch, enable_silent_start } =
     verge ?? {};
@@ -167,6 +176,17 @@ const SettingSystem = ({ onError }: Props) => {
     },
   );
 
+  const onGrantCoreCapabilities = useLockFn(async () => {
+    try {
+      await grantCoreCapabilities();
+      await mutate("getCoreCapabilityState");
+      await mutateRunningMode();
+      showNotice("success", t("Core Capabilities Granted"));
+    } catch (err: any) {
+      showNotice("error", err.message || err.toString());
+    }
+  });
+
   const onUninstallService = () =>
     handleServiceOperation({
       beforeMsg: t("Stopping Core..."),
@@ -181,6 +201,8 @@ const SettingSystem = ({ onError }: Props) => {
       <div className="space-y-1">
         <SysproxyViewer ref={sysproxyRef} />
         <TunViewer ref={tunRef} />
+        <HwidViewer ref={hwidRef} />
+        <ServiceRepairViewer ref={serviceRepairRef} />
 
         <SettingRow
           label={<LabelWithIcon icon={Funnel} text={t("Tun Mode")} />}
@@ -222,6 +244,44 @@ const SettingSystem = ({ onError }: Props) => {
                   </Tooltip>
                 </TooltipProvider>
               )}
+              {isLINUX && !isServiceMode && !isAdminMode && !isCoreCapable && (
+                <TooltipProvider>
+                  <Tooltip>
+                    <TooltipTrigger asChild>
+                      <Button
+                        variant="outline"
+                        size="icon"
+                        className="h-7 w-7"
+                        onClick={onGrantCoreCapabilities}
+                      >
+                        <KeyRound className="h-4 w-4" />
+                      </Button>
+                    </TooltipTrigger>
+                    <TooltipContent>
+                      <p>{t("Grant Core Capabilities")}</p>
+                    </TooltipContent>
+                  </Tooltip>
+                </TooltipProvider>
+              )}
+              {!isAdminMode && (
+                <TooltipProvider>
+                  <Tooltip>
+                    <TooltipTrigger asChild>
+                      <Button
+                        variant="outline"
+                        size="icon"
+                        className="h-7 w-7"
+                        onClick={() => serviceRepairRef.current?.open()}
+                      >
+                        <Stethoscope className="h-4 w-4" />
+                      </Button>
+                    </TooltipTrigger>
+                    <TooltipContent>
+                      <p>{t("Service Health")}</p>
+                    </TooltipContent>
+                  </Tooltip>
+                </TooltipProvider>
+              )}
               {isServiceMode && (
                 <TooltipProvider>
                   <Tooltip>
@@ -404,6 +464,13 @@ const SettingSystem = ({ onError }: Props) => {
 
         <SettingRow
           label={<LabelWithIcon icon={Fingerprint} text={t("Send HWID")} />}
+          extra={
+            <TooltipIcon
+              tooltip={t("HWID Settings")}
+              icon={<Settings className="h-4 w-4" />}
+              onClick={() => hwidRef.current?.open()}
+            />
+          }
         >
           <GuardState
             value={verge?.enable_send_hwid ?? true}
@@ -34,6 +34,7 @@ import {
   Feather,
   LogOut,
   ClipboardList,
+  Webhook,
 } from "lucide-react";
 
 // Модальные окна
@@ -45,6 +46,7 @@ import { LayoutViewer } from "./mods/layout-viewer";
 import { UpdateViewer } from "./mods/update-viewer";
 import { BackupViewer } from "./mods/backup-viewer";
 import { LiteModeViewer } from "./mods/lite-mode-viewer";
+import { ControlApiViewer } from "./mods/control-api-viewer";
 
 interface Props {
   onError?: (err: Error) => void;
@@ -86,6 +88,7 @@ const SettingVergeAdvanced = ({ onError }: Props) => {
   const updateRef = useRef<DialogRef>(null);
   const backupRef = useRef<DialogRef>(null);
   const liteModeRef = useRef<DialogRef>(null);
+  const controlApiRef = useRef<DialogRef>(null);
 
   const onCheckUpdate = async () => {
     try {
@@ -136,6 +139,7 @@ const SettingVergeAdvanced = ({ onError }: Props) => {
         <UpdateViewer ref={updateRef} />
         <BackupViewer ref={backupRef} />
         <LiteModeViewer ref={liteModeRef} />
+        <ControlApiViewer ref={controlApiRef} />
 
         {/* --- НАЧАЛО ИЗМЕНЕНИЙ 2: Добавляем иконки к каждому пункту --- */}
         <SettingRow
@@ -180,6 +184,11 @@ const SettingVergeAdvanced = ({ onError }: Props) => {
           extra={<TooltipIcon tooltip={t("LightWeight Mode Info")} />}
           onClick={() => liteModeRef.current?.open()}
         />
+        <SettingRow
+          label={<LabelWithIcon icon={Webhook} text={t("Control API")} />}
+          extra={<TooltipIcon tooltip={t("Control API Info")} />}
+          onClick={() => controlApiRef.current?.open()}
+        />
 
         <SettingRow
           label={<LabelWithIcon icon={Info} text={t("Verge Version")} />}
@@ -1,5 +1,10 @@
 import useSWR from "swr";
-import { getRunningMode, isAdmin, isServiceAvailable } from "@/services/cmds";
+import {
+  getCoreCapabilityState,
+  getRunningMode,
+  isAdmin,
+  isServiceAvailable,
+} from "@/services/cmds";
 
 /**
  * 自定义 hook 用于获取系统运行状态
@@ -34,12 +39,24 @@ export function useSystemState() {
     },
   );
 
+  // 获取 Linux 下 core 副本的 capabilities 状态
+  const { data: coreCapState = "unsupported" } = useSWR(
+    "getCoreCapabilityState",
+    getCoreCapabilityState,
+    {
+      suspense: false,
+      revalidateOnFocus: false,
+    },
+  );
+
   return {
     runningMode,
     isAdminMode,
     isSidecarMode: runningMode === "Sidecar",
     isServiceMode: runningMode === "Service",
     isServiceOk,
+    coreCapState,
+    isCoreCapable: coreCapState === "granted",
     mutateRunningMode,
   };
 }
@@ -21,6 +21,8 @@ import { useClashInfo } from "@/hooks/use-clash";
 import { initGlobalLogService } from "@/services/global-log-service";
 import { invoke } from "@tauri-apps/api/core";
 import { showNotice } from "@/services/noticeService";
+import { reassignPortConflicts } from "@/services/cmds";
+import { toast } from "sonner";
 import { Toaster } from "@/components/ui/sonner";
 import { SidebarProvider, useSidebar } from "@/components/ui/sidebar";
 import { AppSidebar } from "@/components/layout/sidebar";
@@ -34,11 +36,33 @@ dayjs.extend(relativeTime);
 
 const OS = getSystem();
 
+const PORT_LABELS: Record<IPortConflict["kind"], string> = {
+  mixed: "Mixed Port",
+  socks: "Socks Port",
+  http: "Http Port",
+  redir: "Redir Port",
+  tproxy: "Tproxy Port",
+  controller: "External Controller",
+};
+
+// Список занятых портов с именами процессов для уведомлений
+const describePortConflicts = (
+  conflicts: IPortConflict[],
+  t: (key: string, options?: Record<string, unknown>) => string,
+) =>
+  conflicts
+    .map(({ kind, port, process, pid, reassigned }) => {
+      const owner = process ?? (pid ? `PID ${pid}` : t("Unknown Process"));
+      const target = reassigned ? ` → ${reassigned}` : "";
+      return `${t(PORT_LABELS[kind])} ${port} (${owner})${target}`;
+    })
+    .join(", ");
+
 // Notification Handler
 const handleNoticeMessage = (
   status: string,
   msg: string,
-  t: (key: string) => string,
+  t: (key: string, options?: Record<string, unknown>) => string,
   navigate: (path: string, options?: any) => void,
 ) => {
   console.log("[Notification Listener V2] Receiving a message:", status, msg);
@@ -52,16 +76,10 @@ const handleNoticeMessage = (
       break;
     case "import_sub_url::error":
       console.log(msg);
-      if (
-        msg.toLowerCase().includes("device") ||
-        msg.toLowerCase().includes("устройств")
-      ) {
-        window.dispatchEvent(
-          new CustomEvent("show-hwid-error", { detail: msg }),
-        );
-      } else {
-        showNotice("error", msg);
-      }
+      showNotice("error", msg);
+      break;
+    case "profile::device_limit":
+      window.dispatchEvent(new CustomEvent("show-hwid-error", { detail: msg }));
       break;
     case "set_config::error":
       showNotice("error", msg);
@@ -84,6 +102,60 @@ const handleNoticeMessage = (
     case "update_failed":
       showNotice("error", msg);
       break;
+    case "subscription_quota_alert": {
+      const { name, percent } = JSON.parse(msg);
+      showNotice(
+        "warning",
+        t("Subscription Quota Alert", { name, percent }),
+      );
+      break;
+    }
+    case "subscription_expire_alert": {
+      const { name, days } = JSON.parse(msg);
+      showNotice("warning", t("Subscription Expire Alert", { name, days }));
+      break;
+    }
+    case "core_capabilities_lost":
+      showNotice("error", t("Core Capabilities Lost"));
+      break;
+    case "proxy_guard_tampered":
+      showNotice("warning", `${t("Proxy Guard Tampered")} ${msg}`);
+      break;
+    case "proxy_guard_paused":
+      showNotice("warning", t("Proxy Guard Paused", { minutes: msg }));
+      break;
+    case "port_conflict": {
+      const conflicts: IPortConflict[] = JSON.parse(msg);
+      toast.warning(
+        `${t("Ports In Use")}: ${describePortConflicts(conflicts, t)}`,
+        {
+          duration: 15000,
+          action: {
+            label: t("Reassign Ports"),
+            onClick: () => {
+              reassignPortConflicts()
+                .then((result) =>
+                  showNotice(
+                    "success",
+                    `${t("Ports Reassigned")}: ${describePortConflicts(result, t)}`,
+                  ),
+                )
+                .catch((err) => showNotice("error", err.toString()));
+            },
+          },
+        },
+      );
+      break;
+    }
+    case "port_reassigned":
+      showNotice(
+        "info",
+        `${t("Ports Reassigned")}: ${describePortConflicts(JSON.parse(msg), t)}`,
+      );
+      break;
+    case "network_auto_switch":
+      showNotice("info", t("Network Auto Switch Applied", { name: msg }));
+      break;
     case "config_validate::boot_error":
       showNotice("error", `${t("Boot Config Validation Failed")} ${msg}`);
       break;
@@ -22,6 +22,10 @@ import {
   ConnectionDetail,
   ConnectionDetailRef,
 } from "@/components/connection/connection-detail";
+import {
+  ConnectionStats,
+  ConnectionStatsRef,
+} from "@/components/connection/connection-stats";
 import { BaseSearchBox } from "@/components/base/base-search-box";
 import { Button } from "@/components/ui/button";
 import {
@@ -47,6 +51,7 @@ import {
   PauseCircle,
   ArrowDown,
   ArrowUp,
+  ChartColumn,
 } from "lucide-react";
 import { SidebarTrigger } from "@/components/ui/sidebar";
 
@@ -155,6 +160,7 @@ const ConnectionsPage = () => {
 
   const onCloseAll = useLockFn(closeAllConnections);
   const detailRef = useRef<ConnectionDetailRef>(null!);
+  const statsRef = useRef<ConnectionStatsRef>(null!);
   const handleSearch = useCallback(
     (m: (content: string) => boolean) => setMatch(() => m),
     [],
@@ -245,6 +251,20 @@ const ConnectionsPage = () => {
                   <p>{isPaused ? t("Resume") : t("Pause")}</p>
                 </TooltipContent>
               </Tooltip>
+              <Tooltip>
+                <TooltipTrigger asChild>
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    onClick={() => statsRef.current?.open()}
+                  >
+                    <ChartColumn className="h-5 w-5" />
+                  </Button>
+                </TooltipTrigger>
+                <TooltipContent>
+                  <p>{t("Connection Statistics")}</p>
+                </TooltipContent>
+              </Tooltip>
               <Button size="sm" variant="destructive" onClick={onCloseAll}>
                 {t("Close All")}
               </Button>
@@ -304,6 +324,7 @@ const ConnectionsPage = () => {
           />
         )}
         <ConnectionDetail ref={detailRef} />
+        <ConnectionStats ref={statsRef} />
       </div>
     </div>
   );
@@ -38,6 +38,11 @@ import {
 import { ProfileItem } from "@/components/profile/profile-item";
 import { useProfiles } from "@/hooks/use-profiles";
 import { ConfigViewer } from "@/components/setting/mods/config-viewer";
+import {
+  AnnouncementsViewer,
+  useAnnouncements,
+} from "@/components/profile/announcements-viewer";
+import { NetworkRulesViewer } from "@/components/profile/network-rules-viewer";
 import { throttle } from "lodash-es";
 import { readTextFile } from "@tauri-apps/plugin-fs";
 import { readText } from "@tauri-apps/plugin-clipboard-manager";
@@ -55,7 +60,15 @@ import {
   TooltipTrigger,
 } from "@/components/ui/tooltip";
 
-import { PlusCircle, RefreshCw, Zap, FileText, Loader2 } from "lucide-react";
+import {
+  PlusCircle,
+  RefreshCw,
+  Zap,
+  FileText,
+  Loader2,
+  Bell,
+  Network,
+} from "lucide-react";
 import { SidebarTrigger } from "@/components/ui/sidebar";
 
 const ProfilePage = () => {
@@ -107,6 +120,9 @@ const ProfilePage = () => {
 
   const viewerRef = useRef<ProfileViewerRef>(null);
   const configRef = useRef<DialogRef>(null);
+  const announcementsRef = useRef<DialogRef>(null);
+  const networkRulesRef = useRef<DialogRef>(null);
+  const { unread: unreadAnnouncements } = useAnnouncements();
 
   const profileItems = useMemo(() => {
     const items =
@@ -397,6 +413,40 @@ const ProfilePage = () => {
                   <p>{t("View Runtime Config")}</p>
                 </TooltipContent>
               </Tooltip>
+              <Tooltip>
+                <TooltipTrigger asChild>
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    onClick={() => networkRulesRef.current?.open()}
+                  >
+                    <Network className="h-5 w-5" />
+                  </Button>
+                </TooltipTrigger>
+                <TooltipContent>
+                  <p>{t("Network Rules")}</p>
+                </TooltipContent>
+              </Tooltip>
+              <Tooltip>
+                <TooltipTrigger asChild>
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    className="relative"
+                    onClick={() => announcementsRef.current?.open()}
+                  >
+                    <Bell className="h-5 w-5" />
+                    {unreadAnnouncements > 0 && (
+                      <span
+                        className="absolute top-1 right-1 h-2 w-2 rounded-full bg-destructive"
+                      />
+                    )}
+                  </Button>
+                </TooltipTrigger>
+                <TooltipContent>
+                  <p>{t("Announcements")}</p>
+                </TooltipContent>
+              </Tooltip>
             </div>
           </TooltipProvider>
         </div>
@@ -447,6 +497,8 @@ const ProfilePage = () => {
         }}
       />
       <ConfigViewer ref={configRef} />
+      <AnnouncementsViewer ref={announcementsRef} />
+      <NetworkRulesViewer ref={networkRulesRef} />
     </div>
   );
 };
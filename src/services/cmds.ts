@@ -65,6 +65,25 @@ export async function patchProfile(
   return invoke<void>("patch_profile", { index, profile });
 }
 
+export async function getProfileAuth(index: string) {
+  return invoke<IProfileAuth | null>("get_profile_auth", { index });
+}
+
+export async function patchProfileAuth(
+  index: string,
+  auth: IProfileAuth | null,
+) {
+  return invoke<void>("patch_profile_auth", { index, auth });
+}
+
+export async function patchProfileGroups(groups: IProfileGroup[]) {
+  return invoke<void>("patch_profile_groups", { groups });
+}
+
+export async function patchNetworkRules(rules: INetworkRule[]) {
+  return invoke<void>("patch_network_rules", { rules });
+}
+
 export async function getClashInfo() {
   return invoke<IClashInfo | null>("get_clash_info");
 }
@@ -301,6 +320,38 @@ export async function getNetworkInterfacesInfo() {
   return invoke<INetworkInterface[]>("get_network_interfaces_info");
 }
 
+export async function reassignPortConflicts() {
+  return invoke<IPortConflict[]>("reassign_port_conflicts");
+}
+
+export async function getConnectionStats(group: IConnectionGroup) {
+  return invoke<IConnectionStat[]>("get_connection_stats", { group });
+}
+
+export async function getClosedConnections() {
+  return invoke<IClosedConnection[]>("get_closed_connections");
+}
+
+export async function clearClosedConnections() {
+  return invoke<void>("clear_closed_connections");
+}
+
+export async function closeConnections(filter: IConnectionFilter) {
+  return invoke<number>("close_connections", { filter });
+}
+
+export async function getLanGatewayInfo() {
+  return invoke<ILanGatewayInfo>("get_lan_gateway_info");
+}
+
+export async function getQrCodeSvg(text: string) {
+  return invoke<string>("get_qr_code_svg", { text });
+}
+
+export async function getNetworkSnapshot() {
+  return invoke<INetworkSnapshot>("get_network_snapshot");
+}
+
 export async function createWebdavBackup() {
   return invoke<void>("create_webdav_backup");
 }
@@ -368,7 +419,7 @@ export const reinstallService = async () => {
 
 // 修复系统服务
 export const repairService = async () => {
-  return invoke<void>("repair_service");
+  return invoke<IServiceHealth>("repair_service");
 };
 
 // 系统服务是否可用
@@ -380,6 +431,22 @@ export const isServiceAvailable = async () => {
     return false;
   }
 };
+
+export const getServiceCoreLogs = async (lines?: number) => {
+  return invoke<string[]>("get_service_core_logs", { lines });
+};
+
+export const setServiceDns = async (servers: string[]) => {
+  return invoke<void>("set_service_dns", { servers });
+};
+
+export const getServiceHealthReport = async () => {
+  return invoke<IServiceHealthReport>("get_service_health_report");
+};
+
+export const getServiceHealth = async () => {
+  return invoke<IServiceHealth>("get_service_health");
+};
 export const entry_lightweight_mode = async () => {
   return invoke<void>("entry_lightweight_mode");
 };
@@ -397,13 +464,81 @@ export const isAdmin = async () => {
   }
 };
 
+export const getTransparentProxyScript = async () => {
+  return invoke<string>("get_transparent_proxy_script");
+};
+
+export const getCoreCapabilityState = async () => {
+  return invoke<CoreCapState>("get_core_capability_state");
+};
+
+export const grantCoreCapabilities = async () => {
+  return invoke<void>("grant_core_capabilities");
+};
+
+export const revokeCoreCapabilities = async () => {
+  return invoke<void>("revoke_core_capabilities");
+};
+
 export async function getNextUpdateTime(uid: string) {
   return invoke<number | null>("get_next_update_time", { uid });
 }
 
+export async function getAnnouncements() {
+  return invoke<IAnnouncement[]>("get_announcements");
+}
+
+export async function markAnnouncementRead(id?: string) {
+  return invoke<void>("mark_announcement_read", { id: id ?? null });
+}
+
+export async function getHwid() {
+  return invoke<string>("get_hwid");
+}
+
+export async function resetHwid(random: boolean) {
+  return invoke<string>("reset_hwid", { random });
+}
+
+export async function getProfileUsageHistory(uid: string) {
+  return invoke<IProfileUsageSample[]>("get_profile_usage_history", { uid });
+}
+
 export async function createProfileFromShareLink(
   link: string,
   templateName: string,
 ) {
   return invoke<void>("create_profile_from_share_link", { link, templateName });
 }
+
+export async function benchmarkDns(options?: IDnsBenchmarkOptions) {
+  return invoke<IDnsBenchmarkReport>("benchmark_dns", { options });
+}
+
+export async function applyFastestDns(report: IDnsBenchmarkReport) {
+  return invoke<string[]>("apply_fastest_dns", { report });
+}
+
+export async function flushFakeIpCache() {
+  return invoke<void>("flush_fakeip_cache");
+}
+
+export async function queryDns(name: string, queryType?: string) {
+  return invoke<IDnsQueryResponse>("query_dns", { name, queryType });
+}
+
+export async function importSystemHosts(overwrite = false) {
+  return invoke<IHostsImportReport>("import_system_hosts", { overwrite });
+}
+
+export async function regenerateControlApiToken() {
+  return invoke<string>("regenerate_control_api_token");
+}
+
+export async function getProxyGuardStatus() {
+  return invoke<IProxyGuardStatus>("get_proxy_guard_status");
+}
+
+export async function resetProxyGuard() {
+  return invoke<void>("reset_proxy_guard");
+}
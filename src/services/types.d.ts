@@ -25,6 +25,10 @@ interface IConfigData {
   ipv6: boolean;
   "socket-port": number;
   "allow-lan": boolean;
+  "bind-address"?: string;
+  "lan-allowed-ips"?: string[];
+  "lan-disallowed-ips"?: string[];
+  authentication?: string[];
   "log-level": string;
   "mixed-port": number;
   "redir-port": number;
@@ -219,12 +223,74 @@ interface IProfileOption {
   rules?: string;
   proxies?: string;
   groups?: string;
+  mirrors?: string[];
+  mirror_race?: boolean;
+  // encrypted string when read back from get_profiles
+  auth?: IProfileAuth | string;
+}
+
+interface IProfileAuth {
+  headers?: Record<string, string>;
+  username?: string;
+  password?: string;
+  bearer_token?: string;
 }
 
 interface IProfilesConfig {
   current?: string;
   valid?: string[];
   items?: IProfileItem[];
+  groups?: IProfileGroup[];
+  network_rules?: INetworkRule[];
+}
+
+interface IProfileGroup {
+  id: string;
+  name: string;
+  items: string[];
+}
+
+interface INetworkRule {
+  id: string;
+  name?: string;
+  enabled?: boolean;
+  ssid?: string;
+  interface?: string;
+  gateway?: string;
+  subnet?: string;
+  profile?: string;
+  group?: string;
+  mode?: "rule" | "global" | "direct";
+}
+
+interface INetworkSnapshot {
+  ssid?: string;
+  gateway?: string;
+  gateway_interface?: string;
+  interfaces: {
+    name: string;
+    addrs: string[];
+    subnets: string[];
+  }[];
+}
+
+interface IVergeTunSettings {
+  stack?: "system" | "gvisor" | "mixed";
+  device?: string;
+  mtu?: number;
+  auto_route?: boolean;
+  auto_detect_interface?: boolean;
+  strict_route?: boolean;
+  dns_hijack?: string[];
+  route_exclude_address?: string[];
+  inet4_route_address?: string[];
+  inet6_route_address?: string[];
+  include_uid?: number[];
+  exclude_uid?: number[];
+  include_package?: string[];
+  exclude_package?: string[];
+  endpoint_independent_nat?: boolean;
+  gso?: boolean;
 }
 
 interface IVergeTestItem {
@@ -803,6 +869,37 @@ interface IVergeConfig {
   webdav_password?: string;
   home_cards?: Record<string, boolean>;
   enable_hover_jump_navigator?: boolean;
+  enable_subscription_alerts?: boolean;
+  enable_network_auto_switch?: boolean;
+  enable_core_capabilities?: boolean;
+  enable_transparent_proxy?: boolean;
+  tun_settings?: IVergeTunSettings;
+  enable_control_api?: boolean;
+  control_api_token?: string;
+  subscription_quota_thresholds?: number[];
+  subscription_expire_alert_days?: number;
+  custom_hwid?: string;
+  enable_shell_env?: boolean;
+  shell_env_socks_only?: boolean;
+  auto_reassign_ports?: boolean;
+}
+
+interface IAnnouncement {
+  id: string;
+  profile_uid?: string;
+  profile_name?: string;
+  message: string;
+  url?: string;
+  received_at: number;
+  read: boolean;
+}
+
+interface IProfileUsageSample {
+  timestamp: number;
+  upload: number;
+  download: number;
+  total: number;
+  expire: number;
 }
 
 interface IWebDavFile {
@@ -819,3 +916,148 @@ interface IWebDavConfig {
   username: string;
   password: string;
 }
+
+interface IServiceHealthReport {
+  version?: string;
+  core_running: boolean;
+  core_pid?: number;
+  core_uptime_secs?: number;
+  core_restarts: number;
+  last_error?: string;
+}
+
+interface IServiceHealth {
+  installed: boolean;
+  running: boolean;
+  socket_reachable: boolean;
+  signature_valid: boolean;
+  secret_missing: boolean;
+  version?: string;
+  required_version: string;
+  version_match: boolean;
+  can_reinstall: boolean;
+  last_error?: string;
+  report?: IServiceHealthReport;
+  recommended_action: "none" | "install" | "reinstall";
+}
+
+type CoreCapState =
+  | "unsupported"
+  | "not_granted"
+  | "granted"
+  | "dropped"
+  | "outdated";
+
+interface IServiceRepairProgress {
+  step:
+    | "diagnose"
+    | "uninstall"
+    | "install"
+    | "wait_for_service"
+    | "verify"
+    | "restart_core";
+  status: "running" | "done" | "skipped" | "failed";
+  message?: string;
+}
+
+interface IDnsBenchmarkOptions {
+  nameservers?: string[];
+  domains?: string[];
+  trusted?: string;
+  rounds?: number;
+  timeout_ms?: number;
+  via_proxy?: boolean;
+}
+
+interface IDnsBenchmarkResult {
+  nameserver: string;
+  route: "direct" | "proxy";
+  queries: number;
+  failures: number;
+  failure_rate: number;
+  median_ms?: number;
+  p95_ms?: number;
+  poisoned: string[];
+  mismatched: string[];
+  error?: string;
+}
+
+interface IDnsBenchmarkReport {
+  trusted: string;
+  domains: string[];
+  results: IDnsBenchmarkResult[];
+}
+
+interface IDnsQueryResponse {
+  Status: number;
+  Question?: { name: string; qtype: number; qclass: number }[];
+  Answer?: { name: string; type: number; TTL: number; data: string }[];
+  [key: string]: any;
+}
+
+interface IHostsConflict {
+  domain: string;
+  current: string[];
+  system: string[];
+}
+
+interface IHostsImportReport {
+  imported: string[];
+  unchanged: string[];
+  conflicts: IHostsConflict[];
+  skipped: string[];
+}
+
+interface IProxyFieldChange {
+  field: string;
+  expected: string;
+  actual: string;
+}
+
+interface IProxyTamper {
+  time: number;
+  changes: IProxyFieldChange[];
+  restored: boolean;
+}
+
+interface IProxyGuardStatus {
+  history: IProxyTamper[];
+  backoff_secs?: number | null;
+}
+
+interface ILanGatewayInfo {
+  allow_lan: boolean;
+  addresses: string[];
+  mixed_port: number;
+  pac_port: number;
+  authentication: boolean;
+}
+
+interface IPortConflict {
+  kind: "mixed" | "socks" | "http" | "redir" | "tproxy" | "controller";
+  port: number;
+  pid?: number | null;
+  process?: string | null;
+  reassigned?: number | null;
+}
+
+type IConnectionGroup = "host" | "process" | "rule" | "chain";
+
+interface IConnectionStat {
+  key: string;
+  upload: number;
+  download: number;
+  active: number;
+  closed: number;
+}
+
+interface IClosedConnection extends IConnectionsItem {
+  closedAt: number;
+}
+
+interface IConnectionFilter {
+  host?: string;
+  process?: string;
+  rule?: string;
+  chain?: string;
+}